@@ -15,7 +15,11 @@
       ],
       "cflags!": [ "-fno-exceptions" ],
       "cflags_cc!": [ "-fno-exceptions" ],
-      "defines": [ "NAPI_DISABLE_CPP_EXCEPTIONS" ],
+      "defines": [
+        "NAPI_DISABLE_CPP_EXCEPTIONS",
+        "AUDIO_CAPTURE_NATIVE_VERSION=<!(node -p \"JSON.stringify(require('./package.json').version)\")",
+        "WEBRTC_BUILD_TAG=<!(node -p \"const fs = require('fs'); JSON.stringify(fs.existsSync('webrtc/VERSION') ? fs.readFileSync('webrtc/VERSION', 'utf8').trim() : 'unknown')\")"
+      ],
       "xcode_settings": {
         "GCC_ENABLE_CPP_EXCEPTIONS": "YES",
         "CLANG_CXX_LIBRARY": "libc++",
@@ -30,6 +34,31 @@
           "-framework CoreFoundation"
         ]
       }
+    },
+    {
+      "target_name": "pcm_stream_test",
+      "type": "executable",
+      "sources": [
+        "src/pcm_stream_test.cc",
+        "src/aec_processor.cc"
+      ],
+      "include_dirs": [
+        "webrtc/include"
+      ],
+      "libraries": [
+        "../webrtc/lib/libwebrtc.a"
+      ],
+      "cflags!": [ "-fno-exceptions" ],
+      "cflags_cc!": [ "-fno-exceptions" ],
+      "xcode_settings": {
+        "GCC_ENABLE_CPP_EXCEPTIONS": "YES",
+        "CLANG_CXX_LIBRARY": "libc++",
+        "MACOSX_DEPLOYMENT_TARGET": "12.0",
+        "OTHER_CPLUSPLUSFLAGS": [
+          "-std=c++17",
+          "-stdlib=libc++"
+        ]
+      }
     }
   ]
 }
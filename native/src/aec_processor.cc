@@ -5,364 +5,3388 @@
 #include "api/audio/echo_canceller3_config.h"
 #include "api/scoped_refptr.h"
 #include <iostream>
+#include <sstream>
+#include <istream>
+#include <ostream>
 #include <cstring>
 #include <cmath>
 #include <algorithm>
 #include <queue>
 #include <optional>
+#include <chrono>
+#include <cstdio>
+#include <deque>
+#include <mutex>
+#include <thread>
+#include <condition_variable>
+#include <type_traits>
 
 namespace kakarot {
 
+namespace {
+
+constexpr float kSilenceDbfs = -100.0f;
+constexpr float kActivityMeanSquare = 1e-5f;  // -50 dBFS: signal considered active
+constexpr float kDoubleTalkErleDb = 6.0f;     // Near-end survives cancellation below this
+constexpr int kDoubleTalkHangoverMs = 30;     // NLMS adaptation stays slowed this long after detection
+constexpr float kConvergedErleDb = 10.0f;     // Reported as converged at or above this
+constexpr size_t kCorrelationWindowMs = 500;  // Mic span correlated against render
+constexpr size_t kCorrelationMaxLagMs = 500;  // Longest echo delay searched
+constexpr size_t kMaxQueuedRenderFrames = 4;  // AEC3 render held back for pairing; older goes straight in
+constexpr double kRtfWindowMs = 5000.0;       // Audio span of the recent real-time factor
+constexpr double kOverloadRecoveryRatio = 0.5; // Overload clears below this fraction of the threshold
+constexpr float kEchoCheckIntervalMs = 1000.0f; // Correlation search is costly; run at most this often
+constexpr size_t kMaxQueuedEvents = 64;
+constexpr float kHealthWindowMs = 2000.0f;    // How long a pathology must persist to fail
+constexpr float kHealthGainMarginDb = 12.0f;  // Output above input beyond output-stage gain
+constexpr int kMaxLogRecordsPerSecond = 50;   // Forwarded to the sink; the rest are counted
+
+float ToDbfs(float linear) {
+    if (linear <= 0.0f) return kSilenceDbfs;
+    return std::max(kSilenceDbfs, 20.0f * std::log10(linear));
+}
+
+// Mean square and absolute peak of a single chunk
+struct ChunkLevels {
+    float mean_square = 0.0f;
+    float peak = 0.0f;
+};
+
+ChunkLevels MeasureChunk(const float* data, size_t num_samples) {
+    ChunkLevels levels;
+    if (num_samples == 0) return levels;
+    float sum = 0.0f;
+    for (size_t i = 0; i < num_samples; i++) {
+        float val = std::abs(data[i]);
+        sum += val * val;
+        levels.peak = std::max(levels.peak, val);
+    }
+    levels.mean_square = sum / num_samples;
+    return levels;
+}
+
+struct ChunkRange {
+    float min = 0.0f;
+    float max = 0.0f;
+    bool finite = true;
+};
+
+ChunkRange ScanChunk(const float* data, size_t num_samples) {
+    ChunkRange range;
+    if (num_samples == 0) return range;
+    range.min = range.max = data[0];
+    for (size_t i = 0; i < num_samples; i++) {
+        if (!std::isfinite(data[i])) {
+            range.finite = false;
+            continue;
+        }
+        range.min = std::min(range.min, data[i]);
+        range.max = std::max(range.max, data[i]);
+    }
+    return range;
+}
+
+// Energy ratio in dB, floored so silence never yields log-of-zero
+float EnergyRatioDb(float numerator, float denominator) {
+    constexpr float kEpsilon = 1e-10f;
+    return 10.0f * std::log10((numerator + kEpsilon) / (denominator + kEpsilon));
+}
+
+// Exponentially smoothed RMS/peak tracker. The smoothing coefficient is
+// derived per chunk so the time constant holds for any chunk size.
+struct LevelMeter {
+    float mean_square = 0.0f;
+    float peak = 0.0f;
+
+    void Update(const ChunkLevels& levels, float alpha) {
+        mean_square += alpha * (levels.mean_square - mean_square);
+        peak = levels.peak > peak ? levels.peak : peak + alpha * (levels.peak - peak);
+    }
+
+    float RmsDbfs() const { return ToDbfs(std::sqrt(mean_square)); }
+    float PeakDbfs() const { return ToDbfs(peak); }
+
+    void Reset() {
+        mean_square = 0.0f;
+        peak = 0.0f;
+    }
+};
+
+// Classic UI peak-hold: the held peak stays for hold_ms, then decays at a
+// fixed dB/s rate until a new chunk peak exceeds it.
+struct PeakHold {
+    float instantaneous = 0.0f;
+    float held = 0.0f;
+    float hold_remaining_ms = 0.0f;
+
+    void Update(float chunk_peak, float chunk_ms, float hold_ms, float decay_db_per_sec) {
+        instantaneous = chunk_peak;
+        if (chunk_peak >= held) {
+            held = chunk_peak;
+            hold_remaining_ms = hold_ms;
+            return;
+        }
+
+        float decay_ms = chunk_ms;
+        if (hold_remaining_ms > 0.0f) {
+            hold_remaining_ms -= chunk_ms;
+            if (hold_remaining_ms >= 0.0f) return;
+            decay_ms = -hold_remaining_ms;
+            hold_remaining_ms = 0.0f;
+        }
+
+        held *= std::pow(10.0f, -decay_db_per_sec * decay_ms / 20000.0f);
+        held = std::max(held, chunk_peak);
+    }
+
+    void Reset() {
+        instantaneous = 0.0f;
+        held = 0.0f;
+        hold_remaining_ms = 0.0f;
+    }
+};
+
+// Gated, slow makeup gain toward a target loudness. The level estimate only
+// moves on chunks above the gate so pauses don't pull the gain up, and the
+// applied gain slews at a bounded dB/s rate so short utterances don't pump.
+struct LoudnessNormalizer {
+    static constexpr float kGateMeanSquare = 1e-5f;   // -50 dBFS
+    static constexpr float kWindowMs = 3000.0f;       // Level estimate time constant
+    static constexpr float kMaxSlewDbPerSec = 3.0f;
+
+    float estimate_mean_square = 0.0f;
+    float gain_db = 0.0f;
+
+    void Process(float* data, size_t num_samples, float mean_square, float chunk_ms,
+                 float target_db, float max_gain_db, bool clip) {
+        if (num_samples == 0) return;
+
+        if (mean_square > kGateMeanSquare) {
+            float alpha = 1.0f - std::exp(-chunk_ms / kWindowMs);
+            estimate_mean_square = estimate_mean_square > 0.0f
+                ? estimate_mean_square + alpha * (mean_square - estimate_mean_square)
+                : mean_square;
+        }
+
+        float start_gain_db = gain_db;
+        if (estimate_mean_square > 0.0f) {
+            float level_db = 10.0f * std::log10(estimate_mean_square);
+            float desired_db = std::clamp(target_db - level_db, -max_gain_db, max_gain_db);
+            float max_step = kMaxSlewDbPerSec * chunk_ms / 1000.0f;
+            gain_db += std::clamp(desired_db - gain_db, -max_step, max_step);
+        }
+
+        // Ramp linearly across the chunk to avoid zipper noise
+        float start_gain = std::pow(10.0f, start_gain_db / 20.0f);
+        float end_gain = std::pow(10.0f, gain_db / 20.0f);
+        for (size_t i = 0; i < num_samples; i++) {
+            float gain = start_gain + (end_gain - start_gain) * (i + 1) / num_samples;
+            data[i] = clip ? std::clamp(data[i] * gain, -1.0f, 1.0f) : data[i] * gain;
+        }
+    }
+
+    void Reset() {
+        estimate_mean_square = 0.0f;
+        gain_db = 0.0f;
+    }
+};
+
+// Fast output AGC. Gain steers toward the per-chunk target with a short attack
+// (reductions) and a longer release (boosts), so loud bursts are caught within
+// a chunk or two while gain recovers smoothly. Chunks under the gate hold gain.
+struct OutputAgc {
+    static constexpr float kGateMeanSquare = 1e-5f;  // -50 dBFS
+    static constexpr float kAttackMs = 20.0f;
+    static constexpr float kReleaseMs = 500.0f;
+
+    float gain_db = 0.0f;
+
+    void Process(float* data, size_t num_samples, float mean_square, float chunk_ms,
+                 float target_db, float max_gain_db, bool clip) {
+        if (num_samples == 0) return;
+
+        float start_gain_db = gain_db;
+        if (mean_square > kGateMeanSquare) {
+            float level_db = 10.0f * std::log10(mean_square);
+            float desired_db = std::min(target_db - level_db, max_gain_db);
+            float time_constant = desired_db < gain_db ? kAttackMs : kReleaseMs;
+            gain_db += (1.0f - std::exp(-chunk_ms / time_constant)) * (desired_db - gain_db);
+        }
+
+        float start_gain = std::pow(10.0f, start_gain_db / 20.0f);
+        float end_gain = std::pow(10.0f, gain_db / 20.0f);
+        for (size_t i = 0; i < num_samples; i++) {
+            float gain = start_gain + (end_gain - start_gain) * (i + 1) / num_samples;
+            data[i] = clip ? std::clamp(data[i] * gain, -1.0f, 1.0f) : data[i] * gain;
+        }
+    }
+
+    void Reset() { gain_db = 0.0f; }
+};
+
+// Fixed gain that moves linearly to a new setting over kGainRampMs instead of
+// jumping, so changes mid-stream don't click. Unity with no ramp pending is a
+// no-op so the usual 0 dB setting costs nothing.
+struct GainRamp {
+    float gain = 1.0f;     // Linear gain at the last sample written
+    float target = 1.0f;
+    float step = 0.0f;     // Per-sample change while remaining > 0
+    size_t remaining = 0;
+
+    bool Idle(float target_db) const { return remaining == 0 && gain == 1.0f && target_db == 0.0f; }
+
+    void Process(const float* input, float* output, size_t num_samples, float target_db, int sample_rate) {
+        float next = std::pow(10.0f, target_db / 20.0f);
+        if (next != target) {
+            target = next;
+            remaining = std::max<size_t>(1, static_cast<size_t>(sample_rate * kGainRampMs / 1000.0f));
+            step = (target - gain) / remaining;
+        }
+        for (size_t i = 0; i < num_samples; i++) {
+            if (remaining > 0) {
+                gain = --remaining == 0 ? target : gain + step;  // Land exactly on the target
+            }
+            output[i] = std::clamp(input[i] * gain, -1.0f, 1.0f);
+        }
+    }
+};
+
+// Integer-factor downsampler behind a windowed-sinc anti-aliasing lowpass.
+// history carries the filter's tail across chunks, so chunk boundaries don't
+// click and every input sample counts toward exactly one output phase.
+struct Decimator {
+    static constexpr int kTapsPerPhase = 16;
+    static constexpr float kPassband = 0.9f;  // Cutoff as a fraction of the output Nyquist
+
+    int factor = 0;  // 0 = unsupported ratio, 1 = passthrough
+    std::vector<float> taps;
+    std::vector<float> history;
+
+    void Configure(int new_factor) {
+        factor = new_factor;
+        taps.clear();
+        history.clear();
+        if (factor <= 1) return;
+
+        size_t length = static_cast<size_t>(kTapsPerPhase * factor + 1);
+        float cutoff = 0.5f * kPassband / factor;  // Cycles per input sample
+        float center = (length - 1) / 2.0f;
+        float sum = 0.0f;
+        taps.resize(length);
+        for (size_t i = 0; i < length; i++) {
+            float m = i - center;
+            float sinc = m == 0.0f ? 2.0f * cutoff
+                                   : std::sin(2.0f * static_cast<float>(M_PI) * cutoff * m) / (static_cast<float>(M_PI) * m);
+            float phase = 2.0f * static_cast<float>(M_PI) * i / (length - 1);
+            float blackman = 0.42f - 0.5f * std::cos(phase) + 0.08f * std::cos(2.0f * phase);
+            taps[i] = sinc * blackman;
+            sum += taps[i];
+        }
+        for (float& tap : taps) tap /= sum;  // Unity gain at DC
+        history.assign(length - 1, 0.0f);
+    }
+
+    void Process(const float* data, size_t num_samples, std::vector<float>* out) {
+        out->clear();
+        if (factor == 1) {
+            out->assign(data, data + num_samples);
+            return;
+        }
+        if (factor < 1) return;
+
+        history.insert(history.end(), data, data + num_samples);
+        size_t pos = 0;
+        for (; pos + taps.size() <= history.size(); pos += factor) {
+            float acc = 0.0f;
+            for (size_t k = 0; k < taps.size(); k++) {
+                acc += taps[k] * history[pos + k];
+            }
+            out->push_back(acc);
+        }
+        history.erase(history.begin(), history.begin() + pos);
+    }
+
+    void Reset() { Configure(factor); }
+};
+
+// Peak limiter with a soft knee: gain reduction eases in over kKneeDb around
+// the threshold, then holds peaks at it. Instant attack, so nothing overshoots.
+struct SoftKneeLimiter {
+    static constexpr float kKneeDb = 6.0f;
+    static constexpr float kReleaseMs = 50.0f;
+
+    float envelope = 0.0f;
+    float gain_reduction_db = 0.0f;      // Largest reduction in the last chunk
+    float max_gain_reduction_db = 0.0f;  // Cleared by ResetMetrics, not Reset
+
+    void Process(float* data, size_t num_samples, int sample_rate, float threshold_db) {
+        if (num_samples == 0 || sample_rate <= 0) return;
+
+        float release = std::exp(-1000.0f / (kReleaseMs * sample_rate));
+        float knee_start = std::pow(10.0f, (threshold_db - kKneeDb / 2.0f) / 20.0f);
+        float chunk_reduction_db = 0.0f;
+        for (size_t i = 0; i < num_samples; i++) {
+            envelope = std::max(std::fabs(data[i]), envelope * release);
+            if (envelope > knee_start) {
+                float over_db = 20.0f * std::log10(envelope) - threshold_db;
+                float knee_db = over_db + kKneeDb / 2.0f;
+                float reduction_db = over_db >= kKneeDb / 2.0f ? over_db : knee_db * knee_db / (2.0f * kKneeDb);
+                data[i] *= std::pow(10.0f, -reduction_db / 20.0f);
+                chunk_reduction_db = std::max(chunk_reduction_db, reduction_db);
+            }
+            data[i] = std::clamp(data[i], -1.0f, 1.0f);
+        }
+        gain_reduction_db = chunk_reduction_db;
+        max_gain_reduction_db = std::max(max_gain_reduction_db, chunk_reduction_db);
+    }
+
+    void Reset() {
+        envelope = 0.0f;
+        gain_reduction_db = 0.0f;
+    }
+};
+
+// First-order pre-emphasis y[n] = x[n] - a*x[n-1] and its exact inverse
+// x[n] = y[n] + a*x[n-1]. Emphasized audio goes into scratch so input may
+// alias output; each direction keeps its own single-sample state.
+struct PreEmphasis {
+    float emphasis_state = 0.0f;  // Last raw input sample
+    float restore_state = 0.0f;   // Last restored output sample
+    std::vector<float> scratch;
+
+    const float* Emphasize(const float* input, size_t num_samples, float coefficient) {
+        scratch.resize(num_samples);
+        for (size_t i = 0; i < num_samples; i++) {
+            scratch[i] = input[i] - coefficient * emphasis_state;
+            emphasis_state = input[i];
+        }
+        return scratch.data();
+    }
+
+    void Restore(float* data, size_t num_samples, float coefficient) {
+        for (size_t i = 0; i < num_samples; i++) {
+            restore_state = data[i] + coefficient * restore_state;
+            data[i] = restore_state;
+        }
+    }
+
+    void Reset() {
+        emphasis_state = 0.0f;
+        restore_state = 0.0f;
+    }
+};
+
+// Minimum-statistics noise floor: follows the chunk level down immediately and
+// creeps up slowly, so speech bursts barely move it while a rising ambient
+// level is tracked within seconds.
+struct NoiseFloorTracker {
+    static constexpr float kRiseDbPerSec = 1.0f;
+
+    float mean_square = 0.0f;
+
+    void Update(float chunk_mean_square, float chunk_ms) {
+        if (chunk_mean_square <= 0.0f) return;  // Digital zero says nothing about ambient noise
+        if (mean_square <= 0.0f || chunk_mean_square < mean_square) {
+            mean_square = chunk_mean_square;
+            return;
+        }
+        mean_square *= std::pow(10.0f, kRiseDbPerSec * chunk_ms / 10000.0f);
+        mean_square = std::min(mean_square, chunk_mean_square);
+    }
+
+    float Dbfs() const { return ToDbfs(std::sqrt(mean_square)); }
+
+    void Reset() { mean_square = 0.0f; }
+};
+
+// Normalized LMS echo canceller: a time-domain FIR estimate of the echo path
+// driven by the render signal. Weaker than AEC3 (no delay estimator, no
+// suppressor, no double-talk protection) but has no external dependencies.
+struct NlmsFilter {
+    static constexpr float kStepSize = 0.5f;
+    static constexpr float kRegularization = 1e-6f;
+    static constexpr float kThrottledStepScale = 0.1f;  // Slowed, not frozen, so false alarms still converge
+
+    std::vector<float> taps;
+    std::vector<float> history;  // Reference history mirrored twice so the window is contiguous
+    size_t pos = 0;
+    float history_power = 0.0f;  // Sum of squares over the current window
+    size_t throttle_hold = 0;    // Samples left at the throttled step size
+    float step_size = kStepSize; // kStepSize x AECConfig::adaptation_rate
+
+    void Configure(size_t length) {
+        taps.assign(length, 0.0f);
+        history.assign(length * 2, 0.0f);
+        pos = 0;
+        history_power = 0.0f;
+        throttle_hold = 0;
+    }
+
+    // Push one reference sample and return the echo-cancelled mic sample.
+    // geigel_threshold > 0 enables double-talk detection: a mic sample above
+    // that fraction of the window's reference peak slows adaptation for the
+    // next hangover samples. Returns true in *throttled when it was slowed.
+    float Process(float reference, float mic, float geigel_threshold, size_t hangover, bool* throttled) {
+        return Step<true>(reference, mic, geigel_threshold, hangover, throttled);
+    }
+
+    // Process without the detector; only valid while throttle_hold is 0
+    float ProcessPlain(float reference, float mic) {
+        bool throttled = false;
+        return Step<false>(reference, mic, 0.0f, 0, &throttled);
+    }
+
+private:
+    template <bool kDetectDoubleTalk>
+    float Step(float reference, float mic, float geigel_threshold, size_t hangover, bool* throttled) {
+        const size_t n = taps.size();
+        if (n == 0) return mic;
+
+        float oldest = history[pos + n - 1];
+        pos = pos == 0 ? n - 1 : pos - 1;
+        history[pos] = reference;
+        history[pos + n] = reference;
+        history_power = std::max(0.0f, history_power + reference * reference - oldest * oldest);
+
+        const float* window = history.data() + pos;  // window[k] = reference k samples ago
+        float estimate = 0.0f;
+        if constexpr (kDetectDoubleTalk) {
+            float window_peak = 0.0f;
+            for (size_t k = 0; k < n; k++) {
+                estimate += taps[k] * window[k];
+                window_peak = std::max(window_peak, std::fabs(window[k]));
+            }
+            if (geigel_threshold > 0.0f && std::fabs(mic) > geigel_threshold * window_peak) {
+                throttle_hold = hangover;
+            }
+        } else {
+            for (size_t k = 0; k < n; k++) {
+                estimate += taps[k] * window[k];
+            }
+        }
+
+        float error = mic - estimate;
+        float step = step_size * error / (history_power + kRegularization * n);
+        if constexpr (kDetectDoubleTalk) {
+            if (throttle_hold > 0) {
+                throttle_hold--;
+                step *= kThrottledStepScale;
+                *throttled = true;
+            }
+        }
+        for (size_t k = 0; k < n; k++) {
+            taps[k] += step * window[k];
+        }
+        return error;
+    }
+};
+
+// Fixed-bucket histograms of per-call processing time. `counts` is the coarse
+// view (bucket i holds calls under kUpperBoundsMs[i], the last one everything
+// slower); `fine` is log-spaced from 10us to 50ms and backs the percentiles.
+struct LatencyHistogram {
+    static constexpr double kUpperBoundsMs[] = {0.5, 1.0, 2.0, 4.0, 8.0};
+    static constexpr size_t kFineBuckets = 32;
+    static constexpr double kFineMinMs = 0.01;
+    static constexpr double kFineMaxMs = 50.0;
+
+    std::array<uint64_t, 6> counts{};
+    std::array<uint64_t, kFineBuckets> fine{};
+    uint64_t total = 0;
+    double max_ms = 0.0;
+    size_t max_chunk_samples = 0;
+
+    void Record(double elapsed_ms, size_t num_samples) {
+        size_t bucket = 0;
+        while (bucket < std::size(kUpperBoundsMs) && elapsed_ms >= kUpperBoundsMs[bucket]) {
+            bucket++;
+        }
+        counts[bucket]++;
+
+        fine[FineBucket(elapsed_ms)]++;
+        total++;
+        if (elapsed_ms > max_ms) {
+            max_ms = elapsed_ms;
+            max_chunk_samples = num_samples;
+        }
+    }
+
+    // Upper bound of the bucket holding the given quantile (0..1)
+    double PercentileMs(double quantile) const {
+        if (total == 0) return 0.0;
+        uint64_t rank = static_cast<uint64_t>(std::ceil(quantile * total));
+        uint64_t seen = 0;
+        for (size_t i = 0; i < kFineBuckets; i++) {
+            seen += fine[i];
+            if (seen >= rank) return std::min(FineUpperBoundMs(i), max_ms);
+        }
+        return max_ms;
+    }
+
+    void Reset() {
+        counts.fill(0);
+        fine.fill(0);
+        total = 0;
+        max_ms = 0.0;
+        max_chunk_samples = 0;
+    }
+
+private:
+    static double FineRatio() {
+        static const double ratio = std::log(kFineMaxMs / kFineMinMs) / (kFineBuckets - 1);
+        return ratio;
+    }
+
+    static size_t FineBucket(double elapsed_ms) {
+        if (elapsed_ms <= kFineMinMs) return 0;
+        size_t bucket = static_cast<size_t>(std::ceil(std::log(elapsed_ms / kFineMinMs) / FineRatio()));
+        return std::min(bucket, kFineBuckets - 1);
+    }
+
+    static double FineUpperBoundMs(size_t bucket) {
+        return kFineMinMs * std::exp(FineRatio() * bucket);
+    }
+};
+
+// Echo return loss enhancement from mic vs output energy, fed only with
+// chunks where the far end is active. Keeps a smoothed running value and a
+// session-wide ratio of accumulated energies.
+struct ErleEstimator {
+    static constexpr float kWindowMs = 1000.0f;
+
+    float input_mean_square = 0.0f;
+    float output_mean_square = 0.0f;
+    double input_energy = 0.0;
+    double output_energy = 0.0;
+
+    void Update(float chunk_input_ms, float chunk_output_ms, float chunk_ms, size_t num_samples) {
+        float alpha = 1.0f - std::exp(-chunk_ms / kWindowMs);
+        input_mean_square += alpha * (chunk_input_ms - input_mean_square);
+        output_mean_square += alpha * (chunk_output_ms - output_mean_square);
+        input_energy += static_cast<double>(chunk_input_ms) * num_samples;
+        output_energy += static_cast<double>(chunk_output_ms) * num_samples;
+    }
+
+    float InstantDb() const { return EnergyRatioDb(input_mean_square, output_mean_square); }
+    float AverageDb() const {
+        return EnergyRatioDb(static_cast<float>(input_energy), static_cast<float>(output_energy));
+    }
+
+    void Reset() {
+        input_mean_square = 0.0f;
+        output_mean_square = 0.0f;
+        input_energy = 0.0;
+        output_energy = 0.0;
+    }
+};
+
+// Flags an echo path change: after the smoothed ERLE has converged, a drop of
+// kDropDb below its running baseline held for kHoldMs of echo-active audio.
+// Re-arms only once ERLE converges again.
+struct PathChangeDetector {
+    static constexpr float kBaselineWindowMs = 5000.0f;
+    static constexpr float kDropDb = 10.0f;
+    static constexpr float kHoldMs = 1000.0f;
+
+    bool armed = false;
+    float baseline_db = 0.0f;
+    float drop_ms = 0.0f;
+
+    bool Update(float erle_db, float chunk_ms) {
+        if (!armed) {
+            if (erle_db >= kConvergedErleDb) {
+                armed = true;
+                baseline_db = erle_db;
+            }
+            return false;
+        }
+
+        if (erle_db < baseline_db - kDropDb) {
+            drop_ms += chunk_ms;
+            if (drop_ms < kHoldMs) return false;
+            Reset();
+            return true;
+        }
+
+        drop_ms = 0.0f;
+        float alpha = 1.0f - std::exp(-chunk_ms / kBaselineWindowMs);
+        baseline_db += alpha * (erle_db - baseline_db);
+        return false;
+    }
+
+    void Reset() {
+        armed = false;
+        baseline_db = 0.0f;
+        drop_ms = 0.0f;
+    }
+};
+
+// Render fed minus capture consumed, checkpointed once per second of capture
+// audio. Capture time is the clock, so a render side running 10 samples/s
+// fast reads +10/s however the calls are paced.
+struct ReferenceDrift {
+    static constexpr size_t kWindowSeconds = 60;
+
+    uint64_t fed = 0;
+    uint64_t consumed = 0;
+    std::deque<std::pair<uint64_t, int64_t>> checkpoints;  // (consumed, drift)
+
+    int64_t Samples() const {
+        return static_cast<int64_t>(fed) - static_cast<int64_t>(consumed);
+    }
+
+    void Consume(size_t num_samples, int sample_rate) {
+        consumed += num_samples;
+        if (sample_rate <= 0) return;
+        if (checkpoints.empty() || consumed - checkpoints.back().first >= static_cast<uint64_t>(sample_rate)) {
+            checkpoints.emplace_back(consumed, Samples());
+            if (checkpoints.size() > kWindowSeconds + 1) {
+                checkpoints.pop_front();
+            }
+        }
+    }
+
+    float PerSecond(int sample_rate) const {
+        if (checkpoints.size() < 2 || sample_rate <= 0) return 0.0f;
+        double seconds = static_cast<double>(checkpoints.back().first - checkpoints.front().first) / sample_rate;
+        return static_cast<float>((checkpoints.back().second - checkpoints.front().second) / seconds);
+    }
+
+    void Reset() {
+        fed = 0;
+        consumed = 0;
+        checkpoints.clear();
+    }
+};
+
+// Processing time over audio time, lifetime and over a sliding window of
+// recent calls bounded by kRtfWindowMs of audio
+struct RealTimeFactor {
+    double total_processing_ms = 0.0;
+    double total_audio_ms = 0.0;
+    std::deque<std::pair<double, double>> window;  // (processing, audio) per call
+    double window_processing_ms = 0.0;
+    double window_audio_ms = 0.0;
+
+    void Record(double processing_ms, double audio_ms) {
+        total_processing_ms += processing_ms;
+        total_audio_ms += audio_ms;
+        window.emplace_back(processing_ms, audio_ms);
+        window_processing_ms += processing_ms;
+        window_audio_ms += audio_ms;
+        while (window.size() > 1 && window_audio_ms - window.front().second >= kRtfWindowMs) {
+            window_processing_ms -= window.front().first;
+            window_audio_ms -= window.front().second;
+            window.pop_front();
+        }
+    }
+
+    double Overall() const {
+        return total_audio_ms > 0.0 ? total_processing_ms / total_audio_ms : 0.0;
+    }
+
+    double Recent() const {
+        return window_audio_ms > 0.0 ? window_processing_ms / window_audio_ms : 0.0;
+    }
+
+    void Reset() {
+        total_processing_ms = 0.0;
+        total_audio_ms = 0.0;
+        window.clear();
+        window_processing_ms = 0.0;
+        window_audio_ms = 0.0;
+    }
+};
+
+// Streaks of pathological chunks for HealthCheck; each streak resets on the
+// first normal chunk, so a failure means the condition is current
+struct HealthMonitor {
+    float output_stuck_ms = 0.0f;
+    float output_hot_ms = 0.0f;
+    float reference_pinned_ms = 0.0f;
+    bool non_finite = false;
+
+    void UpdateCapture(const ChunkRange& input, const ChunkRange& output, float input_mean_square,
+                       float output_mean_square, float allowed_gain_db, float chunk_ms) {
+        non_finite = non_finite || !input.finite || !output.finite;
+
+        bool stuck = output.max == output.min && input.max != input.min;
+        output_stuck_ms = stuck ? output_stuck_ms + chunk_ms : 0.0f;
+
+        bool hot = input_mean_square > kActivityMeanSquare &&
+                   EnergyRatioDb(output_mean_square, input_mean_square) > allowed_gain_db + kHealthGainMarginDb;
+        output_hot_ms = hot ? output_hot_ms + chunk_ms : 0.0f;
+    }
+
+    void UpdateRender(const ChunkRange& render, float chunk_ms) {
+        bool pinned = (render.max == render.min && render.max != 0.0f) ||
+                      render.min >= 0.999f || render.max <= -0.999f;
+        reference_pinned_ms = pinned ? reference_pinned_ms + chunk_ms : 0.0f;
+    }
+
+    void Reset() {
+        output_stuck_ms = 0.0f;
+        output_hot_ms = 0.0f;
+        reference_pinned_ms = 0.0f;
+        non_finite = false;
+    }
+};
+
+// Fixed-capacity ring of float samples; overwrites the oldest when full
+struct HistoryRing {
+    std::vector<float> values;
+    size_t head = 0;   // Next write position
+    size_t count = 0;
+
+    void SetCapacity(size_t capacity) {
+        std::vector<float> kept = Snapshot();
+        if (kept.size() > capacity) {
+            kept.erase(kept.begin(), kept.end() - capacity);
+        }
+        values.assign(capacity, 0.0f);
+        std::copy(kept.begin(), kept.end(), values.begin());
+        count = kept.size();
+        head = capacity > 0 ? count % capacity : 0;
+    }
+
+    void Push(float value) {
+        if (values.empty()) return;
+        values[head] = value;
+        head = (head + 1) % values.size();
+        count = std::min(count + 1, values.size());
+    }
+
+    void Push(const float* data, size_t num_samples) {
+        for (size_t i = 0; i < num_samples; i++) {
+            Push(data[i]);
+        }
+    }
+
+    std::vector<float> Snapshot() const {
+        std::vector<float> ordered;
+        ordered.reserve(count);
+        size_t start = (head + values.size() - count) % std::max<size_t>(values.size(), 1);
+        for (size_t i = 0; i < count; i++) {
+            ordered.push_back(values[(start + i) % values.size()]);
+        }
+        return ordered;
+    }
+
+    void Clear() {
+        head = 0;
+        count = 0;
+    }
+};
+
+// Block-averaged (crudely low-passed) copy of a stream, cheap enough to keep
+// for correlation diagnostics without storing full-rate audio
+struct DecimatedHistory {
+    static constexpr int kFactor = 8;
+
+    HistoryRing ring;
+    float sum = 0.0f;
+    int filled = 0;
+
+    void Push(const float* data, size_t num_samples) {
+        for (size_t i = 0; i < num_samples; i++) {
+            sum += data[i];
+            if (++filled == kFactor) {
+                ring.Push(sum / kFactor);
+                sum = 0.0f;
+                filled = 0;
+            }
+        }
+    }
+
+    void Clear() {
+        ring.Clear();
+        sum = 0.0f;
+        filled = 0;
+    }
+};
+
+// Streams interleaved 16-bit frames to a WAV file from a background thread.
+// Enqueue never waits on disk: when the bounded queue is full the chunk is
+// dropped and counted. Stop patches the RIFF/data sizes once the file is final.
+class DebugDumpWriter {
+public:
+    static constexpr int kChannels = 3;
+    static constexpr size_t kMaxQueuedChunks = 500;  // ~5 s of 10 ms chunks
+
+    ~DebugDumpWriter() { Stop(); }
+
+    bool Start(const std::string& path, int sample_rate) {
+        file_ = std::fopen(path.c_str(), "wb");
+        if (!file_) return false;
+        sample_rate_ = sample_rate;
+        WriteHeader(0);
+        running_ = true;
+        thread_ = std::thread([this] { Run(); });
+        return true;
+    }
+
+    void Enqueue(const float* mic, const float* reference, const float* output, size_t num_samples) {
+        std::vector<int16_t> frames(num_samples * kChannels);
+        for (size_t i = 0; i < num_samples; i++) {
+            frames[i * kChannels] = ToPcm16(mic[i]);
+            frames[i * kChannels + 1] = ToPcm16(reference[i]);
+            frames[i * kChannels + 2] = ToPcm16(output[i]);
+        }
+
+        {
+            std::lock_guard<std::mutex> lock(mutex_);
+            if (queue_.size() >= kMaxQueuedChunks) {
+                stats_.dropped_chunks++;
+                return;
+            }
+            queue_.push_back(std::move(frames));
+        }
+        ready_.notify_one();
+    }
+
+    AECDebugDumpStats Stop() {
+        if (thread_.joinable()) {
+            {
+                std::lock_guard<std::mutex> lock(mutex_);
+                running_ = false;
+            }
+            ready_.notify_one();
+            thread_.join();
+        }
+        if (file_) {
+            WriteHeader(stats_.frames_written);
+            std::fclose(file_);
+            file_ = nullptr;
+        }
+        return stats_;
+    }
+
+private:
+    static int16_t ToPcm16(float sample) {
+        float clamped = std::clamp(sample, -1.0f, 1.0f);
+        return static_cast<int16_t>(std::lround(clamped * 32767.0f));
+    }
+
+    void Run() {
+        std::unique_lock<std::mutex> lock(mutex_);
+        while (true) {
+            ready_.wait(lock, [this] { return !queue_.empty() || !running_; });
+            if (queue_.empty()) break;  // Stopped and drained
+
+            std::vector<int16_t> frames = std::move(queue_.front());
+            queue_.pop_front();
+            lock.unlock();
+            std::fwrite(frames.data(), sizeof(int16_t), frames.size(), file_);
+            lock.lock();
+            stats_.frames_written += frames.size() / kChannels;
+        }
+    }
+
+    void WriteHeader(uint64_t frames) {
+        auto put32 = [this](uint32_t v) { std::fwrite(&v, 4, 1, file_); };
+        auto put16 = [this](uint16_t v) { std::fwrite(&v, 2, 1, file_); };
+        uint32_t block_align = kChannels * sizeof(int16_t);
+        uint32_t data_bytes = static_cast<uint32_t>(std::min<uint64_t>(frames * block_align, UINT32_MAX - 36));
+
+        std::fseek(file_, 0, SEEK_SET);
+        std::fwrite("RIFF", 1, 4, file_);
+        put32(36 + data_bytes);
+        std::fwrite("WAVEfmt ", 1, 8, file_);
+        put32(16);                                    // fmt chunk size
+        put16(1);                                     // PCM
+        put16(kChannels);
+        put32(static_cast<uint32_t>(sample_rate_));
+        put32(static_cast<uint32_t>(sample_rate_) * block_align);
+        put16(static_cast<uint16_t>(block_align));
+        put16(16);                                    // Bits per sample
+        std::fwrite("data", 1, 4, file_);
+        put32(data_bytes);
+        std::fseek(file_, 0, SEEK_END);
+    }
+
+    std::FILE* file_ = nullptr;
+    int sample_rate_ = 0;
+    std::thread thread_;
+    std::mutex mutex_;
+    std::condition_variable ready_;
+    std::deque<std::vector<int16_t>> queue_;
+    bool running_ = false;
+    AECDebugDumpStats stats_;  // Guarded by mutex_ while the thread runs
+};
+
+// Linear interpolation to a new rate, for buffered audio that has to survive
+// a sample-rate change
+void ResampleLinear(std::vector<float>* samples, int from_rate, int to_rate) {
+    if (samples->empty() || from_rate <= 0 || to_rate <= 0 || from_rate == to_rate) return;
+    size_t out_size = std::max<size_t>(1, samples->size() * static_cast<size_t>(to_rate) / from_rate);
+    std::vector<float> out(out_size);
+    double step = static_cast<double>(from_rate) / to_rate;
+    for (size_t i = 0; i < out_size; i++) {
+        double position = i * step;
+        size_t index = static_cast<size_t>(position);
+        if (index + 1 >= samples->size()) {
+            out[i] = samples->back();
+            continue;
+        }
+        float frac = static_cast<float>(position - index);
+        out[i] = (*samples)[index] + frac * ((*samples)[index + 1] - (*samples)[index]);
+    }
+    samples->swap(out);
+}
+
+} // namespace
+
+bool ParseLogLevel(const std::string& name, AECLogLevel* level) {
+    static const std::pair<const char*, AECLogLevel> kLevels[] = {
+        {"error", AECLogLevel::kError}, {"warn", AECLogLevel::kWarn}, {"info", AECLogLevel::kInfo},
+        {"debug", AECLogLevel::kDebug}, {"trace", AECLogLevel::kTrace},
+    };
+    for (const auto& entry : kLevels) {
+        if (name == entry.first) {
+            *level = entry.second;
+            return true;
+        }
+    }
+    return false;
+}
+
+const char* LogLevelName(AECLogLevel level) {
+    switch (level) {
+        case AECLogLevel::kError: return "error";
+        case AECLogLevel::kWarn: return "warn";
+        case AECLogLevel::kInfo: return "info";
+        case AECLogLevel::kDebug: return "debug";
+        case AECLogLevel::kTrace: return "trace";
+    }
+    return "info";
+}
+
+bool IsKnownEngine(const std::string& engine) {
+    return engine == "webrtc" || engine == "nlms";
+}
+
+int16_t SaturateInt16(int32_t value) {
+    return static_cast<int16_t>(std::clamp<int32_t>(value, INT16_MIN, INT16_MAX));
+}
+
+void MixInt16Saturating(const int16_t* const* sources, size_t num_sources, size_t num_samples,
+                        int16_t* output) {
+    for (size_t i = 0; i < num_samples; i++) {
+        int32_t sum = 0;
+        for (size_t s = 0; s < num_sources; s++) {
+            sum += sources[s][i];
+        }
+        output[i] = SaturateInt16(sum);
+    }
+}
+
+bool IsKnownEmptyInputMode(const std::string& mode) {
+    return mode == "empty" || mode == "silence" || mode == "comfortNoise";
+}
+
+bool IsKnownUnderrunStrategy(const std::string& strategy) {
+    return strategy == "zeros" || strategy == "hold";
+}
+
+// AEC3's fixed block length; frames are a whole number of these
+constexpr int kApmBlockMs = 10;
+
+constexpr int kCanonicalSampleRates[] = {8000, 16000, 32000, 48000};
+constexpr int kCanonicalFrameDurationsMs[] = {10, 20, 40};
+
+bool IsValidFrameDuration(int frame_duration_ms) {
+    return std::find(std::begin(kCanonicalFrameDurationsMs), std::end(kCanonicalFrameDurationsMs),
+                     frame_duration_ms) != std::end(kCanonicalFrameDurationsMs);
+}
+
+bool IsValidStreamFormat(int sample_rate, int frame_duration_ms) {
+    if (sample_rate < 8000 || sample_rate > 384000) return false;
+    if (!IsValidFrameDuration(frame_duration_ms)) return false;
+    return (sample_rate * kApmBlockMs) % 1000 == 0;  // Whole 10 ms blocks, so whole frames
+}
+
+bool IsCanonicalFrameFormat(int sample_rate, int frame_duration_ms) {
+    return std::find(std::begin(kCanonicalSampleRates), std::end(kCanonicalSampleRates),
+                     sample_rate) != std::end(kCanonicalSampleRates) &&
+           IsValidFrameDuration(frame_duration_ms);
+}
+
+size_t FrameSamples(int sample_rate, int frame_duration_ms) {
+    if (!IsValidStreamFormat(sample_rate, frame_duration_ms)) return 0;
+    return static_cast<size_t>(sample_rate) * frame_duration_ms / 1000;
+}
+
+bool IsSupportedSampleRate(const AECConfig& config, int sample_rate) {
+    if (!IsValidStreamFormat(sample_rate, config.frame_duration_ms)) return false;
+    const std::vector<int>& rates = config.supported_sample_rates;
+    return std::find(rates.begin(), rates.end(), sample_rate) != rates.end();
+}
+
+std::string DescribeSupportedSampleRates(const AECConfig& config) {
+    std::ostringstream out;
+    for (size_t i = 0; i < config.supported_sample_rates.size(); i++) {
+        out << (i > 0 ? ", " : "") << config.supported_sample_rates[i];
+    }
+    return out.str();
+}
+
+namespace {
+
+// Minimal writer for flat JSON objects with number, bool, string and
+// number-array values; enough for the metrics schema
+class JsonObjectWriter {
+public:
+    JsonObjectWriter() {
+        out_.precision(9);
+        out_ << '{';
+    }
+
+    void Number(const char* key, double value) {
+        Key(key);
+        WriteNumber(value);
+    }
+
+    void Integer(const char* key, uint64_t value) {
+        Key(key);
+        out_ << value;
+    }
+
+    void Bool(const char* key, bool value) {
+        Key(key);
+        out_ << (value ? "true" : "false");
+    }
+
+    void String(const char* key, const std::string& value) {
+        Key(key);
+        out_ << '"';
+        for (char ch : value) {
+            if (ch == '"' || ch == '\\') {
+                out_ << '\\' << ch;
+            } else if (static_cast<unsigned char>(ch) < 0x20) {
+                char escaped[8];
+                std::snprintf(escaped, sizeof(escaped), "\\u%04x", static_cast<unsigned char>(ch));
+                out_ << escaped;
+            } else {
+                out_ << ch;
+            }
+        }
+        out_ << '"';
+    }
+
+    template <typename T, size_t N>
+    void IntegerArray(const char* key, const std::array<T, N>& values) {
+        Key(key);
+        out_ << '[';
+        for (size_t i = 0; i < N; i++) {
+            out_ << (i > 0 ? "," : "") << values[i];
+        }
+        out_ << ']';
+    }
+
+    std::string Finish() {
+        out_ << '}';
+        return out_.str();
+    }
+
+private:
+    void Key(const char* key) {
+        out_ << (first_ ? "" : ",") << '"' << key << "\":";
+        first_ = false;
+    }
+
+    void WriteNumber(double value) {
+        if (std::isfinite(value)) {
+            out_ << value;
+        } else {
+            out_ << "null";
+        }
+    }
+
+    std::ostringstream out_;
+    bool first_ = true;
+};
+
+constexpr uint32_t kStateMagic = 0x5345414b;  // "KAES" little-endian
+
+// SaveState encoding: scalars as their native bytes, strings and float
+// vectors as a uint64 count followed by the elements
+class StateWriter {
+public:
+    template <typename T>
+    void operator()(const T& value) {
+        static_assert(std::is_trivially_copyable<T>::value, "scalar state only");
+        Append(&value, sizeof(T));
+    }
+
+    void operator()(const std::vector<float>& values) {
+        (*this)(static_cast<uint64_t>(values.size()));
+        Append(values.data(), values.size() * sizeof(float));
+    }
+
+    void operator()(const std::string& text) {
+        (*this)(static_cast<uint64_t>(text.size()));
+        Append(text.data(), text.size());
+    }
+
+    std::vector<uint8_t> Take() { return std::move(data_); }
+
+private:
+    void Append(const void* bytes, size_t size) {
+        const uint8_t* begin = static_cast<const uint8_t*>(bytes);
+        data_.insert(data_.end(), begin, begin + size);
+    }
+
+    std::vector<uint8_t> data_;
+};
+
+// Reads what StateWriter wrote. A short read or an oversized count clears
+// ok() and leaves the value alone; later reads are no-ops.
+class StateReader {
+public:
+    StateReader(const uint8_t* data, size_t size) : data_(data), size_(size) {}
+
+    template <typename T>
+    void operator()(T& value) {
+        static_assert(std::is_trivially_copyable<T>::value, "scalar state only");
+        Extract(&value, sizeof(T));
+    }
+
+    void operator()(bool& value) {
+        uint8_t byte = 0;
+        Extract(&byte, 1);
+        if (byte > 1) ok_ = false;
+        value = byte == 1;
+    }
+
+    void operator()(std::vector<float>& values) {
+        uint64_t count = 0;
+        (*this)(count);
+        if (!ok_ || count > Remaining() / sizeof(float)) {
+            ok_ = false;
+            return;
+        }
+        values.resize(static_cast<size_t>(count));
+        Extract(values.data(), values.size() * sizeof(float));
+    }
+
+    void operator()(std::string& text) {
+        uint64_t count = 0;
+        (*this)(count);
+        if (!ok_ || count > Remaining()) {
+            ok_ = false;
+            return;
+        }
+        text.assign(reinterpret_cast<const char*>(data_ + offset_), static_cast<size_t>(count));
+        offset_ += static_cast<size_t>(count);
+    }
+
+    bool ok() const { return ok_; }
+    bool AtEnd() const { return offset_ == size_; }
+
+private:
+    size_t Remaining() const { return size_ - offset_; }
+
+    void Extract(void* bytes, size_t size) {
+        if (!ok_ || size > Remaining()) {
+            ok_ = false;
+            return;
+        }
+        std::memcpy(bytes, data_ + offset_, size);
+        offset_ += size;
+    }
+
+    const uint8_t* data_;
+    size_t size_;
+    size_t offset_ = 0;
+    bool ok_ = true;
+};
+
+} // namespace
+
+std::string ConfigFingerprint(const AECConfig& config, int sample_rate) {
+    std::ostringstream canonical;
+    canonical << sample_rate << '|' << config.enable_aec << config.enable_agc << config.enable_ns
+              << config.disable_aec_on_headphones << '|' << config.frame_duration_ms << '|'
+              << DescribeSupportedSampleRates(config) << '|' << config.level_smoothing_ms << '|'
+              << config.peak_hold_ms << '|' << config.peak_decay_db_per_sec << '|' << config.engine << '|'
+              << config.preset << '|' << config.filter_length_blocks << '|' << config.noise_suppression_level
+              << '|' << config.enable_loudness_normalization << config.loudness_target_db << '|'
+              << config.loudness_max_gain_db << '|' << config.enable_output_agc << config.agc_target_db << '|'
+              << config.agc_max_gain_db << '|' << config.enable_limiter << config.limiter_threshold_db
+              << '|' << config.skip_silent_chunks << config.silent_chunk_floor_db
+              << '|' << config.enable_silence_bypass << config.silence_bypass_threshold_db << '|'
+              << config.dead_input_warning_ms << '|' << config.strict_chunk_size << config.render_granularity
+              << '|' << config.max_input_samples << '|' << config.double_talk_sensitivity
+              << '|' << config.adaptation_rate << '|' << config.fallback_to_passthrough << config.test_init_failure
+              << '|' << config.auto_reset_after_idle_ms << config.idle_reset_filter
+              << '|' << config.empty_input_mode << config.underrun_strategy << config.overrun_drain_fraction << '|'
+              << config.reference_gain << '|' << config.input_gain_db << '|' << config.output_gain_db << '|'
+              << config.pre_emphasis << '|' << config.erle_history_length << '|' << config.overload_rtf_threshold << '|'
+              << config.overload_consecutive_chunks << config.auto_bypass_on_overload << '|'
+              << config.auto_reconverge_on_path_change << '|' << config.converged_erle_db << '|'
+              << config.converged_hold_ms << '|' << config.echo_detect_correlation << '|'
+              << config.echo_detect_max_erle_db << '|' << config.convergence_cold_ms;
+
+    uint64_t hash = 14695981039346656037ULL;
+    for (unsigned char ch : canonical.str()) {
+        hash ^= ch;
+        hash *= 1099511628211ULL;
+    }
+    char hex[17];
+    std::snprintf(hex, sizeof(hex), "%016llx", static_cast<unsigned long long>(hash));
+    return hex;
+}
+
+std::string MetricsToJson(const AECMetrics& metrics, const AECConfig& config, int sample_rate) {
+    JsonObjectWriter json;
+    json.Integer("schemaVersion", 1);
+    json.String("configFingerprint", ConfigFingerprint(config, sample_rate));
+    json.String("label", config.label);
+    json.Integer("sampleRate", static_cast<uint64_t>(std::max(sample_rate, 0)));
+    json.String("engine", metrics.engine);
+    json.Number("echoReturnLoss", metrics.echo_return_loss);
+    json.Number("echoReturnLossEnhancement", metrics.echo_return_loss_enhancement);
+    json.Number("renderDelayMs", metrics.render_delay_ms);
+    json.Number("calibratedDelayMs", metrics.calibrated_delay_ms);
+    json.Number("msSinceLastReference", metrics.ms_since_last_reference);
+    json.Bool("aecConverged", metrics.aec_converged);
+    json.Number("rmsLevel", metrics.rms_level);
+    json.Number("peakLevel", metrics.peak_level);
+    json.Number("inputRmsDbfs", metrics.input_rms_dbfs);
+    json.Number("inputPeakDbfs", metrics.input_peak_dbfs);
+    json.Number("outputRmsDbfs", metrics.output_rms_dbfs);
+    json.Number("outputPeakDbfs", metrics.output_peak_dbfs);
+    json.Number("inputPeakInstantDbfs", metrics.input_peak_instant_dbfs);
+    json.Number("inputPeakHoldDbfs", metrics.input_peak_hold_dbfs);
+    json.Number("outputPeakInstantDbfs", metrics.output_peak_instant_dbfs);
+    json.Number("outputPeakHoldDbfs", metrics.output_peak_hold_dbfs);
+    json.Number("loudnessGainDb", metrics.loudness_gain_db);
+    json.Number("agcGainDb", metrics.agc_gain_db);
+    json.Number("limiterGainReductionDb", metrics.limiter_gain_reduction_db);
+    json.Number("limiterMaxGainReductionDb", metrics.limiter_max_gain_reduction_db);
+    json.Integer("skippedSilentChunks", metrics.skipped_silent_chunks);
+    json.Integer("silenceBypassChunks", metrics.silence_bypass_chunks);
+    json.Number("deadInputMs", metrics.dead_input_ms);
+    json.Bool("deadInput", metrics.dead_input);
+    json.Number("noiseFloorDbfs", metrics.noise_floor_dbfs);
+    json.Number("erleInstantDb", metrics.erle_instant_db);
+    json.Number("erleAverageDb", metrics.erle_average_db);
+    json.IntegerArray("latencyHistogram", metrics.latency_histogram);
+    json.Number("latencyP50Ms", metrics.latency_p50_ms);
+    json.Number("latencyP95Ms", metrics.latency_p95_ms);
+    json.Number("latencyP99Ms", metrics.latency_p99_ms);
+    json.Number("latencyMaxMs", metrics.latency_max_ms);
+    json.Integer("latencyMaxChunkSamples", metrics.latency_max_chunk_samples);
+    json.Integer("underrunCount", metrics.underrun_count);
+    json.Integer("underrunSamples", metrics.underrun_samples);
+    json.Integer("underrunZeroFillCount", metrics.underrun_zero_fill_count);
+    json.Integer("underrunHoldCount", metrics.underrun_hold_count);
+    json.Integer("overflowCount", metrics.overflow_count);
+    json.Integer("overflowSamples", metrics.overflow_samples);
+    json.Number("refBufferFillRatio", metrics.ref_buffer_fill_ratio);
+    json.Number("referenceDriftSamples", static_cast<double>(metrics.reference_drift_samples));
+    json.Number("referenceDriftPerSecond", metrics.reference_drift_per_second);
+    json.Number("realTimeFactor", metrics.real_time_factor);
+    json.Number("realTimeFactorRecent", metrics.real_time_factor_recent);
+    json.Number("audioProcessedMs", metrics.audio_processed_ms);
+    json.Integer("inputSamplesProcessed", metrics.input_samples_processed);
+    json.Integer("outputSamplesProduced", metrics.output_samples_produced);
+    json.Integer("referenceSamplesConsumed", metrics.reference_samples_consumed);
+    json.Number("audioSecondsProcessed", metrics.audio_seconds_processed);
+    json.Bool("overloaded", metrics.overloaded);
+    json.Bool("overloadBypass", metrics.overload_bypass);
+    json.Integer("overloadEvents", metrics.overload_events);
+    json.Integer("pathChangesDetected", metrics.path_changes_detected);
+    json.Integer("adaptationThrottledChunks", metrics.adaptation_throttled_chunks);
+    json.Integer("idleResets", metrics.idle_resets);
+    json.Bool("suboptimalConfig", metrics.suboptimal_config);
+    json.Integer("emptyInputCalls", metrics.empty_input_calls);
+    json.String("convergenceState", metrics.convergence_state);
+    json.Number("timeSinceConvergedMs", metrics.time_since_converged_ms);
+    json.Integer("droppedInputEvents", metrics.dropped_input_events);
+    json.Integer("droppedInputSamples", metrics.dropped_input_samples);
+    json.Integer("scratchGrowCount", metrics.scratch_grow_count);
+    json.Bool("engineActive", metrics.engine_active);
+    json.String("initError", metrics.init_error);
+    return json.Finish();
+}
+
+// Preset bundles. "aggressive" is the long-standing Kakarot tuning (2x stronger
+// suppression than WebRTC defaults), "balanced" matches WebRTC's defaults, and
+// "gentle" favours near-end transparency over echo removal.
+// Delay, leakage and audibility tweaks are shared by every preset.
+bool ResolveTuning(const AECConfig& config, AECTuning* tuning) {
+    AECTuning resolved;
+    resolved.preset = config.preset;
+
+    if (config.preset == "gentle") {
+        resolved.filter_length_blocks = 13;
+        resolved.initial_filter_length_blocks = 12;
+        resolved.enr_transparent_lf = 0.4f;
+        resolved.enr_suppress_lf = 0.6f;
+        resolved.enr_transparent_hf = 0.1f;
+        resolved.enr_suppress_hf = 0.15f;
+        resolved.max_gain_during_echo = 1.0f;
+        resolved.noise_suppression_level = 0;
+    } else if (config.preset == "balanced") {
+        resolved.filter_length_blocks = 13;
+        resolved.initial_filter_length_blocks = 12;
+        resolved.enr_transparent_lf = 0.3f;
+        resolved.enr_suppress_lf = 0.4f;
+        resolved.enr_transparent_hf = 0.07f;
+        resolved.enr_suppress_hf = 0.1f;
+        resolved.max_gain_during_echo = 1.0f;
+        resolved.noise_suppression_level = 1;
+    } else if (config.preset != "aggressive") {
+        return false;  // AECTuning defaults are the aggressive bundle
+    }
+
+    if (config.filter_length_blocks > 0) {
+        resolved.filter_length_blocks = config.filter_length_blocks;
+        resolved.initial_filter_length_blocks =
+            std::min(resolved.initial_filter_length_blocks, config.filter_length_blocks);
+    }
+    if (config.noise_suppression_level >= 0) {
+        resolved.noise_suppression_level = config.noise_suppression_level;
+    }
+
+    *tuning = resolved;
+    return true;
+}
+
 class AECProcessor::Impl {
 public:
-    explicit Impl(const AECConfig& config) : config_(config) {}
+    explicit Impl(const AECConfig& config) : config_(config) {
+        config_.erle_history_length = std::min(config_.erle_history_length, kMaxErleHistoryLength);
+        erle_history_.SetCapacity(config_.erle_history_length);
+        UpdateActiveStages();
+    }
     
     ~Impl() {
         // scoped_refptr will automatically clean up
     }
-    
-    bool Initialize(int sample_rate, int num_channels) {
-        sample_rate_ = sample_rate;
-        num_channels_ = num_channels;
-        frame_size_ = (sample_rate * config_.frame_duration_ms) / 1000;
+    
+    bool Initialize(int sample_rate, int num_channels) {
+        UpdateActiveStages();
+        if (!IsSupportedSampleRate(config_, sample_rate)) {
+            Log(AECLogLevel::kError, "❌ Unsupported stream format: ", sample_rate, "Hz, ",
+                                     config_.frame_duration_ms, "ms frames (supported rates: ",
+                                     DescribeSupportedSampleRates(config_), ")");
+            init_error_ = "Unsupported stream format: " + std::to_string(sample_rate) + " Hz, " +
+                          std::to_string(config_.frame_duration_ms) + " ms frames";
+            return false;
+        }
+        // Validate everything that can fail before touching stream state, so
+        // a rejected config leaves the previous format intact
+        AECTuning tuning;
+        if (!ResolveTuning(config_, &tuning)) {
+            Log(AECLogLevel::kError, "❌ Unknown AEC preset: ", config_.preset);
+            init_error_ = "Unknown AEC preset: " + config_.preset;
+            return false;
+        }
+        if (!IsKnownEngine(config_.engine)) {
+            Log(AECLogLevel::kError, "❌ Unknown AEC engine: ", config_.engine);
+            init_error_ = "Unknown AEC engine: " + config_.engine;
+            return false;
+        }
+        passthrough_ = false;  // Every path from here leaves a working canceller
+        init_error_.clear();
+        tuning_ = tuning;
+
+        // Correlation windows: the mic window is searched against render up to
+        // kCorrelationMaxLagMs older, so render keeps the two spans combined
+        size_t decimated_rate = sample_rate / DecimatedHistory::kFactor;
+        if (debug_dump_ && sample_rate_ != 0 && sample_rate != sample_rate_) {
+            Log(AECLogLevel::kWarn, "⚠️ Sample rate changed, stopping AEC debug dump");
+            StopDebugDump();
+        }
+        if (debug_capture_seconds_ > 0.0f && sample_rate != sample_rate_) {
+            ResizeDebugCapture(sample_rate);  // Old-rate audio can't share a buffer with new
+        }
+        if (sample_rate != sample_rate_) {
+            capture_recent_.Clear();
+            render_recent_.Clear();
+            reference_drift_.Reset();  // Counts at the old rate aren't comparable
+        }
+        capture_recent_.ring.SetCapacity(decimated_rate * kCorrelationWindowMs / 1000);
+        render_recent_.ring.SetCapacity(decimated_rate * (kCorrelationWindowMs + kCorrelationMaxLagMs) / 1000);
+
+        sample_rate_ = sample_rate;
+        num_channels_ = num_channels;
+        frame_size_ = FrameSamples(sample_rate, config_.frame_duration_ms);
+        held_reference_.assign(frame_size_, 0.0f);
+        last_raw_input_.clear();
+        last_raw_input_.reserve(frame_size_);
+        apm_block_size_ = FrameSamples(sample_rate, kApmBlockMs);
+        asr_decimator_.Configure(sample_rate % AECProcessor::kAsrSampleRate == 0
+                                     ? sample_rate / AECProcessor::kAsrSampleRate : 0);
+
+        Log(AECLogLevel::kInfo, "🔧 Initializing AEC with frame_size=", frame_size_, " samples (",
+                                config_.frame_duration_ms, "ms at ", sample_rate, "Hz)");
+        if (!IsCanonicalFrameFormat(sample_rate, config_.frame_duration_ms)) {
+            Log(AECLogLevel::kDebug, "Frame format is valid but not one of the canonical "
+                                     "10/20/40 ms at 8/16/32/48 kHz sizes");
+        }
+
+        CheckFilterLength();
+
+        if (config_.engine == "nlms") {
+            // One tap per sample; filter_length_blocks counts 64-sample blocks
+            nlms_.Configure(static_cast<size_t>(tuning_.filter_length_blocks) * 64);
+            nlms_.step_size = NlmsFilter::kStepSize * config_.adaptation_rate;
+            nlms_reference_.assign(CalibratedDelaySamples(), 0.0f);  // Keeps a calibrated delay
+            audio_processing_ = nullptr;
+            frames_processed_ = 0;
+            Log(AECLogLevel::kInfo, "✅ NLMS echo canceller initialized with ", nlms_.taps.size(), " taps");
+            return true;
+        }
+        try {
+            // Create custom EchoCanceller3Config from the resolved preset
+            webrtc::EchoCanceller3Config aec3_config;
+            
+            // DELAY - allow up to 500ms of delay between speakers and mic
+            aec3_config.delay.default_delay = 5;
+            aec3_config.delay.down_sampling_factor = 4;
+            aec3_config.delay.num_filters = 5;
+            aec3_config.delay.delay_headroom_samples = 64;  // More headroom (was 32)
+            aec3_config.delay.hysteresis_limit_blocks = 2;  // More stable (was 1)
+            
+            // FILTER - Make adaptive filter longer and more aggressive
+            aec3_config.filter.refined.length_blocks = tuning_.filter_length_blocks;  // Default 13
+            aec3_config.filter.refined.leakage_converged = 0.00002f;  // Less leakage (was 0.00005)
+            aec3_config.filter.refined_initial.length_blocks = tuning_.initial_filter_length_blocks;  // Default 12
+            aec3_config.filter.refined_initial.leakage_converged = 0.0002f;  // Less leakage
+            aec3_config.filter.coarse.rate = std::min(1.0f, aec3_config.filter.coarse.rate * config_.adaptation_rate);
+            aec3_config.filter.coarse_initial.rate =
+                std::min(1.0f, aec3_config.filter.coarse_initial.rate * config_.adaptation_rate);
+            aec3_config.filter.config_change_duration_blocks = 100;  // Faster adaptation (was 250)
+            aec3_config.filter.initial_state_seconds = 1.5f;  // Faster startup (was 2.5)
+            aec3_config.filter.conservative_initial_phase = false;  // Aggressive from start
+            
+            // SUPPRESSOR - MUCH more aggressive echo suppression
+            aec3_config.suppressor.nearend_average_blocks = 4;
+            
+            // Normal tuning - more aggressive suppression
+            aec3_config.suppressor.normal_tuning.mask_lf.enr_transparent = tuning_.enr_transparent_lf;  // Default 0.3
+            aec3_config.suppressor.normal_tuning.mask_lf.enr_suppress = tuning_.enr_suppress_lf;  // Default 0.4
+            aec3_config.suppressor.normal_tuning.mask_hf.enr_transparent = tuning_.enr_transparent_hf;  // Default 0.07
+            aec3_config.suppressor.normal_tuning.mask_hf.enr_suppress = tuning_.enr_suppress_hf;  // Default 0.1
+            aec3_config.suppressor.normal_tuning.max_inc_factor = 1.5f;  // Slower gain increase (was 2.0)
+            aec3_config.suppressor.normal_tuning.max_dec_factor_lf = 0.1f;  // Faster gain decrease (was 0.25)
+            
+            // High bands suppression - crush echo in high frequencies
+            aec3_config.suppressor.high_bands_suppression.enr_threshold = 0.5f;  // More sensitive (was 1.0)
+            aec3_config.suppressor.high_bands_suppression.max_gain_during_echo = tuning_.max_gain_during_echo;  // Default 1.0
+            
+            // Floor first increase - allow quick suppression
+            aec3_config.suppressor.floor_first_increase = 0.000001f;  // Very small (was 0.00001)
+            
+            // Dominant nearend detection - detect voice more sensitively
+            aec3_config.suppressor.dominant_nearend_detection.enr_threshold = 0.15f;  // Lower threshold (was 0.25)
+            aec3_config.suppressor.dominant_nearend_detection.trigger_threshold = 8;  // Faster trigger (was 12)
+            
+            // Echo audibility - be more aggressive about detecting echo
+            aec3_config.echo_audibility.floor_power = 32.f;  // Lower floor (was 128)
+            aec3_config.echo_audibility.audibility_threshold_lf = 5.f;  // More sensitive (was 10)
+            aec3_config.echo_audibility.audibility_threshold_mf = 5.f;
+            aec3_config.echo_audibility.audibility_threshold_hf = 5.f;
+            
+            // Render levels - capture more echo
+            aec3_config.render_levels.active_render_limit = 50.f;  // Lower threshold (was 100)
+            
+            // EP strength - protect nearend speech
+            aec3_config.ep_strength.default_len = 0.95f;  // Strong protection (was 0.83)
+            
+            Log(AECLogLevel::kInfo, "✅ AEC3 config created with '", tuning_.preset, "' preset");
+            
+            // Create AudioProcessing::Config
+            webrtc::AudioProcessing::Config apm_config;
+            
+            // Configure echo cancellation
+            if (config_.enable_aec) {
+                apm_config.echo_canceller.enabled = true;
+                apm_config.echo_canceller.mobile_mode = false;
+                Log(AECLogLevel::kInfo, "✅ AEC3 enabled");
+            }
+            
+            // Configure noise suppression
+            if (config_.enable_ns) {
+                apm_config.noise_suppression.enabled = true;
+                apm_config.noise_suppression.level = 
+                    static_cast<webrtc::AudioProcessing::Config::NoiseSuppression::Level>(
+                        std::clamp(tuning_.noise_suppression_level, 0, 3));
+                Log(AECLogLevel::kInfo, "✅ Noise suppression enabled");
+            }
+            
+            // Configure AGC
+            if (config_.enable_agc) {
+                apm_config.gain_controller2.enabled = true;
+                apm_config.gain_controller2.adaptive_digital.enabled = true;
+                Log(AECLogLevel::kInfo, "✅ AGC enabled");
+            }
+            
+            // High-pass filter
+            apm_config.high_pass_filter.enabled = true;
+            
+            // Create Environment (required for Build)
+            webrtc::Environment env = webrtc::CreateEnvironment();
+            
+            // Build AudioProcessing instance with custom AEC3 config
+            webrtc::BuiltinAudioProcessingBuilder builder(apm_config);
+            
+            // Pass our custom AEC3 configuration using the correct method
+            if (config_.enable_aec) {
+                // Second parameter is for multichannel config (we use mono, so pass empty optional)
+                builder.SetEchoCancellerConfig(aec3_config, {});
+                Log(AECLogLevel::kInfo, "✅ Custom AEC3 config applied");
+            }
+            
+            audio_processing_ = builder.Build(env);
+            
+            if (!audio_processing_) {
+                Log(AECLogLevel::kError, "❌ Failed to create AudioProcessing, using fallback");
+                return true;  // Continue with naive fallback
+            }
+            
+            // Initialize frame buffers
+            render_buffer_.reserve(frame_size_ * 2);  // Buffer for render audio
+            capture_buffer_.reserve(frame_size_ * 2);  // Buffer for capture audio
+            render_frame_.reserve(frame_size_);
+            capture_frame_.reserve(frame_size_);
+            render_history_.resize(apm_block_size_ * 10);  // 100ms history for fallback
+            
+            frames_processed_ = 0;
+            
+            Log(AECLogLevel::kInfo, "✅ WebRTC AEC3 initialized successfully with frame buffering");
+            return true;
+            
+        } catch (const std::exception& e) {
+            Log(AECLogLevel::kError, "❌ Error initializing AEC: ", e.what());
+            audio_processing_ = nullptr;
+            return true;  // Continue with fallback
+        }
+    }
+
+    void InitializePassthrough(int sample_rate, const std::string& error) {
+        passthrough_ = true;
+        passthrough_warned_ = false;
+        if (!error.empty()) init_error_ = error;
+        sample_rate_ = sample_rate;   // Only used for metering here
+        num_channels_ = 1;
+        frame_size_ = FrameSamples(sample_rate, config_.frame_duration_ms);
+        size_t decimated_rate = sample_rate / DecimatedHistory::kFactor;
+        capture_recent_.ring.SetCapacity(decimated_rate * kCorrelationWindowMs / 1000);
+        render_recent_.ring.SetCapacity(decimated_rate * (kCorrelationWindowMs + kCorrelationMaxLagMs) / 1000);
+        Log(AECLogLevel::kError, "❌ AEC engine unavailable, passing audio through: ", init_error_);
+    }
+
+    void ProcessRenderAudio(const float* data, size_t num_samples) {
+        if (config_.reference_gain != 1.0f) {
+            scaled_render_.resize(num_samples);
+            for (size_t i = 0; i < num_samples; i++) {
+                scaled_render_[i] = std::clamp(data[i] * config_.reference_gain, -1.0f, 1.0f);
+            }
+            data = scaled_render_.data();
+        }
+        render_levels_ = MeasureChunk(data, num_samples);
+        health_.UpdateRender(ScanChunk(data, num_samples), ChunkMs(num_samples));
+        render_recent_.Push(data, num_samples);
+        if (AligningDumpReference()) {
+            QueueDumpReference(data, num_samples);
+        }
+        size_t drained = TrackRenderBalance(num_samples);
+
+        if (!config_.enable_aec || overload_bypass_ || passthrough_) return;
+        
+        if (config_.engine == "nlms") {
+            // Queue for sample-aligned consumption by the next capture chunks. An
+            // overrun drops what the balance drained; the one-second cap bounds a
+            // queue padded by a calibrated delay.
+            nlms_reference_.insert(nlms_reference_.end(), data, data + num_samples);
+            nlms_reference_.erase(nlms_reference_.begin(),
+                                  nlms_reference_.begin() + std::min(drained, nlms_reference_.size()));
+            size_t max_queued = static_cast<size_t>(sample_rate_);
+            if (nlms_reference_.size() > max_queued) {
+                nlms_reference_.erase(nlms_reference_.begin(),
+                                      nlms_reference_.begin() + (nlms_reference_.size() - max_queued));
+            }
+            return;
+        }
+        
+        // Store for fallback
+        std::copy(data, data + std::min(num_samples, render_history_.size()), 
+                  render_history_.begin());
+        
+        if (!audio_processing_) return;
+        
+        // The capture path hands AEC3 the queued frame paired with each capture
+        // frame (PushRenderFrame), so capture chunks the engine never sees can
+        // drop their share. Only the newest few frames wait there: older render
+        // goes to AEC3 as it arrives, so a feed that runs ahead of capture never
+        // leaves the engine cancelling against stale reference.
+        render_buffer_.insert(render_buffer_.end(), data, data + num_samples);
+        while (render_buffer_.size() > frame_size_ * kMaxQueuedRenderFrames) {
+            PushRenderFrame();
+        }
+    }
+    
+    // Feed AEC3 the oldest queued render frame; if the reference is late the
+    // capture frame runs without one, or under "hold" against the last frame again
+    void PushRenderFrame() {
+        bool starved = render_buffer_.size() < frame_size_;
+        if (starved && !HoldUnderruns()) return;
+        try {
+            // Create render stream config
+            webrtc::StreamConfig stream_config(sample_rate_, num_channels_);
+            
+            // Extract one frame into reused scratch (no per-frame allocation)
+            if (starved) {
+                render_frame_.assign(held_reference_.begin(), held_reference_.end());
+            } else {
+                render_frame_.assign(render_buffer_.begin(), render_buffer_.begin() + frame_size_);
+                HoldReference(render_frame_.data(), frame_size_);
+            }
+            
+            // WebRTC needs non-const pointers; it takes one 10 ms block per call
+            for (size_t block = 0; block < frame_size_; block += apm_block_size_) {
+                float* output_ptr = render_frame_.data() + block;
+                const float* input_ptr = output_ptr;
+                audio_processing_->ProcessReverseStream(
+                    &input_ptr, stream_config, stream_config, &output_ptr);
+            }
+            
+            // Remove processed frame from buffer
+            if (!starved) {
+                render_buffer_.erase(render_buffer_.begin(), render_buffer_.begin() + frame_size_);
+            }
+            
+        } catch (const std::exception& e) {
+            Log(AECLogLevel::kError, "❌ ProcessReverseStream error: ", e.what());
+            // Clear buffer on error to prevent backup
+            render_buffer_.clear();
+        }
+    }
+    
+    // Mean square of the reference this capture chunk pairs with: the front of
+    // the engine's queue. Less than a chunk queued means the reference comes
+    // after capture, or the naive path, which uses the newest render chunk.
+    float PairedReferenceMeanSquare(size_t num_samples) const {
+        const std::vector<float>& queue = config_.engine == "nlms" ? nlms_reference_ : render_buffer_;
+        if (queue.size() < num_samples) {
+            return render_levels_.mean_square;
+        }
+        return MeasureChunk(queue.data(), num_samples).mean_square;
+    }
+
+    // Discard the queued reference for capture samples the engine never sees
+    // (skipped, bypassed or dropped), so the next chunk lines up. Returns the
+    // number of reference samples discarded.
+    size_t DropQueuedReference(size_t num_samples) {
+        std::vector<float>& queue = config_.engine == "nlms" ? nlms_reference_ : render_buffer_;
+        size_t drop = std::min(num_samples, queue.size());
+        queue.erase(queue.begin(), queue.begin() + drop);
+        return drop;
+    }
+
+    void ProcessCaptureAudio(const float* input, float* output, size_t num_samples,
+                             const AECCallOptions& options = AECCallOptions()) {
+        if (num_samples == 0) {
+            // Nothing to process; counted so capture stalls show up in metrics
+            empty_input_calls_++;
+            Log(AECLogLevel::kDebug, "Empty capture buffer (", empty_input_calls_, " so far)");
+            return;
+        }
+        auto start = std::chrono::steady_clock::now();
+        // A frame-sized copy into reserved space; the accessor does the allocating
+        size_t raw_keep = std::min(num_samples, frame_size_ > 0 ? frame_size_ : num_samples);
+        last_raw_input_.assign(input + num_samples - raw_keep, input + num_samples);
+        if (!input_gain_.Idle(config_.input_gain_db)) {
+            input_gain_scratch_.resize(num_samples);  // input may alias output
+            input_gain_.Process(input, input_gain_scratch_.data(), num_samples, config_.input_gain_db, sample_rate_);
+            input = input_gain_scratch_.data();
+        }
+        capture_recent_.Push(input, num_samples);
+        TrackCaptureBalance(num_samples);
+        if (AligningDumpReference()) {
+            dump_mic_.assign(input, input + num_samples);  // input may alias output
+        }
+        ChunkRange input_range = ScanChunk(input, num_samples);  // Before output can overwrite it
+        float input_mean_square = MeasureChunk(input, num_samples).mean_square;
+        if (overload_bypass_ || options.bypass) {
+            // Metered like a processed chunk, which also fills last_call_stats_:
+            // overload is when the app most needs levels and ERLE
+            ChunkLevels input_levels = MeterInput(input, num_samples);
+            if (output != input) {
+                std::copy(input, input + num_samples, output);
+            }
+            CalculateMetrics(input_levels, output, num_samples, false);
+            if (options.bypass) {
+                // This chunk's queued reference, for either engine, would
+                // misalign the next one. Overload bypass never queues any.
+                DropQueuedReference(num_samples);
+            }
+        } else {
+            ProcessCaptureChunk(input, output, num_samples);
+        }
+        float stage_gain_db = options.override_output_gain ? options.output_gain_db : config_.output_gain_db;
+        if (!output_gain_.Idle(stage_gain_db)) {
+            output_gain_.Process(output, output, num_samples, stage_gain_db, sample_rate_);
+        }
+        if (path_change_pending_) {
+            HandlePathChange();
+        }
+        std::chrono::duration<double, std::milli> elapsed = std::chrono::steady_clock::now() - start;
+        double elapsed_ms = deterministic_ ? deterministic_elapsed_ms_ : elapsed.count();
+        latency_.Record(elapsed_ms, num_samples);
+        rtf_.Record(elapsed_ms, ChunkMs(num_samples));
+        TrackOverload();
+        float output_gain_db = (config_.enable_loudness_normalization ? loudness_.gain_db : 0.0f) +
+                               (config_.enable_output_agc ? output_agc_.gain_db : 0.0f) +
+                               stage_gain_db;
+        health_.UpdateCapture(input_range, ScanChunk(output, num_samples), input_mean_square,
+                              MeasureChunk(output, num_samples).mean_square, output_gain_db, ChunkMs(num_samples));
+        last_call_stats_.elapsed_us = static_cast<float>(elapsed_ms * 1000.0);
+        TrackRealtimeBudget(elapsed_ms, num_samples);
+        if (AligningDumpReference()) {
+            WriteDumpChunk(output, num_samples);
+        }
+        TrackScratchGrowth();
+    }
+
+    // Feed mic/reference pairs one frame at a time, render first, so the
+    // filter sees the same alignment as live audio; output is discarded
+    size_t Warmup(const float* mic, const float* reference, size_t num_samples) {
+        size_t step = frame_size_ > 0 ? frame_size_ : num_samples;
+        warmup_scratch_.resize(step);
+        size_t frames = 0;
+        for (size_t offset = 0; offset < num_samples; offset += step) {
+            size_t n = std::min(step, num_samples - offset);
+            ProcessRenderAudio(reference + offset, n);
+            ProcessCaptureAudio(mic + offset, warmup_scratch_.data(), n);
+            frames++;
+        }
+        Log(AECLogLevel::kDebug, "Warmup ran ", frames, " frames (", num_samples, " samples)");
+        return frames;
+    }
+
+    // Everything the capture and render paths resize or insert into per call.
+    // Debug dumps and capture rings still allocate when they are switched on.
+    void EnsureCapacity(size_t max_samples) {
+        render_buffer_.reserve(frame_size_ * kMaxQueuedRenderFrames + max_samples);
+        capture_buffer_.reserve(frame_size_ + max_samples);
+        render_frame_.reserve(frame_size_);
+        capture_frame_.reserve(frame_size_);
+        scaled_render_.reserve(max_samples);
+        loopback_scratch_.reserve(max_samples);
+        warmup_scratch_.reserve(frame_size_ > 0 ? frame_size_ : max_samples);
+        pre_emphasis_.scratch.reserve(max_samples);
+        input_gain_scratch_.reserve(max_samples);
+        last_raw_input_.reserve(frame_size_ > 0 ? frame_size_ : max_samples);
+        nlms_reference_.reserve(static_cast<size_t>(sample_rate_) + max_samples);  // One-second cap plus a chunk
+        asr_decimator_.history.reserve(asr_decimator_.taps.size() + max_samples);
+        scratch_capacity_ = ScratchCapacity();
+    }
+
+    // Combined capacity of the buffers EnsureCapacity reserves
+    size_t ScratchCapacity() const {
+        return render_buffer_.capacity() + capture_buffer_.capacity() + render_frame_.capacity() +
+               capture_frame_.capacity() + scaled_render_.capacity() + loopback_scratch_.capacity() +
+               warmup_scratch_.capacity() + pre_emphasis_.scratch.capacity() + input_gain_scratch_.capacity() +
+               last_raw_input_.capacity() + nlms_reference_.capacity() + asr_decimator_.history.capacity();
+    }
+
+    // Run after each capture call, so render fed since the last one is covered
+    // too. The first call and EnsureCapacity only set the baseline.
+    void TrackScratchGrowth() {
+        size_t capacity = ScratchCapacity();
+        if (scratch_capacity_ > 0 && capacity > scratch_capacity_) {
+            scratch_grow_count_++;
+        }
+        scratch_capacity_ = capacity;
+    }
+
+    // Equivalent to ProcessCaptureAudio then ProcessRenderAudio of the scaled
+    // output; input may alias output
+    void ProcessAndFeed(const float* input, float* output, size_t num_samples, float gain) {
+        ProcessCaptureAudio(input, output, num_samples);
+        loopback_scratch_.resize(num_samples);
+        for (size_t i = 0; i < num_samples; i++) {
+            loopback_scratch_[i] = output[i] * gain;
+        }
+        ProcessRenderAudio(loopback_scratch_.data(), num_samples);
+    }
+
+    bool ProcessWithAsrTap(const float* input, float* output, size_t num_samples, std::vector<float>* asr) {
+        if (asr_decimator_.factor < 1) {
+            return false;
+        }
+        ProcessCaptureAudio(input, output, num_samples);
+        asr_decimator_.Process(output, num_samples, asr);
+        return true;
+    }
+
+    // The matching render audio will never see its capture chunk; drop it so
+    // the next chunk lines up
+    void NotifyDroppedInput(size_t num_samples) {
+        dropped_input_events_++;
+        dropped_input_samples_ += num_samples;
+        reference_drift_.Consume(num_samples, sample_rate_);  // Its reference is consumed below
+        pending_render_samples_ -= std::min(num_samples, pending_render_samples_);
+        size_t reference_drop = DropQueuedReference(num_samples);
+        size_t dump_drop = std::min(num_samples, dump_reference_.size());
+        dump_reference_.erase(dump_reference_.begin(), dump_reference_.begin() + dump_drop);
+        Log(AECLogLevel::kDebug, "Caller dropped ", num_samples, " capture samples, discarded ",
+            reference_drop, " queued reference samples");
+    }
+
+    // Flag a filter shorter than one frame. AEC3 adapts on 64-sample blocks of
+    // its 16 kHz band (4 ms each); NLMS taps run at the stream rate.
+    void CheckFilterLength() {
+        float block_ms = config_.engine == "nlms" ? 64000.0f / sample_rate_ : 4.0f;
+        float filter_ms = tuning_.filter_length_blocks * block_ms;
+        suboptimal_config_ = filter_ms < config_.frame_duration_ms;
+        if (suboptimal_config_) {
+            int suggested = std::min(kMaxFilterLengthBlocks,
+                                     static_cast<int>(std::ceil(config_.frame_duration_ms / block_ms)));
+            Log(AECLogLevel::kWarn, "⚠️ Filter length of ", tuning_.filter_length_blocks, " blocks (",
+                filter_ms, " ms) is shorter than one ", config_.frame_duration_ms,
+                " ms frame; consider filterLengthBlocks ", suggested, " or more");
+        }
+    }
+
+    // Input-side meters, fed whether or not the canceller runs on the chunk
+    ChunkLevels MeterInput(const float* input, size_t num_samples) {
+        ChunkLevels input_levels = MeasureChunk(input, num_samples);
+        input_meter_.Update(input_levels, LevelAlpha(num_samples));
+        input_peak_hold_.Update(input_levels.peak, ChunkMs(num_samples),
+                                config_.peak_hold_ms, config_.peak_decay_db_per_sec);
+        TrackDeadInput(input_levels, num_samples);
+        noise_floor_.Update(input_levels.mean_square, ChunkMs(num_samples));
+        return input_levels;
+    }
+
+    // Untimed body of ProcessCaptureAudio
+    void ProcessCaptureChunk(const float* input, float* output, size_t num_samples) {
+        ChunkLevels input_levels = MeterInput(input, num_samples);
+        if (passthrough_) {
+            if (!passthrough_warned_) {
+                passthrough_warned_ = true;  // Once, so it reaches a logger set after creation
+                Log(AECLogLevel::kWarn, "⚠️ AEC engine failed to initialize (", init_error_,
+                    "); audio is passed through without echo cancellation");
+            }
+            if (output != input) {
+                std::copy(input, input + num_samples, output);
+            }
+            CalculateMetrics(input_levels, output, num_samples, false);
+            return;
+        }
+        if (config_.pre_emphasis > 0.0f) {
+            // Meters above saw the raw mic; ApplyOutputStages undoes this
+            input = pre_emphasis_.Emphasize(input, num_samples, config_.pre_emphasis);
+        }
+
+        // Silent mic and silent reference: nothing to cancel, so skip the
+        // engine. Only skip on a frame boundary (no partial AEC3 frame pending)
+        // to keep framing aligned, and drop the chunk's queued reference so the
+        // engine sees neither side.
+        if (config_.skip_silent_chunks && capture_buffer_.empty()) {
+            float floor = std::pow(10.0f, config_.silent_chunk_floor_db / 10.0f);
+            if (input_levels.mean_square < floor && PairedReferenceMeanSquare(num_samples) < floor) {
+                DropQueuedReference(num_samples);
+                std::copy(input, input + num_samples, output);
+                skipped_silent_chunks_++;
+                ApplyOutputStages(output, num_samples);
+                Log(AECLogLevel::kTrace, "Skipped silent chunk of ", num_samples, " samples");
+                CalculateMetrics(input_levels, output, num_samples);
+                return;
+            }
+        }
+        
+        // Silent reference alone: no echo to cancel, and the canceller and NS
+        // would only color the mic. Same frame-boundary rule and reference
+        // drop; the output stages still run.
+        if (config_.enable_silence_bypass && capture_buffer_.empty()) {
+            float threshold = std::pow(10.0f, config_.silence_bypass_threshold_db / 10.0f);
+            if (PairedReferenceMeanSquare(num_samples) < threshold) {
+                DropQueuedReference(num_samples);
+                std::copy(input, input + num_samples, output);
+                silence_bypass_chunks_++;
+                ApplyOutputStages(output, num_samples);
+                CalculateMetrics(input_levels, output, num_samples);
+                return;
+            }
+        }
+
+        if (config_.engine == "nlms" && config_.enable_aec) {
+            if (config_.double_talk_sensitivity <= 0.0f && nlms_.throttle_hold == 0) {
+                ProcessNlmsPlain(input, output, num_samples);
+            } else {
+                ProcessNlms(input, output, num_samples);
+            }
+            if (config_.enable_ns) {
+                ApplyNoiseSuppression(output, num_samples);
+            }
+            ApplyOutputStages(output, num_samples);
+            CalculateMetrics(input_levels, output, num_samples);
+            return;
+        }
+
+        if (!audio_processing_ || !config_.enable_aec) {
+            // Fallback to improved naive algorithm
+            ProcessNaive(input, output, num_samples);
+            ApplyOutputStages(output, num_samples);
+            CalculateMetrics(input_levels, output, num_samples);
+            return;
+        }
+        
+        // Add input to capture buffer
+        capture_buffer_.insert(capture_buffer_.end(), input, input + num_samples);
+        
+        // Process complete frames and accumulate output
+        size_t output_written = 0;
+        
+        while (capture_buffer_.size() >= frame_size_ && output_written < num_samples) {
+            try {
+                // Create capture stream config
+                webrtc::StreamConfig stream_config(sample_rate_, num_channels_);
+                
+                // Extract one frame into reused scratch (no per-frame allocation)
+                capture_frame_.assign(capture_buffer_.begin(), capture_buffer_.begin() + frame_size_);
+                PushRenderFrame();
+                
+                // WebRTC processes in-place, one 10 ms block per call
+                int result = 0;
+                for (size_t block = 0; block < frame_size_ && result == 0; block += apm_block_size_) {
+                    float* frame_ptr = capture_frame_.data() + block;
+                    const float* input_ptr = frame_ptr;
+                    result = audio_processing_->ProcessStream(
+                        &input_ptr, stream_config, stream_config, &frame_ptr);
+                }
+                
+                if (result != 0) {
+                    Log(AECLogLevel::kError, "❌ ProcessStream returned error: ", result);
+                    // Copy unprocessed frame to output
+                    size_t to_copy = std::min(frame_size_, num_samples - output_written);
+                    std::copy(capture_frame_.begin(), capture_frame_.begin() + to_copy, output + output_written);
+                } else {
+                    // Copy processed frame to output
+                    size_t to_copy = std::min(frame_size_, num_samples - output_written);
+                    std::copy(capture_frame_.begin(), capture_frame_.begin() + to_copy, output + output_written);
+                    
+                    // Log occasionally
+                    frames_processed_++;
+                    if (frames_processed_ % 1000 == 0) {
+                        Log(AECLogLevel::kInfo, "✅ Processed ", frames_processed_,
+                                                " frames through WebRTC AEC3");
+                    }
+                }
+                
+                output_written += frame_size_;
+                
+                // Remove processed frame from buffer
+                capture_buffer_.erase(capture_buffer_.begin(), capture_buffer_.begin() + frame_size_);
+                
+            } catch (const std::exception& e) {
+                Log(AECLogLevel::kError, "❌ ProcessCaptureAudio error: ", e.what());
+                // Fall back to naive for this buffer
+                ProcessNaive(input, output, num_samples);
+                capture_buffer_.clear();
+                break;
+            }
+        }
+        
+        // If we couldn't process all samples (buffer not full yet), copy remaining input
+        if (output_written < num_samples) {
+            size_t remaining = num_samples - output_written;
+            std::copy(input + output_written, input + num_samples, output + output_written);
+        }
+        
+        ApplyOutputStages(output, num_samples);
+        CalculateMetrics(input_levels, output, num_samples);
+    }
 
-        std::cout << "🔧 Initializing AEC with frame_size=" << frame_size_ << " samples (" 
-                  << config_.frame_duration_ms << "ms at " << sample_rate << "Hz)\n";
+    void SetEchoCancellationEnabled(bool enabled) {
+        config_.enable_aec = enabled;
+        
+        if (audio_processing_) {
+            try {
+                auto apm_config = audio_processing_->GetConfig();
+                apm_config.echo_canceller.enabled = enabled;
+                audio_processing_->ApplyConfig(apm_config);
+                Log(AECLogLevel::kInfo, (enabled ? "✅ AEC enabled" : "⚠️ AEC disabled"));
+            } catch (const std::exception& e) {
+                Log(AECLogLevel::kError, "❌ Error setting AEC enabled: ", e.what());
+            }
+        }
+    }
 
-        try {
-            // Create custom EchoCanceller3Config for aggressive echo suppression
-            webrtc::EchoCanceller3Config aec3_config;
-            
-            // DELAY - allow up to 500ms of delay between speakers and mic
-            aec3_config.delay.default_delay = 5;
-            aec3_config.delay.down_sampling_factor = 4;
-            aec3_config.delay.num_filters = 5;
-            aec3_config.delay.delay_headroom_samples = 64;  // More headroom (was 32)
-            aec3_config.delay.hysteresis_limit_blocks = 2;  // More stable (was 1)
-            
-            // FILTER - Make adaptive filter longer and more aggressive
-            aec3_config.filter.refined.length_blocks = 24;  // Longer filter (default 13)
-            aec3_config.filter.refined.leakage_converged = 0.00002f;  // Less leakage (was 0.00005)
-            aec3_config.filter.refined_initial.length_blocks = 18;  // Longer initial (default 12)
-            aec3_config.filter.refined_initial.leakage_converged = 0.0002f;  // Less leakage
-            aec3_config.filter.config_change_duration_blocks = 100;  // Faster adaptation (was 250)
-            aec3_config.filter.initial_state_seconds = 1.5f;  // Faster startup (was 2.5)
-            aec3_config.filter.conservative_initial_phase = false;  // Aggressive from start
-            
-            // SUPPRESSOR - MUCH more aggressive echo suppression
-            aec3_config.suppressor.nearend_average_blocks = 4;
-            
-            // Normal tuning - more aggressive suppression
-            aec3_config.suppressor.normal_tuning.mask_lf.enr_transparent = 0.15f;  // Start suppressing earlier (was 0.3)
-            aec3_config.suppressor.normal_tuning.mask_lf.enr_suppress = 0.2f;  // Suppress harder (was 0.4)
-            aec3_config.suppressor.normal_tuning.mask_hf.enr_transparent = 0.04f;  // Very aggressive HF (was 0.07)
-            aec3_config.suppressor.normal_tuning.mask_hf.enr_suppress = 0.05f;  // Very aggressive HF (was 0.1)
-            aec3_config.suppressor.normal_tuning.max_inc_factor = 1.5f;  // Slower gain increase (was 2.0)
-            aec3_config.suppressor.normal_tuning.max_dec_factor_lf = 0.1f;  // Faster gain decrease (was 0.25)
-            
-            // High bands suppression - crush echo in high frequencies
-            aec3_config.suppressor.high_bands_suppression.enr_threshold = 0.5f;  // More sensitive (was 1.0)
-            aec3_config.suppressor.high_bands_suppression.max_gain_during_echo = 0.01f;  // Near silence during echo (was 1.0)
-            
-            // Floor first increase - allow quick suppression
-            aec3_config.suppressor.floor_first_increase = 0.000001f;  // Very small (was 0.00001)
-            
-            // Dominant nearend detection - detect voice more sensitively
-            aec3_config.suppressor.dominant_nearend_detection.enr_threshold = 0.15f;  // Lower threshold (was 0.25)
-            aec3_config.suppressor.dominant_nearend_detection.trigger_threshold = 8;  // Faster trigger (was 12)
-            
-            // Echo audibility - be more aggressive about detecting echo
-            aec3_config.echo_audibility.floor_power = 32.f;  // Lower floor (was 128)
-            aec3_config.echo_audibility.audibility_threshold_lf = 5.f;  // More sensitive (was 10)
-            aec3_config.echo_audibility.audibility_threshold_mf = 5.f;
-            aec3_config.echo_audibility.audibility_threshold_hf = 5.f;
-            
-            // Render levels - capture more echo
-            aec3_config.render_levels.active_render_limit = 50.f;  // Lower threshold (was 100)
-            
-            // EP strength - protect nearend speech
-            aec3_config.ep_strength.default_len = 0.95f;  // Strong protection (was 0.83)
-            
-            std::cout << "✅ Aggressive AEC3 config created (2x stronger suppression)\n";
-            
-            // Create AudioProcessing::Config
-            webrtc::AudioProcessing::Config apm_config;
-            
-            // Configure echo cancellation
-            if (config_.enable_aec) {
-                apm_config.echo_canceller.enabled = true;
-                apm_config.echo_canceller.mobile_mode = false;
-                std::cout << "✅ AEC3 enabled with aggressive settings\n";
+    // Runtime noise suppression control; level is 0=low .. 3=veryHigh
+    void SetNoiseSuppression(bool enabled, int level) {
+        config_.enable_ns = enabled;
+        config_.noise_suppression_level = std::clamp(level, 0, 3);
+        UpdateActiveStages();
+        tuning_.noise_suppression_level = config_.noise_suppression_level;
+        
+        if (audio_processing_) {
+            try {
+                auto apm_config = audio_processing_->GetConfig();
+                apm_config.noise_suppression.enabled = enabled;
+                apm_config.noise_suppression.level =
+                    static_cast<webrtc::AudioProcessing::Config::NoiseSuppression::Level>(
+                        config_.noise_suppression_level);
+                audio_processing_->ApplyConfig(apm_config);
+                Log(AECLogLevel::kInfo, (enabled ? "✅ Noise suppression enabled" : "⚠️ Noise suppression disabled"));
+            } catch (const std::exception& e) {
+                Log(AECLogLevel::kError, "❌ Error setting noise suppression: ", e.what());
             }
-            
-            // Configure noise suppression
-            if (config_.enable_ns) {
-                apm_config.noise_suppression.enabled = true;
-                apm_config.noise_suppression.level = 
-                    webrtc::AudioProcessing::Config::NoiseSuppression::kModerate;
-                std::cout << "✅ Noise suppression enabled\n";
+        }
+    }
+
+    AECMetrics GetMetrics() const {
+        AECMetrics metrics;
+        metrics.calibrated_delay_ms = calibrated_delay_ms_;
+        metrics.rms_level = current_rms_;
+        metrics.peak_level = current_peak_;
+        metrics.input_rms_dbfs = input_meter_.RmsDbfs();
+        metrics.input_peak_dbfs = input_meter_.PeakDbfs();
+        metrics.output_rms_dbfs = output_meter_.RmsDbfs();
+        metrics.output_peak_dbfs = output_meter_.PeakDbfs();
+        metrics.input_peak_instant_dbfs = ToDbfs(input_peak_hold_.instantaneous);
+        metrics.input_peak_hold_dbfs = ToDbfs(input_peak_hold_.held);
+        metrics.output_peak_instant_dbfs = ToDbfs(output_peak_hold_.instantaneous);
+        metrics.output_peak_hold_dbfs = ToDbfs(output_peak_hold_.held);
+        metrics.loudness_gain_db = config_.enable_loudness_normalization ? loudness_.gain_db : 0.0f;
+        metrics.agc_gain_db = config_.enable_output_agc ? output_agc_.gain_db : 0.0f;
+        metrics.limiter_gain_reduction_db = config_.enable_limiter ? limiter_.gain_reduction_db : 0.0f;
+        metrics.limiter_max_gain_reduction_db = limiter_.max_gain_reduction_db;
+        metrics.skipped_silent_chunks = skipped_silent_chunks_;
+        metrics.silence_bypass_chunks = silence_bypass_chunks_;
+        metrics.dead_input_ms = dead_input_ms_;
+        metrics.dead_input = dead_input_warned_;
+        metrics.noise_floor_dbfs = noise_floor_.Dbfs();
+        metrics.latency_histogram = latency_.counts;
+        metrics.latency_p50_ms = static_cast<float>(latency_.PercentileMs(0.50));
+        metrics.latency_p95_ms = static_cast<float>(latency_.PercentileMs(0.95));
+        metrics.latency_p99_ms = static_cast<float>(latency_.PercentileMs(0.99));
+        metrics.latency_max_ms = static_cast<float>(latency_.max_ms);
+        metrics.latency_max_chunk_samples = static_cast<uint32_t>(latency_.max_chunk_samples);
+        metrics.underrun_count = underrun_count_;
+        metrics.underrun_samples = underrun_samples_;
+        metrics.underrun_zero_fill_count = underrun_zero_fill_count_;
+        metrics.underrun_hold_count = underrun_hold_count_;
+        metrics.overflow_count = overflow_count_;
+        metrics.overflow_samples = overflow_samples_;
+        metrics.ref_buffer_fill_ratio = sample_rate_ > 0
+            ? static_cast<float>(pending_render_samples_) / static_cast<float>(sample_rate_) : 0.0f;
+        metrics.reference_drift_samples = reference_drift_.Samples();
+        metrics.reference_drift_per_second = reference_drift_.PerSecond(sample_rate_);
+        metrics.real_time_factor = static_cast<float>(rtf_.Overall());
+        metrics.real_time_factor_recent = static_cast<float>(rtf_.Recent());
+        metrics.audio_processed_ms = rtf_.total_audio_ms;
+        metrics.input_samples_processed = input_samples_processed_;
+        metrics.output_samples_produced = output_samples_produced_;
+        metrics.reference_samples_consumed = reference_samples_consumed_;
+        metrics.audio_seconds_processed = audio_seconds_processed_;
+        metrics.overloaded = overloaded_;
+        metrics.overload_bypass = overload_bypass_;
+        metrics.overload_events = overload_events_;
+        metrics.path_changes_detected = path_changes_detected_;
+        metrics.adaptation_throttled_chunks = adaptation_throttled_chunks_;
+        metrics.idle_resets = idle_resets_;
+        metrics.suboptimal_config = suboptimal_config_;
+        metrics.empty_input_calls = empty_input_calls_;
+        metrics.convergence_state = ConvergenceState();
+        metrics.time_since_converged_ms = converged_ ? converged_for_ms_ : -1.0f;
+        metrics.dropped_input_events = dropped_input_events_;
+        metrics.dropped_input_samples = dropped_input_samples_;
+        metrics.scratch_grow_count = scratch_grow_count_;
+        metrics.engine_active = !passthrough_;
+        metrics.init_error = init_error_;
+        
+        metrics.erle_instant_db = erle_.InstantDb();
+        metrics.erle_average_db = erle_.AverageDb();
+        metrics.echo_return_loss_enhancement = metrics.erle_instant_db;
+        
+        if (config_.engine == "nlms" && config_.enable_aec) {
+            metrics.engine = "nlms";
+            metrics.aec_converged = metrics.erle_instant_db >= kConvergedErleDb;
+        } else if (audio_processing_ && config_.enable_aec) {
+            metrics.engine = "webrtc";
+            // Prefer AEC3's own estimates; they are unset until it has seen echo
+            webrtc::AudioProcessingStats stats = audio_processing_->GetStatistics();
+            if (stats.echo_return_loss) {
+                metrics.echo_return_loss = static_cast<float>(*stats.echo_return_loss);
             }
-            
-            // Configure AGC
-            if (config_.enable_agc) {
-                apm_config.gain_controller2.enabled = true;
-                apm_config.gain_controller2.adaptive_digital.enabled = true;
-                std::cout << "✅ AGC enabled\n";
+            if (stats.echo_return_loss_enhancement) {
+                metrics.echo_return_loss_enhancement = static_cast<float>(*stats.echo_return_loss_enhancement);
             }
-            
-            // High-pass filter
-            apm_config.high_pass_filter.enabled = true;
-            
-            // Create Environment (required for Build)
-            webrtc::Environment env = webrtc::CreateEnvironment();
-            
-            // Build AudioProcessing instance with custom AEC3 config
-            webrtc::BuiltinAudioProcessingBuilder builder(apm_config);
-            
-            // Pass our custom AEC3 configuration using the correct method
-            if (config_.enable_aec) {
-                // Second parameter is for multichannel config (we use mono, so pass empty optional)
-                builder.SetEchoCancellerConfig(aec3_config, {});
-                std::cout << "✅ Custom aggressive AEC3 config applied\n";
+            if (stats.delay_ms) {
+                metrics.render_delay_ms = *stats.delay_ms;
+            }
+            metrics.aec_converged = metrics.echo_return_loss_enhancement >= kConvergedErleDb;
+        } else {
+            // Using fallback
+            metrics.engine = "naive";
+            metrics.aec_converged = false;
+        }
+        
+        return metrics;
+    }
+
+    // Swap the suppression preset at runtime. Rebuilds the AudioProcessing
+    // instance, so the echo canceller re-converges afterwards.
+    bool ApplyPreset(const std::string& name) {
+        AECTuning tuning;
+        AECConfig candidate = config_;
+        candidate.preset = name;
+        if (!ResolveTuning(candidate, &tuning)) {
+            return false;
+        }
+        config_ = candidate;
+        return Initialize(sample_rate_, num_channels_);
+    }
+
+    // Rebuilds the canceller with a new filter length. Called between chunks, so
+    // no frame is split; metrics and every other setting carry over.
+    bool SetFilterLength(int blocks) {
+        if (blocks < kMinFilterLengthBlocks || blocks > kMaxFilterLengthBlocks) {
+            return false;
+        }
+        config_.filter_length_blocks = blocks;
+        Log(AECLogLevel::kInfo, "🔧 Rebuilding AEC with filter length ", blocks, " blocks");
+        return Initialize(sample_rate_, num_channels_);
+    }
+
+    // Switch to a new input rate. The frame size is recomputed from the
+    // configured frame duration; buffered samples at the old rate are dropped.
+    bool SetSampleRate(int sample_rate) {
+        if (!IsSupportedSampleRate(config_, sample_rate)) {
+            return false;
+        }
+        render_buffer_.clear();
+        capture_buffer_.clear();
+        return Initialize(sample_rate, num_channels_);
+    }
+
+    // Apply a whole new config. Fields the running canceller can take live do
+    // so; a changed rate, frame duration, engine, preset, filter length or AGC
+    // rebuilds it, keeping metrics and buffered audio (resampled on a rate
+    // change). rebuild_fields gets the option names that forced the rebuild.
+    bool Reconfigure(const AECConfig& config, int sample_rate, std::vector<std::string>* rebuild_fields) {
+        AECTuning tuning;
+        if (!IsSupportedSampleRate(config, sample_rate) || !IsKnownEngine(config.engine) ||
+            !ResolveTuning(config, &tuning)) {
+            return false;
+        }
+        rebuild_fields->clear();
+        if (sample_rate != sample_rate_) rebuild_fields->push_back("sampleRate");
+        if (config.frame_duration_ms != config_.frame_duration_ms) rebuild_fields->push_back("frameDurationMs");
+        if (config.engine != config_.engine) rebuild_fields->push_back("engine");
+        if (config.preset != config_.preset) rebuild_fields->push_back("preset");
+        if (config.filter_length_blocks != config_.filter_length_blocks) rebuild_fields->push_back("filterLengthBlocks");
+        if (config.enable_agc != config_.enable_agc) rebuild_fields->push_back("enableAgc");  // Part of the APM chain
+        if (config.adaptation_rate != config_.adaptation_rate && config.engine != "nlms") {
+            rebuild_fields->push_back("adaptationRate");  // Baked into the AEC3 config
+        }
+
+        AECConfig previous = config_;
+        config_ = config;  // Plain settings are read per chunk, so they apply from the next one
+        config_.erle_history_length = std::min(config_.erle_history_length, kMaxErleHistoryLength);
+        UpdateActiveStages();
+        nlms_.step_size = NlmsFilter::kStepSize * config_.adaptation_rate;
+        if (config_.pre_emphasis != previous.pre_emphasis) {
+            pre_emphasis_.Reset();
+        }
+        if (config_.erle_history_length != previous.erle_history_length) {
+            erle_history_.SetCapacity(config_.erle_history_length);
+        }
+        if (rebuild_fields->empty()) {
+            if (config_.enable_aec != previous.enable_aec) {
+                SetEchoCancellationEnabled(config_.enable_aec);
+            }
+            if (config_.enable_ns != previous.enable_ns ||
+                config_.noise_suppression_level != previous.noise_suppression_level) {
+                SetNoiseSuppression(config_.enable_ns, tuning.noise_suppression_level);
+                config_.noise_suppression_level = config.noise_suppression_level;  // Keep -1 = preset
+            }
+            return true;
+        }
+
+        std::vector<float> nlms_reference;
+        nlms_reference.swap(nlms_reference_);  // Initialize clears it for the NLMS engine
+        ResampleLinear(&render_buffer_, sample_rate_, sample_rate);
+        ResampleLinear(&capture_buffer_, sample_rate_, sample_rate);
+        ResampleLinear(&nlms_reference, sample_rate_, sample_rate);
+        Log(AECLogLevel::kInfo, "🔧 Reconfigure rebuilds AEC (", rebuild_fields->size(), " fields changed)");
+        if (!Initialize(sample_rate, num_channels_)) {
+            return false;
+        }
+        if (config_.engine == "nlms") {
+            nlms_reference_.swap(nlms_reference);
+        }
+        return true;
+    }
+
+    // For Clone: rebuild at the source's format, then take its adaptive state
+    // and buffered audio. Metrics, events, logging, the watchdog and debug
+    // capture stay at their defaults. AEC3 internals can't be copied, so a
+    // webrtc clone starts that filter from scratch.
+    bool CopyStateFrom(const Impl& source) {
+        config_ = source.config_;
+        erle_history_.SetCapacity(config_.erle_history_length);
+        if (!Initialize(source.sample_rate_, source.num_channels_)) {
+            return false;
+        }
+        render_buffer_ = source.render_buffer_;
+        capture_buffer_ = source.capture_buffer_;
+        render_history_ = source.render_history_;
+        asr_decimator_ = source.asr_decimator_;
+        calibrated_delay_ms_ = source.calibrated_delay_ms_;
+        nlms_ = source.nlms_;
+        nlms_reference_ = source.nlms_reference_;
+        hp_prev_ = source.hp_prev_;
+        loudness_ = source.loudness_;
+        output_agc_ = source.output_agc_;
+        limiter_ = source.limiter_;
+        input_gain_ = source.input_gain_;
+        output_gain_ = source.output_gain_;
+        pre_emphasis_ = source.pre_emphasis_;
+        noise_floor_ = source.noise_floor_;
+        comfort_noise_seed_ = source.comfort_noise_seed_;
+        deterministic_ = source.deterministic_;
+        deterministic_elapsed_ms_ = source.deterministic_elapsed_ms_;
+        render_levels_ = source.render_levels_;
+        pending_render_samples_ = source.pending_render_samples_;
+        return true;
+    }
+
+    // The adaptive state SaveState carries, in snapshot order; the same list
+    // as CopyStateFrom. Self is Impl for reading and const Impl for writing.
+    template <typename Self, typename Visitor>
+    static void VisitState(Self& self, Visitor& visit) {
+        visit(self.render_buffer_);
+        visit(self.capture_buffer_);
+        visit(self.render_history_);
+        visit(self.asr_decimator_.history);
+        visit(self.calibrated_delay_ms_);
+        visit(self.nlms_.taps);
+        visit(self.nlms_.history);
+        visit(self.nlms_.pos);
+        visit(self.nlms_.history_power);
+        visit(self.nlms_.throttle_hold);
+        visit(self.nlms_reference_);
+        visit(self.hp_prev_);
+        visit(self.loudness_.estimate_mean_square);
+        visit(self.loudness_.gain_db);
+        visit(self.output_agc_.gain_db);
+        visit(self.limiter_.envelope);
+        visit(self.pre_emphasis_.emphasis_state);
+        visit(self.pre_emphasis_.restore_state);
+        visit(self.noise_floor_.mean_square);
+        visit(self.comfort_noise_seed_);
+        visit(self.deterministic_);
+        visit(self.deterministic_elapsed_ms_);
+        visit(self.render_levels_.mean_square);
+        visit(self.render_levels_.peak);
+        visit(self.pending_render_samples_);
+    }
+
+    std::vector<uint8_t> SaveState() const {
+        if (sample_rate_ == 0) return {};
+        StateWriter out;
+        out(kStateMagic);
+        out(AECProcessor::kStateVersion);
+        out(ConfigFingerprint(config_, sample_rate_));
+        out(num_channels_);
+        VisitState(*this, out);
+        return out.Take();
+    }
+
+    // Decode into a processor built like this one, check the buffers that
+    // Initialize sized still fit, then take it over as Clone would
+    AECStateResult RestoreState(const uint8_t* data, size_t size) {
+        StateReader in(data, size);
+        uint32_t magic = 0;
+        uint32_t version = 0;
+        in(magic);
+        in(version);
+        if (!in.ok() || magic != kStateMagic) return AECStateResult::kMalformed;
+        if (version != AECProcessor::kStateVersion) return AECStateResult::kVersionMismatch;
+
+        std::string fingerprint;
+        int num_channels = 0;
+        in(fingerprint);
+        in(num_channels);
+        if (!in.ok()) return AECStateResult::kMalformed;
+        if (fingerprint != ConfigFingerprint(config_, sample_rate_) || num_channels != num_channels_) {
+            return AECStateResult::kConfigMismatch;
+        }
+
+        Impl staged(config_);
+        if (!staged.Initialize(sample_rate_, num_channels_)) return AECStateResult::kInitFailed;
+        const size_t taps = staged.nlms_.taps.size();
+        const size_t history = staged.render_history_.size();
+        VisitState(staged, in);
+        const Decimator& decimator = staged.asr_decimator_;
+        bool consistent = staged.nlms_.taps.size() == taps && staged.nlms_.history.size() == taps * 2 &&
+                          (taps == 0 || staged.nlms_.pos < taps) && staged.render_history_.size() == history &&
+                          (decimator.factor > 1 ? decimator.history.size() < decimator.taps.size() + decimator.factor
+                                                : decimator.history.empty());
+        if (!in.ok() || !in.AtEnd() || !consistent) return AECStateResult::kMalformed;
+        return CopyStateFrom(staged) ? AECStateResult::kOk : AECStateResult::kInitFailed;
+    }
+
+    bool SetEmptyInputMode(const std::string& mode) {
+        if (!IsKnownEmptyInputMode(mode)) return false;
+        config_.empty_input_mode = mode;
+        return true;
+    }
+
+    bool SetUnderrunStrategy(const std::string& strategy) {
+        if (!IsKnownUnderrunStrategy(strategy)) return false;
+        config_.underrun_strategy = strategy;
+        return true;
+    }
+
+    // Uniform noise at the tracked mic noise floor, or silence before one is known
+    void FillEmptyInput(float* output, size_t num_samples) {
+        output_samples_produced_ += num_samples;
+        float amplitude = 0.0f;
+        if (config_.empty_input_mode == "comfortNoise") {
+            amplitude = std::sqrt(3.0f * noise_floor_.mean_square);  // Uniform RMS is a/sqrt(3)
+        }
+        for (size_t i = 0; i < num_samples; i++) {
+            comfort_noise_seed_ = comfort_noise_seed_ * 1664525u + 1013904223u;
+            float uniform = static_cast<float>(comfort_noise_seed_ >> 8) / 8388608.0f - 1.0f;
+            output[i] = amplitude * uniform;
+        }
+    }
+
+    void SetDeterministic(bool enabled, uint32_t seed, double elapsed_ms) {
+        deterministic_ = enabled;
+        if (enabled) {
+            comfort_noise_seed_ = seed;
+            deterministic_elapsed_ms_ = elapsed_ms;
+        }
+        Log(AECLogLevel::kInfo, "Deterministic mode ", enabled ? "enabled (testing only)" : "disabled");
+    }
+
+    bool SetSupportedSampleRates(const std::vector<int>& rates) {
+        if (rates.empty()) return false;
+        for (int rate : rates) {
+            if (!IsValidStreamFormat(rate, config_.frame_duration_ms)) return false;
+        }
+        config_.supported_sample_rates = rates;
+        return true;
+    }
+
+    AECTuning GetTuning() const {
+        return tuning_;
+    }
+
+    AECConfig GetConfig() const {
+        return config_;
+    }
+
+    int GetSampleRate() const { return sample_rate_; }
+    int GetNumChannels() const { return num_channels_; }
+    size_t GetFrameSize() const { return frame_size_; }
+
+    size_t GetMaxInputSamples() const { return static_cast<size_t>(config_.max_input_samples); }
+
+    void SetMaxInputSamples(int max_samples) { config_.max_input_samples = std::max(0, max_samples); }
+
+    size_t GetChunkGranularity(bool render) const {
+        if (!config_.strict_chunk_size) return 0;
+        if (render && config_.render_granularity > 0) return config_.render_granularity;
+        return frame_size_;
+    }
+
+    void SetLoudnessNormalization(bool enabled, float target_db, float max_gain_db) {
+        if (enabled && !config_.enable_loudness_normalization) {
+            loudness_.Reset();
+        }
+        config_.enable_loudness_normalization = enabled;
+        config_.loudness_target_db = target_db;
+        config_.loudness_max_gain_db = std::max(0.0f, max_gain_db);
+        UpdateActiveStages();
+    }
+
+    void SetOutputAgc(bool enabled, float target_db, float max_gain_db) {
+        if (enabled && !config_.enable_output_agc) {
+            output_agc_.Reset();
+        }
+        config_.enable_output_agc = enabled;
+        config_.agc_target_db = target_db;
+        config_.agc_max_gain_db = std::max(0.0f, max_gain_db);
+        UpdateActiveStages();
+    }
+
+    void SetLimiter(bool enabled, float threshold_db) {
+        if (enabled && !config_.enable_limiter) {
+            limiter_.Reset();
+        }
+        config_.enable_limiter = enabled;
+        config_.limiter_threshold_db = std::min(0.0f, threshold_db);
+        UpdateActiveStages();
+    }
+
+    void SetSilentChunkSkip(bool enabled, float floor_db) {
+        config_.skip_silent_chunks = enabled;
+        config_.silent_chunk_floor_db = floor_db;
+    }
+
+    bool StartDebugDump(const std::string& path) {
+        StopDebugDump();
+        auto writer = std::make_unique<DebugDumpWriter>();
+        if (!writer->Start(path, sample_rate_)) {
+            Log(AECLogLevel::kError, "❌ Failed to open AEC debug dump: ", path);
+            return false;
+        }
+        debug_dump_ = std::move(writer);
+        if (debug_capture_seconds_ <= 0.0f) {
+            dump_reference_.clear();  // Still aligned if the capture rings were using it
+        }
+        Log(AECLogLevel::kInfo, "🎙️ AEC debug dump started: ", path);
+        return true;
+    }
+
+    AECDebugDumpStats StopDebugDump() {
+        if (!debug_dump_) return AECDebugDumpStats{};
+        AECDebugDumpStats stats = debug_dump_->Stop();
+        debug_dump_.reset();
+        if (debug_capture_seconds_ <= 0.0f) {
+            dump_reference_.clear();
+        }
+        Log(AECLogLevel::kInfo, "🎙️ AEC debug dump stopped: ", stats.frames_written, " frames, ",
+                                stats.dropped_chunks, " chunks dropped");
+        return stats;
+    }
+
+    bool IsDebugDumping() const {
+        return debug_dump_ != nullptr;
+    }
+
+    void EnableDebugCapture(float max_seconds) {
+        bool was_aligning = AligningDumpReference();
+        debug_capture_seconds_ = std::clamp(max_seconds, 0.0f, kMaxDebugCaptureSeconds);
+        ResizeDebugCapture(sample_rate_);
+        if (!was_aligning || !AligningDumpReference()) {
+            dump_reference_.clear();
+        }
+        if (debug_capture_seconds_ > 0.0f) {
+            Log(AECLogLevel::kInfo, "🎙️ AEC debug capture enabled: last ", debug_capture_seconds_, "s");
+        } else {
+            Log(AECLogLevel::kInfo, "🎙️ AEC debug capture disabled");
+        }
+    }
+
+    bool IsDebugCapturing() const {
+        return debug_capture_seconds_ > 0.0f;
+    }
+
+    AECDebugCapture GetDebugCapture() const {
+        AECDebugCapture capture;
+        capture.mic = capture_mic_.Snapshot();
+        capture.reference = capture_reference_.Snapshot();
+        capture.output = capture_output_.Snapshot();
+        capture.sample_rate = sample_rate_;
+        return capture;
+    }
+
+    void SetOverloadDetection(float rtf_threshold, int consecutive_chunks, bool auto_bypass) {
+        config_.overload_rtf_threshold = rtf_threshold;
+        config_.overload_consecutive_chunks = std::max(0, consecutive_chunks);
+        config_.auto_bypass_on_overload = auto_bypass;
+        overload_streak_ = 0;
+        if (config_.overload_consecutive_chunks == 0) {
+            overloaded_ = false;
+        }
+        if (overload_bypass_ && (!overloaded_ || !auto_bypass)) {
+            overload_bypass_ = false;
+            Log(AECLogLevel::kInfo, "✅ AEC re-engaged after overload bypass was turned off");
+        }
+    }
+
+    void SetAutoReconvergeOnPathChange(bool enabled) {
+        config_.auto_reconverge_on_path_change = enabled;
+    }
+
+    void SetLogSink(AECLogSink sink, AECLogLevel level) {
+        log_sink_ = std::move(sink);
+        log_level_ = level;
+        log_window_count_ = 0;
+        log_suppressed_ = 0;
+    }
+
+    void SetRealtimeWatchdog(int consecutive_chunks) {
+        watchdog_threshold_ = std::max(0, consecutive_chunks);
+        overrun_streak_ = 0;
+        overrun_streak_ms_ = 0.0;
+        watchdog_pending_ = false;
+    }
+
+    std::vector<AECEvent> TakeEvents() {
+        std::vector<AECEvent> events;
+        events.swap(events_);
+        return events;
+    }
+
+    bool ConsumeWatchdogEvent(AECWatchdogEvent* event) {
+        if (!watchdog_pending_) return false;
+        *event = watchdog_event_;
+        watchdog_pending_ = false;
+        return true;
+    }
+
+    AECCrossCorrelation GetCrossCorrelation() const {
+        return CorrelateRecent(kCorrelationMaxLagMs, nullptr);
+    }
+
+    // Correlation peak over lags up to max_lag_ms. active, when given, is set
+    // if both the mic window and the searched render carry signal.
+    AECCrossCorrelation CorrelateRecent(float max_lag_ms, bool* active) const {
+        AECCrossCorrelation result;
+        if (active) *active = false;
+        std::vector<float> mic = capture_recent_.ring.Snapshot();
+        std::vector<float> ref = render_recent_.ring.Snapshot();
+        if (mic.empty() || ref.size() < mic.size()) return result;
+
+        double mic_energy = 0.0;
+        for (float v : mic) mic_energy += v * v;
+        if (mic_energy <= 0.0) return result;
+
+        // lag = 0 aligns the newest mic and render samples; larger lags pair the
+        // mic window with older render
+        size_t lag_limit = static_cast<size_t>(std::max(0.0f, max_lag_ms) * sample_rate_ /
+                                               (1000.0f * DecimatedHistory::kFactor));
+        const size_t max_lag = ref.size() - mic.size();
+        if (active) {
+            double ref_energy = 0.0;
+            for (size_t i = max_lag - std::min(lag_limit, max_lag); i < ref.size(); i++) {
+                ref_energy += ref[i] * ref[i];
             }
-            
-            audio_processing_ = builder.Build(env);
-            
-            if (!audio_processing_) {
-                std::cerr << "❌ Failed to create AudioProcessing, using fallback\n";
-                return true;  // Continue with naive fallback
+            size_t ref_span = mic.size() + std::min(lag_limit, max_lag);
+            *active = mic_energy / mic.size() > kActivityMeanSquare && ref_energy / ref_span > kActivityMeanSquare;
+        }
+        for (size_t lag = 0; lag <= std::min(lag_limit, max_lag); lag++) {
+            const float* window = ref.data() + (max_lag - lag);
+            double dot = 0.0;
+            double ref_energy = 0.0;
+            for (size_t i = 0; i < mic.size(); i++) {
+                dot += mic[i] * window[i];
+                ref_energy += window[i] * window[i];
             }
-            
-            // Initialize frame buffers
-            render_buffer_.reserve(frame_size_ * 2);  // Buffer for render audio
-            capture_buffer_.reserve(frame_size_ * 2);  // Buffer for capture audio
-            render_history_.resize(frame_size_ * 10);  // 100ms history for fallback
-            
-            frames_processed_ = 0;
-            
-            std::cout << "✅ WebRTC AEC3 initialized successfully with frame buffering\n";
+            if (ref_energy <= 0.0) continue;
+            float value = static_cast<float>(std::abs(dot) / std::sqrt(mic_energy * ref_energy));
+            if (value > result.peak) {
+                result.peak = value;
+                result.lag_ms = 1000.0f * lag * DecimatedHistory::kFactor / sample_rate_;
+            }
+        }
+        return result;
+    }
+
+    void SetErleHistoryLength(size_t length) {
+        config_.erle_history_length = std::min(length, kMaxErleHistoryLength);
+        erle_history_.SetCapacity(config_.erle_history_length);
+    }
+
+    std::vector<float> GetErleHistory() const {
+        return erle_history_.Snapshot();
+    }
+
+    size_t RefBufferAvailable() const {
+        size_t capacity = static_cast<size_t>(sample_rate_);
+        return capacity - std::min(capacity, pending_render_samples_);
+    }
+
+    std::vector<float> GetLastRawInput() const {
+        return last_raw_input_;
+    }
+
+    void SetReferenceGain(float gain) {
+        config_.reference_gain = std::max(0.0f, gain);
+    }
+
+    void SetOverrunDrainFraction(float fraction) {
+        config_.overrun_drain_fraction = std::clamp(fraction, 0.0f, 1.0f);
+    }
+
+    // The ramp picks up the new target on the next capture chunk
+    void SetInputGain(float gain_db) {
+        config_.input_gain_db = std::clamp(gain_db, kMinStageGainDb, kMaxStageGainDb);
+    }
+
+    void SetOutputGain(float gain_db) {
+        config_.output_gain_db = std::clamp(gain_db, kMinStageGainDb, kMaxStageGainDb);
+    }
+
+    void SetPreEmphasis(float coefficient) {
+        config_.pre_emphasis = std::clamp(coefficient, 0.0f, 0.99f);
+        pre_emphasis_.Reset();
+        UpdateActiveStages();
+        Log(AECLogLevel::kInfo, "🔧 Pre-emphasis coefficient: ", config_.pre_emphasis);
+    }
+
+    void SetDoubleTalkSensitivity(float sensitivity) {
+        config_.double_talk_sensitivity = std::clamp(sensitivity, 0.0f, 0.99f);
+        UpdateActiveStages();
+        Log(AECLogLevel::kInfo, "🔧 Double-talk sensitivity: ", config_.double_talk_sensitivity);
+    }
+
+    bool SetAdaptationRate(float rate) {
+        if (!(rate >= kMinAdaptationRate && rate <= kMaxAdaptationRate)) {
+            return false;
+        }
+        config_.adaptation_rate = rate;
+        nlms_.step_size = NlmsFilter::kStepSize * rate;
+        Log(AECLogLevel::kInfo, "🔧 Adaptation rate: ", rate);
+        if (config_.engine == "nlms") {
             return true;
-            
-        } catch (const std::exception& e) {
-            std::cerr << "❌ Error initializing AEC: " << e.what() << "\n";
-            audio_processing_ = nullptr;
-            return true;  // Continue with fallback
         }
+        return Initialize(sample_rate_, num_channels_);  // AEC3 reads its filter rates only at creation
     }
 
-    void ProcessRenderAudio(const float* data, size_t num_samples) {
+    void SetAutoResetAfterIdle(float idle_ms, bool reset_filter) {
+        config_.auto_reset_after_idle_ms = std::max(0.0f, idle_ms);
+        config_.idle_reset_filter = reset_filter;
+    }
+
+    void SetClock(AECClock clock) {
+        clock_ = std::move(clock);
+        last_capture_time_.reset();  // Times from the old source aren't comparable
+    }
+
+    std::chrono::steady_clock::time_point Now() const {
+        return clock_ ? clock_() : std::chrono::steady_clock::now();
+    }
+
+    // Called before queued reference is handed over, so audio fed after the
+    // pause survives: it belongs to the chunk about to be processed
+    void ResetIfIdle() {
+        auto now = Now();
+        bool idle = false;
+        if (config_.auto_reset_after_idle_ms > 0.0f && last_capture_time_) {
+            std::chrono::duration<double, std::milli> gap = now - *last_capture_time_;
+            idle = gap.count() > config_.auto_reset_after_idle_ms;
+        }
+        last_capture_time_ = now;
+        if (!idle) return;
+
+        idle_resets_++;
+        Log(AECLogLevel::kInfo, "🔄 Capture idle over ", config_.auto_reset_after_idle_ms,
+            " ms, dropping stale reference", config_.idle_reset_filter ? " and resetting the canceller" : "");
+        if (config_.idle_reset_filter) {
+            Reset(false);
+            return;
+        }
+        render_buffer_.clear();
+        std::fill(render_history_.begin(), render_history_.end(), 0.0f);
+        nlms_reference_.assign(CalibratedDelaySamples(), 0.0f);
+        render_levels_ = ChunkLevels();
+        pending_render_samples_ = 0;
+    }
+
+    void SetSilenceBypass(bool enabled, float threshold_db) {
+        config_.enable_silence_bypass = enabled;
+        config_.silence_bypass_threshold_db = threshold_db;
+    }
+
+    // Drop buffered audio and adaptation state and rebuild the canceller.
+    // Counters and meters survive unless reset_metrics is set.
+    bool Reset(bool reset_metrics) {
+        render_buffer_.clear();
+        capture_buffer_.clear();
+        std::fill(render_history_.begin(), render_history_.end(), 0.0f);
+        hp_prev_ = 0.0f;
+        pre_emphasis_.Reset();
+        loudness_.Reset();
+        output_agc_.Reset();
+        limiter_.Reset();
+        asr_decimator_.Reset();
+        render_levels_ = ChunkLevels();
+        pending_render_samples_ = 0;
+        health_.Reset();
+        path_change_.Reset();
+        converged_ = false;
+        ever_converged_ = false;
+        transition_ms_ = 0.0f;
+        converged_for_ms_ = 0.0f;
+        echo_active_ms_ = 0.0f;
+        echo_check_ms_ = 0.0f;
+        echo_reported_ = false;
+        if (reset_metrics) {
+            ResetMetrics();
+        }
+        return Initialize(sample_rate_, num_channels_);
+    }
+
+    AECCallStats GetLastCallStats() const {
+        return last_call_stats_;
+    }
+
+    AECHealthReport HealthCheck(bool auto_recover) {
+        AECHealthReport report;
+        report.output_stuck = health_.output_stuck_ms >= kHealthWindowMs;
+        report.output_exceeds_input = health_.output_hot_ms >= kHealthWindowMs;
+        report.reference_pinned = health_.reference_pinned_ms >= kHealthWindowMs;
+        report.non_finite = health_.non_finite;
+        report.healthy = !report.output_stuck && !report.output_exceeds_input &&
+                         !report.reference_pinned && !report.non_finite;
+        health_.non_finite = false;
+
+        if (!report.healthy) {
+            Log(AECLogLevel::kWarn, "⚠️ AEC health check failed: stuck=", report.output_stuck,
+                " hot=", report.output_exceeds_input, " pinned=", report.reference_pinned,
+                " non_finite=", report.non_finite);
+            if (auto_recover) {
+                report.recovered = Reset(false);
+                Log(AECLogLevel::kInfo, report.recovered ? "✅ AEC reset by health check"
+                                                         : "❌ AEC health check reset failed");
+            }
+        }
+        return report;
+    }
+
+    // Processing and metric calls share one thread, so no chunk can be counted
+    // between the snapshot and the reset
+    AECMetrics GetAndResetMetrics() {
+        AECMetrics metrics = GetMetrics();
+        ResetMetrics();
+        return metrics;
+    }
+
+    // Search recent mic/render audio for the echo delay and, on the nlms
+    // engine, shift the reference queue by it. AEC3 runs its own delay
+    // estimator, so there the estimate is only reported. Only runs when both
+    // signals are active.
+    AECDelayEstimate CalibrateDelay(float max_delay_ms) {
+        AECDelayEstimate estimate;
+        bool active = false;
+        AECCrossCorrelation peak = CorrelateRecent(std::min<float>(max_delay_ms, kCorrelationMaxLagMs), &active);
+        if (!active || peak.peak <= 0.0f) {
+            Log(AECLogLevel::kWarn, "⚠️ Delay calibration skipped: mic or reference too quiet");
+            return estimate;
+        }
+        estimate.delay_samples = static_cast<int>(std::lround(peak.lag_ms * sample_rate_ / 1000.0f));
+        estimate.delay_ms = 1000.0f * estimate.delay_samples / sample_rate_;
+        estimate.correlation = peak.peak;
+        if (config_.engine != "nlms") {
+            Log(AECLogLevel::kInfo, "Estimated reference delay: ", estimate.delay_ms,
+                " ms; not applied, AEC3 tracks the delay itself");
+            return estimate;
+        }
+
+        estimate.applied = true;
+        int shift = estimate.delay_samples - CalibratedDelaySamples();
+        if (shift > 0) {
+            nlms_reference_.insert(nlms_reference_.begin(), static_cast<size_t>(shift), 0.0f);
+        } else {
+            size_t drop = std::min(static_cast<size_t>(-shift), nlms_reference_.size());
+            nlms_reference_.erase(nlms_reference_.begin(), nlms_reference_.begin() + drop);
+        }
+        calibrated_delay_ms_ = estimate.delay_ms;
+        Log(AECLogLevel::kInfo, "🔧 Calibrated reference delay: ", estimate.delay_ms, " ms (",
+            estimate.delay_samples, " samples, correlation ", estimate.correlation, ")");
+        return estimate;
+    }
+
+    int CalibratedDelaySamples() const {
+        return static_cast<int>(std::lround(calibrated_delay_ms_ * sample_rate_ / 1000.0f));
+    }
+
+    void ResetMetrics() {
+        input_meter_.Reset();
+        output_meter_.Reset();
+        input_peak_hold_.Reset();
+        output_peak_hold_.Reset();
+        current_rms_ = 0.0f;
+        current_peak_ = 0.0f;
+        skipped_silent_chunks_ = 0;
+        silence_bypass_chunks_ = 0;
+        noise_floor_.Reset();
+        latency_.Reset();
+        rtf_.Reset();
+        overload_events_ = 0;
+        path_changes_detected_ = 0;
+        adaptation_throttled_chunks_ = 0;
+        idle_resets_ = 0;
+        empty_input_calls_ = 0;
+        dropped_input_events_ = 0;
+        dropped_input_samples_ = 0;
+        scratch_grow_count_ = 0;
+        erle_.Reset();
+        erle_history_.Clear();
+        underrun_count_ = 0;
+        underrun_samples_ = 0;
+        underrun_zero_fill_count_ = 0;
+        underrun_hold_count_ = 0;
+        overflow_count_ = 0;
+        overflow_samples_ = 0;
+        reference_drift_.Reset();
+        limiter_.max_gain_reduction_db = 0.0f;
+    }
+
+private:
+    // Built-in NLMS engine; runs sample by sample against the queued reference
+    void ProcessNlms(const float* input, float* output, size_t num_samples) {
+        size_t available = std::min(num_samples, nlms_reference_.size());
+        float geigel_threshold = config_.double_talk_sensitivity > 0.0f ? 1.0f - config_.double_talk_sensitivity : 0.0f;
+        size_t hangover = static_cast<size_t>(sample_rate_ * kDoubleTalkHangoverMs / 1000);
+        bool hold = HoldUnderruns();
+        for (size_t i = 0; i < num_samples; i++) {
+            // Starved: assume silence, or replay the held frame
+            float reference = i < available ? nlms_reference_[i]
+                              : hold ? held_reference_[(i - available) % held_reference_.size()] : 0.0f;
+            output[i] = nlms_.Process(reference, input[i], geigel_threshold, hangover, &chunk_throttled_);
+        }
+        ConsumeNlmsReference(available, hold);
+    }
+
+    // ProcessNlms without the double-talk detector, for sensitivity 0 with no
+    // hangover pending, where both give identical output: the queued
+    // reference, then starved silence, straight through the filter
+    void ProcessNlmsPlain(const float* input, float* output, size_t num_samples) {
+        size_t available = std::min(num_samples, nlms_reference_.size());
+        bool hold = HoldUnderruns();
+        for (size_t i = 0; i < available; i++) {
+            output[i] = nlms_.ProcessPlain(nlms_reference_[i], input[i]);
+        }
+        if (hold) {
+            for (size_t i = available; i < num_samples; i++) {
+                output[i] = nlms_.ProcessPlain(held_reference_[(i - available) % held_reference_.size()], input[i]);
+            }
+        } else {
+            for (size_t i = available; i < num_samples; i++) {
+                output[i] = nlms_.ProcessPlain(0.0f, input[i]);
+            }
+        }
+        ConsumeNlmsReference(available, hold);
+    }
+
+    void ConsumeNlmsReference(size_t count, bool hold) {
+        if (hold) HoldReference(nlms_reference_.data(), count);
+        nlms_reference_.erase(nlms_reference_.begin(), nlms_reference_.begin() + count);
+    }
+
+    // Under "hold", with a frame to replay
+    bool HoldUnderruns() const {
+        return config_.underrun_strategy == "hold" && !held_reference_.empty();
+    }
+
+    // Keep the newest frame of reference the canceller has consumed, oldest first
+    void HoldReference(const float* consumed, size_t count) {
+        size_t frame = held_reference_.size();
+        if (count >= frame) {
+            std::copy(consumed + count - frame, consumed + count, held_reference_.begin());
+        } else {
+            std::copy(held_reference_.begin() + count, held_reference_.end(), held_reference_.begin());
+            std::copy(consumed, consumed + count, held_reference_.end() - count);
+        }
+    }
+
+    // Improved naive algorithm (fallback when WebRTC not available)
+    void ProcessNaive(const float* input, float* output, size_t num_samples) {
+        std::copy(input, input + num_samples, output);
+        
         if (!config_.enable_aec) return;
         
-        // Store for fallback
-        std::copy(data, data + std::min(num_samples, render_history_.size()), 
-                  render_history_.begin());
+        // Echo cancellation
+        size_t samples = std::min(num_samples, render_history_.size());
+        for (size_t i = 0; i < samples; i++) {
+            output[i] -= render_history_[i] * 0.5f;
+        }
         
-        if (!audio_processing_) return;
+        // High-pass filter
+        ApplyHighPassFilter(output, num_samples);
         
-        // Add to render buffer
-        render_buffer_.insert(render_buffer_.end(), data, data + num_samples);
+        // Noise suppression
+        if (config_.enable_ns) {
+            ApplyNoiseSuppression(output, num_samples);
+        }
+    }
+    
+    void ApplyHighPassFilter(float* data, size_t num_samples) {
+        const float cutoff = 80.0f / sample_rate_;
+        const float alpha = 1.0f / (1.0f + cutoff);
         
-        // Process complete frames
-        while (render_buffer_.size() >= frame_size_) {
-            try {
-                // Create render stream config
-                webrtc::StreamConfig stream_config(sample_rate_, num_channels_);
-                
-                // Extract one frame
-                std::vector<float> frame(render_buffer_.begin(), render_buffer_.begin() + frame_size_);
-                
-                // WebRTC needs non-const pointers
-                float* output_ptr = frame.data();
-                const float* input_ptr = frame.data();
-                
-                audio_processing_->ProcessReverseStream(
-                    &input_ptr, stream_config, stream_config, &output_ptr);
-                
-                // Remove processed frame from buffer
-                render_buffer_.erase(render_buffer_.begin(), render_buffer_.begin() + frame_size_);
-                
-            } catch (const std::exception& e) {
-                std::cerr << "❌ ProcessReverseStream error: " << e.what() << "\n";
-                // Clear buffer on error to prevent backup
-                render_buffer_.clear();
-                break;
+        for (size_t i = 1; i < num_samples; i++) {
+            data[i] = alpha * (hp_prev_ + data[i] - data[i-1]);
+            hp_prev_ = data[i];
+        }
+    }
+    
+    void ApplyNoiseSuppression(float* data, size_t num_samples) {
+        const float threshold = 0.01f;
+        for (size_t i = 0; i < num_samples; i++) {
+            if (std::abs(data[i]) < threshold) {
+                data[i] *= 0.1f;
             }
         }
     }
+    
+    // A peak of exactly 0.0 means the device delivered digital zeros (permission
+    // or device failure), which real microphones never produce even in silence.
+    void TrackDeadInput(const ChunkLevels& input_levels, size_t num_samples) {
+        if (num_samples == 0) return;
 
-    void ProcessCaptureAudio(const float* input, float* output, size_t num_samples) {
-        if (!audio_processing_ || !config_.enable_aec) {
-            // Fallback to improved naive algorithm
-            ProcessNaive(input, output, num_samples);
-            CalculateMetrics(output, num_samples);
+        if (input_levels.peak != 0.0f) {
+            if (dead_input_warned_) {
+                Log(AECLogLevel::kInfo, "✅ Microphone input recovered after ", dead_input_ms_,
+                                        "ms of digital silence");
+            }
+            dead_input_ms_ = 0.0f;
+            dead_input_warned_ = false;
             return;
         }
-        
-        // Add input to capture buffer
-        capture_buffer_.insert(capture_buffer_.end(), input, input + num_samples);
-        
-        // Process complete frames and accumulate output
-        size_t output_written = 0;
-        
-        while (capture_buffer_.size() >= frame_size_ && output_written < num_samples) {
-            try {
-                // Create capture stream config
-                webrtc::StreamConfig stream_config(sample_rate_, num_channels_);
-                
-                // Extract one frame
-                std::vector<float> frame(capture_buffer_.begin(), capture_buffer_.begin() + frame_size_);
-                
-                // WebRTC processes in-place
-                float* frame_ptr = frame.data();
-                const float* input_ptr = frame.data();
-                
-                int result = audio_processing_->ProcessStream(
-                    &input_ptr, stream_config, stream_config, &frame_ptr);
-                
-                if (result != 0) {
-                    std::cerr << "❌ ProcessStream returned error: " << result << "\n";
-                    // Copy unprocessed frame to output
-                    size_t to_copy = std::min(frame_size_, num_samples - output_written);
-                    std::copy(frame.begin(), frame.begin() + to_copy, output + output_written);
-                } else {
-                    // Copy processed frame to output
-                    size_t to_copy = std::min(frame_size_, num_samples - output_written);
-                    std::copy(frame.begin(), frame.begin() + to_copy, output + output_written);
-                    
-                    // Log occasionally
-                    frames_processed_++;
-                    if (frames_processed_ % 1000 == 0) {
-                        std::cout << "✅ Processed " << frames_processed_ << " frames through WebRTC AEC3\n";
-                    }
+
+        dead_input_ms_ += ChunkMs(num_samples);
+        if (!dead_input_warned_ && dead_input_ms_ >= config_.dead_input_warning_ms) {
+            Log(AECLogLevel::kWarn, "⚠️ Microphone delivering pure digital zeros for ", dead_input_ms_,
+                                    "ms (check mic permission/device)");
+            dead_input_warned_ = true;
+        }
+    }
+
+    // Info and above keep going to the console as before; debug/trace only
+    // exist for the sink, so with none set they return before formatting
+    template <typename... Args>
+    void Log(AECLogLevel level, const Args&... args) {
+        bool to_console = level <= AECLogLevel::kInfo;
+        bool to_sink = log_sink_ && level <= log_level_;
+        if (!to_console && !to_sink) return;
+
+        std::ostringstream message;
+        if (!config_.label.empty()) {
+            message << '[' << config_.label << "] ";
+        }
+        (message << ... << args);
+        if (to_console) {
+            (level <= AECLogLevel::kWarn ? std::cerr : std::cout) << message.str() << "\n";
+        }
+        if (to_sink) {
+            ForwardLog(level, message.str());
+        }
+    }
+
+    // At most kMaxLogRecordsPerSecond per one-second window reach the sink; the
+    // excess is reported as a single warning when the next window opens
+    void ForwardLog(AECLogLevel level, const std::string& message) {
+        auto now = std::chrono::steady_clock::now();
+        if (now - log_window_start_ >= std::chrono::seconds(1)) {
+            if (log_suppressed_ > 0) {
+                log_sink_(AECLogLevel::kWarn, std::to_string(log_suppressed_) + " log records suppressed by rate limit");
+            }
+            log_window_start_ = now;
+            log_window_count_ = 0;
+            log_suppressed_ = 0;
+        }
+        if (log_window_count_ >= kMaxLogRecordsPerSecond) {
+            log_suppressed_++;
+            return;
+        }
+        log_window_count_++;
+        log_sink_(level, message);
+    }
+
+    // Render is consumed FIFO by capture chunks, so each dumped frame pairs the
+    // mic with the reference delivered for that same stretch of time
+    void QueueDumpReference(const float* data, size_t num_samples) {
+        dump_reference_.insert(dump_reference_.end(), data, data + num_samples);
+        size_t max_queued = static_cast<size_t>(sample_rate_);
+        if (dump_reference_.size() > max_queued) {
+            dump_reference_.erase(dump_reference_.begin(),
+                                  dump_reference_.begin() + (dump_reference_.size() - max_queued));
+        }
+    }
+
+    // The WAV dump and the in-memory capture rings share one aligned reference queue
+    bool AligningDumpReference() const {
+        return debug_dump_ != nullptr || debug_capture_seconds_ > 0.0f;
+    }
+
+    void WriteDumpChunk(const float* output, size_t num_samples) {
+        size_t available = std::min(num_samples, dump_reference_.size());
+        dump_chunk_reference_.assign(num_samples, 0.0f);  // Zero-fill render underruns
+        std::copy(dump_reference_.begin(), dump_reference_.begin() + available, dump_chunk_reference_.begin());
+        dump_reference_.erase(dump_reference_.begin(), dump_reference_.begin() + available);
+        if (debug_dump_) {
+            debug_dump_->Enqueue(dump_mic_.data(), dump_chunk_reference_.data(), output, num_samples);
+        }
+        if (debug_capture_seconds_ > 0.0f) {
+            capture_mic_.Push(dump_mic_.data(), num_samples);
+            capture_reference_.Push(dump_chunk_reference_.data(), num_samples);
+            capture_output_.Push(output, num_samples);
+        }
+    }
+
+    // Capacity 0 when disabled, so the rings hold no memory
+    void ResizeDebugCapture(int sample_rate) {
+        size_t capacity = static_cast<size_t>(debug_capture_seconds_ * std::max(sample_rate, 0));
+        for (HistoryRing* ring : {&capture_mic_, &capture_reference_, &capture_output_}) {
+            ring->Clear();
+            ring->SetCapacity(capacity);
+            ring->values.shrink_to_fit();
+        }
+    }
+
+    // Hold timers make each transition fire once; echoDetected re-arms on convergence
+    void TrackConvergenceEvents(float erle_db, float chunk_ms) {
+        echo_active_ms_ += chunk_ms;
+        bool above = erle_db >= config_.converged_erle_db;
+        if (above != converged_) {
+            transition_ms_ += chunk_ms;
+            if (transition_ms_ >= config_.converged_hold_ms) {
+                converged_ = above;
+                transition_ms_ = 0.0f;
+                converged_for_ms_ = 0.0f;
+                ever_converged_ = ever_converged_ || converged_;
+                QueueEvent(AECEvent{converged_ ? "converged" : "diverged", erle_db});
+                if (converged_) {
+                    echo_reported_ = false;
                 }
-                
-                output_written += frame_size_;
-                
-                // Remove processed frame from buffer
-                capture_buffer_.erase(capture_buffer_.begin(), capture_buffer_.begin() + frame_size_);
-                
-            } catch (const std::exception& e) {
-                std::cerr << "❌ ProcessCaptureAudio error: " << e.what() << "\n";
-                // Fall back to naive for this buffer
-                ProcessNaive(input, output, num_samples);
-                capture_buffer_.clear();
-                break;
             }
+        } else {
+            transition_ms_ = 0.0f;
         }
-        
-        // If we couldn't process all samples (buffer not full yet), copy remaining input
-        if (output_written < num_samples) {
-            size_t remaining = num_samples - output_written;
-            std::copy(input + output_written, input + num_samples, output + output_written);
+
+        if (converged_ || echo_reported_ || erle_db >= config_.echo_detect_max_erle_db) {
+            echo_check_ms_ = 0.0f;
+            return;
+        }
+        echo_check_ms_ += chunk_ms;
+        if (echo_check_ms_ < kEchoCheckIntervalMs) return;
+        echo_check_ms_ = 0.0f;
+        AECCrossCorrelation correlation = GetCrossCorrelation();
+        if (correlation.peak >= config_.echo_detect_correlation) {
+            echo_reported_ = true;
+            QueueEvent(AECEvent{"echoDetected", erle_db, correlation.peak, correlation.lag_ms});
+        }
+    }
+
+    const char* ConvergenceState() const {
+        if (converged_) return "converged";
+        if (ever_converged_) return "diverged";
+        return echo_active_ms_ < config_.convergence_cold_ms ? "cold" : "adapting";
+    }
+
+    void QueueEvent(AECEvent event) {
+        Log(AECLogLevel::kDebug, "AEC event: ", event.type, " (ERLE ", event.erle_db, " dB)");
+        if (events_.size() >= kMaxQueuedEvents) {
+            events_.erase(events_.begin());
+        }
+        events_.push_back(std::move(event));
+    }
+
+    // Runs after the chunk that detected it, so output is complete before any reset
+    void HandlePathChange() {
+        path_change_pending_ = false;
+        path_changes_detected_++;
+        Log(AECLogLevel::kWarn, "⚠️ Echo path change detected (ERLE dropped ", PathChangeDetector::kDropDb,
+            " dB below baseline)", config_.auto_reconverge_on_path_change ? ", resetting adaptation" : "");
+        if (config_.auto_reconverge_on_path_change) {
+            Reset(false);
+        }
+    }
+
+    // Enter overload after a sustained high recent RTF; leave it (and any
+    // bypass) only after a sustained RTF below half the threshold
+    void TrackOverload() {
+        int needed = config_.overload_consecutive_chunks;
+        if (needed == 0) return;
+
+        double rtf = rtf_.Recent();
+        if (!overloaded_) {
+            overload_streak_ = rtf > config_.overload_rtf_threshold ? overload_streak_ + 1 : 0;
+            if (overload_streak_ < needed) return;
+            overloaded_ = true;
+            overload_events_++;
+            overload_streak_ = 0;
+            Log(AECLogLevel::kWarn, "⚠️ AEC overloaded: real-time factor ", rtf, " above ",
+                config_.overload_rtf_threshold, " for ", needed, " chunks");
+            if (config_.auto_bypass_on_overload) {
+                overload_bypass_ = true;
+                Log(AECLogLevel::kWarn, "⚠️ AEC bypassed until load recovers");
+            }
+            return;
+        }
+
+        overload_streak_ = rtf < config_.overload_rtf_threshold * kOverloadRecoveryRatio ? overload_streak_ + 1 : 0;
+        if (overload_streak_ < needed) return;
+        overloaded_ = false;
+        overload_streak_ = 0;
+        if (overload_bypass_) {
+            overload_bypass_ = false;
+            Log(AECLogLevel::kInfo, "✅ AEC re-engaged: real-time factor ", rtf);
+        } else {
+            Log(AECLogLevel::kInfo, "✅ AEC load recovered: real-time factor ", rtf);
+        }
+    }
+
+    // Count consecutive calls slower than real time; the event fires when the
+    // streak reaches the threshold and re-arms once a call is back in budget
+    void TrackRealtimeBudget(double elapsed_ms, size_t num_samples) {
+        if (watchdog_threshold_ == 0) return;
+
+        double budget_ms = ChunkMs(num_samples);
+        if (elapsed_ms <= budget_ms) {
+            overrun_streak_ = 0;
+            overrun_streak_ms_ = 0.0;
+            return;
         }
-        
-        CalculateMetrics(output, num_samples);
-    }
 
-    void SetEchoCancellationEnabled(bool enabled) {
-        config_.enable_aec = enabled;
-        
-        if (audio_processing_) {
-            try {
-                auto apm_config = audio_processing_->GetConfig();
-                apm_config.echo_canceller.enabled = enabled;
-                audio_processing_->ApplyConfig(apm_config);
-                std::cout << (enabled ? "✅ AEC enabled" : "⚠️ AEC disabled") << "\n";
-            } catch (const std::exception& e) {
-                std::cerr << "❌ Error setting AEC enabled: " << e.what() << "\n";
-            }
+        overrun_streak_++;
+        overrun_streak_ms_ += elapsed_ms;
+        if (overrun_streak_ == watchdog_threshold_) {
+            watchdog_event_.consecutive_chunks = overrun_streak_;
+            watchdog_event_.avg_processing_ms = static_cast<float>(overrun_streak_ms_ / overrun_streak_);
+            watchdog_event_.budget_ms = static_cast<float>(budget_ms);
+            watchdog_pending_ = true;
+            Log(AECLogLevel::kWarn, "⚠️ AEC processing over real-time budget for ", overrun_streak_,
+                                    " chunks (avg ", watchdog_event_.avg_processing_ms, "ms, budget ",
+                                    budget_ms, "ms)");
         }
     }
 
-    AECMetrics GetMetrics() const {
-        AECMetrics metrics;
-        metrics.rms_level = current_rms_;
-        metrics.peak_level = current_peak_;
-        
-        if (audio_processing_ && config_.enable_aec) {
-            // WebRTC is active - assume good performance
-            metrics.echo_return_loss = 20.0f;
-            metrics.echo_return_loss_enhancement = 15.0f;
-            metrics.aec_converged = true;
+    // Render samples delivered but not yet matched by capture. Mirrors the NLMS
+    // reference queue and measures AEC3 render starvation the same way. Past one
+    // second the oldest samples drain down to overrun_drain_fraction of it;
+    // returns how many were dropped.
+    size_t TrackRenderBalance(size_t num_samples) {
+        reference_drift_.fed += num_samples;
+        pending_render_samples_ += num_samples;
+        size_t max_pending = static_cast<size_t>(sample_rate_);
+        if (pending_render_samples_ <= max_pending) return 0;
+        size_t keep = static_cast<size_t>(max_pending * config_.overrun_drain_fraction);
+        size_t drained = pending_render_samples_ - keep;
+        overflow_count_++;
+        overflow_samples_ += drained;
+        Log(AECLogLevel::kDebug, "Render ahead of capture by over 1s, dropped ", drained, " samples");
+        pending_render_samples_ = keep;
+        return drained;
+    }
+
+    void TrackCaptureBalance(size_t num_samples) {
+        input_samples_processed_ += num_samples;
+        output_samples_produced_ += num_samples;  // Every path writes the whole chunk
+        reference_samples_consumed_ += std::min(num_samples, pending_render_samples_);
+        if (sample_rate_ > 0) {
+            audio_seconds_processed_ += static_cast<double>(num_samples) / sample_rate_;
+        }
+        reference_drift_.Consume(num_samples, sample_rate_);
+        if (pending_render_samples_ < num_samples) {
+            Log(AECLogLevel::kDebug, "Render underrun: ", num_samples - pending_render_samples_,
+                " capture samples without reference");
+            underrun_count_++;
+            underrun_samples_ += num_samples - pending_render_samples_;
+            (config_.underrun_strategy == "hold" ? underrun_hold_count_ : underrun_zero_fill_count_)++;
+            pending_render_samples_ = 0;
         } else {
-            // Using fallback
-            metrics.echo_return_loss = 5.0f;
-            metrics.echo_return_loss_enhancement = 3.0f;
-            metrics.aec_converged = false;
+            pending_render_samples_ -= num_samples;
         }
-        
-        return metrics;
     }
 
-private:
-    // Improved naive algorithm (fallback when WebRTC not available)
-    void ProcessNaive(const float* input, float* output, size_t num_samples) {
-        std::copy(input, input + num_samples, output);
-        
-        if (!config_.enable_aec) return;
-        
-        // Echo cancellation
-        size_t samples = std::min(num_samples, render_history_.size());
-        for (size_t i = 0; i < samples; i++) {
-            output[i] -= render_history_[i] * 0.5f;
-        }
-        
-        // High-pass filter
-        ApplyHighPassFilter(output, num_samples);
-        
-        // Noise suppression
-        if (config_.enable_ns) {
-            ApplyNoiseSuppression(output, num_samples);
+    void RestoreEmphasis(float* output, size_t num_samples) {
+        if (config_.pre_emphasis > 0.0f) {
+            pre_emphasis_.Restore(output, num_samples, config_.pre_emphasis);
         }
     }
-    
-    void ApplyHighPassFilter(float* data, size_t num_samples) {
-        const float cutoff = 80.0f / sample_rate_;
-        const float alpha = 1.0f / (1.0f + cutoff);
-        
-        for (size_t i = 1; i < num_samples; i++) {
-            data[i] = alpha * (hp_prev_ + data[i] - data[i-1]);
-            hp_prev_ = data[i];
+
+    // Whether any optional post-cancellation stage (pre-emphasis restore, AGC,
+    // loudness, limiter) is on. All are off by default, so every engine's
+    // ApplyOutputStages returns at once; noise suppression and double-talk
+    // detection belong to the engines and don't count. Cached per chunk;
+    // every setter of those fields calls this.
+    void UpdateActiveStages() {
+        any_stage_active_ = config_.pre_emphasis > 0.0f || config_.enable_output_agc ||
+                            config_.enable_loudness_normalization || config_.enable_limiter;
+    }
+
+    // Post-cancellation stages, applied in order to the final output
+    void ApplyOutputStages(float* output, size_t num_samples) {
+        if (!any_stage_active_) return;
+        RestoreEmphasis(output, num_samples);
+        if (config_.enable_output_agc) {
+            output_agc_.Process(output, num_samples, MeasureChunk(output, num_samples).mean_square,
+                                ChunkMs(num_samples), config_.agc_target_db, config_.agc_max_gain_db,
+                                !config_.enable_limiter);
+        }
+        if (config_.enable_loudness_normalization) {
+            loudness_.Process(output, num_samples, MeasureChunk(output, num_samples).mean_square,
+                              ChunkMs(num_samples), config_.loudness_target_db,
+                              config_.loudness_max_gain_db, !config_.enable_limiter);
+        }
+        if (config_.enable_limiter) {
+            limiter_.Process(output, num_samples, sample_rate_, config_.limiter_threshold_db);
         }
     }
-    
-    void ApplyNoiseSuppression(float* data, size_t num_samples) {
-        const float threshold = 0.01f;
-        for (size_t i = 0; i < num_samples; i++) {
-            if (std::abs(data[i]) < threshold) {
-                data[i] *= 0.1f;
+
+    float ChunkMs(size_t num_samples) const {
+        return sample_rate_ > 0 ? 1000.0f * num_samples / sample_rate_ : 0.0f;
+    }
+
+    // Smoothing coefficient for a chunk of num_samples given the configured time constant
+    float LevelAlpha(size_t num_samples) const {
+        if (config_.level_smoothing_ms <= 0.0f || sample_rate_ <= 0) return 1.0f;
+        return 1.0f - std::exp(-ChunkMs(num_samples) / config_.level_smoothing_ms);
+    }
+
+    // canceller_ran is false for chunks copied past the engine, whose 0 dB ERLE
+    // says nothing about the echo path
+    void CalculateMetrics(const ChunkLevels& input_levels, const float* output, size_t num_samples,
+                          bool canceller_ran = true) {
+        ChunkLevels output_levels = MeasureChunk(output, num_samples);
+        output_meter_.Update(output_levels, LevelAlpha(num_samples));
+        output_peak_hold_.Update(output_levels.peak, ChunkMs(num_samples),
+                                 config_.peak_hold_ms, config_.peak_decay_db_per_sec);
+
+        current_rms_ = std::sqrt(output_levels.mean_square);
+        current_peak_ = output_levels.peak;
+
+        last_call_stats_.input_rms = std::sqrt(input_levels.mean_square);
+        last_call_stats_.output_rms = current_rms_;
+        last_call_stats_.erle_db = EnergyRatioDb(input_levels.mean_square, output_levels.mean_square);
+        if (render_levels_.mean_square > kActivityMeanSquare && input_levels.mean_square > kActivityMeanSquare) {
+            erle_.Update(input_levels.mean_square, output_levels.mean_square, ChunkMs(num_samples), num_samples);
+            if (canceller_ran && path_change_.Update(erle_.InstantDb(), ChunkMs(num_samples))) {
+                path_change_pending_ = true;
             }
+            TrackConvergenceEvents(erle_.InstantDb(), ChunkMs(num_samples));
         }
-    }
-    
-    void CalculateMetrics(const float* data, size_t num_samples) {
-        float sum = 0.0f;
-        float peak = 0.0f;
-        for (size_t i = 0; i < num_samples; i++) {
-            float val = std::abs(data[i]);
-            sum += val * val;
-            peak = std::max(peak, val);
+        if (converged_) {
+            converged_for_ms_ += ChunkMs(num_samples);
         }
-        current_rms_ = std::sqrt(sum / num_samples);
-        current_peak_ = peak;
+        erle_history_.Push(erle_.InstantDb());
+        last_call_stats_.double_talk = render_levels_.mean_square > kActivityMeanSquare &&
+                                       input_levels.mean_square > kActivityMeanSquare &&
+                                       last_call_stats_.erle_db < kDoubleTalkErleDb;
+        last_call_stats_.adaptation_throttled = chunk_throttled_;
+        if (chunk_throttled_) {
+            adaptation_throttled_chunks_++;
+        }
+        chunk_throttled_ = false;
     }
 
     AECConfig config_;
+    AECTuning tuning_;
     webrtc::scoped_refptr<webrtc::AudioProcessing> audio_processing_;
     
     // Frame buffering
-    std::vector<float> render_buffer_;   // Accumulates render audio into frames
+    std::vector<float> render_buffer_;   // Render audio not yet handed to AEC3, see PushRenderFrame
     std::vector<float> capture_buffer_;  // Accumulates capture audio into frames
+    std::vector<float> render_frame_;    // Scratch for the frame handed to AEC3
+    std::vector<float> capture_frame_;
     std::vector<float> render_history_;  // For fallback algorithm
+    std::vector<float> scaled_render_;   // Render chunk after reference_gain
+    std::vector<float> warmup_scratch_;  // Discarded Warmup output
+    std::vector<float> loopback_scratch_;  // ProcessAndFeed reference
+    Decimator asr_decimator_;  // ProcessWithAsrTap, fed the final output
+    float calibrated_delay_ms_ = 0.0f;     // Set by CalibrateDelay, kept across Reset
+    
+    NlmsFilter nlms_;
+    std::vector<float> nlms_reference_;  // Render samples not yet consumed by capture
+    std::vector<float> held_reference_;  // Last frame consumed, replayed on underrun under "hold"
     
     int sample_rate_ = 0;
     int num_channels_ = 0;
     size_t frame_size_ = 0;
+    size_t apm_block_size_ = 0;  // Samples per 10 ms AEC3 block
     size_t frames_processed_ = 0;
     
     float current_rms_ = 0.0f;
     float current_peak_ = 0.0f;
     float hp_prev_ = 0.0f;
+
+    LevelMeter input_meter_;
+    LevelMeter output_meter_;
+    PeakHold input_peak_hold_;
+    PeakHold output_peak_hold_;
+    LoudnessNormalizer loudness_;
+    OutputAgc output_agc_;
+    SoftKneeLimiter limiter_;
+    GainRamp input_gain_;   // config_.input_gain_db, ahead of everything else
+    GainRamp output_gain_;  // config_.output_gain_db, after the output stages
+    std::vector<float> input_gain_scratch_;
+    std::vector<float> last_raw_input_;  // Tail of the last capture chunk, at most one frame
+    bool passthrough_ = false;         // See InitializePassthrough
+    bool passthrough_warned_ = false;
+    std::string init_error_;           // Why the last Initialize failed, "" after one succeeds
+    PreEmphasis pre_emphasis_;
+    uint64_t skipped_silent_chunks_ = 0;
+    uint64_t silence_bypass_chunks_ = 0;
+    float dead_input_ms_ = 0.0f;
+    bool dead_input_warned_ = false;
+    NoiseFloorTracker noise_floor_;
+    uint32_t comfort_noise_seed_ = 22222;
+    bool deterministic_ = false;
+    double deterministic_elapsed_ms_ = AECProcessor::kDeterministicElapsedMs;  // Reported per call when deterministic_
+    uint64_t empty_input_calls_ = 0;
+    LatencyHistogram latency_;
+    RealTimeFactor rtf_;
+    HealthMonitor health_;
+    PathChangeDetector path_change_;
+    bool path_change_pending_ = false;
+    uint64_t path_changes_detected_ = 0;
+    uint64_t adaptation_throttled_chunks_ = 0;
+    uint64_t idle_resets_ = 0;
+    AECClock clock_;  // Empty = steady_clock
+    std::optional<std::chrono::steady_clock::time_point> last_capture_time_;  // Unset until the first call
+    bool chunk_throttled_ = false;  // Set by ProcessNlms, reported by CalculateMetrics
+    bool any_stage_active_ = true;  // See UpdateActiveStages
+    bool converged_ = false;        // Last state reported via events
+    float transition_ms_ = 0.0f;    // Time ERLE has disagreed with converged_
+    bool ever_converged_ = false;   // Since the last Reset
+    float converged_for_ms_ = 0.0f;
+    float echo_active_ms_ = 0.0f;   // Echo-active audio since the last Reset
+    float echo_check_ms_ = 0.0f;
+    bool echo_reported_ = false;
+    std::vector<AECEvent> events_;
+    bool overloaded_ = false;
+    bool overload_bypass_ = false;
+    bool suboptimal_config_ = false;  // Set by CheckFilterLength on every Initialize
+    int overload_streak_ = 0;
+    uint64_t overload_events_ = 0;
+    ErleEstimator erle_;
+    HistoryRing erle_history_;
+    DecimatedHistory capture_recent_;  // Raw mic, for GetCrossCorrelation
+    DecimatedHistory render_recent_;   // Render after reference gain
+    size_t pending_render_samples_ = 0;
+    int watchdog_threshold_ = 0;
+    int overrun_streak_ = 0;
+    double overrun_streak_ms_ = 0.0;
+    bool watchdog_pending_ = false;
+    AECWatchdogEvent watchdog_event_;
+    AECLogSink log_sink_;
+    AECLogLevel log_level_ = AECLogLevel::kWarn;
+    std::chrono::steady_clock::time_point log_window_start_;
+    int log_window_count_ = 0;
+    uint64_t log_suppressed_ = 0;
+    std::unique_ptr<DebugDumpWriter> debug_dump_;
+    std::vector<float> dump_mic_;              // Capture input copy for the current chunk
+    std::deque<float> dump_reference_;         // Render awaiting its capture chunk
+    std::vector<float> dump_chunk_reference_;  // Reference span for the current chunk
+    float debug_capture_seconds_ = 0.0f;       // 0 = in-memory capture off
+    HistoryRing capture_mic_;
+    HistoryRing capture_reference_;
+    HistoryRing capture_output_;
+    uint64_t input_samples_processed_ = 0;     // Lifetime; see AECMetrics
+    uint64_t output_samples_produced_ = 0;
+    uint64_t reference_samples_consumed_ = 0;
+    double audio_seconds_processed_ = 0.0;
+    uint64_t underrun_count_ = 0;
+    uint64_t underrun_samples_ = 0;
+    uint64_t underrun_zero_fill_count_ = 0;
+    uint64_t underrun_hold_count_ = 0;
+    uint64_t dropped_input_events_ = 0;
+    uint64_t dropped_input_samples_ = 0;
+    uint64_t scratch_grow_count_ = 0;
+    size_t scratch_capacity_ = 0;    // ScratchCapacity after the last capture call
+    uint64_t overflow_count_ = 0;
+    uint64_t overflow_samples_ = 0;
+    ReferenceDrift reference_drift_;
+    ChunkLevels render_levels_;       // Most recent render chunk, gates double-talk
+    AECCallStats last_call_stats_;    // Stats for the most recent capture call
 };
 
 AECProcessor::AECProcessor(const AECConfig& config) 
@@ -370,24 +3394,587 @@ AECProcessor::AECProcessor(const AECConfig& config)
 
 AECProcessor::~AECProcessor() = default;
 
+// Queued reference chunks before the feeder stops queueing and hands them to
+// the engine itself, about 640 ms of 10 ms chunks with no capture calls
+constexpr size_t kMaxPendingReferenceChunks = 64;
+
+void AECProcessor::DrainReference() {
+    {
+        std::lock_guard<std::mutex> lock(reference_mutex_);
+        draining_reference_.swap(pending_reference_);
+        draining_reference_chunks_.swap(pending_reference_chunks_);
+    }
+    size_t offset = 0;
+    for (size_t chunk : draining_reference_chunks_) {
+        impl_->ProcessRenderAudio(draining_reference_.data() + offset, chunk);
+        offset += chunk;
+    }
+    draining_reference_.clear();  // Capacity is kept for the next swap
+    draining_reference_chunks_.clear();
+}
+
 bool AECProcessor::Initialize(int sample_rate, int num_channels) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
     return impl_->Initialize(sample_rate, num_channels);
 }
 
+void AECProcessor::InitializePassthrough(int sample_rate, const std::string& error) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
+    impl_->InitializePassthrough(sample_rate, error);
+}
+
 void AECProcessor::ProcessRenderAudio(const float* data, size_t num_samples) {
-    impl_->ProcessRenderAudio(data, num_samples);
+    bool backlogged = false;
+    {
+        std::lock_guard<std::mutex> lock(reference_mutex_);
+        last_reference_time_ = reference_clock_ ? reference_clock_() : std::chrono::steady_clock::now();
+        pending_reference_.insert(pending_reference_.end(), data, data + num_samples);
+        pending_reference_chunks_.push_back(num_samples);
+        backlogged = pending_reference_chunks_.size() >= kMaxPendingReferenceChunks;
+    }
+    if (backlogged) {
+        std::lock_guard<std::mutex> lock(mutex_);
+        DrainReference();
+    }
 }
 
 void AECProcessor::ProcessCaptureAudio(const float* input, float* output, size_t num_samples) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->ResetIfIdle();
+    DrainReference();
     impl_->ProcessCaptureAudio(input, output, num_samples);
 }
 
+void AECProcessor::ProcessCaptureAudio(const float* input, float* output, size_t num_samples,
+                                       const AECCallOptions& options) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->ResetIfIdle();
+    DrainReference();
+    impl_->ProcessCaptureAudio(input, output, num_samples, options);
+}
+
+size_t AECProcessor::Warmup(const float* mic, const float* reference, size_t num_samples) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
+    return impl_->Warmup(mic, reference, num_samples);
+}
+
+void AECProcessor::EnsureCapacity(size_t max_samples) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    // Between capture calls about one chunk of reference queues up; two
+    // leave room for jitter without reserving the whole backlog cap
+    {
+        std::lock_guard<std::mutex> reference_lock(reference_mutex_);
+        pending_reference_.reserve(max_samples * 2);
+        pending_reference_chunks_.reserve(kMaxPendingReferenceChunks);
+    }
+    draining_reference_.reserve(max_samples * 2);  // Swapped with pending_reference_
+    draining_reference_chunks_.reserve(kMaxPendingReferenceChunks);
+    impl_->EnsureCapacity(max_samples);
+}
+
+void AECProcessor::ProcessAndFeed(const float* input, float* output, size_t num_samples, float gain) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->ResetIfIdle();
+    DrainReference();
+    impl_->ProcessAndFeed(input, output, num_samples, gain);
+}
+
+bool AECProcessor::ProcessWithAsrTap(const float* input, float* output, size_t num_samples,
+                                     std::vector<float>* asr) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->ResetIfIdle();
+    DrainReference();
+    return impl_->ProcessWithAsrTap(input, output, num_samples, asr);
+}
+
+void AECProcessor::NotifyDroppedInput(size_t num_samples) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
+    impl_->NotifyDroppedInput(num_samples);
+}
+
+AECSelfTestResult RunSelfTest(int sample_rate, const std::string& engine) {
+    constexpr float kToneHz = 440.0f;
+    constexpr float kAmplitude = 0.3f;
+    constexpr float kEchoGain = 0.5f;
+    constexpr int kEchoDelayMs = 20;
+    constexpr int kDurationMs = 4000;
+    constexpr int kMeasureMs = 1000;      // Measure only the converged tail
+    constexpr float kMinErleDb = 10.0f;
+
+    AECConfig config;
+    config.enable_ns = false;              // Measure echo removal only
+    config.engine = engine;
+    AECProcessor processor(config);
+    AECSelfTestResult result;
+    if (!processor.Initialize(sample_rate, 1)) {
+        return result;
+    }
+
+    const size_t frame = FrameSamples(sample_rate, config.frame_duration_ms);
+    const size_t delay = static_cast<size_t>(sample_rate * kEchoDelayMs / 1000);
+    const size_t total = static_cast<size_t>(sample_rate) * kDurationMs / 1000;
+    const size_t measure_from = total - static_cast<size_t>(sample_rate) * kMeasureMs / 1000;
+
+    std::vector<float> reference(total + delay);
+    for (size_t i = 0; i < reference.size(); i++) {
+        reference[i] = kAmplitude * std::sin(2.0f * static_cast<float>(M_PI) * kToneHz * i / sample_rate);
+    }
+
+    std::vector<float> mic(frame);
+    std::vector<float> out(frame);
+    double input_energy = 0.0;
+    double output_energy = 0.0;
+
+    for (size_t offset = 0; offset + frame <= total; offset += frame) {
+        processor.ProcessRenderAudio(reference.data() + delay + offset, frame);
+        for (size_t i = 0; i < frame; i++) {
+            mic[i] = kEchoGain * reference[offset + i];  // Lags the render by `delay` samples
+        }
+        processor.ProcessCaptureAudio(mic.data(), out.data(), frame);
+
+        if (offset >= measure_from) {
+            for (size_t i = 0; i < frame; i++) {
+                input_energy += mic[i] * mic[i];
+                output_energy += out[i] * out[i];
+            }
+        }
+    }
+
+    result.erle_db = EnergyRatioDb(static_cast<float>(input_energy), static_cast<float>(output_energy));
+    result.passed = result.erle_db >= kMinErleDb;
+    return result;
+}
+
+namespace {
+
+// Both directions scale by 32768, the int16 input convention elsewhere in the
+// addon, so PCM that passes through unchanged comes back bit-exact; +1.0
+// clamps to 32767.
+constexpr float kPcm16Scale = 32768.0f;
+
+// Up to max_samples 16-bit little-endian samples from in, as floats. Short
+// only at the end of the stream; a lone trailing byte is dropped.
+size_t ReadPcm16(std::istream& in, std::vector<uint8_t>* bytes, float* samples, size_t max_samples) {
+    bytes->resize(max_samples * 2);
+    in.read(reinterpret_cast<char*>(bytes->data()), static_cast<std::streamsize>(bytes->size()));
+    size_t count = static_cast<size_t>(in.gcount()) / 2;
+    for (size_t i = 0; i < count; i++) {
+        uint16_t raw = static_cast<uint16_t>((*bytes)[2 * i] | ((*bytes)[2 * i + 1] << 8));
+        samples[i] = static_cast<int16_t>(raw) / kPcm16Scale;
+    }
+    return count;
+}
+
+void WritePcm16(std::ostream& out, const float* samples, size_t count, std::vector<uint8_t>* bytes) {
+    bytes->resize(count * 2);
+    for (size_t i = 0; i < count; i++) {
+        long scaled = std::lround(std::clamp(samples[i], -1.0f, 1.0f) * kPcm16Scale);
+        uint16_t raw = static_cast<uint16_t>(static_cast<int16_t>(std::clamp(scaled, -32768L, 32767L)));
+        (*bytes)[2 * i] = static_cast<uint8_t>(raw & 0xff);
+        (*bytes)[2 * i + 1] = static_cast<uint8_t>(raw >> 8);
+    }
+    out.write(reinterpret_cast<const char*>(bytes->data()), static_cast<std::streamsize>(bytes->size()));
+}
+
+// Shared loop: read_frame fills mic and reference with up to frame samples
+// each and reports how many it got; a short mic frame is the last one
+template <typename ReadFrame>
+AECStreamStats RunPcmStream(AECProcessor& processor, ReadFrame read_frame, std::ostream& out) {
+    AECStreamStats stats;
+    const size_t frame = processor.GetFrameSize();
+    if (frame == 0) return stats;
+
+    std::vector<float> mic(frame);
+    std::vector<float> reference(frame);
+    std::vector<float> clean(frame);
+    std::vector<uint8_t> bytes;
+    while (true) {
+        size_t mic_count = 0;
+        size_t reference_count = 0;
+        read_frame(mic.data(), reference.data(), frame, &mic_count, &reference_count);
+        if (mic_count == 0) break;
+        std::fill(mic.begin() + mic_count, mic.end(), 0.0f);
+        std::fill(reference.begin() + reference_count, reference.end(), 0.0f);
+
+        processor.ProcessRenderAudio(reference.data(), frame);
+        processor.ProcessCaptureAudio(mic.data(), clean.data(), frame);
+        WritePcm16(out, clean.data(), mic_count, &bytes);
+        if (!out) return stats;
+
+        stats.frames++;
+        stats.mic_samples += mic_count;
+        stats.reference_samples += reference_count;
+        if (mic_count < frame) break;
+    }
+    out.flush();
+    stats.ok = static_cast<bool>(out);
+    return stats;
+}
+
+} // namespace
+
+AECStreamStats ProcessPcmStreams(AECProcessor& processor, std::istream& mic, std::istream& reference,
+                                 std::ostream& out) {
+    std::vector<uint8_t> mic_bytes;
+    std::vector<uint8_t> reference_bytes;
+    return RunPcmStream(processor, [&](float* mic_frame, float* reference_frame, size_t frame,
+                                       size_t* mic_count, size_t* reference_count) {
+        *mic_count = ReadPcm16(mic, &mic_bytes, mic_frame, frame);
+        *reference_count = ReadPcm16(reference, &reference_bytes, reference_frame, frame);
+    }, out);
+}
+
+AECStreamStats ProcessInterleavedPcmStream(AECProcessor& processor, std::istream& input, std::ostream& out) {
+    std::vector<uint8_t> bytes;
+    std::vector<float> interleaved;
+    return RunPcmStream(processor, [&](float* mic_frame, float* reference_frame, size_t frame,
+                                       size_t* mic_count, size_t* reference_count) {
+        interleaved.resize(frame * 2);
+        size_t pairs = ReadPcm16(input, &bytes, interleaved.data(), frame * 2) / 2;
+        for (size_t i = 0; i < pairs; i++) {
+            mic_frame[i] = interleaved[2 * i];
+            reference_frame[i] = interleaved[2 * i + 1];
+        }
+        *mic_count = pairs;
+        *reference_count = pairs;
+    }, out);
+}
+
 void AECProcessor::SetEchoCancellationEnabled(bool enabled) {
+    std::lock_guard<std::mutex> lock(mutex_);
     impl_->SetEchoCancellationEnabled(enabled);
 }
 
+void AECProcessor::SetNoiseSuppression(bool enabled, int level) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetNoiseSuppression(enabled, level);
+}
+
+void AECProcessor::FillReferenceAge(AECMetrics* metrics) const {
+    std::lock_guard<std::mutex> lock(reference_mutex_);
+    if (last_reference_time_) {
+        auto now = reference_clock_ ? reference_clock_() : std::chrono::steady_clock::now();
+        std::chrono::duration<double, std::milli> since = now - *last_reference_time_;
+        metrics->ms_since_last_reference = std::max(0.0, since.count());
+    }
+}
+
 AECMetrics AECProcessor::GetMetrics() const {
-    return impl_->GetMetrics();
+    std::lock_guard<std::mutex> lock(mutex_);
+    AECMetrics metrics = impl_->GetMetrics();
+    FillReferenceAge(&metrics);
+    return metrics;
+}
+
+AECCallStats AECProcessor::GetLastCallStats() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetLastCallStats();
+}
+
+void AECProcessor::ResetMetrics() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->ResetMetrics();
+}
+
+AECMetrics AECProcessor::GetAndResetMetrics() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    AECMetrics metrics = impl_->GetAndResetMetrics();
+    FillReferenceAge(&metrics);
+    return metrics;
+}
+
+bool AECProcessor::Reset(bool reset_metrics) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
+    return impl_->Reset(reset_metrics);
+}
+
+AECHealthReport AECProcessor::HealthCheck(bool auto_recover) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
+    return impl_->HealthCheck(auto_recover);
+}
+
+bool AECProcessor::ApplyPreset(const std::string& name) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->ApplyPreset(name);
+}
+
+bool AECProcessor::SetEmptyInputMode(const std::string& mode) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->SetEmptyInputMode(mode);
+}
+
+bool AECProcessor::SetUnderrunStrategy(const std::string& strategy) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->SetUnderrunStrategy(strategy);
+}
+
+void AECProcessor::FillEmptyInput(float* output, size_t num_samples) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->FillEmptyInput(output, num_samples);
+}
+
+void AECProcessor::SetDeterministic(bool enabled, uint32_t seed, double elapsed_ms) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetDeterministic(enabled, seed, elapsed_ms);
+}
+
+bool AECProcessor::SetFilterLength(int blocks) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->SetFilterLength(blocks);
+}
+
+bool AECProcessor::SetSampleRate(int sample_rate) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
+    return impl_->SetSampleRate(sample_rate);
+}
+
+bool AECProcessor::Reconfigure(const AECConfig& config, int sample_rate, std::vector<std::string>* rebuild_fields) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
+    return impl_->Reconfigure(config, sample_rate, rebuild_fields);
+}
+
+bool AECProcessor::SetSupportedSampleRates(const std::vector<int>& rates) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->SetSupportedSampleRates(rates);
+}
+
+void AECProcessor::SetLoudnessNormalization(bool enabled, float target_db, float max_gain_db) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetLoudnessNormalization(enabled, target_db, max_gain_db);
+}
+
+void AECProcessor::SetOutputAgc(bool enabled, float target_db, float max_gain_db) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetOutputAgc(enabled, target_db, max_gain_db);
+}
+
+void AECProcessor::SetLimiter(bool enabled, float threshold_db) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetLimiter(enabled, threshold_db);
+}
+
+void AECProcessor::SetSilentChunkSkip(bool enabled, float floor_db) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetSilentChunkSkip(enabled, floor_db);
+}
+
+void AECProcessor::SetSilenceBypass(bool enabled, float threshold_db) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetSilenceBypass(enabled, threshold_db);
+}
+
+void AECProcessor::SetOverloadDetection(float rtf_threshold, int consecutive_chunks, bool auto_bypass) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetOverloadDetection(rtf_threshold, consecutive_chunks, auto_bypass);
+}
+
+void AECProcessor::SetAutoReconvergeOnPathChange(bool enabled) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetAutoReconvergeOnPathChange(enabled);
+}
+
+void AECProcessor::SetReferenceGain(float gain) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetReferenceGain(gain);
+}
+
+void AECProcessor::SetOverrunDrainFraction(float fraction) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetOverrunDrainFraction(fraction);
+}
+
+void AECProcessor::SetInputGain(float gain_db) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetInputGain(gain_db);
+}
+
+void AECProcessor::SetOutputGain(float gain_db) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetOutputGain(gain_db);
+}
+
+void AECProcessor::SetPreEmphasis(float coefficient) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetPreEmphasis(coefficient);
+}
+
+void AECProcessor::SetDoubleTalkSensitivity(float sensitivity) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetDoubleTalkSensitivity(sensitivity);
+}
+
+bool AECProcessor::SetAdaptationRate(float rate) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->SetAdaptationRate(rate);
+}
+
+void AECProcessor::SetAutoResetAfterIdle(float idle_ms, bool reset_filter) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetAutoResetAfterIdle(idle_ms, reset_filter);
+}
+
+void AECProcessor::SetClock(AECClock clock) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    {
+        std::lock_guard<std::mutex> reference_lock(reference_mutex_);
+        reference_clock_ = clock;
+        last_reference_time_.reset();  // Times from the old source aren't comparable
+    }
+    impl_->SetClock(std::move(clock));
+}
+
+void AECProcessor::SetErleHistoryLength(size_t length) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetErleHistoryLength(length);
+}
+
+std::vector<float> AECProcessor::GetErleHistory() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetErleHistory();
+}
+
+std::vector<float> AECProcessor::GetLastRawInput() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetLastRawInput();
+}
+
+size_t AECProcessor::RefBufferAvailable() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->RefBufferAvailable();
+}
+
+AECCrossCorrelation AECProcessor::GetCrossCorrelation() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetCrossCorrelation();
+}
+
+AECDelayEstimate AECProcessor::CalibrateDelay(float max_delay_ms) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
+    return impl_->CalibrateDelay(max_delay_ms);
+}
+
+bool AECProcessor::StartDebugDump(const std::string& path) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->StartDebugDump(path);
+}
+
+AECDebugDumpStats AECProcessor::StopDebugDump() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->StopDebugDump();
+}
+
+bool AECProcessor::IsDebugDumping() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->IsDebugDumping();
+}
+
+void AECProcessor::EnableDebugCapture(float max_seconds) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->EnableDebugCapture(max_seconds);
+}
+
+bool AECProcessor::IsDebugCapturing() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->IsDebugCapturing();
+}
+
+AECDebugCapture AECProcessor::GetDebugCapture() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetDebugCapture();
+}
+
+void AECProcessor::SetLogSink(AECLogSink sink, AECLogLevel level) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetLogSink(std::move(sink), level);
+}
+
+void AECProcessor::SetRealtimeWatchdog(int consecutive_chunks) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetRealtimeWatchdog(consecutive_chunks);
+}
+
+bool AECProcessor::ConsumeWatchdogEvent(AECWatchdogEvent* event) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->ConsumeWatchdogEvent(event);
+}
+
+std::vector<AECEvent> AECProcessor::TakeEvents() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->TakeEvents();
+}
+
+AECTuning AECProcessor::GetTuning() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetTuning();
+}
+
+std::unique_ptr<AECProcessor> AECProcessor::Clone() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
+    if (impl_->GetSampleRate() == 0) {
+        return nullptr;
+    }
+    auto clone = std::make_unique<AECProcessor>(impl_->GetConfig());
+    if (!clone->impl_->CopyStateFrom(*impl_)) {
+        return nullptr;
+    }
+    return clone;
+}
+
+std::vector<uint8_t> AECProcessor::SaveState() {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
+    return impl_->SaveState();
+}
+
+AECStateResult AECProcessor::RestoreState(const uint8_t* data, size_t size) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    DrainReference();
+    return impl_->RestoreState(data, size);
+}
+
+AECConfig AECProcessor::GetConfig() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetConfig();
+}
+
+int AECProcessor::GetSampleRate() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetSampleRate();
+}
+
+int AECProcessor::GetNumChannels() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetNumChannels();
+}
+
+size_t AECProcessor::GetFrameSize() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetFrameSize();
+}
+
+size_t AECProcessor::GetChunkGranularity(bool render) const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetChunkGranularity(render);
+}
+
+size_t AECProcessor::GetMaxInputSamples() const {
+    std::lock_guard<std::mutex> lock(mutex_);
+    return impl_->GetMaxInputSamples();
+}
+
+void AECProcessor::SetMaxInputSamples(int max_samples) {
+    std::lock_guard<std::mutex> lock(mutex_);
+    impl_->SetMaxInputSamples(max_samples);
 }
 
 } // namespace kakarot
\ No newline at end of file
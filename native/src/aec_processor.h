@@ -1,26 +1,444 @@
 #pragma once
 
+#include <array>
+#include <chrono>
 #include <memory>
 #include <vector>
 #include <string>
+#include <cstdint>
+#include <functional>
+#include <iosfwd>
+#include <mutex>
+#include <optional>
 
 namespace kakarot {
 
+// Log records at or below the sink's level are forwarded; error is most severe
+enum class AECLogLevel { kError = 0, kWarn, kInfo, kDebug, kTrace };
+
+// Why AECProcessor::RestoreState refused a snapshot
+enum class AECStateResult { kOk = 0, kMalformed, kVersionMismatch, kConfigMismatch, kInitFailed };
+using AECLogSink = std::function<void(AECLogLevel level, const std::string& message)>;
+
+// Time source for idle detection; SetClock swaps it for tests
+using AECClock = std::function<std::chrono::steady_clock::time_point()>;
+
+// "error", "warn", "info", "debug", "trace"
+bool ParseLogLevel(const std::string& name, AECLogLevel* level);
+const char* LogLevelName(AECLogLevel level);
+
+// AEC3 works in 64-sample blocks; these bound filter_length_blocks to a
+// 64..2048 sample echo path.
+constexpr int kMinFilterLengthBlocks = 1;
+constexpr int kMaxFilterLengthBlocks = 32;
+
+// Bounds and ramp length for AECConfig::input_gain_db / output_gain_db
+constexpr float kMinStageGainDb = -60.0f;
+constexpr float kMaxStageGainDb = 40.0f;
+constexpr float kGainRampMs = 10.0f;
+
+// Bounds for AECConfig::adaptation_rate; 2 puts NLMS at a step size of 1,
+// well inside its 0..2 stability range
+constexpr float kMinAdaptationRate = 0.1f;
+constexpr float kMaxAdaptationRate = 2.0f;
+
+// In-memory debug capture keeps three float tracks, ~35 MB at 48 kHz for this
+constexpr float kMaxDebugCaptureSeconds = 60.0f;
+
+// ERLE history keeps one value per capture call: ten minutes of 10 ms calls
+constexpr size_t kMaxErleHistoryLength = 60000;
+
+// Suppression tuning bundle. Presets ("gentle", "balanced", "aggressive")
+// fill every field; explicit AECConfig overrides are applied on top.
+struct AECTuning {
+    std::string preset;
+    int filter_length_blocks = 24;          // AEC3 refined filter length
+    int initial_filter_length_blocks = 18;  // AEC3 filter length during startup
+    float enr_transparent_lf = 0.15f;       // Low-band echo-to-nearend ratio left untouched
+    float enr_suppress_lf = 0.2f;           // Low-band ratio fully suppressed
+    float enr_transparent_hf = 0.04f;
+    float enr_suppress_hf = 0.05f;
+    float max_gain_during_echo = 0.01f;     // High-band gain ceiling while echo is present
+    int noise_suppression_level = 1;        // 0=low, 1=moderate, 2=high, 3=veryHigh
+};
+
 struct AECConfig {
     bool enable_aec = true;
     bool enable_agc = false;
     bool enable_ns = true;
     bool disable_aec_on_headphones = true;
     int frame_duration_ms = 10;
+    // Rates Initialize/SetSampleRate accept; each must also pass IsValidStreamFormat
+    std::vector<int> supported_sample_rates = {8000, 16000, 32000, 44100, 48000};
+    float level_smoothing_ms = 300.0f;  // Time constant for level meters
+    float peak_hold_ms = 1500.0f;       // How long a held peak stays before decaying
+    float peak_decay_db_per_sec = 20.0f;
+
+    std::string label;                  // Names the processor in log lines and listings; not a setting
+    std::string engine = "webrtc";      // "webrtc" (AEC3) or "nlms" (built-in fallback)
+    bool fallback_to_passthrough = false;  // Creation policy: pass audio through if Initialize fails
+    bool test_init_failure = false;     // Testing only: creation initializes at an unsupported rate
+    std::string preset = "aggressive";  // Suppression preset, see AECTuning
+    int filter_length_blocks = 0;       // Override preset filter length (0 = preset)
+    int noise_suppression_level = -1;   // Override preset NS level (-1 = preset)
+
+    // Slow loudness normalization of the output toward a consistent level for STT
+    bool enable_loudness_normalization = false;
+    float loudness_target_db = -20.0f;  // Target gated RMS in dBFS
+    float loudness_max_gain_db = 12.0f; // Applied gain stays within +/- this
+
+    // Fast output AGC (attack/release) for near-end level swings. Independent
+    // of enable_agc, which builds WebRTC's AGC2 into the AudioProcessing chain.
+    bool enable_output_agc = false;
+    float agc_target_db = -18.0f;   // Target chunk RMS in dBFS
+    float agc_max_gain_db = 20.0f;  // Boost ceiling; attenuation is unbounded
+
+    // Soft-knee peak limiter, the last output stage. While it is enabled the
+    // AGC and loudness stages leave overs for it instead of hard-clipping.
+    bool enable_limiter = false;
+    float limiter_threshold_db = -1.0f;  // Output ceiling in dBFS
+
+    // Pass silent chunks through without running AEC3 when render is silent too
+    bool skip_silent_chunks = false;
+    float silent_chunk_floor_db = -70.0f;  // Chunk RMS in dBFS; keep conservative
+
+    // Pass the mic through untouched while the render (far end) is silent
+    bool enable_silence_bypass = false;
+    float silence_bypass_threshold_db = -60.0f;  // Render chunk RMS in dBFS
+
+    float dead_input_warning_ms = 3000.0f;  // Warn after this much pure digital zero
+
+    // Reject chunks that don't line up instead of buffering the remainder:
+    // capture must be whole frames, render whole multiples of
+    // render_granularity (0 = the frame size). Checked by the addon.
+    bool strict_chunk_size = false;
+    int render_granularity = 0;
+
+    // Longest capture chunk accepted, bounding how long one call can hold the
+    // audio thread; 0 = no limit. Checked by the addon.
+    int max_input_samples = 48000;
+
+    // What a zero-length capture call (usually a stalled device) returns:
+    // "empty", or one frame of "silence" or "comfortNoise" at the noise floor
+    std::string empty_input_mode = "empty";
+
+    // What an underrun feeds the canceller in place of the missing reference:
+    // "zeros", or "hold" to replay the last reference frame it consumed
+    std::string underrun_strategy = "zeros";
+
+    // Share of the one-second reference buffer kept when render overruns it:
+    // 0.5 drops the oldest half plus the excess, 1.0 only the excess
+    float overrun_drain_fraction = 0.5f;
+
+    // Linear gain applied to render audio before cancellation, so the digital
+    // reference can track the speaker output volume that shapes the echo
+    float reference_gain = 1.0f;
+
+    // Gain in dB on the mic before the canceller and on its output after the
+    // output stages, ramped over kGainRampMs on change and clipped to [-1, 1]
+    float input_gain_db = 0.0f;
+    float output_gain_db = 0.0f;
+
+    // First-order pre-emphasis (x[n] - a*x[n-1]) of the mic ahead of the
+    // canceller, undone on the output before the output stages. 0 disables;
+    // ~0.97 tilts the filter's effort toward the speech band.
+    float pre_emphasis = 0.0f;
+
+    // NLMS engine: slow adaptation while a Geigel detector sees near-end
+    // speech (mic peak above (1 - sensitivity) x recent reference peak), so
+    // double-talk can't drag the filter off the echo path. The filter keeps
+    // cancelling meanwhile. 0 disables; AEC3 protects its own filter.
+    float double_talk_sensitivity = 0.5f;
+
+    // Scales how fast the adaptive filter learns, kMinAdaptationRate to
+    // kMaxAdaptationRate. Above 1 it converges sooner after a start or an echo
+    // path change but tracks noise and double-talk more, leaving more residual
+    // echo and artifacts once converged; below 1 it settles slower but steadier.
+    // NLMS: step size kStepSize x rate. AEC3: its coarse filter rates, capped at 1.
+    float adaptation_rate = 1.0f;
+
+    // A capture call after a pause longer than this drops the queued
+    // reference, which no longer lines up with the mic, and with
+    // idle_reset_filter also resets the canceller since the echo path has
+    // probably changed. 0 disables.
+    float auto_reset_after_idle_ms = 0.0f;
+    bool idle_reset_filter = false;
+
+    size_t erle_history_length = 1000;  // Smoothed ERLE values kept, one per capture call; at most kMaxErleHistoryLength
+
+    // Overload: the recent real-time factor above overload_rtf_threshold for
+    // overload_consecutive_chunks calls in a row. Clears once it stays below
+    // half the threshold for as many calls. 0 chunks disables detection.
+    float overload_rtf_threshold = 0.8f;
+    int overload_consecutive_chunks = 50;
+    bool auto_bypass_on_overload = false;  // Pass the mic through while overloaded
+
+    // Reset adaptation when a sudden, sustained ERLE drop signals the echo path
+    // changed (laptop moved, headphones plugged in)
+    bool auto_reconverge_on_path_change = false;
+
+    // State-transition events. "converged" fires after the smoothed ERLE stays
+    // at or above converged_erle_db for converged_hold_ms of echo-active audio,
+    // "diverged" after it stays below for as long. "echoDetected" fires once per
+    // episode when, unconverged with ERLE under echo_detect_max_erle_db, the
+    // mic/render correlation peak reaches echo_detect_correlation.
+    float converged_erle_db = 10.0f;
+    float converged_hold_ms = 2000.0f;
+    float echo_detect_correlation = 0.5f;
+    float echo_detect_max_erle_db = 6.0f;
+
+    // Convergence state stays "cold" until this much echo-active audio has
+    // been seen since the last reset; see AECMetrics::convergence_state
+    float convergence_cold_ms = 500.0f;
 };
 
+// Frame lengths accepted for frame_duration_ms: 10, 20 or 40. AEC3 consumes
+// exactly 10 ms per ProcessStream call, so longer frames run as 2 or 4 blocks.
+bool IsValidFrameDuration(int frame_duration_ms);
+
+// A valid frame length that comes out to a whole number of samples
+bool IsValidStreamFormat(int sample_rate, int frame_duration_ms);
+
+// The WebRTC / Opus frame sizes, looked up rather than derived:
+//            10 ms   20 ms   40 ms
+//   8 kHz      80     160     320
+//  16 kHz     160     320     640
+//  32 kHz     320     640    1280
+//  48 kHz     480     960    1920
+// Other valid formats (44.1 kHz, 96 kHz, ...) still work.
+bool IsCanonicalFrameFormat(int sample_rate, int frame_duration_ms);
+
+// Samples per frame, or 0 if the format isn't valid
+size_t FrameSamples(int sample_rate, int frame_duration_ms);
+
+// A valid stream format whose rate is in config.supported_sample_rates
+bool IsSupportedSampleRate(const AECConfig& config, int sample_rate);
+
+// "8000, 16000, ..." for error messages
+std::string DescribeSupportedSampleRates(const AECConfig& config);
+
+// Echo canceller implementations selectable via AECConfig::engine
+bool IsKnownEngine(const std::string& engine);
+
+// Clamp a 32-bit intermediate to int16 range
+int16_t SaturateInt16(int32_t value);
+
+// Sum num_sources int16 streams sample by sample in 32 bits and saturate, so
+// several near-full-scale playback streams clip instead of wrapping into
+// clicks. Every reference-mixing path goes through this.
+void MixInt16Saturating(const int16_t* const* sources, size_t num_sources, size_t num_samples,
+                        int16_t* output);
+
+// Values accepted for AECConfig::empty_input_mode
+bool IsKnownEmptyInputMode(const std::string& mode);
+
+// Values accepted for AECConfig::underrun_strategy
+bool IsKnownUnderrunStrategy(const std::string& strategy);
+
+// Resolve a preset plus the config's explicit overrides. Returns false for an unknown preset.
+bool ResolveTuning(const AECConfig& config, AECTuning* tuning);
+
 struct AECMetrics {
     float echo_return_loss = 0.0f;
     float echo_return_loss_enhancement = 0.0f;
     int render_delay_ms = 0;
+    float calibrated_delay_ms = 0.0f;  // Applied by CalibrateDelay; 0 until then
+    // Wall-clock time since reference audio last reached the canceller, -1 if
+    // none yet. Queued reference is handed over at the next capture call, so
+    // this lags the feed by at most one capture interval.
+    double ms_since_last_reference = -1.0;
     bool aec_converged = false;
     float rms_level = 0.0f;
     float peak_level = 0.0f;
+
+    // Exponentially smoothed levels in dBFS (-100 = silence)
+    float input_rms_dbfs = -100.0f;
+    float input_peak_dbfs = -100.0f;
+    float output_rms_dbfs = -100.0f;
+    float output_peak_dbfs = -100.0f;
+
+    // Peak-hold meters in dBFS: per-chunk peak and held peak with decay
+    float input_peak_instant_dbfs = -100.0f;
+    float input_peak_hold_dbfs = -100.0f;
+    float output_peak_instant_dbfs = -100.0f;
+    float output_peak_hold_dbfs = -100.0f;
+
+    float loudness_gain_db = 0.0f;  // Gain currently applied by loudness normalization
+    float agc_gain_db = 0.0f;       // Gain currently applied by the output AGC
+    float limiter_gain_reduction_db = 0.0f;      // Largest limiter reduction in the last chunk
+    float limiter_max_gain_reduction_db = 0.0f;  // Largest since the last metrics reset
+    uint64_t skipped_silent_chunks = 0;
+    uint64_t silence_bypass_chunks = 0;  // Chunks passed through because render was silent
+
+    // Consecutive duration of exactly-zero mic input (not merely quiet audio)
+    float dead_input_ms = 0.0f;
+    bool dead_input = false;  // dead_input_ms crossed dead_input_warning_ms
+
+    float noise_floor_dbfs = -100.0f;  // Estimated ambient noise level of the mic input
+
+    std::string engine;  // Echo canceller actually running: "webrtc", "nlms" or "naive"
+
+    // Measured ERLE (mic vs output energy) over chunks where the far end is
+    // playing. Near-end speech inside those chunks pulls the estimate down.
+    float erle_instant_db = 0.0f;  // Smoothed over ~1s
+    float erle_average_db = 0.0f;  // Since the last ResetMetrics
+
+    // ProcessCaptureAudio call counts by duration:
+    // <0.5ms, <1ms, <2ms, <4ms, <8ms, >=8ms
+    std::array<uint64_t, 6> latency_histogram{};
+
+    // ProcessCaptureAudio duration percentiles (bucket upper bounds, ~30%
+    // resolution) and the exact worst call, in ms
+    float latency_p50_ms = 0.0f;
+    float latency_p95_ms = 0.0f;
+    float latency_p99_ms = 0.0f;
+    float latency_max_ms = 0.0f;
+    uint32_t latency_max_chunk_samples = 0;  // Chunk length of the slowest call
+
+    // Render/capture balance: a capture chunk with less render audio delivered
+    // than it needs is an underrun (reference starvation, echo leaks through);
+    // render arriving more than 1 s ahead of capture is an overflow, and every
+    // sample the overrun drain discards is counted in overflow_samples
+    uint64_t underrun_count = 0;
+    uint64_t underrun_samples = 0;
+    // underrun_count split by the underrun_strategy in effect at the time
+    uint64_t underrun_zero_fill_count = 0;
+    uint64_t underrun_hold_count = 0;
+    uint64_t overflow_count = 0;
+    uint64_t overflow_samples = 0;
+    // That balance over its one-second capacity; 1 means the next render
+    // sample overruns
+    float ref_buffer_fill_ratio = 0.0f;
+
+    // Render samples delivered minus capture samples consumed (unclamped, so
+    // unlike the balance above it keeps growing), and its slope over the last
+    // minute of capture audio. A persistent non-zero rate means the two sides
+    // run at different sample rates.
+    int64_t reference_drift_samples = 0;
+    float reference_drift_per_second = 0.0f;
+
+    // Processing time / audio time of ProcessCaptureAudio calls: 0.02 means
+    // 2% of real time. Recent covers roughly the last 5 s of audio.
+    float real_time_factor = 0.0f;
+    float real_time_factor_recent = 0.0f;
+    double audio_processed_ms = 0.0;
+
+    // Lifetime sample counts, not cleared by ResetMetrics. Every non-empty
+    // capture call counts in full, whichever path (silent skip, bypass) it
+    // took; output also counts empty-input filler. Reference counts the render
+    // samples matched to capture, not those discarded for dropped input.
+    uint64_t input_samples_processed = 0;
+    uint64_t output_samples_produced = 0;
+    uint64_t reference_samples_consumed = 0;
+    double audio_seconds_processed = 0.0;  // Input at the rate it arrived, across rate changes
+
+    bool overloaded = false;
+    bool overload_bypass = false;  // Passing through because of auto_bypass_on_overload
+    uint64_t overload_events = 0;
+
+    uint64_t path_changes_detected = 0;
+    uint64_t adaptation_throttled_chunks = 0;  // Capture calls with adaptation slowed for double-talk
+    uint64_t idle_resets = 0;  // Resets after a capture pause over auto_reset_after_idle_ms
+
+    // The adaptive filter spans less than one frame, so it can't model most
+    // real echo paths. Accepted anyway; Initialize logs a suggested length.
+    bool suboptimal_config = false;
+    uint64_t empty_input_calls = 0;  // Zero-length ProcessCaptureAudio calls
+
+    // "cold": under convergence_cold_ms of echo-active audio since reset.
+    // "adapting": past that, not yet converged. "converged" / "diverged":
+    // set by the events of the same name, so they change exactly together.
+    std::string convergence_state = "cold";
+    float time_since_converged_ms = -1.0f;  // Audio time in "converged", -1 otherwise
+
+    // Capture audio the caller skipped and reported via NotifyDroppedInput
+    uint64_t dropped_input_events = 0;
+    uint64_t dropped_input_samples = 0;
+
+    // Capture calls that had to grow one of the buffers EnsureCapacity
+    // reserves; stays flat once it covers the chunk sizes in use
+    uint64_t scratch_grow_count = 0;
+
+    // False while in passthrough after a failed Initialize (see
+    // InitializePassthrough); init_error holds why the last Initialize failed
+    bool engine_active = true;
+    std::string init_error;
+};
+
+// Overrides for a single ProcessCaptureAudio call; nothing here outlives it
+struct AECCallOptions {
+    bool bypass = false;                // Copy input to output past the canceller; its queued reference is dropped
+    bool override_output_gain = false;
+    float output_gain_db = 0.0f;        // Ramp target instead of AECConfig::output_gain_db when overridden
+};
+
+// Measurements for a single ProcessCaptureAudio call
+struct AECCallStats {
+    float erle_db = 0.0f;      // Input vs output energy for this call
+    float input_rms = 0.0f;
+    float output_rms = 0.0f;
+    bool double_talk = false;  // Render active while near-end survived cancellation
+    bool adaptation_throttled = false;  // NLMS adaptation was slowed for double-talk
+    float elapsed_us = 0.0f;   // Wall-clock time spent in ProcessCaptureAudio
+};
+
+// Raised once when ProcessCaptureAudio has overrun the chunk's real-time
+// budget (its own audio duration) for the configured number of calls in a row
+struct AECWatchdogEvent {
+    int consecutive_chunks = 0;
+    float avg_processing_ms = 0.0f;  // Mean over the overrunning streak
+    float budget_ms = 0.0f;          // Audio duration of the latest chunk
+};
+
+// Cheap pathology checks over recent processing; true means the check failed.
+// The streak-based checks must persist for about 2 s of audio to fail.
+struct AECHealthReport {
+    bool output_stuck = false;         // Output constant while the input moves
+    bool output_exceeds_input = false; // Output well above input beyond applied output gain
+    bool reference_pinned = false;     // Render stuck at a non-zero constant or full scale
+    bool non_finite = false;           // NaN/Inf seen in capture input or output since the last check
+    bool healthy = true;
+    bool recovered = false;            // Engine was reset because of this report
+};
+
+struct AECDebugDumpStats {
+    uint64_t frames_written = 0;   // Per-channel samples in the WAV
+    uint64_t dropped_chunks = 0;   // Capture chunks lost to a full write queue
+};
+
+// Most recent mic / reference / output audio, sample-aligned the same way as
+// the debug dump WAV (reference zero-filled where render underran)
+struct AECDebugCapture {
+    std::vector<float> mic;
+    std::vector<float> reference;
+    std::vector<float> output;
+    int sample_rate = 0;
+};
+
+struct AECEvent {
+    std::string type;  // "converged", "diverged" or "echoDetected"
+    float erle_db = 0.0f;
+    float correlation = 0.0f;  // echoDetected only
+    float lag_ms = 0.0f;       // echoDetected only
+};
+
+// Peak of the normalized mic/render cross-correlation over recent audio. A
+// strong peak at a plausible lag confirms the mic really carries echo of the
+// render stream; a near-zero peak suggests swapped or unrelated inputs.
+struct AECCrossCorrelation {
+    float peak = 0.0f;    // |normalized correlation|, 0..1
+    float lag_ms = 0.0f;  // How far the mic lags the render at the peak
+};
+
+// Outcome of CalibrateDelay: the mic lag behind the reference with the
+// strongest correlation over recent audio. applied is false, leaving the
+// delay unchanged, when either signal was too quiet to measure or the engine
+// is AEC3, which estimates the delay itself.
+struct AECDelayEstimate {
+    bool applied = false;
+    int delay_samples = 0;
+    float delay_ms = 0.0f;
+    float correlation = 0.0f;  // |normalized correlation| at that lag, 0..1
 };
 
 class AECProcessor {
@@ -29,14 +447,187 @@ public:
     ~AECProcessor();
 
     bool Initialize(int sample_rate, int num_channels);
+    // For when Initialize failed: capture is copied to the output through the
+    // usual meters and metrics, render is measured but not queued, and the
+    // first capture call logs a warning. error overrides the reason Initialize
+    // recorded. A later successful Initialize (e.g. SetSampleRate) leaves it.
+    void InitializePassthrough(int sample_rate, const std::string& error = "");
+    // Queues the reference under its own lock and returns without waiting for
+    // a capture call in progress; see reference_mutex_.
     void ProcessRenderAudio(const float* data, size_t num_samples);
     void ProcessCaptureAudio(const float* input, float* output, size_t num_samples);
+    void ProcessCaptureAudio(const float* input, float* output, size_t num_samples, const AECCallOptions& options);
+    size_t Warmup(const float* mic, const float* reference, size_t num_samples);  // Returns frames run
+    // Reserve scratch buffers for calls of up to max_samples so the first
+    // real chunk doesn't allocate on the audio thread. Capacity only grows,
+    // so repeat calls are cheap; call again after a sample rate change.
+    void EnsureCapacity(size_t max_samples);
+    // Capture, then the output (times gain) as render, under one lock: for
+    // monitoring, where the processed mic is played back as the next reference
+    void ProcessAndFeed(const float* input, float* output, size_t num_samples, float gain);
+    // Capture, plus the output decimated to kAsrSampleRate for speech
+    // recognition, under one lock. False (nothing processed) unless the
+    // stream rate is a whole multiple of kAsrSampleRate.
+    bool ProcessWithAsrTap(const float* input, float* output, size_t num_samples, std::vector<float>* asr);
+    void NotifyDroppedInput(size_t num_samples);  // Caller skipped this much capture audio
     void SetEchoCancellationEnabled(bool enabled);
+    void SetNoiseSuppression(bool enabled, int level);
     AECMetrics GetMetrics() const;
+    AECCallStats GetLastCallStats() const;
+    void ResetMetrics();
+    AECMetrics GetAndResetMetrics();  // Snapshot, then ResetMetrics
+    bool Reset(bool reset_metrics = false);
+    AECHealthReport HealthCheck(bool auto_recover);  // auto_recover: Reset() when unhealthy
+    bool ApplyPreset(const std::string& name);
+    bool SetEmptyInputMode(const std::string& mode);
+    bool SetUnderrunStrategy(const std::string& strategy);  // False for an unknown strategy
+    void FillEmptyInput(float* output, size_t num_samples);  // Per empty_input_mode
+    // Testing only: reseed the comfort noise and report a fixed per-call
+    // processing time, so output and metrics are identical run to run.
+    // elapsed_ms sets that time, which also lets tests feed synthetic delays.
+    void SetDeterministic(bool enabled, uint32_t seed, double elapsed_ms = kDeterministicElapsedMs);
+    static constexpr double kDeterministicElapsedMs = 0.1;
+
+    static constexpr int kAsrSampleRate = 16000;
+    bool SetFilterLength(int blocks);
+    bool SetSampleRate(int sample_rate);
+    // Start from GetConfig() and change what's needed; false (unchanged) if the
+    // result is invalid. Lists the option names that forced a rebuild.
+    bool Reconfigure(const AECConfig& config, int sample_rate, std::vector<std::string>* rebuild_fields);
+    // Replace the accepted rate set; false (unchanged) if empty or any rate is invalid.
+    // The current rate keeps running even if it is no longer listed.
+    bool SetSupportedSampleRates(const std::vector<int>& rates);
+    void SetLoudnessNormalization(bool enabled, float target_db, float max_gain_db);
+    void SetOutputAgc(bool enabled, float target_db, float max_gain_db);
+    void SetLimiter(bool enabled, float threshold_db);
+    void SetSilentChunkSkip(bool enabled, float floor_db);
+    void SetSilenceBypass(bool enabled, float threshold_db);
+    void SetOverloadDetection(float rtf_threshold, int consecutive_chunks, bool auto_bypass);
+    void SetAutoReconvergeOnPathChange(bool enabled);
+    void SetReferenceGain(float gain);
+    void SetOverrunDrainFraction(float fraction);  // Clamped to 0..1
+    void SetInputGain(float gain_db);   // Clamped to kMinStageGainDb..kMaxStageGainDb
+    void SetOutputGain(float gain_db);
+    void SetPreEmphasis(float coefficient);  // 0 disables, clears filter state
+    void SetDoubleTalkSensitivity(float sensitivity);  // 0 disables, < 1
+    // Live for NLMS; rebuilds AEC3, which re-converges. False if out of range
+    bool SetAdaptationRate(float rate);
+    void SetAutoResetAfterIdle(float idle_ms, bool reset_filter);  // 0 disables
+    void SetClock(AECClock clock);  // Testing only; empty restores steady_clock
+    void SetErleHistoryLength(size_t length);  // Clamped to kMaxErleHistoryLength
+    std::vector<float> GetErleHistory() const;  // Oldest first, in dB
+    // Debugging: the last frame of mic audio handed to ProcessCaptureAudio,
+    // echo and all, before input gain or cancellation. Shorter if the last
+    // chunk was; empty before the first one.
+    std::vector<float> GetLastRawInput() const;
+    // Render samples that can be fed before the reference buffer overruns
+    size_t RefBufferAvailable() const;
+    AECCrossCorrelation GetCrossCorrelation() const;
+    AECDelayEstimate CalibrateDelay(float max_delay_ms);  // Searches at most 500 ms
+    // 3-channel 16-bit WAV of mic / reference / output, written off-thread
+    bool StartDebugDump(const std::string& path);
+    AECDebugDumpStats StopDebugDump();
+    bool IsDebugDumping() const;
+    void EnableDebugCapture(float max_seconds);  // 0 disables and frees the rings
+    bool IsDebugCapturing() const;
+    AECDebugCapture GetDebugCapture() const;
+    // Also forwards what goes to the console; empty sink disables. Rate limited.
+    void SetLogSink(AECLogSink sink, AECLogLevel level);
+    void SetRealtimeWatchdog(int consecutive_chunks);  // 0 disables
+    bool ConsumeWatchdogEvent(AECWatchdogEvent* event);  // True once per streak
+    std::vector<AECEvent> TakeEvents();  // State transitions since the last call
+    AECTuning GetTuning() const;
+    AECConfig GetConfig() const;  // Current settings, including runtime changes
+    // New processor with this one's config, buffered reference and capture
+    // audio, calibrated delay and NLMS filter; its metrics start at zero.
+    // The AEC3 filter can't be copied and re-converges in the clone.
+    // Null before Initialize or if the copy can't be initialized.
+    std::unique_ptr<AECProcessor> Clone();
+    // What Clone copies, as a versioned binary snapshot for rebuilding the
+    // processor after a restart. Native-endian, for the build that wrote it.
+    // Empty before Initialize.
+    std::vector<uint8_t> SaveState();
+    // Replace the state with a SaveState snapshot taken under the same config
+    // fingerprint and channel count. Metrics are kept; nothing changes on failure.
+    AECStateResult RestoreState(const uint8_t* data, size_t size);
+    static constexpr uint32_t kStateVersion = 1;
+    int GetSampleRate() const;
+    int GetNumChannels() const;
+    size_t GetFrameSize() const;  // Samples per AEC frame
+    // Required chunk multiple for capture or render; 0 unless strict_chunk_size
+    size_t GetChunkGranularity(bool render) const;
+    size_t GetMaxInputSamples() const;  // 0 = no limit
+    void SetMaxInputSamples(int max_samples);
 
 private:
     class Impl;
     std::unique_ptr<Impl> impl_;
+
+    // Locking: mutex_ serializes everything that touches the engine (JS thread,
+    // processAsync workers, shared-processor threads). Reference audio only
+    // takes reference_mutex_ to append to a queue, so a feeder thread never
+    // waits out a capture chunk. The queue is handed to the engine, in order
+    // and chunk by chunk, at the start of each call that consumes or rebuilds
+    // buffered audio; read-only getters see it after the next such call. Lock
+    // order is mutex_ then reference_mutex_.
+    mutable std::mutex mutex_;
+    mutable std::mutex reference_mutex_;
+    std::vector<float> pending_reference_;         // Guarded by reference_mutex_
+    std::vector<size_t> pending_reference_chunks_;  // Chunk lengths, same guard
+    // When the caller last fed reference, for msSinceLastReference; stamped on
+    // feed rather than drain, with a copy of the SetClock source. Same guard.
+    std::optional<std::chrono::steady_clock::time_point> last_reference_time_;
+    AECClock reference_clock_;
+    void FillReferenceAge(AECMetrics* metrics) const;
+    std::vector<float> draining_reference_;        // Guarded by mutex_
+    std::vector<size_t> draining_reference_chunks_;
+    void DrainReference();  // Caller holds mutex_
+};
+
+struct AECSelfTestResult {
+    bool passed = false;
+    float erle_db = 0.0f;  // Input vs output energy over the converged tail
 };
 
+// Run a synthetic echo scenario (reference sine, mic = delayed attenuated copy)
+// through a fresh processor and check the output energy dropped. Used as a
+// startup health check for build/ABI problems that silently break processing.
+AECSelfTestResult RunSelfTest(int sample_rate = 48000, const std::string& engine = "webrtc");
+
+struct AECStreamStats {
+    bool ok = false;                 // Ran to the end of the mic and wrote every sample
+    size_t frames = 0;               // Frames processed, the last possibly zero-padded
+    size_t mic_samples = 0;          // Also the number of samples written
+    size_t reference_samples = 0;    // Before the reference ran out, if it did
+};
+
+// Cancel echo in recordings without Node, e.g. on a server: 16-bit
+// little-endian mono PCM at the processor's rate, read and processed one
+// frame at a time and written out in the same format. Reference past its end
+// counts as silence; a final partial mic frame is zero-padded through the
+// processor and trimmed on output, and a stray odd byte is ignored. Samples
+// scale by 32768 both ways, so unchanged PCM round-trips exactly. Any
+// std::istream works, including pipes; short reads just wait for more.
+// Covered by the pcm_stream_test target.
+AECStreamStats ProcessPcmStreams(AECProcessor& processor, std::istream& mic, std::istream& reference,
+                                 std::ostream& out);
+
+// The same from one stereo stream: mic on channel 0, reference on channel 1
+AECStreamStats ProcessInterleavedPcmStream(AECProcessor& processor, std::istream& input, std::ostream& out);
+
+// 16 hex digits (FNV-1a 64) over every config field but label, plus the sample rate.
+// Equal fingerprints mean equal settings; the hash itself is not stable
+// across releases that add config fields.
+std::string ConfigFingerprint(const AECConfig& config, int sample_rate);
+
+// One JSON object for telemetry pipelines. Schema version 1:
+//   schemaVersion      1, bumped only when a key is renamed or removed
+//   configFingerprint  ConfigFingerprint(config, sample_rate)
+//   label              config.label, "" if unset
+//   sampleRate         Hz
+//   every other key    the getMetrics() key of the same name and meaning
+//                      (latencyHistogram is an array of 6 counts)
+// Keys are only ever added within a schema version. Non-finite numbers are null.
+std::string MetricsToJson(const AECMetrics& metrics, const AECConfig& config, int sample_rate);
+
 } // namespace kakarot
@@ -3,14 +3,597 @@
 #include <CoreAudio/CoreAudio.h>
 #include <mach/mach_time.h>
 #include <dispatch/dispatch.h>
+#include <pthread.h>
+#if defined(__APPLE__)
+#include <pthread/qos.h>
+#endif
+#include <atomic>
 #include <chrono>
+#include <deque>
 #include <iostream>
+#include <map>
+#include <memory>
+#include <mutex>
+#include <unordered_map>
 #include <vector>
 #include <string>
+#include <algorithm>
+#include <cmath>
+#include <sstream>
+#include <type_traits>
 #include "aec_processor.h"
 
 using namespace kakarot;
 
+namespace {
+
+// Stable `code` on every exception the addon throws, so callers can branch on
+// err.code instead of parsing messages. Mirrored by AECErrorCode in
+// AECProcessor.ts. Wrong argument types throw TypeError, bad values and
+// buffer sizes RangeError, everything else Error.
+constexpr const char* kErrHandleDestroyed = "ERR_AEC_HANDLE_DESTROYED";
+constexpr const char* kErrInvalidArgument = "ERR_AEC_INVALID_ARGUMENT";
+constexpr const char* kErrInvalidValue = "ERR_AEC_INVALID_VALUE";
+constexpr const char* kErrUnknownOption = "ERR_AEC_UNKNOWN_OPTION";
+constexpr const char* kErrBufferLength = "ERR_AEC_BUFFER_LENGTH";
+constexpr const char* kErrInputTooLarge = "ERR_AEC_INPUT_TOO_LARGE";
+constexpr const char* kErrUnsupportedSampleRate = "ERR_AEC_UNSUPPORTED_SAMPLE_RATE";
+constexpr const char* kErrConfigFailed = "ERR_AEC_CONFIG_FAILED";
+constexpr const char* kErrProcessingFailed = "ERR_AEC_PROCESSING_FAILED";
+constexpr const char* kErrDevice = "ERR_AEC_DEVICE";
+constexpr const char* kErrReentrantCall = "ERR_AEC_REENTRANT_CALL";
+constexpr const char* kErrStateMismatch = "ERR_AEC_STATE_MISMATCH";
+
+template <typename ErrorType>
+ErrorType CodedError(Napi::Env env, const char* code, const std::string& message) {
+    ErrorType error = ErrorType::New(env, message);
+    error.Set("code", Napi::String::New(env, code));
+    return error;
+}
+
+template <typename ErrorType>
+void ThrowCodedError(Napi::Env env, const char* code, const std::string& message) {
+    CodedError<ErrorType>(env, code, message).ThrowAsJavaScriptException();
+}
+
+// A native exception from a processing or reference call, which would
+// otherwise surface as a null result indistinguishable from "no output"
+void ThrowProcessingError(Napi::Env env, const char* method, const std::exception& e) {
+    ThrowCodedError<Napi::Error>(env, kErrProcessingFailed, std::string(method) + " failed: " + e.what());
+}
+
+// IsTypedArray accepts any element type; reading an Int8Array as floats would
+// run past its end, so every Float32Array argument is checked with this
+bool IsFloat32Array(const Napi::Value& value) {
+    return value.IsTypedArray() && value.As<Napi::TypedArray>().TypedArrayType() == napi_float32_array;
+}
+
+const char* kNoiseSuppressionLevels[] = {"low", "moderate", "high", "veryHigh"};
+
+// Map a JS noise suppression level name to its AECTuning index, or -1 if unknown
+int ParseNoiseSuppressionLevel(const std::string& name) {
+    for (int i = 0; i < 4; i++) {
+        if (name == kNoiseSuppressionLevels[i]) return i;
+    }
+    return -1;
+}
+
+// Read a JS array of sample rates; false if it isn't an array of numbers
+bool ReadSampleRates(const Napi::Value& value, std::vector<int>* rates) {
+    if (!value.IsArray()) return false;
+    Napi::Array array = value.As<Napi::Array>();
+    rates->clear();
+    for (uint32_t i = 0; i < array.Length(); i++) {
+        Napi::Value rate = array.Get(i);
+        if (!rate.IsNumber()) return false;
+        rates->push_back(rate.As<Napi::Number>().Int32Value());
+    }
+    return true;
+}
+
+std::string UnsupportedSampleRateMessage(const AECConfig& config, int sample_rate) {
+    return "sampleRate " + std::to_string(sample_rate) + " Hz is not supported; allowed: " +
+           DescribeSupportedSampleRates(config) + " (" + std::to_string(config.frame_duration_ms) + " ms frames)";
+}
+
+// Output/input energy ratio for each full frame of a processed chunk. A silent
+// input frame reads as 1 (nothing removed); AGC gain can push it above 1.
+void FrameSuppressionGains(const float* input, const float* output, size_t num_samples,
+                           size_t frame_size, float* gains) {
+    for (size_t frame = 0; frame_size > 0 && (frame + 1) * frame_size <= num_samples; frame++) {
+        double input_energy = 0.0;
+        double output_energy = 0.0;
+        for (size_t i = frame * frame_size; i < (frame + 1) * frame_size; i++) {
+            input_energy += static_cast<double>(input[i]) * input[i];
+            output_energy += static_cast<double>(output[i]) * output[i];
+        }
+        gains[frame] = input_energy > 0.0 ? static_cast<float>(output_energy / input_energy) : 1.0f;
+    }
+}
+
+// Option readers for ReadConfigOptions. An absent (undefined or null) field
+// keeps *value; a present one must have the right type (else TypeError) and
+// range (else RangeError), the error naming the field, or false is returned.
+bool IsAbsentOption(const Napi::Value& value) {
+    return value.IsUndefined() || value.IsNull();
+}
+
+template <typename ErrorType>
+bool ThrowOptionError(Napi::Env env, const char* code, const std::string& message) {
+    ThrowCodedError<ErrorType>(env, code, message);
+    return false;
+}
+
+template <typename T>
+bool ReadNumberOption(Napi::Env env, const Napi::Object& options, const char* name,
+                      double min, double max, T* value) {
+    Napi::Value field = options.Get(name);
+    if (IsAbsentOption(field)) return true;
+    if (!field.IsNumber()) {
+        return ThrowOptionError<Napi::TypeError>(env, kErrInvalidArgument, std::string(name) + " must be a number");
+    }
+    double number = field.As<Napi::Number>().DoubleValue();
+    if (std::is_integral<T>::value && std::floor(number) != number) {
+        return ThrowOptionError<Napi::RangeError>(env, kErrInvalidValue, std::string(name) + " must be an integer");
+    }
+    if (!(number >= min && number <= max)) {  // Also rejects NaN
+        std::ostringstream message;
+        message << name << " must be between " << min << " and " << max;
+        return ThrowOptionError<Napi::RangeError>(env, kErrInvalidValue, message.str());
+    }
+    *value = static_cast<T>(number);
+    return true;
+}
+
+bool ReadBoolOption(Napi::Env env, const Napi::Object& options, const char* name, bool* value) {
+    Napi::Value field = options.Get(name);
+    if (IsAbsentOption(field)) return true;
+    if (!field.IsBoolean()) {
+        return ThrowOptionError<Napi::TypeError>(env, kErrInvalidArgument, std::string(name) + " must be a boolean");
+    }
+    *value = field.As<Napi::Boolean>().Value();
+    return true;
+}
+
+bool ReadStringOption(Napi::Env env, const Napi::Object& options, const char* name, std::string* value) {
+    Napi::Value field = options.Get(name);
+    if (IsAbsentOption(field)) return true;
+    if (!field.IsString()) {
+        return ThrowOptionError<Napi::TypeError>(env, kErrInvalidArgument, std::string(name) + " must be a string");
+    }
+    *value = field.As<Napi::String>().Utf8Value();
+    return true;
+}
+
+// Upper bound for maxInputSamples: 10 s at the highest supported rate
+constexpr int kMaxInputSamplesLimit = 3840000;
+
+// Every key ReadConfigOptions reads; anything else is rejected as a likely typo
+const char* kConfigOptionNames[] = {
+    "sampleRate", "frameDurationMs", "supportedSampleRates", "enableAec", "enableNs", "enableAgc",
+    "levelSmoothingMs", "peakHoldMs", "peakDecayDbPerSec", "deadInputWarningMs", "convergedErleDb",
+    "convergedHoldMs", "echoDetectCorrelation", "echoDetectMaxErleDb", "convergenceColdMs",
+    "autoBypassOnOverload", "filterLengthBlocks", "engine", "preset", "noiseSuppressionLevel",
+    "strictChunkSize", "renderGranularity", "maxInputSamples", "doubleTalkSensitivity",
+    "autoResetAfterIdleMs", "idleResetFilter", "label", "adaptationRate", "fallbackToPassthrough",
+    "erleHistoryLength", "testInitFailure",
+};
+
+// Read AECConfig options over *config and *sample_rate, throwing a TypeError
+// and returning false on the first invalid or unknown one. Absent fields keep
+// the AECConfig defaults. Shared by the constructor and reconfigure(), so both
+// accept exactly the same options.
+bool ReadConfigOptions(Napi::Env env, const Napi::Object& options, AECConfig* config, int* sample_rate) {
+    Napi::Array keys = options.GetPropertyNames();
+    for (uint32_t i = 0; i < keys.Length(); i++) {
+        std::string key = keys.Get(i).As<Napi::String>().Utf8Value();
+        if (std::none_of(std::begin(kConfigOptionNames), std::end(kConfigOptionNames),
+                         [&](const char* name) { return key == name; })) {
+            return ThrowOptionError<Napi::TypeError>(env, kErrUnknownOption, "Unknown option: " + key);
+        }
+    }
+    
+    // The frame size in samples is derived from these two, never passed directly
+    if (!ReadNumberOption(env, options, "sampleRate", 8000, 384000, sample_rate) ||
+        !ReadNumberOption(env, options, "frameDurationMs", 1, 100, &config->frame_duration_ms)) {
+        return false;
+    }
+    if (!IsValidFrameDuration(config->frame_duration_ms)) {
+        return ThrowOptionError<Napi::RangeError>(env, kErrInvalidValue, "frameDurationMs must be 10, 20 or 40");
+    }
+    Napi::Value supported_rates = options.Get("supportedSampleRates");
+    if (!IsAbsentOption(supported_rates)) {
+        std::vector<int> rates;
+        if (!ReadSampleRates(supported_rates, &rates) || rates.empty() ||
+            !std::all_of(rates.begin(), rates.end(), [&](int r) { return IsValidStreamFormat(r, config->frame_duration_ms); })) {
+            return ThrowOptionError<Napi::RangeError>(env, kErrInvalidValue, "supportedSampleRates must be a non-empty array of 8000-384000 Hz rates");
+        }
+        config->supported_sample_rates = rates;
+    }
+    if (!IsSupportedSampleRate(*config, *sample_rate)) {
+        return ThrowOptionError<Napi::RangeError>(env, kErrUnsupportedSampleRate, UnsupportedSampleRateMessage(*config, *sample_rate));
+    }
+    
+    if (!ReadBoolOption(env, options, "enableAec", &config->enable_aec) ||
+        !ReadBoolOption(env, options, "enableNs", &config->enable_ns) ||
+        !ReadBoolOption(env, options, "enableAgc", &config->enable_agc) ||
+        !ReadNumberOption(env, options, "levelSmoothingMs", 0, 60000, &config->level_smoothing_ms) ||
+        !ReadNumberOption(env, options, "peakHoldMs", 0, 60000, &config->peak_hold_ms) ||
+        !ReadNumberOption(env, options, "peakDecayDbPerSec", 0, 1000, &config->peak_decay_db_per_sec) ||
+        !ReadNumberOption(env, options, "deadInputWarningMs", 0, 3600000, &config->dead_input_warning_ms) ||
+        !ReadNumberOption(env, options, "convergedErleDb", 0, 100, &config->converged_erle_db) ||
+        !ReadNumberOption(env, options, "convergedHoldMs", 0, 600000, &config->converged_hold_ms) ||
+        !ReadNumberOption(env, options, "echoDetectCorrelation", 0, 1, &config->echo_detect_correlation) ||
+        !ReadNumberOption(env, options, "echoDetectMaxErleDb", -100, 100, &config->echo_detect_max_erle_db) ||
+        !ReadNumberOption(env, options, "convergenceColdMs", 0, 600000, &config->convergence_cold_ms) ||
+        !ReadBoolOption(env, options, "autoBypassOnOverload", &config->auto_bypass_on_overload) ||
+        !ReadBoolOption(env, options, "strictChunkSize", &config->strict_chunk_size) ||
+        !ReadNumberOption(env, options, "renderGranularity", 0, 384000, &config->render_granularity) ||
+        !ReadNumberOption(env, options, "maxInputSamples", 0, kMaxInputSamplesLimit, &config->max_input_samples) ||
+        !ReadNumberOption(env, options, "doubleTalkSensitivity", 0, 0.99, &config->double_talk_sensitivity) ||
+        !ReadNumberOption(env, options, "adaptationRate", kMinAdaptationRate, kMaxAdaptationRate,
+                          &config->adaptation_rate) ||
+        !ReadNumberOption(env, options, "autoResetAfterIdleMs", 0, 3600000, &config->auto_reset_after_idle_ms) ||
+        !ReadNumberOption(env, options, "erleHistoryLength", 0, kMaxErleHistoryLength, &config->erle_history_length) ||
+        !ReadBoolOption(env, options, "idleResetFilter", &config->idle_reset_filter) ||
+        !ReadBoolOption(env, options, "fallbackToPassthrough", &config->fallback_to_passthrough) ||
+        !ReadBoolOption(env, options, "testInitFailure", &config->test_init_failure) ||
+        !ReadNumberOption(env, options, "filterLengthBlocks", kMinFilterLengthBlocks, kMaxFilterLengthBlocks,
+                          &config->filter_length_blocks)) {
+        return false;
+    }
+    
+    if (!ReadStringOption(env, options, "label", &config->label) ||
+        !ReadStringOption(env, options, "engine", &config->engine)) {
+        return false;
+    }
+    if (!IsKnownEngine(config->engine)) {
+        return ThrowOptionError<Napi::RangeError>(env, kErrInvalidValue, "engine must be one of: webrtc, nlms");
+    }
+    if (!ReadStringOption(env, options, "preset", &config->preset)) {
+        return false;
+    }
+    AECTuning tuning;
+    if (!ResolveTuning(*config, &tuning)) {
+        return ThrowOptionError<Napi::RangeError>(env, kErrInvalidValue, "preset must be one of: gentle, balanced, aggressive");
+    }
+    std::string ns_level;
+    if (!ReadStringOption(env, options, "noiseSuppressionLevel", &ns_level)) {
+        return false;
+    }
+    if (!ns_level.empty()) {
+        config->noise_suppression_level = ParseNoiseSuppressionLevel(ns_level);
+        if (config->noise_suppression_level < 0) {
+            return ThrowOptionError<Napi::RangeError>(env, kErrInvalidValue, "noiseSuppressionLevel must be one of: low, moderate, high, veryHigh");
+        }
+    }
+    return true;
+}
+
+const char* kCallOptionNames[] = {"bypass", "outputGainDb", "collectStats", "strict"};
+
+// Per-call options for processCaptureAudio. Unknown keys are ignored so
+// callers can pass options a newer addon understands, unless strict is set.
+bool ReadCallOptions(Napi::Env env, const Napi::Object& options, AECCallOptions* call_options, bool* collect_stats) {
+    bool strict = false;
+    if (!ReadBoolOption(env, options, "strict", &strict)) {
+        return false;
+    }
+    if (strict) {
+        Napi::Array keys = options.GetPropertyNames();
+        for (uint32_t i = 0; i < keys.Length(); i++) {
+            std::string key = keys.Get(i).As<Napi::String>().Utf8Value();
+            if (std::none_of(std::begin(kCallOptionNames), std::end(kCallOptionNames),
+                             [&](const char* name) { return key == name; })) {
+                return ThrowOptionError<Napi::TypeError>(env, kErrUnknownOption, "Unknown option: " + key);
+            }
+        }
+    }
+    
+    call_options->override_output_gain = !IsAbsentOption(options.Get("outputGainDb"));
+    return ReadBoolOption(env, options, "bypass", &call_options->bypass) &&
+           ReadNumberOption(env, options, "outputGainDb", kMinStageGainDb, kMaxStageGainDb,
+                            &call_options->output_gain_db) &&
+           ReadBoolOption(env, options, "collectStats", collect_stats);
+}
+
+// Fields of AECCallStats shared by processAndMeasure and collectStats results
+void SetCallStats(Napi::Object result, const AECCallStats& stats) {
+    result.Set("erleDb", stats.erle_db);
+    result.Set("inputRms", stats.input_rms);
+    result.Set("outputRms", stats.output_rms);
+    result.Set("doubleTalk", stats.double_talk);
+    result.Set("adaptationThrottled", stats.adaptation_throttled);
+    result.Set("elapsedUs", stats.elapsed_us);
+}
+
+// Output callback frames allowed to wait on the event loop, about 640 ms of
+// 10 ms frames, before new ones are dropped
+constexpr size_t kOutputCallbackQueueFrames = 64;
+
+const char* kThreadPriorities[] = {"normal", "high", "realtime"};
+
+// Map a JS thread priority hint to its index, or -1 if unknown
+int ParseThreadPriority(const std::string& name) {
+    for (int i = 0; i < 3; i++) {
+        if (name == kThreadPriorities[i]) return i;
+    }
+    return -1;
+}
+
+// Raise (or restore) the calling thread's scheduling class. False when the
+// platform has no equivalent or the OS refused.
+bool ApplyThreadPriority(int priority) {
+#if defined(__APPLE__)
+    const qos_class_t classes[] = {QOS_CLASS_DEFAULT, QOS_CLASS_USER_INITIATED, QOS_CLASS_USER_INTERACTIVE};
+    return pthread_set_qos_class_self_np(classes[priority], 0) == 0;
+#else
+    (void)priority;
+    return false;
+#endif
+}
+
+// Reject a chunk before any processing: capture chunks longer than
+// maxInputSamples, and with strictChunkSize, chunks that aren't a whole
+// multiple of the frame size (capture) or render granularity
+bool ThrowIfInvalidChunk(Napi::Env env, const AECProcessor& processor, size_t num_samples, bool render) {
+    size_t max_samples = render ? 0 : processor.GetMaxInputSamples();
+    if (max_samples > 0 && num_samples > max_samples) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInputTooLarge,
+            "Capture chunk of " + std::to_string(num_samples) + " samples exceeds maxInputSamples (" +
+            std::to_string(max_samples) + ")");
+        return true;
+    }
+    size_t granularity = processor.GetChunkGranularity(render);
+    if (granularity == 0 || num_samples % granularity == 0) {
+        return false;
+    }
+    ThrowCodedError<Napi::RangeError>(env, kErrBufferLength,
+        std::string(render ? "Render" : "Capture") + " chunk of " + std::to_string(num_samples) +
+        " samples is not a multiple of " + std::to_string(granularity) +
+        (render ? " (renderGranularity)" : " (frame size)"));
+    return true;
+}
+
+// JS view of AECMetrics, shared by getMetrics and getMetricsShared
+Napi::Object MetricsToObject(Napi::Env env, const AECMetrics& metrics) {
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("echoReturnLoss", metrics.echo_return_loss);
+    result.Set("echoReturnLossEnhancement", metrics.echo_return_loss_enhancement);
+    result.Set("renderDelayMs", metrics.render_delay_ms);
+    result.Set("calibratedDelayMs", metrics.calibrated_delay_ms);
+    result.Set("msSinceLastReference", metrics.ms_since_last_reference);
+    result.Set("aecConverged", metrics.aec_converged);
+    result.Set("rmsLevel", metrics.rms_level);
+    result.Set("peakLevel", metrics.peak_level);
+    result.Set("inputRmsDbfs", metrics.input_rms_dbfs);
+    result.Set("inputPeakDbfs", metrics.input_peak_dbfs);
+    result.Set("outputRmsDbfs", metrics.output_rms_dbfs);
+    result.Set("outputPeakDbfs", metrics.output_peak_dbfs);
+    result.Set("inputPeakInstantDbfs", metrics.input_peak_instant_dbfs);
+    result.Set("inputPeakHoldDbfs", metrics.input_peak_hold_dbfs);
+    result.Set("outputPeakInstantDbfs", metrics.output_peak_instant_dbfs);
+    result.Set("outputPeakHoldDbfs", metrics.output_peak_hold_dbfs);
+    result.Set("loudnessGainDb", metrics.loudness_gain_db);
+    result.Set("agcGainDb", metrics.agc_gain_db);
+    result.Set("limiterGainReductionDb", metrics.limiter_gain_reduction_db);
+    result.Set("limiterMaxGainReductionDb", metrics.limiter_max_gain_reduction_db);
+    result.Set("skippedSilentChunks", static_cast<double>(metrics.skipped_silent_chunks));
+    result.Set("silenceBypassChunks", static_cast<double>(metrics.silence_bypass_chunks));
+    result.Set("deadInputMs", metrics.dead_input_ms);
+    result.Set("deadInput", metrics.dead_input);
+    result.Set("noiseFloorDbfs", metrics.noise_floor_dbfs);
+    result.Set("engine", metrics.engine);
+    result.Set("erleInstantDb", metrics.erle_instant_db);
+    result.Set("erleAverageDb", metrics.erle_average_db);
+    
+    Napi::Array histogram = Napi::Array::New(env, metrics.latency_histogram.size());
+    for (size_t i = 0; i < metrics.latency_histogram.size(); i++) {
+        histogram.Set(static_cast<uint32_t>(i), static_cast<double>(metrics.latency_histogram[i]));
+    }
+    result.Set("latencyHistogram", histogram);
+    result.Set("latencyP50Ms", metrics.latency_p50_ms);
+    result.Set("latencyP95Ms", metrics.latency_p95_ms);
+    result.Set("latencyP99Ms", metrics.latency_p99_ms);
+    result.Set("latencyMaxMs", metrics.latency_max_ms);
+    result.Set("latencyMaxChunkSamples", metrics.latency_max_chunk_samples);
+    result.Set("underrunCount", static_cast<double>(metrics.underrun_count));
+    result.Set("underrunSamples", static_cast<double>(metrics.underrun_samples));
+    result.Set("underrunZeroFillCount", static_cast<double>(metrics.underrun_zero_fill_count));
+    result.Set("underrunHoldCount", static_cast<double>(metrics.underrun_hold_count));
+    result.Set("overflowCount", static_cast<double>(metrics.overflow_count));
+    result.Set("overflowSamples", static_cast<double>(metrics.overflow_samples));
+    result.Set("refBufferFillRatio", metrics.ref_buffer_fill_ratio);
+    result.Set("referenceDriftSamples", static_cast<double>(metrics.reference_drift_samples));
+    result.Set("referenceDriftPerSecond", metrics.reference_drift_per_second);
+    result.Set("realTimeFactor", metrics.real_time_factor);
+    result.Set("realTimeFactorRecent", metrics.real_time_factor_recent);
+    result.Set("audioProcessedMs", metrics.audio_processed_ms);
+    result.Set("inputSamplesProcessed", static_cast<double>(metrics.input_samples_processed));
+    result.Set("outputSamplesProduced", static_cast<double>(metrics.output_samples_produced));
+    result.Set("referenceSamplesConsumed", static_cast<double>(metrics.reference_samples_consumed));
+    result.Set("audioSecondsProcessed", metrics.audio_seconds_processed);
+    result.Set("overloaded", metrics.overloaded);
+    result.Set("overloadBypass", metrics.overload_bypass);
+    result.Set("overloadEvents", static_cast<double>(metrics.overload_events));
+    result.Set("pathChangesDetected", static_cast<double>(metrics.path_changes_detected));
+    result.Set("adaptationThrottledChunks", static_cast<double>(metrics.adaptation_throttled_chunks));
+    result.Set("idleResets", static_cast<double>(metrics.idle_resets));
+    result.Set("suboptimalConfig", metrics.suboptimal_config);
+    result.Set("emptyInputCalls", static_cast<double>(metrics.empty_input_calls));
+    result.Set("convergenceState", metrics.convergence_state);
+    result.Set("timeSinceConvergedMs", metrics.time_since_converged_ms);
+    result.Set("droppedInputEvents", static_cast<double>(metrics.dropped_input_events));
+    result.Set("droppedInputSamples", static_cast<double>(metrics.dropped_input_samples));
+    result.Set("scratchGrowCount", static_cast<double>(metrics.scratch_grow_count));
+    result.Set("engineActive", metrics.engine_active);
+    result.Set("initError", metrics.init_error);
+    
+    return result;
+}
+
+// getConfig() shape, shared with listInstances. Built fresh each call;
+// mutating it has no effect on the processor.
+Napi::Object ConfigToObject(Napi::Env env, const AECProcessor& processor) {
+    AECTuning tuning = processor.GetTuning();
+    AECConfig config = processor.GetConfig();
+    
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("sampleRate", processor.GetSampleRate());
+    result.Set("channels", processor.GetNumChannels());
+    result.Set("frameDurationMs", config.frame_duration_ms);
+    Napi::Array supported_rates = Napi::Array::New(env, config.supported_sample_rates.size());
+    for (size_t i = 0; i < config.supported_sample_rates.size(); i++) {
+        supported_rates.Set(static_cast<uint32_t>(i), config.supported_sample_rates[i]);
+    }
+    result.Set("supportedSampleRates", supported_rates);
+    result.Set("frameSize", static_cast<double>(processor.GetFrameSize()));
+    result.Set("label", config.label);
+    result.Set("engine", config.engine);
+    result.Set("fallbackToPassthrough", config.fallback_to_passthrough);
+    result.Set("enableAec", config.enable_aec);
+    result.Set("enableNs", config.enable_ns);
+    result.Set("enableAgc", config.enable_agc);
+    result.Set("referenceGain", config.reference_gain);
+    result.Set("inputGainDb", config.input_gain_db);
+    result.Set("outputGainDb", config.output_gain_db);
+    result.Set("emptyInputMode", config.empty_input_mode);
+    result.Set("underrunStrategy", config.underrun_strategy);
+    result.Set("overrunDrainFraction", config.overrun_drain_fraction);
+    result.Set("preEmphasis", config.pre_emphasis);
+    result.Set("strictChunkSize", config.strict_chunk_size);
+    result.Set("renderGranularity", config.render_granularity);
+    result.Set("maxInputSamples", config.max_input_samples);
+    result.Set("doubleTalkSensitivity", config.double_talk_sensitivity);
+    result.Set("adaptationRate", config.adaptation_rate);
+    result.Set("autoResetAfterIdleMs", config.auto_reset_after_idle_ms);
+    result.Set("idleResetFilter", config.idle_reset_filter);
+    result.Set("outputAgcEnabled", config.enable_output_agc);
+    result.Set("agcTargetDb", config.agc_target_db);
+    result.Set("agcMaxGainDb", config.agc_max_gain_db);
+    result.Set("limiterEnabled", config.enable_limiter);
+    result.Set("limiterThresholdDb", config.limiter_threshold_db);
+    result.Set("loudnessNormalizationEnabled", config.enable_loudness_normalization);
+    result.Set("loudnessTargetDb", config.loudness_target_db);
+    result.Set("loudnessMaxGainDb", config.loudness_max_gain_db);
+    result.Set("silentChunkSkipEnabled", config.skip_silent_chunks);
+    result.Set("silentChunkFloorDb", config.silent_chunk_floor_db);
+    if (config.enable_silence_bypass) {
+        result.Set("silenceBypassThresholdDb", config.silence_bypass_threshold_db);
+    } else {
+        result.Set("silenceBypassThresholdDb", env.Null());
+    }
+    result.Set("erleHistoryLength", static_cast<double>(config.erle_history_length));
+    result.Set("overloadRtfThreshold", config.overload_rtf_threshold);
+    result.Set("overloadConsecutiveChunks", config.overload_consecutive_chunks);
+    result.Set("autoBypassOnOverload", config.auto_bypass_on_overload);
+    result.Set("autoReconvergeOnPathChange", config.auto_reconverge_on_path_change);
+    result.Set("convergedErleDb", config.converged_erle_db);
+    result.Set("convergedHoldMs", config.converged_hold_ms);
+    result.Set("echoDetectCorrelation", config.echo_detect_correlation);
+    result.Set("echoDetectMaxErleDb", config.echo_detect_max_erle_db);
+    result.Set("convergenceColdMs", config.convergence_cold_ms);
+    result.Set("preset", tuning.preset);
+    result.Set("filterLengthBlocks", tuning.filter_length_blocks);
+    result.Set("initialFilterLengthBlocks", tuning.initial_filter_length_blocks);
+    result.Set("enrTransparentLf", tuning.enr_transparent_lf);
+    result.Set("enrSuppressLf", tuning.enr_suppress_lf);
+    result.Set("enrTransparentHf", tuning.enr_transparent_hf);
+    result.Set("enrSuppressHf", tuning.enr_suppress_hf);
+    result.Set("maxGainDuringEcho", tuning.max_gain_during_echo);
+    result.Set("noiseSuppressionLevel", kNoiseSuppressionLevels[std::clamp(tuning.noise_suppression_level, 0, 3)]);
+    return result;
+}
+
+// Processors made by createShared, addressed by id so that any thread's
+// instance of this module can use them. The map lock is only held for lookups;
+// each AECProcessor serializes its own calls, and the shared_ptr keeps a
+// processor alive for calls already running when it is destroyed.
+std::mutex g_shared_mutex;
+std::unordered_map<uint32_t, std::shared_ptr<AECProcessor>> g_shared_processors;
+uint32_t g_next_shared_id = 1;
+
+// Look up the processor named by info[0], throwing and returning null if the
+// id isn't a number or was never created or already destroyed
+std::shared_ptr<AECProcessor> FindSharedProcessor(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected shared processor id");
+        return nullptr;
+    }
+    uint32_t id = info[0].As<Napi::Number>().Uint32Value();
+    std::lock_guard<std::mutex> lock(g_shared_mutex);
+    auto it = g_shared_processors.find(id);
+    if (it == g_shared_processors.end()) {
+        ThrowCodedError<Napi::Error>(env, kErrHandleDestroyed,
+            "Shared processor " + std::to_string(id) + " does not exist or has been destroyed");
+        return nullptr;
+    }
+    return it->second;
+}
+
+// Live AudioCaptureAddon instances for listInstances, across every thread's
+// copy of the module. Entries share the processor, so listing from one thread
+// while another processes is safe; destroy and finalization remove them.
+struct InstanceEntry {
+    double created_at_ms = 0.0;  // Unix epoch
+    std::shared_ptr<AECProcessor> processor;
+};
+
+std::mutex g_instances_mutex;
+std::map<uint64_t, InstanceEntry> g_instances;  // Ordered by id, so by creation
+uint64_t g_next_instance_id = 1;
+
+// Build and initialize a processor for the constructor and createShared.
+// With fallback_to_passthrough a failed Initialize keeps a passthrough
+// processor; otherwise returns null with the reason in *error. The
+// test_init_failure option initializes at rate 0, which no config supports,
+// so tests reach the failure through the real validation.
+std::unique_ptr<AECProcessor> CreateProcessor(const AECConfig& config, int sample_rate, std::string* error) {
+    std::unique_ptr<AECProcessor> processor;
+    bool initialized = false;
+    std::string init_error;  // Empty keeps the reason Initialize recorded
+    try {
+        processor = std::make_unique<AECProcessor>(config);
+        initialized = processor->Initialize(config.test_init_failure ? 0 : sample_rate, 1);
+        if (initialized) {
+            std::cout << "✅ AEC processor initialized" << std::endl;
+        } else {
+            std::cerr << "❌ Failed to initialize AEC processor" << std::endl;
+        }
+    } catch (const std::exception& e) {
+        std::cerr << "❌ Exception initializing AEC: " << e.what() << std::endl;
+        init_error = e.what();
+        processor.reset();
+    }
+    
+    if (!initialized && config.fallback_to_passthrough) {
+        // Reuse the processor Initialize rejected; after an exception (e.g.
+        // allocation failure) try a fresh one
+        try {
+            if (!processor) {
+                processor = std::make_unique<AECProcessor>(config);
+            }
+            processor->InitializePassthrough(sample_rate, init_error);
+        } catch (const std::exception& e) {
+            std::cerr << "❌ Passthrough fallback failed: " << e.what() << std::endl;
+            init_error = e.what();
+            processor.reset();
+        }
+    } else if (!initialized) {
+        if (init_error.empty() && processor) {
+            init_error = processor->GetMetrics().init_error;
+        }
+        processor.reset();
+    }
+    *error = init_error;
+    return processor;
+}
+
+} // namespace
+
+class ProcessAsyncWorker;
+
 class AudioCaptureAddon : public Napi::ObjectWrap<AudioCaptureAddon> {
 public:
     static Napi::Object Init(Napi::Env env, Napi::Object exports);
@@ -25,9 +608,86 @@ private:
     
     // AEC methods
     Napi::Value ProcessRenderAudio(const Napi::CallbackInfo& info);
+    Napi::Value ProcessRenderInterleaved(const Napi::CallbackInfo& info);
     Napi::Value ProcessCaptureAudio(const Napi::CallbackInfo& info);
+    Napi::Value ProcessAndMeasure(const Napi::CallbackInfo& info);
+    Napi::Value ProcessInto(const Napi::CallbackInfo& info);
+    Napi::Value ProcessSplit(const Napi::CallbackInfo& info);
+    Napi::Value Warmup(const Napi::CallbackInfo& info);
+    Napi::Value EnsureCapacity(const Napi::CallbackInfo& info);
+    Napi::Value ProcessAndFeed(const Napi::CallbackInfo& info);
+    Napi::Value ProcessWithAsrTap(const Napi::CallbackInfo& info);
+    Napi::Value ProcessAsync(const Napi::CallbackInfo& info);
+    Napi::Value ProcessMany(const Napi::CallbackInfo& info);
+    Napi::Float32Array EmptyInputFiller(Napi::Env env);
+    void FinishAsync(Napi::Env env);
+    friend class ProcessAsyncWorker;
+    Napi::Value NotifyDroppedInput(const Napi::CallbackInfo& info);
     Napi::Value GetMetrics(const Napi::CallbackInfo& info);
+    Napi::Value GetAndResetMetrics(const Napi::CallbackInfo& info);
+    Napi::Value GetMetricsJson(const Napi::CallbackInfo& info);
+    Napi::Value MetricsSnapshot(Napi::Env env, bool reset);
+    Napi::Value ResetMetrics(const Napi::CallbackInfo& info);
+    Napi::Value ResetAEC(const Napi::CallbackInfo& info);
+    Napi::Value HealthCheck(const Napi::CallbackInfo& info);
     Napi::Value SetEchoCancellationEnabled(const Napi::CallbackInfo& info);
+    Napi::Value SetNoiseSuppression(const Napi::CallbackInfo& info);
+    Napi::Value ApplyPreset(const Napi::CallbackInfo& info);
+    Napi::Value SetEmptyInputMode(const Napi::CallbackInfo& info);
+    Napi::Value SetUnderrunStrategy(const Napi::CallbackInfo& info);
+    Napi::Value SetThreadPriority(const Napi::CallbackInfo& info);
+    Napi::Value SetDeterministic(const Napi::CallbackInfo& info);
+    Napi::Value SetTestClock(const Napi::CallbackInfo& info);
+    Napi::Value Reconfigure(const Napi::CallbackInfo& info);
+    Napi::Value SetFilterLength(const Napi::CallbackInfo& info);
+    Napi::Value SetMaxInputSamples(const Napi::CallbackInfo& info);
+    Napi::Value SetSampleRate(const Napi::CallbackInfo& info);
+    Napi::Value SetSupportedSampleRates(const Napi::CallbackInfo& info);
+    Napi::Value SetLoudnessNormalization(const Napi::CallbackInfo& info);
+    Napi::Value SetAgc(const Napi::CallbackInfo& info);
+    Napi::Value SetLimiter(const Napi::CallbackInfo& info);
+    Napi::Value SetSilentChunkSkip(const Napi::CallbackInfo& info);
+    Napi::Value SetSilenceBypassThreshold(const Napi::CallbackInfo& info);
+    Napi::Value SetOverloadDetection(const Napi::CallbackInfo& info);
+    Napi::Value SetAutoReconvergeOnPathChange(const Napi::CallbackInfo& info);
+    Napi::Value SetReferenceGain(const Napi::CallbackInfo& info);
+    Napi::Value SetOverrunDrainFraction(const Napi::CallbackInfo& info);
+    Napi::Value SetInputGain(const Napi::CallbackInfo& info);
+    Napi::Value SetOutputGain(const Napi::CallbackInfo& info);
+    Napi::Value SetPreEmphasis(const Napi::CallbackInfo& info);
+    Napi::Value SetDoubleTalkSensitivity(const Napi::CallbackInfo& info);
+    Napi::Value SetAdaptationRate(const Napi::CallbackInfo& info);
+    Napi::Value SetAutoResetAfterIdle(const Napi::CallbackInfo& info);
+    Napi::Value SetErleHistoryLength(const Napi::CallbackInfo& info);
+    Napi::Value GetErleHistory(const Napi::CallbackInfo& info);
+    Napi::Value GetLastRawInput(const Napi::CallbackInfo& info);
+    Napi::Value RefBufferAvailable(const Napi::CallbackInfo& info);
+    Napi::Value GetCrossCorrelation(const Napi::CallbackInfo& info);
+    Napi::Value CalibrateDelay(const Napi::CallbackInfo& info);
+    Napi::Value SetRealtimeWatchdog(const Napi::CallbackInfo& info);
+    Napi::Value SetLogger(const Napi::CallbackInfo& info);
+    void ReleaseLogger();
+    Napi::Value SetOutputCallback(const Napi::CallbackInfo& info);
+    void EmitOutput(const float* samples, size_t num_samples);
+    void ReleaseOutputCallback();
+    Napi::Value StartDebugDump(const Napi::CallbackInfo& info);
+    Napi::Value StopDebugDump(const Napi::CallbackInfo& info);
+    Napi::Value EnableDebugCapture(const Napi::CallbackInfo& info);
+    Napi::Value DumpDebugCapture(const Napi::CallbackInfo& info);
+    void NotifyWatchdog(Napi::Env env);
+    Napi::Value SetEventCallback(const Napi::CallbackInfo& info);
+    void NotifyEvents(Napi::Env env);
+    Napi::Value GetConfig(const Napi::CallbackInfo& info);
+    Napi::Value Clone(const Napi::CallbackInfo& info);
+    Napi::Value GetState(const Napi::CallbackInfo& info);
+    Napi::Value SetState(const Napi::CallbackInfo& info);
+    
+    // Lifecycle
+    Napi::Value Destroy(const Napi::CallbackInfo& info);
+    bool ThrowIfDestroyed(Napi::Env env);
+    bool ThrowIfReentrant(Napi::Env env);
+    void RegisterInstance();  // Adds or refreshes this instance's listInstances entry
+    void UnregisterInstance();
     
     // Placeholder methods
     Napi::Value Start(const Napi::CallbackInfo& info);
@@ -38,11 +698,81 @@ private:
     AudioDeviceID device_id_;
     AudioDeviceIOProcID io_proc_id_;
     Napi::ThreadSafeFunction tsfn_;
+    Napi::FunctionReference watchdog_callback_;
+    Napi::FunctionReference event_callback_;
+    std::vector<float> render_scratch_;  // De-interleaved reference, reused per call
+    std::vector<float> asr_scratch_;  // processWithAsrTap's 16 kHz output
+    Napi::ThreadSafeFunction logger_tsfn_;
+    Napi::ThreadSafeFunction output_tsfn_;
+    std::vector<float> output_pending_;  // Processed samples short of a full frame
+    uint64_t output_sequence_ = 0;       // Frames produced since the callback was set
+    uint64_t output_frames_dropped_ = 0;
     bool is_capturing_;
+    bool is_destroyed_ = false;
+    int callback_depth_ = 0;  // Event/watchdog callbacks being dispatched, see ThrowIfReentrant
+    uint64_t instance_id_ = 0;  // Key in g_instances, 0 until registered
+    double created_at_ms_ = 0.0;
+    std::shared_ptr<std::atomic<double>> test_clock_ms_;  // setTestClock's time, null = steady_clock
     std::string selected_device_id_;
     
-    // AEC processor
-    std::unique_ptr<AECProcessor> aec_processor_;
+    // AEC processor. Shared so a processAsync job in flight outlives destroy().
+    std::shared_ptr<AECProcessor> aec_processor_;
+    std::deque<ProcessAsyncWorker*> async_queue_;  // Waiting behind the running job
+    bool async_running_ = false;
+};
+
+// processAsync job: copies the input, runs ProcessCaptureAudio on the libuv
+// pool and resolves with the output. The addon runs one job at a time in call
+// order, so reference consumption matches the synchronous path exactly.
+class ProcessAsyncWorker : public Napi::AsyncWorker {
+public:
+    ProcessAsyncWorker(Napi::Env env, AudioCaptureAddon* addon, std::shared_ptr<AECProcessor> processor,
+                       const float* input, size_t num_samples)
+        : Napi::AsyncWorker(env, "AECProcessAsync"),
+          deferred_(Napi::Promise::Deferred::New(env)),
+          addon_(addon),
+          self_(Napi::Persistent(addon->Value())),
+          processor_(std::move(processor)),
+          input_(input, input + num_samples),
+          output_(num_samples) {}
+
+    Napi::Promise Promise() const { return deferred_.Promise(); }
+
+    // Settle a job that will never run (still queued at destroy())
+    void Abandon(const char* reason) {
+        deferred_.Reject(CodedError<Napi::Error>(Env(), kErrHandleDestroyed, reason).Value());
+    }
+
+protected:
+    void Execute() override {
+        try {
+            processor_->ProcessCaptureAudio(input_.data(), output_.data(), input_.size());
+        } catch (const std::exception& e) {
+            SetError(e.what());
+        }
+    }
+
+    void OnOK() override {
+        Napi::Env env = Env();
+        Napi::Float32Array output = Napi::Float32Array::New(env, output_.size());
+        std::copy(output_.begin(), output_.end(), output.Data());
+        deferred_.Resolve(output);
+        addon_->EmitOutput(output_.data(), output_.size());
+        addon_->FinishAsync(env);
+    }
+
+    void OnError(const Napi::Error& error) override {
+        deferred_.Reject(CodedError<Napi::Error>(Env(), kErrProcessingFailed, error.Message()).Value());
+        addon_->FinishAsync(Env());
+    }
+
+private:
+    Napi::Promise::Deferred deferred_;
+    AudioCaptureAddon* addon_;
+    Napi::ObjectReference self_;  // Keeps the addon alive until the job settles
+    std::shared_ptr<AECProcessor> processor_;
+    std::vector<float> input_;
+    std::vector<float> output_;
 };
 
 Napi::Object AudioCaptureAddon::Init(Napi::Env env, Napi::Object exports) {
@@ -51,9 +781,71 @@ Napi::Object AudioCaptureAddon::Init(Napi::Env env, Napi::Object exports) {
         InstanceMethod("stopMicrophoneCapture", &AudioCaptureAddon::StopMicrophoneCapture),
         InstanceMethod("getDevices", &AudioCaptureAddon::GetDevices),
         InstanceMethod("processRenderAudio", &AudioCaptureAddon::ProcessRenderAudio),
+        InstanceMethod("processRenderInterleaved", &AudioCaptureAddon::ProcessRenderInterleaved),
         InstanceMethod("processCaptureAudio", &AudioCaptureAddon::ProcessCaptureAudio),
+        InstanceMethod("processAndMeasure", &AudioCaptureAddon::ProcessAndMeasure),
+        InstanceMethod("processInto", &AudioCaptureAddon::ProcessInto),
+        InstanceMethod("processSplit", &AudioCaptureAddon::ProcessSplit),
+        InstanceMethod("warmup", &AudioCaptureAddon::Warmup),
+        InstanceMethod("ensureCapacity", &AudioCaptureAddon::EnsureCapacity),
+        InstanceMethod("processAndFeed", &AudioCaptureAddon::ProcessAndFeed),
+        InstanceMethod("processWithAsrTap", &AudioCaptureAddon::ProcessWithAsrTap),
+        InstanceMethod("processAsync", &AudioCaptureAddon::ProcessAsync),
+        InstanceMethod("processMany", &AudioCaptureAddon::ProcessMany),
+        InstanceMethod("notifyDroppedInput", &AudioCaptureAddon::NotifyDroppedInput),
         InstanceMethod("getMetrics", &AudioCaptureAddon::GetMetrics),
+        InstanceMethod("getAndResetMetrics", &AudioCaptureAddon::GetAndResetMetrics),
+        InstanceMethod("getMetricsJson", &AudioCaptureAddon::GetMetricsJson),
+        InstanceMethod("resetMetrics", &AudioCaptureAddon::ResetMetrics),
+        InstanceMethod("resetAEC", &AudioCaptureAddon::ResetAEC),
+        InstanceMethod("healthCheck", &AudioCaptureAddon::HealthCheck),
         InstanceMethod("setEchoCancellationEnabled", &AudioCaptureAddon::SetEchoCancellationEnabled),
+        InstanceMethod("setNoiseSuppression", &AudioCaptureAddon::SetNoiseSuppression),
+        InstanceMethod("applyPreset", &AudioCaptureAddon::ApplyPreset),
+        InstanceMethod("setEmptyInputMode", &AudioCaptureAddon::SetEmptyInputMode),
+        InstanceMethod("setUnderrunStrategy", &AudioCaptureAddon::SetUnderrunStrategy),
+        InstanceMethod("setThreadPriority", &AudioCaptureAddon::SetThreadPriority),
+        InstanceMethod("setDeterministic", &AudioCaptureAddon::SetDeterministic),
+        InstanceMethod("setTestClock", &AudioCaptureAddon::SetTestClock),
+        InstanceMethod("reconfigure", &AudioCaptureAddon::Reconfigure),
+        InstanceMethod("setFilterLength", &AudioCaptureAddon::SetFilterLength),
+        InstanceMethod("setMaxInputSamples", &AudioCaptureAddon::SetMaxInputSamples),
+        InstanceMethod("setSampleRate", &AudioCaptureAddon::SetSampleRate),
+        InstanceMethod("setSupportedSampleRates", &AudioCaptureAddon::SetSupportedSampleRates),
+        InstanceMethod("setLoudnessNormalization", &AudioCaptureAddon::SetLoudnessNormalization),
+        InstanceMethod("setAgc", &AudioCaptureAddon::SetAgc),
+        InstanceMethod("setLimiter", &AudioCaptureAddon::SetLimiter),
+        InstanceMethod("setSilentChunkSkip", &AudioCaptureAddon::SetSilentChunkSkip),
+        InstanceMethod("setSilenceBypassThreshold", &AudioCaptureAddon::SetSilenceBypassThreshold),
+        InstanceMethod("setOverloadDetection", &AudioCaptureAddon::SetOverloadDetection),
+        InstanceMethod("setAutoReconvergeOnPathChange", &AudioCaptureAddon::SetAutoReconvergeOnPathChange),
+        InstanceMethod("setReferenceGain", &AudioCaptureAddon::SetReferenceGain),
+        InstanceMethod("setOverrunDrainFraction", &AudioCaptureAddon::SetOverrunDrainFraction),
+        InstanceMethod("setInputGain", &AudioCaptureAddon::SetInputGain),
+        InstanceMethod("setOutputGain", &AudioCaptureAddon::SetOutputGain),
+        InstanceMethod("setPreEmphasis", &AudioCaptureAddon::SetPreEmphasis),
+        InstanceMethod("setDoubleTalkSensitivity", &AudioCaptureAddon::SetDoubleTalkSensitivity),
+        InstanceMethod("setAdaptationRate", &AudioCaptureAddon::SetAdaptationRate),
+        InstanceMethod("setAutoResetAfterIdle", &AudioCaptureAddon::SetAutoResetAfterIdle),
+        InstanceMethod("setErleHistoryLength", &AudioCaptureAddon::SetErleHistoryLength),
+        InstanceMethod("getErleHistory", &AudioCaptureAddon::GetErleHistory),
+        InstanceMethod("getLastRawInput", &AudioCaptureAddon::GetLastRawInput),
+        InstanceMethod("refBufferAvailable", &AudioCaptureAddon::RefBufferAvailable),
+        InstanceMethod("getCrossCorrelation", &AudioCaptureAddon::GetCrossCorrelation),
+        InstanceMethod("calibrateDelay", &AudioCaptureAddon::CalibrateDelay),
+        InstanceMethod("setRealtimeWatchdog", &AudioCaptureAddon::SetRealtimeWatchdog),
+        InstanceMethod("setEventCallback", &AudioCaptureAddon::SetEventCallback),
+        InstanceMethod("setLogger", &AudioCaptureAddon::SetLogger),
+        InstanceMethod("setOutputCallback", &AudioCaptureAddon::SetOutputCallback),
+        InstanceMethod("startDebugDump", &AudioCaptureAddon::StartDebugDump),
+        InstanceMethod("stopDebugDump", &AudioCaptureAddon::StopDebugDump),
+        InstanceMethod("enableDebugCapture", &AudioCaptureAddon::EnableDebugCapture),
+        InstanceMethod("dumpDebugCapture", &AudioCaptureAddon::DumpDebugCapture),
+        InstanceMethod("getConfig", &AudioCaptureAddon::GetConfig),
+        InstanceMethod("clone", &AudioCaptureAddon::Clone),
+        InstanceMethod("getState", &AudioCaptureAddon::GetState),
+        InstanceMethod("setState", &AudioCaptureAddon::SetState),
+        InstanceMethod("destroy", &AudioCaptureAddon::Destroy),
         InstanceMethod("start", &AudioCaptureAddon::Start),
         InstanceMethod("stop", &AudioCaptureAddon::Stop)
     });
@@ -73,6 +865,16 @@ AudioCaptureAddon::AudioCaptureAddon(const Napi::CallbackInfo& info)
       io_proc_id_(nullptr),
       is_capturing_(false) {
     
+    // clone() hands over a processor it already built; JS can't create an
+    // External, so this path is private to the addon
+    if (info.Length() > 0 && info[0].IsExternal()) {
+        aec_processor_ = std::move(*info[0].As<Napi::External<std::unique_ptr<AECProcessor>>>().Data());
+        created_at_ms_ = std::chrono::duration<double, std::milli>(
+            std::chrono::system_clock::now().time_since_epoch()).count();
+        RegisterInstance();
+        return;
+    }
+    
     std::cout << "✅ AudioCaptureAddon created" << std::endl;
     
     // Initialize AEC processor
@@ -81,38 +883,55 @@ AudioCaptureAddon::AudioCaptureAddon(const Napi::CallbackInfo& info)
     config.enable_ns = true;
     config.enable_agc = false;
     config.frame_duration_ms = 10;
+    int sample_rate = 48000;
     
-    try {
-        aec_processor_ = std::make_unique<AECProcessor>(config);
-        if (aec_processor_->Initialize(48000, 1)) {
-            std::cout << "✅ AEC processor initialized" << std::endl;
-        } else {
-            std::cerr << "❌ Failed to initialize AEC processor" << std::endl;
-            aec_processor_.reset();
+    // Optional options object: new AudioCaptureAddon({ levelSmoothingMs, ... })
+    if (info.Length() > 0 && info[0].IsObject()) {
+        if (!ReadConfigOptions(info.Env(), info[0].As<Napi::Object>(), &config, &sample_rate)) {
+            return;
         }
-    } catch (const std::exception& e) {
-        std::cerr << "❌ Exception initializing AEC: " << e.what() << std::endl;
-        aec_processor_.reset();
     }
+    
+    std::string init_error;
+    aec_processor_ = CreateProcessor(config, sample_rate, &init_error);
+    if (!aec_processor_) {
+        ThrowCodedError<Napi::Error>(info.Env(), kErrConfigFailed,
+            "Failed to initialize AEC processor" + (init_error.empty() ? std::string() : ": " + init_error));
+        return;
+    }
+    
+    created_at_ms_ = std::chrono::duration<double, std::milli>(
+        std::chrono::system_clock::now().time_since_epoch()).count();
+    RegisterInstance();
 }
 
 AudioCaptureAddon::~AudioCaptureAddon() {
     if (is_capturing_) {
         StopMicrophoneCapture(Napi::CallbackInfo(Env(), nullptr));
     }
+    UnregisterInstance();
+    if (aec_processor_) {
+        aec_processor_->SetLogSink(nullptr, AECLogLevel::kWarn);  // Before the TSFN it captured is released
+    }
     aec_processor_.reset();
+    ReleaseLogger();
+    ReleaseOutputCallback();
 }
 
 Napi::Value AudioCaptureAddon::StartMicrophoneCapture(const Napi::CallbackInfo& info) {
     Napi::Env env = info.Env();
     
+    if (ThrowIfDestroyed(env)) {
+        return Napi::Boolean::New(env, false);
+    }
+    
     if (is_capturing_) {
         return Napi::Boolean::New(env, false);
     }
     
     // Get callback function
     if (info.Length() < 1 || !info[0].IsFunction()) {
-        Napi::Error::New(env, "Callback function required").ThrowAsJavaScriptException();
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Callback function required");
         return Napi::Boolean::New(env, false);
     }
     
@@ -147,7 +966,7 @@ Napi::Value AudioCaptureAddon::StartMicrophoneCapture(const Napi::CallbackInfo&
         &device_id_);
     
     if (status != noErr || device_id_ == kAudioObjectUnknown) {
-        Napi::Error::New(env, "Failed to get default input device").ThrowAsJavaScriptException();
+        ThrowCodedError<Napi::Error>(env, kErrDevice, "Failed to get default input device");
         return Napi::Boolean::New(env, false);
     }
     
@@ -181,7 +1000,7 @@ Napi::Value AudioCaptureAddon::StartMicrophoneCapture(const Napi::CallbackInfo&
     
     AudioComponent component = AudioComponentFindNext(nullptr, &desc);
     if (!component) {
-        Napi::Error::New(env, "Failed to find HALOutput AudioComponent").ThrowAsJavaScriptException();
+        ThrowCodedError<Napi::Error>(env, kErrDevice, "Failed to find HALOutput AudioComponent");
         return Napi::Boolean::New(env, false);
     }
     std::cout << "✅ Step 1: Found HALOutput AudioComponent" << std::endl;
@@ -189,7 +1008,7 @@ Napi::Value AudioCaptureAddon::StartMicrophoneCapture(const Napi::CallbackInfo&
     // STEP 2: Create AudioUnit instance
     status = AudioComponentInstanceNew(component, &mic_audio_unit_);
     if (status != noErr) {
-        Napi::Error::New(env, "Failed to create AudioUnit instance").ThrowAsJavaScriptException();
+        ThrowCodedError<Napi::Error>(env, kErrDevice, "Failed to create AudioUnit instance");
         return Napi::Boolean::New(env, false);
     }
     std::cout << "✅ Step 2: Created AudioUnit instance" << std::endl;
@@ -206,7 +1025,7 @@ Napi::Value AudioCaptureAddon::StartMicrophoneCapture(const Napi::CallbackInfo&
     
     if (status != noErr) {
         AudioComponentInstanceDispose(mic_audio_unit_);
-        Napi::Error::New(env, "Failed to enable input").ThrowAsJavaScriptException();
+        ThrowCodedError<Napi::Error>(env, kErrDevice, "Failed to enable input");
         return Napi::Boolean::New(env, false);
     }
     std::cout << "✅ Step 3: Enabled INPUT on bus 1" << std::endl;
@@ -222,7 +1041,7 @@ Napi::Value AudioCaptureAddon::StartMicrophoneCapture(const Napi::CallbackInfo&
     
     if (status != noErr) {
         AudioComponentInstanceDispose(mic_audio_unit_);
-        Napi::Error::New(env, "Failed to disable output").ThrowAsJavaScriptException();
+        ThrowCodedError<Napi::Error>(env, kErrDevice, "Failed to disable output");
         return Napi::Boolean::New(env, false);
     }
     std::cout << "✅ Step 4: Disabled OUTPUT on bus 0" << std::endl;
@@ -239,7 +1058,7 @@ Napi::Value AudioCaptureAddon::StartMicrophoneCapture(const Napi::CallbackInfo&
     if (status != noErr) {
         AudioComponentInstanceDispose(mic_audio_unit_);
         std::cerr << "❌ Failed to set input device, error: " << status << std::endl;
-        Napi::Error::New(env, "Failed to set input device").ThrowAsJavaScriptException();
+        ThrowCodedError<Napi::Error>(env, kErrDevice, "Failed to set input device");
         return Napi::Boolean::New(env, false);
     }
     std::cout << "✅ Step 5: Set device to " << device_id_ << std::endl;
@@ -266,7 +1085,7 @@ Napi::Value AudioCaptureAddon::StartMicrophoneCapture(const Napi::CallbackInfo&
     
     if (status != noErr) {
         AudioComponentInstanceDispose(mic_audio_unit_);
-        Napi::Error::New(env, "Failed to set stream format").ThrowAsJavaScriptException();
+        ThrowCodedError<Napi::Error>(env, kErrDevice, "Failed to set stream format");
         return Napi::Boolean::New(env, false);
     }
     std::cout << "✅ Step 6: Set Float32 48kHz format on INPUT bus" << std::endl;
@@ -276,7 +1095,7 @@ Napi::Value AudioCaptureAddon::StartMicrophoneCapture(const Napi::CallbackInfo&
     if (status != noErr) {
         AudioComponentInstanceDispose(mic_audio_unit_);
         std::cerr << "❌ Failed to initialize AudioUnit, error: " << status << std::endl;
-        Napi::Error::New(env, "Failed to initialize AudioUnit").ThrowAsJavaScriptException();
+        ThrowCodedError<Napi::Error>(env, kErrDevice, "Failed to initialize AudioUnit");
         return Napi::Boolean::New(env, false);
     }
     std::cout << "✅ Step 7: AudioUnit initialized" << std::endl;
@@ -368,7 +1187,7 @@ Napi::Value AudioCaptureAddon::StartMicrophoneCapture(const Napi::CallbackInfo&
         AudioUnitUninitialize(mic_audio_unit_);
         AudioComponentInstanceDispose(mic_audio_unit_);
         std::cerr << "❌ Failed to create IOProc, error: " << status << std::endl;
-        Napi::Error::New(env, "Failed to create IOProc").ThrowAsJavaScriptException();
+        ThrowCodedError<Napi::Error>(env, kErrDevice, "Failed to create IOProc");
         return Napi::Boolean::New(env, false);
     }
     std::cout << "✅ Step 8: Created HAL IOProc callback" << std::endl;
@@ -380,7 +1199,7 @@ Napi::Value AudioCaptureAddon::StartMicrophoneCapture(const Napi::CallbackInfo&
         AudioUnitUninitialize(mic_audio_unit_);
         AudioComponentInstanceDispose(mic_audio_unit_);
         std::cerr << "❌ Failed to start AudioDevice, error: " << status << std::endl;
-        Napi::Error::New(env, "Failed to start AudioDevice").ThrowAsJavaScriptException();
+        ThrowCodedError<Napi::Error>(env, kErrDevice, "Failed to start AudioDevice");
         return Napi::Boolean::New(env, false);
     }
     std::cout << "✅ Step 9: AudioDevice started!" << std::endl;
@@ -429,106 +1248,2226 @@ Napi::Value AudioCaptureAddon::StopMicrophoneCapture(const Napi::CallbackInfo& i
 Napi::Value AudioCaptureAddon::ProcessRenderAudio(const Napi::CallbackInfo& info) {
     Napi::Env env = info.Env();
     
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Undefined();
+    }
+    
     if (!aec_processor_) {
         return env.Undefined();
     }
     
-    if (info.Length() < 1 || !info[0].IsTypedArray()) {
-        Napi::TypeError::New(env, "Expected Float32Array").ThrowAsJavaScriptException();
+    if (info.Length() < 1 || !IsFloat32Array(info[0])) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected Float32Array");
         return env.Null();
     }
     
     Napi::Float32Array input = info[0].As<Napi::Float32Array>();
+    if (ThrowIfInvalidChunk(env, *aec_processor_, input.ElementLength(), true)) {
+        return env.Undefined();
+    }
     
     try {
         aec_processor_->ProcessRenderAudio(input.Data(), input.ElementLength());
     } catch (const std::exception& e) {
-        std::cerr << "❌ ProcessRenderAudio error: " << e.what() << std::endl;
+        ThrowProcessingError(env, "processRenderAudio", e);
+    }
+    
+    return env.Undefined();
+}
+
+// processRenderInterleaved(buffer, channels, channel = 0)
+// buffer is interleaved Int16Array or Float32Array; channel picks one channel,
+// or -1 averages all of them. Feeds the mono result like processRenderAudio.
+Napi::Value AudioCaptureAddon::ProcessRenderInterleaved(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 2 || !info[0].IsTypedArray() || !info[1].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected (Int16Array | Float32Array, channels, channel?)");
+        return env.Undefined();
+    }
+    
+    Napi::TypedArray buffer = info[0].As<Napi::TypedArray>();
+    napi_typedarray_type type = buffer.TypedArrayType();
+    if (type != napi_int16_array && type != napi_float32_array) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected Int16Array or Float32Array");
+        return env.Undefined();
+    }
+    
+    int channels = info[1].As<Napi::Number>().Int32Value();
+    int channel = info.Length() > 2 && info[2].IsNumber() ? info[2].As<Napi::Number>().Int32Value() : 0;
+    if (channels < 1) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "channels must be at least 1");
+        return env.Undefined();
+    }
+    if (channel < -1 || channel >= channels) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "channel must be -1 (mix) or less than channels");
+        return env.Undefined();
+    }
+    if (buffer.ElementLength() % channels != 0) {
+        ThrowCodedError<Napi::RangeError>(env, kErrBufferLength, "Buffer length must be a multiple of channels");
+        return env.Undefined();
+    }
+    
+    size_t frames = buffer.ElementLength() / channels;
+    if (ThrowIfInvalidChunk(env, *aec_processor_, frames, true)) {
+        return env.Undefined();
+    }
+    render_scratch_.resize(frames);
+    auto extract = [&](auto* data, float scale) {
+        for (size_t i = 0; i < frames; i++) {
+            const auto* frame = data + i * channels;
+            if (channel >= 0) {
+                render_scratch_[i] = frame[channel] * scale;
+            } else {
+                float sum = 0.0f;
+                for (int c = 0; c < channels; c++) sum += frame[c];
+                render_scratch_[i] = sum * scale / channels;
+            }
+        }
+    };
+    if (type == napi_int16_array) {
+        extract(buffer.As<Napi::Int16Array>().Data(), 1.0f / 32768.0f);
+    } else {
+        extract(buffer.As<Napi::Float32Array>().Data(), 1.0f);
+    }
+    
+    try {
+        aec_processor_->ProcessRenderAudio(render_scratch_.data(), frames);
+    } catch (const std::exception& e) {
+        ThrowProcessingError(env, "processRenderInterleaved", e);
     }
     
     return env.Undefined();
 }
 
+// processCaptureAudio(input, options?) -> output
+// options: { bypass, outputGainDb, collectStats, strict } for this call only;
+// collectStats returns { output, erleDb, inputRms, ... } instead.
 Napi::Value AudioCaptureAddon::ProcessCaptureAudio(const Napi::CallbackInfo& info) {
     Napi::Env env = info.Env();
     
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Null();
+    }
+    
     if (!aec_processor_) {
         return env.Null();
     }
     
-    if (info.Length() < 1 || !info[0].IsTypedArray()) {
-        Napi::TypeError::New(env, "Expected Float32Array").ThrowAsJavaScriptException();
+    if (info.Length() < 1 || !IsFloat32Array(info[0])) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected Float32Array");
         return env.Null();
     }
     
+    AECCallOptions call_options;
+    bool collect_stats = false;
+    if (info.Length() > 1 && !IsAbsentOption(info[1])) {
+        if (!info[1].IsObject()) {
+            ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected options object");
+            return env.Null();
+        }
+        if (!ReadCallOptions(env, info[1].As<Napi::Object>(), &call_options, &collect_stats)) {
+            return env.Null();
+        }
+    }
+    
     Napi::Float32Array input = info[0].As<Napi::Float32Array>();
+    if (ThrowIfInvalidChunk(env, *aec_processor_, input.ElementLength(), false)) {
+        return env.Null();
+    }
+    if (input.ElementLength() == 0) {
+        return EmptyInputFiller(env);
+    }
+    
     Napi::Float32Array output = Napi::Float32Array::New(env, input.ElementLength());
+    AECCallStats stats;
     
     try {
-        aec_processor_->ProcessCaptureAudio(input.Data(), output.Data(), input.ElementLength());
+        aec_processor_->ProcessCaptureAudio(input.Data(), output.Data(), input.ElementLength(), call_options);
+        if (collect_stats) {
+            stats = aec_processor_->GetLastCallStats();  // Before listeners can run
+        }
     } catch (const std::exception& e) {
-        std::cerr << "❌ ProcessCaptureAudio error: " << e.what() << std::endl;
+        ThrowProcessingError(env, "processCaptureAudio", e);
         return env.Null();
     }
     
+    EmitOutput(output.Data(), output.ElementLength());
+    NotifyWatchdog(env);
+    NotifyEvents(env);
+    if (collect_stats) {
+        Napi::Object result = Napi::Object::New(env);
+        result.Set("output", output);
+        SetCallStats(result, stats);
+        return result;
+    }
     return output;
 }
 
-Napi::Value AudioCaptureAddon::GetMetrics(const Napi::CallbackInfo& info) {
+// Result for a zero-length capture chunk. Counted natively; a stalled device
+// can get a frame of silence or noise back.
+Napi::Float32Array AudioCaptureAddon::EmptyInputFiller(Napi::Env env) {
+    aec_processor_->ProcessCaptureAudio(nullptr, nullptr, 0);
+    bool fill = aec_processor_->GetConfig().empty_input_mode != "empty";
+    Napi::Float32Array filler = Napi::Float32Array::New(env, fill ? aec_processor_->GetFrameSize() : 0);
+    aec_processor_->FillEmptyInput(filler.Data(), filler.ElementLength());
+    return filler;
+}
+
+// processMany([input, ...]) -> [output, ...]
+// Sequential chunks in one native call, for catching up after a stall or
+// running a pre-recorded file. Each chunk is processed exactly as a
+// processCaptureAudio call would be; watchdog and event listeners are notified
+// once, after the batch. Elements are all validated before any is processed,
+// and an empty array returns an empty array without touching the processor.
+Napi::Value AudioCaptureAddon::ProcessMany(const Napi::CallbackInfo& info) {
     Napi::Env env = info.Env();
     
-    if (!aec_processor_) {
+    if (ThrowIfDestroyed(env)) {
         return env.Null();
     }
     
-    try {
-        AECMetrics metrics = aec_processor_->GetMetrics();
-        
-        Napi::Object result = Napi::Object::New(env);
-        result.Set("echoReturnLoss", metrics.echo_return_loss);
-        result.Set("echoReturnLossEnhancement", metrics.echo_return_loss_enhancement);
-        result.Set("renderDelayMs", metrics.render_delay_ms);
-        result.Set("aecConverged", metrics.aec_converged);
-        result.Set("rmsLevel", metrics.rms_level);
-        result.Set("peakLevel", metrics.peak_level);
-        
-        return result;
-    } catch (const std::exception& e) {
-        std::cerr << "❌ GetMetrics error: " << e.what() << std::endl;
+    if (ThrowIfReentrant(env)) {
         return env.Null();
     }
-}
-
-Napi::Value AudioCaptureAddon::SetEchoCancellationEnabled(const Napi::CallbackInfo& info) {
-    Napi::Env env = info.Env();
     
     if (!aec_processor_) {
-        return env.Undefined();
+        return env.Null();
     }
     
-    if (info.Length() < 1 || !info[0].IsBoolean()) {
-        Napi::TypeError::New(env, "Expected boolean").ThrowAsJavaScriptException();
-        return env.Undefined();
+    if (info.Length() < 1 || !info[0].IsArray()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected array of Float32Array");
+        return env.Null();
     }
     
-    bool enabled = info[0].As<Napi::Boolean>().Value();
+    Napi::Array inputs = info[0].As<Napi::Array>();
+    for (uint32_t i = 0; i < inputs.Length(); i++) {
+        Napi::Value element = inputs.Get(i);
+        if (!IsFloat32Array(element)) {
+            ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "processMany: element " + std::to_string(i) + " is not a Float32Array");
+            return env.Null();
+        }
+        if (ThrowIfInvalidChunk(env, *aec_processor_, element.As<Napi::Float32Array>().ElementLength(), false)) {
+            return env.Null();
+        }
+    }
+    if (inputs.Length() == 0) {
+        return Napi::Array::New(env, 0);
+    }
     
-    try {
-        aec_processor_->SetEchoCancellationEnabled(enabled);
-    } catch (const std::exception& e) {
-        std::cerr << "❌ SetEchoCancellationEnabled error: " << e.what() << std::endl;
+    Napi::Array outputs = Napi::Array::New(env, inputs.Length());
+    for (uint32_t i = 0; i < inputs.Length(); i++) {
+        Napi::Float32Array input = inputs.Get(i).As<Napi::Float32Array>();
+        if (input.ElementLength() == 0) {
+            outputs.Set(i, EmptyInputFiller(env));
+            continue;
+        }
+        
+        Napi::Float32Array output = Napi::Float32Array::New(env, input.ElementLength());
+        try {
+            aec_processor_->ProcessCaptureAudio(input.Data(), output.Data(), input.ElementLength());
+        } catch (const std::exception& e) {
+            ThrowProcessingError(env, "processMany", e);
+            return env.Null();
+        }
+        outputs.Set(i, output);
+        EmitOutput(output.Data(), output.ElementLength());
     }
     
-    return env.Undefined();
+    NotifyWatchdog(env);
+    NotifyEvents(env);
+    return outputs;
 }
 
-Napi::Value AudioCaptureAddon::GetDevices(const Napi::CallbackInfo& info) {
+// processInto(input, output) -> bytes written (4 per sample). Writes into a
+// caller-owned Float32Array (may be the input itself) so steady-state
+// processing doesn't allocate.
+Napi::Value AudioCaptureAddon::ProcessInto(const Napi::CallbackInfo& info) {
     Napi::Env env = info.Env();
-    Napi::Array devices = Napi::Array::New(env);
     
-    AudioObjectPropertyAddress propertyAddress = {
-        kAudioHardwarePropertyDevices,
-        kAudioObjectPropertyScopeGlobal,
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    if (info.Length() < 2 || !IsFloat32Array(info[0]) || !IsFloat32Array(info[1])) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected input and output Float32Array");
+        return env.Null();
+    }
+    
+    Napi::Float32Array input = info[0].As<Napi::Float32Array>();
+    Napi::Float32Array output = info[1].As<Napi::Float32Array>();
+    if (output.ElementLength() < input.ElementLength()) {
+        ThrowCodedError<Napi::RangeError>(env, kErrBufferLength, "Output buffer is smaller than input");
+        return env.Null();
+    }
+    if (ThrowIfInvalidChunk(env, *aec_processor_, input.ElementLength(), false)) {
+        return env.Null();
+    }
+    
+    try {
+        aec_processor_->ProcessCaptureAudio(input.Data(), output.Data(), input.ElementLength());
+    } catch (const std::exception& e) {
+        ThrowProcessingError(env, "processInto", e);
+        return env.Null();
+    }
+    
+    EmitOutput(output.Data(), input.ElementLength());
+    NotifyWatchdog(env);
+    NotifyEvents(env);
+    return Napi::Number::New(env, static_cast<double>(input.ElementLength() * sizeof(float)));
+}
+
+// processAndMeasure(input) -> { output, erleDb, inputRms, outputRms, doubleTalk,
+// elapsedUs, frameGains }. frameGains holds one output/input energy ratio per
+// full frameSize block of the call, aligned to the output; a trailing partial
+// frame gets none.
+Napi::Value AudioCaptureAddon::ProcessAndMeasure(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    if (info.Length() < 1 || !IsFloat32Array(info[0])) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected Float32Array");
+        return env.Null();
+    }
+    
+    Napi::Float32Array input = info[0].As<Napi::Float32Array>();
+    if (ThrowIfInvalidChunk(env, *aec_processor_, input.ElementLength(), false)) {
+        return env.Null();
+    }
+    Napi::Float32Array output = Napi::Float32Array::New(env, input.ElementLength());
+    
+    try {
+        aec_processor_->ProcessCaptureAudio(input.Data(), output.Data(), input.ElementLength());
+        AECCallStats stats = aec_processor_->GetLastCallStats();
+        
+        Napi::Object result = Napi::Object::New(env);
+        result.Set("output", output);
+        SetCallStats(result, stats);
+        
+        size_t frame_size = aec_processor_->GetFrameSize();
+        size_t num_frames = frame_size > 0 ? input.ElementLength() / frame_size : 0;
+        Napi::Float32Array frame_gains = Napi::Float32Array::New(env, num_frames);
+        FrameSuppressionGains(input.Data(), output.Data(), input.ElementLength(), frame_size, frame_gains.Data());
+        result.Set("frameGains", frame_gains);
+        
+        EmitOutput(output.Data(), output.ElementLength());
+        NotifyWatchdog(env);
+        NotifyEvents(env);
+        return result;
+    } catch (const std::exception& e) {
+        ThrowProcessingError(env, "processAndMeasure", e);
+        return env.Null();
+    }
+}
+
+// processSplit(input) -> { output, removed }
+// removed[i] = input[i] - output[i]: what the canceller (plus NS and any output
+// gain stages) took away, so input = output + removed. Gain added by AGC shows
+// up as negative removed signal.
+Napi::Value AudioCaptureAddon::ProcessSplit(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    if (info.Length() < 1 || !IsFloat32Array(info[0])) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected Float32Array");
+        return env.Null();
+    }
+    
+    Napi::Float32Array input = info[0].As<Napi::Float32Array>();
+    size_t num_samples = input.ElementLength();
+    if (ThrowIfInvalidChunk(env, *aec_processor_, num_samples, false)) {
+        return env.Null();
+    }
+    Napi::Float32Array output = Napi::Float32Array::New(env, num_samples);
+    Napi::Float32Array removed = Napi::Float32Array::New(env, num_samples);
+    
+    try {
+        aec_processor_->ProcessCaptureAudio(input.Data(), output.Data(), num_samples);
+    } catch (const std::exception& e) {
+        ThrowProcessingError(env, "processSplit", e);
+        return env.Null();
+    }
+    
+    const float* in = input.Data();
+    const float* out = output.Data();
+    float* diff = removed.Data();
+    for (size_t i = 0; i < num_samples; i++) {
+        diff[i] = in[i] - out[i];
+    }
+    
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("output", output);
+    result.Set("removed", removed);
+    
+    EmitOutput(out, num_samples);
+    NotifyWatchdog(env);
+    NotifyEvents(env);
+    return result;
+}
+
+// warmup(mic, reference) -> frames processed
+// Runs paired mic/reference audio (e.g. a calibration tone) through the
+// canceller to adapt it before live audio flows. No output is returned.
+Napi::Value AudioCaptureAddon::Warmup(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    if (info.Length() < 2 || !IsFloat32Array(info[0]) || !IsFloat32Array(info[1])) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected mic and reference Float32Array");
+        return env.Null();
+    }
+    
+    Napi::Float32Array mic = info[0].As<Napi::Float32Array>();
+    Napi::Float32Array reference = info[1].As<Napi::Float32Array>();
+    if (mic.ElementLength() != reference.ElementLength()) {
+        ThrowCodedError<Napi::RangeError>(env, kErrBufferLength, "Mic and reference must be the same length");
+        return env.Null();
+    }
+    
+    size_t frames = 0;
+    try {
+        frames = aec_processor_->Warmup(mic.Data(), reference.Data(), mic.ElementLength());
+    } catch (const std::exception& e) {
+        ThrowProcessingError(env, "warmup", e);
+        return env.Null();
+    }
+    
+    NotifyWatchdog(env);
+    NotifyEvents(env);
+    return Napi::Number::New(env, static_cast<double>(frames));
+}
+
+// ensureCapacity(maxSamples) - pre-grow scratch buffers for chunks up to maxSamples
+// Native and addon-side; the output Float32Array is still created per call.
+Napi::Value AudioCaptureAddon::EnsureCapacity(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected sample count");
+        return env.Undefined();
+    }
+    
+    double samples = info[0].As<Napi::Number>().DoubleValue();
+    if (!(samples >= 1 && samples <= kMaxInputSamplesLimit) || std::floor(samples) != samples) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue,
+            "maxSamples must be an integer between 1 and " + std::to_string(kMaxInputSamplesLimit));
+        return env.Undefined();
+    }
+    
+    size_t max_samples = static_cast<size_t>(samples);
+    render_scratch_.reserve(max_samples);
+    asr_scratch_.reserve(max_samples);  // Decimated output is never longer than the input
+    output_pending_.reserve(aec_processor_->GetFrameSize() + max_samples);
+    aec_processor_->EnsureCapacity(max_samples);
+    return env.Undefined();
+}
+
+// processAndFeed(input, gain = 1) -> output
+// processCaptureAudio, then the output times gain is fed back as render audio
+// in the same native call, for "hear yourself" monitoring where the processed
+// mic is what the speakers play next.
+Napi::Value AudioCaptureAddon::ProcessAndFeed(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    if (info.Length() < 1 || !IsFloat32Array(info[0])) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected Float32Array");
+        return env.Null();
+    }
+    
+    float gain = 1.0f;
+    if (info.Length() > 1 && !info[1].IsUndefined()) {
+        if (!info[1].IsNumber() || !(info[1].As<Napi::Number>().FloatValue() >= 0.0f)) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "gain must be a non-negative number");
+            return env.Null();
+        }
+        gain = info[1].As<Napi::Number>().FloatValue();
+    }
+    
+    Napi::Float32Array input = info[0].As<Napi::Float32Array>();
+    if (ThrowIfInvalidChunk(env, *aec_processor_, input.ElementLength(), false)) {
+        return env.Null();
+    }
+    Napi::Float32Array output = Napi::Float32Array::New(env, input.ElementLength());
+    
+    try {
+        aec_processor_->ProcessAndFeed(input.Data(), output.Data(), input.ElementLength(), gain);
+    } catch (const std::exception& e) {
+        ThrowProcessingError(env, "processAndFeed", e);
+        return env.Null();
+    }
+    
+    EmitOutput(output.Data(), output.ElementLength());
+    NotifyWatchdog(env);
+    NotifyEvents(env);
+    return output;
+}
+
+// processWithAsrTap(input) -> { output, asr }
+// processCaptureAudio plus the same output decimated to 16 kHz for speech
+// recognition, filtered natively and always in step with the full-rate
+// stream. asr can be a sample longer or shorter than length/factor when
+// chunks aren't a multiple of the factor; the remainder carries over.
+Napi::Value AudioCaptureAddon::ProcessWithAsrTap(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    if (info.Length() < 1 || !IsFloat32Array(info[0])) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected Float32Array");
+        return env.Null();
+    }
+    
+    Napi::Float32Array input = info[0].As<Napi::Float32Array>();
+    if (ThrowIfInvalidChunk(env, *aec_processor_, input.ElementLength(), false)) {
+        return env.Null();
+    }
+    Napi::Float32Array output = Napi::Float32Array::New(env, input.ElementLength());
+    
+    bool processed = false;
+    try {
+        processed = aec_processor_->ProcessWithAsrTap(input.Data(), output.Data(), input.ElementLength(), &asr_scratch_);
+    } catch (const std::exception& e) {
+        ThrowProcessingError(env, "processWithAsrTap", e);
+        return env.Null();
+    }
+    if (!processed) {
+        ThrowCodedError<Napi::RangeError>(env, kErrUnsupportedSampleRate,
+            "ASR tap needs a sample rate that is a multiple of " + std::to_string(AECProcessor::kAsrSampleRate) + " Hz");
+        return env.Null();
+    }
+    
+    Napi::Float32Array asr = Napi::Float32Array::New(env, asr_scratch_.size());
+    std::copy(asr_scratch_.begin(), asr_scratch_.end(), asr.Data());
+    
+    EmitOutput(output.Data(), output.ElementLength());
+    NotifyWatchdog(env);
+    NotifyEvents(env);
+    
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("output", output);
+    result.Set("asr", asr);
+    return result;
+}
+
+// processAsync(input) -> Promise<Float32Array>
+// processCaptureAudio on a worker thread, keeping the JS thread free. Jobs run
+// one at a time in call order; other calls made meanwhile wait on the
+// processor lock. Don't interleave with synchronous processing calls, which
+// would jump ahead of queued jobs.
+Napi::Value AudioCaptureAddon::ProcessAsync(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    if (info.Length() < 1 || !IsFloat32Array(info[0])) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected Float32Array");
+        return env.Null();
+    }
+    
+    Napi::Float32Array input = info[0].As<Napi::Float32Array>();
+    if (ThrowIfInvalidChunk(env, *aec_processor_, input.ElementLength(), false)) {
+        return env.Null();
+    }
+    auto* worker = new ProcessAsyncWorker(env, this, aec_processor_, input.Data(), input.ElementLength());
+    Napi::Promise promise = worker->Promise();
+    if (async_running_) {
+        async_queue_.push_back(worker);
+    } else {
+        async_running_ = true;
+        worker->Queue();
+    }
+    
+    return promise;
+}
+
+void AudioCaptureAddon::FinishAsync(Napi::Env env) {
+    async_running_ = false;
+    NotifyWatchdog(env);
+    NotifyEvents(env);
+    if (!async_queue_.empty()) {
+        ProcessAsyncWorker* next = async_queue_.front();
+        async_queue_.pop_front();
+        async_running_ = true;
+        next->Queue();
+    }
+}
+
+// notifyDroppedInput(sampleCount)
+// Tells the processor the caller skipped this many capture samples so the
+// matching reference is discarded and alignment survives the gap.
+Napi::Value AudioCaptureAddon::NotifyDroppedInput(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected sample count");
+        return env.Undefined();
+    }
+    
+    double samples = info[0].As<Napi::Number>().DoubleValue();
+    if (!std::isfinite(samples) || samples < 0) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "sampleCount must be a non-negative number");
+        return env.Undefined();
+    }
+    
+    aec_processor_->NotifyDroppedInput(static_cast<size_t>(samples));
+    
+    return env.Undefined();
+}
+
+Napi::Value AudioCaptureAddon::GetMetrics(const Napi::CallbackInfo& info) {
+    return MetricsSnapshot(info.Env(), false);
+}
+
+// getAndResetMetrics() - getMetrics() followed by resetMetrics() in one call
+Napi::Value AudioCaptureAddon::GetAndResetMetrics(const Napi::CallbackInfo& info) {
+    return MetricsSnapshot(info.Env(), true);
+}
+
+// getMetricsJson() -> string, see MetricsToJson for the schema
+Napi::Value AudioCaptureAddon::GetMetricsJson(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    try {
+        return Napi::String::New(env, MetricsToJson(aec_processor_->GetMetrics(), aec_processor_->GetConfig(),
+                                                    aec_processor_->GetSampleRate()));
+    } catch (const std::exception& e) {
+        std::cerr << "❌ GetMetricsJson error: " << e.what() << std::endl;
+        return env.Null();
+    }
+}
+
+Napi::Value AudioCaptureAddon::MetricsSnapshot(Napi::Env env, bool reset) {
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    try {
+        AECMetrics metrics = reset ? aec_processor_->GetAndResetMetrics() : aec_processor_->GetMetrics();
+        return MetricsToObject(env, metrics);
+    } catch (const std::exception& e) {
+        std::cerr << "❌ GetMetrics error: " << e.what() << std::endl;
+        return env.Null();
+    }
+}
+
+Napi::Value AudioCaptureAddon::ResetMetrics(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (aec_processor_) {
+        aec_processor_->ResetMetrics();
+    }
+    
+    return env.Undefined();
+}
+
+// healthCheck(autoRecover = false) -> { healthy, recovered, checks: { name: passed } }
+Napi::Value AudioCaptureAddon::HealthCheck(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    bool auto_recover = info.Length() > 0 && info[0].IsBoolean() && info[0].As<Napi::Boolean>().Value();
+    AECHealthReport report = aec_processor_->HealthCheck(auto_recover);
+    
+    Napi::Object checks = Napi::Object::New(env);
+    checks.Set("outputNotStuck", !report.output_stuck);
+    checks.Set("outputNotAboveInput", !report.output_exceeds_input);
+    checks.Set("referenceNotPinned", !report.reference_pinned);
+    checks.Set("finiteSamples", !report.non_finite);
+    
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("healthy", report.healthy);
+    result.Set("recovered", report.recovered);
+    result.Set("checks", checks);
+    
+    return result;
+}
+
+// resetAEC(resetMetrics = false) - clears audio/adaptation state; metrics only on request
+Napi::Value AudioCaptureAddon::ResetAEC(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    bool reset_metrics = info.Length() > 0 && info[0].IsBoolean() && info[0].As<Napi::Boolean>().Value();
+    if (!aec_processor_->Reset(reset_metrics)) {
+        std::cerr << "❌ Failed to reinitialize AEC after reset" << std::endl;
+    }
+    
+    return env.Undefined();
+}
+
+Napi::Value AudioCaptureAddon::SetEchoCancellationEnabled(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsBoolean()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected boolean");
+        return env.Undefined();
+    }
+    
+    bool enabled = info[0].As<Napi::Boolean>().Value();
+    
+    try {
+        aec_processor_->SetEchoCancellationEnabled(enabled);
+    } catch (const std::exception& e) {
+        std::cerr << "❌ SetEchoCancellationEnabled error: " << e.what() << std::endl;
+    }
+    
+    return env.Undefined();
+}
+
+// setNoiseSuppression(enabled, level = "moderate")
+Napi::Value AudioCaptureAddon::SetNoiseSuppression(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsBoolean()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected boolean");
+        return env.Undefined();
+    }
+    
+    int level = aec_processor_->GetTuning().noise_suppression_level;
+    if (info.Length() > 1 && info[1].IsString()) {
+        level = ParseNoiseSuppressionLevel(info[1].As<Napi::String>().Utf8Value());
+        if (level < 0) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "level must be one of: low, moderate, high, veryHigh");
+            return env.Undefined();
+        }
+    }
+    
+    aec_processor_->SetNoiseSuppression(info[0].As<Napi::Boolean>().Value(), level);
+    
+    return env.Undefined();
+}
+
+Napi::Value AudioCaptureAddon::ApplyPreset(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsString()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected preset name");
+        return env.Undefined();
+    }
+    
+    std::string name = info[0].As<Napi::String>().Utf8Value();
+    if (!aec_processor_->ApplyPreset(name)) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "preset must be one of: gentle, balanced, aggressive");
+        return env.Undefined();
+    }
+    
+    std::cout << "✅ AEC preset applied: " << name << std::endl;
+    return env.Undefined();
+}
+
+// setEmptyInputMode("empty" | "silence" | "comfortNoise")
+Napi::Value AudioCaptureAddon::SetEmptyInputMode(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsString()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected empty input mode");
+        return env.Undefined();
+    }
+    
+    std::string mode = info[0].As<Napi::String>().Utf8Value();
+    if (!aec_processor_->SetEmptyInputMode(mode)) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "mode must be one of: empty, silence, comfortNoise");
+        return env.Undefined();
+    }
+    
+    return env.Undefined();
+}
+
+// setUnderrunStrategy("zeros" | "hold")
+// What the canceller runs against when capture outpaces the reference.
+Napi::Value AudioCaptureAddon::SetUnderrunStrategy(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsString()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected underrun strategy");
+        return env.Undefined();
+    }
+    
+    std::string strategy = info[0].As<Napi::String>().Utf8Value();
+    if (!aec_processor_->SetUnderrunStrategy(strategy)) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "strategy must be one of: zeros, hold");
+        return env.Undefined();
+    }
+    
+    return env.Undefined();
+}
+
+// setThreadPriority("normal" | "high" | "realtime") -> applied
+// Applies to the thread making this call, which for addon methods is the JS
+// thread that drives processing. Call it from the thread that runs the audio
+// loop, e.g. a worker dedicated to it. processAsync jobs run on the libuv
+// pool and are unaffected.
+Napi::Value AudioCaptureAddon::SetThreadPriority(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsString()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected thread priority hint");
+        return env.Undefined();
+    }
+    
+    int priority = ParseThreadPriority(info[0].As<Napi::String>().Utf8Value());
+    if (priority < 0) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "priority must be one of: normal, high, realtime");
+        return env.Undefined();
+    }
+    
+    return Napi::Boolean::New(env, ApplyThreadPriority(priority));
+}
+
+// setDeterministic(enabled, seed = 0, elapsedMs = 0.1)
+// Testing only. Reseeds the comfort noise and reports elapsedMs as the
+// processing time of every call, so golden-file runs produce bit-identical
+// output and metrics, and tests can drive the timing metrics.
+Napi::Value AudioCaptureAddon::SetDeterministic(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsBoolean()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected boolean");
+        return env.Undefined();
+    }
+    
+    uint32_t seed = 0;
+    if (info.Length() > 1 && !info[1].IsUndefined()) {
+        if (!info[1].IsNumber()) {
+            ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "seed must be a number");
+            return env.Undefined();
+        }
+        seed = info[1].As<Napi::Number>().Uint32Value();
+    }
+    double elapsed_ms = AECProcessor::kDeterministicElapsedMs;
+    if (info.Length() > 2 && !info[2].IsUndefined()) {
+        if (!info[2].IsNumber()) {
+            ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "elapsedMs must be a number");
+            return env.Undefined();
+        }
+        elapsed_ms = info[2].As<Napi::Number>().DoubleValue();
+        if (!(elapsed_ms >= 0.0 && elapsed_ms <= 10000.0)) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "elapsedMs must be between 0 and 10000");
+            return env.Undefined();
+        }
+    }
+    
+    aec_processor_->SetDeterministic(info[0].As<Napi::Boolean>().Value(), seed, elapsed_ms);
+    
+    return env.Undefined();
+}
+
+// setTestClock(nowMs | null)
+// Testing only. Replaces the clock behind autoResetAfterIdleMs and
+// msSinceLastReference with one that reads nowMs until the next call, so
+// tests can simulate gaps without waiting. null restores the system clock.
+Napi::Value AudioCaptureAddon::SetTestClock(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || info[0].IsNull() || info[0].IsUndefined()) {
+        if (test_clock_ms_) {
+            test_clock_ms_.reset();
+            aec_processor_->SetClock(AECClock());
+        }
+        return env.Undefined();
+    }
+    if (!info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected time in ms or null");
+        return env.Undefined();
+    }
+    double now_ms = info[0].As<Napi::Number>().DoubleValue();
+    if (!std::isfinite(now_ms) || now_ms < 0.0) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "nowMs must be a non-negative number");
+        return env.Undefined();
+    }
+    
+    // Installed once: replacing the clock forgets the last capture time, so
+    // later calls only move the time it reads
+    if (!test_clock_ms_) {
+        test_clock_ms_ = std::make_shared<std::atomic<double>>(now_ms);
+        std::shared_ptr<std::atomic<double>> clock_ms = test_clock_ms_;
+        aec_processor_->SetClock([clock_ms]() {
+            return std::chrono::steady_clock::time_point(std::chrono::duration_cast<std::chrono::steady_clock::duration>(
+                std::chrono::duration<double, std::milli>(clock_ms->load())));
+        });
+    }
+    test_clock_ms_->store(now_ms);
+    
+    return env.Undefined();
+}
+
+// reconfigure(options) -> { rebuilt, rebuildFields }
+// Takes the constructor's options; only those given change. Most apply live
+// from the next chunk. sampleRate, frameDurationMs, engine, preset,
+// filterLengthBlocks and enableAgc rebuild the canceller, which keeps metrics, buffered
+// reference audio and registered callbacks but has to re-converge.
+Napi::Value AudioCaptureAddon::Reconfigure(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsObject()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected options object");
+        return env.Null();
+    }
+    
+    AECConfig config = aec_processor_->GetConfig();
+    int sample_rate = aec_processor_->GetSampleRate();
+    if (!ReadConfigOptions(env, info[0].As<Napi::Object>(), &config, &sample_rate)) {
+        return env.Null();
+    }
+    
+    std::vector<std::string> rebuild_fields;
+    if (!aec_processor_->Reconfigure(config, sample_rate, &rebuild_fields)) {
+        ThrowCodedError<Napi::Error>(env, kErrConfigFailed, "Failed to apply AEC configuration");
+        return env.Null();
+    }
+    
+    Napi::Array fields = Napi::Array::New(env, rebuild_fields.size());
+    for (size_t i = 0; i < rebuild_fields.size(); i++) {
+        fields.Set(static_cast<uint32_t>(i), rebuild_fields[i]);
+    }
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("rebuilt", !rebuild_fields.empty());
+    result.Set("rebuildFields", fields);
+    return result;
+}
+
+// setFilterLength(blocks) - blocks of 64 samples, same bounds as filterLengthBlocks
+Napi::Value AudioCaptureAddon::SetFilterLength(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected number of blocks");
+        return env.Undefined();
+    }
+    
+    int blocks = info[0].As<Napi::Number>().Int32Value();
+    if (!aec_processor_->SetFilterLength(blocks)) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "filterLengthBlocks must be between 1 and 32");
+        return env.Undefined();
+    }
+    
+    std::cout << "✅ AEC filter length set: " << blocks << " blocks" << std::endl;
+    return env.Undefined();
+}
+
+// setMaxInputSamples(samples) - longest capture chunk accepted, 0 = no limit
+Napi::Value AudioCaptureAddon::SetMaxInputSamples(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected sample count");
+        return env.Undefined();
+    }
+    
+    double samples = info[0].As<Napi::Number>().DoubleValue();
+    if (!(samples >= 0 && samples <= kMaxInputSamplesLimit) || std::floor(samples) != samples) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue,
+            "maxInputSamples must be an integer between 0 and " + std::to_string(kMaxInputSamplesLimit));
+        return env.Undefined();
+    }
+    
+    aec_processor_->SetMaxInputSamples(static_cast<int>(samples));
+    return env.Undefined();
+}
+
+// setSampleRate(hz) - frame size follows from the configured frame duration
+Napi::Value AudioCaptureAddon::SetSampleRate(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected sample rate");
+        return env.Undefined();
+    }
+    
+    int sample_rate = info[0].As<Napi::Number>().Int32Value();
+    if (!aec_processor_->SetSampleRate(sample_rate)) {
+        ThrowCodedError<Napi::RangeError>(env, kErrUnsupportedSampleRate,
+                                          UnsupportedSampleRateMessage(aec_processor_->GetConfig(), sample_rate));
+        return env.Undefined();
+    }
+    
+    std::cout << "✅ AEC sample rate set: " << sample_rate << "Hz" << std::endl;
+    return env.Undefined();
+}
+
+// setSupportedSampleRates(rates[]) - extends/replaces the rates setSampleRate accepts
+Napi::Value AudioCaptureAddon::SetSupportedSampleRates(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    std::vector<int> rates;
+    if (info.Length() < 1 || !ReadSampleRates(info[0], &rates)) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected array of sample rates");
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_->SetSupportedSampleRates(rates)) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "supportedSampleRates must be a non-empty array of 8000-384000 Hz rates");
+        return env.Undefined();
+    }
+    
+    return env.Undefined();
+}
+
+// setLoudnessNormalization(enabled, targetDb = -20, maxGainDb = 12)
+Napi::Value AudioCaptureAddon::SetLoudnessNormalization(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsBoolean()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected boolean");
+        return env.Undefined();
+    }
+    
+    bool enabled = info[0].As<Napi::Boolean>().Value();
+    float target_db = -20.0f;
+    if (info.Length() > 1 && !info[1].IsUndefined()) {
+        if (!info[1].IsNumber()) {
+            ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "targetDb must be a number");
+            return env.Undefined();
+        }
+        double value = info[1].As<Napi::Number>().DoubleValue();
+        if (!std::isfinite(value) || value > 0.0 || value < -60.0) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "targetDb must be between -60 and 0");
+            return env.Undefined();
+        }
+        target_db = static_cast<float>(value);
+    }
+    float max_gain_db = 12.0f;
+    if (info.Length() > 2 && !info[2].IsUndefined()) {
+        if (!info[2].IsNumber()) {
+            ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "maxGainDb must be a number");
+            return env.Undefined();
+        }
+        double value = info[2].As<Napi::Number>().DoubleValue();
+        if (!std::isfinite(value) || value < 0.0 || value > 40.0) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "maxGainDb must be between 0 and 40");
+            return env.Undefined();
+        }
+        max_gain_db = static_cast<float>(value);
+    }
+    
+    aec_processor_->SetLoudnessNormalization(enabled, target_db, max_gain_db);
+    
+    return env.Undefined();
+}
+
+// setAgc(enabled, targetDb = -18, maxGainDb = 20)
+Napi::Value AudioCaptureAddon::SetAgc(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsBoolean()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected boolean");
+        return env.Undefined();
+    }
+    
+    bool enabled = info[0].As<Napi::Boolean>().Value();
+    float target_db = -18.0f;
+    if (info.Length() > 1 && !info[1].IsUndefined()) {
+        if (!info[1].IsNumber()) {
+            ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "targetDb must be a number");
+            return env.Undefined();
+        }
+        double value = info[1].As<Napi::Number>().DoubleValue();
+        if (!std::isfinite(value) || value > 0.0 || value < -60.0) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "targetDb must be between -60 and 0");
+            return env.Undefined();
+        }
+        target_db = static_cast<float>(value);
+    }
+    float max_gain_db = 20.0f;
+    if (info.Length() > 2 && !info[2].IsUndefined()) {
+        if (!info[2].IsNumber()) {
+            ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "maxGainDb must be a number");
+            return env.Undefined();
+        }
+        double value = info[2].As<Napi::Number>().DoubleValue();
+        if (!std::isfinite(value) || value < 0.0 || value > 40.0) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "maxGainDb must be between 0 and 40");
+            return env.Undefined();
+        }
+        max_gain_db = static_cast<float>(value);
+    }
+    
+    aec_processor_->SetOutputAgc(enabled, target_db, max_gain_db);
+    
+    return env.Undefined();
+}
+
+// setLimiter(enabled, thresholdDb = -1)
+Napi::Value AudioCaptureAddon::SetLimiter(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsBoolean()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected boolean");
+        return env.Undefined();
+    }
+    
+    bool enabled = info[0].As<Napi::Boolean>().Value();
+    float threshold_db = -1.0f;
+    if (info.Length() > 1 && !info[1].IsUndefined()) {
+        if (!info[1].IsNumber()) {
+            ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "thresholdDb must be a number");
+            return env.Undefined();
+        }
+        double value = info[1].As<Napi::Number>().DoubleValue();
+        if (!std::isfinite(value) || value > 0.0 || value < -24.0) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "thresholdDb must be between -24 and 0");
+            return env.Undefined();
+        }
+        threshold_db = static_cast<float>(value);
+    }
+    
+    aec_processor_->SetLimiter(enabled, threshold_db);
+    
+    return env.Undefined();
+}
+
+// setSilentChunkSkip(enabled, floorDb = -70)
+Napi::Value AudioCaptureAddon::SetSilentChunkSkip(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsBoolean()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected boolean");
+        return env.Undefined();
+    }
+    
+    bool enabled = info[0].As<Napi::Boolean>().Value();
+    float floor_db = -70.0f;
+    if (info.Length() > 1 && !info[1].IsUndefined()) {
+        if (!info[1].IsNumber()) {
+            ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "floorDb must be a number");
+            return env.Undefined();
+        }
+        double value = info[1].As<Napi::Number>().DoubleValue();
+        if (!std::isfinite(value) || value > 0.0 || value < -120.0) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "floorDb must be between -120 and 0");
+            return env.Undefined();
+        }
+        floor_db = static_cast<float>(value);
+    }
+    
+    aec_processor_->SetSilentChunkSkip(enabled, floor_db);
+    
+    return env.Undefined();
+}
+
+// setSilenceBypassThreshold(db | null) - null disables the bypass
+Napi::Value AudioCaptureAddon::SetSilenceBypassThreshold(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !(info[0].IsNumber() || info[0].IsNull())) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected threshold in dBFS or null");
+        return env.Undefined();
+    }
+    
+    if (info[0].IsNull()) {
+        aec_processor_->SetSilenceBypass(false, -60.0f);
+    } else {
+        double value = info[0].As<Napi::Number>().DoubleValue();
+        if (!std::isfinite(value) || value > 0.0 || value < -120.0) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "Silence bypass threshold must be between -120 and 0 dBFS");
+            return env.Undefined();
+        }
+        aec_processor_->SetSilenceBypass(true, static_cast<float>(value));
+    }
+    
+    return env.Undefined();
+}
+
+// setReferenceGain(linear) - scales render audio, e.g. by the system output volume
+Napi::Value AudioCaptureAddon::SetReferenceGain(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected linear gain");
+        return env.Undefined();
+    }
+    
+    float gain = info[0].As<Napi::Number>().FloatValue();
+    if (!std::isfinite(gain) || gain < 0.0f) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "gain must be a non-negative number");
+        return env.Undefined();
+    }
+    
+    aec_processor_->SetReferenceGain(gain);
+    
+    return env.Undefined();
+}
+
+// setOverrunDrainFraction(fraction) - share of the reference buffer kept when
+// render overruns it, 0-1
+Napi::Value AudioCaptureAddon::SetOverrunDrainFraction(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected drain fraction");
+        return env.Undefined();
+    }
+    
+    double fraction = info[0].As<Napi::Number>().DoubleValue();
+    if (!std::isfinite(fraction) || fraction < 0.0 || fraction > 1.0) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "fraction must be between 0 and 1");
+        return env.Undefined();
+    }
+    
+    aec_processor_->SetOverrunDrainFraction(static_cast<float>(fraction));
+    
+    return env.Undefined();
+}
+
+// setInputGain(db) - scales the mic before cancellation, ramped over 10 ms
+Napi::Value AudioCaptureAddon::SetInputGain(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected gain in dB");
+        return env.Undefined();
+    }
+    
+    float gain_db = info[0].As<Napi::Number>().FloatValue();
+    if (!(gain_db >= kMinStageGainDb && gain_db <= kMaxStageGainDb)) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "gain must be between -60 and 40 dB");
+        return env.Undefined();
+    }
+    
+    aec_processor_->SetInputGain(gain_db);
+    
+    return env.Undefined();
+}
+
+// setOutputGain(db) - scales the processed output, ramped over 10 ms
+Napi::Value AudioCaptureAddon::SetOutputGain(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected gain in dB");
+        return env.Undefined();
+    }
+    
+    float gain_db = info[0].As<Napi::Number>().FloatValue();
+    if (!(gain_db >= kMinStageGainDb && gain_db <= kMaxStageGainDb)) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "gain must be between -60 and 40 dB");
+        return env.Undefined();
+    }
+    
+    aec_processor_->SetOutputGain(gain_db);
+    
+    return env.Undefined();
+}
+
+// setPreEmphasis(coefficient) - 0 disables, ~0.97 for speech
+Napi::Value AudioCaptureAddon::SetPreEmphasis(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected pre-emphasis coefficient");
+        return env.Undefined();
+    }
+    
+    float coefficient = info[0].As<Napi::Number>().FloatValue();
+    if (!std::isfinite(coefficient) || coefficient < 0.0f || coefficient >= 1.0f) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "coefficient must be in [0, 1)");
+        return env.Undefined();
+    }
+    
+    aec_processor_->SetPreEmphasis(coefficient);
+    
+    return env.Undefined();
+}
+
+// setAdaptationRate(rate) - filter learning speed, 0.1 to 2, 1 = default tuning
+// Takes effect at once for NLMS; AEC3 is rebuilt and re-converges.
+Napi::Value AudioCaptureAddon::SetAdaptationRate(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected adaptation rate");
+        return env.Undefined();
+    }
+    
+    float rate = info[0].As<Napi::Number>().FloatValue();
+    if (!(rate >= kMinAdaptationRate && rate <= kMaxAdaptationRate)) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "adaptationRate must be between 0.1 and 2");
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_->SetAdaptationRate(rate)) {
+        ThrowCodedError<Napi::Error>(env, kErrConfigFailed, "Failed to rebuild AEC with the new adaptation rate");
+        return env.Undefined();
+    }
+    
+    return env.Undefined();
+}
+
+// setDoubleTalkSensitivity(sensitivity) - 0 disables, up to 0.99; NLMS engine only
+Napi::Value AudioCaptureAddon::SetDoubleTalkSensitivity(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected double-talk sensitivity");
+        return env.Undefined();
+    }
+    
+    float sensitivity = info[0].As<Napi::Number>().FloatValue();
+    if (!std::isfinite(sensitivity) || sensitivity < 0.0f || sensitivity > 0.99f) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "sensitivity must be between 0 and 0.99");
+        return env.Undefined();
+    }
+    
+    aec_processor_->SetDoubleTalkSensitivity(sensitivity);
+    
+    return env.Undefined();
+}
+
+// setAutoResetAfterIdle(idleMs, resetFilter?) - 0 disables; resetFilter
+// defaults to false (only the stale reference is dropped)
+Napi::Value AudioCaptureAddon::SetAutoResetAfterIdle(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber() ||
+        (info.Length() > 1 && !info[1].IsUndefined() && !info[1].IsBoolean())) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected idle ms and optional resetFilter boolean");
+        return env.Undefined();
+    }
+    
+    float idle_ms = info[0].As<Napi::Number>().FloatValue();
+    if (!std::isfinite(idle_ms) || idle_ms < 0.0f || idle_ms > 3600000.0f) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "idleMs must be between 0 and 3600000");
+        return env.Undefined();
+    }
+    bool reset_filter = info.Length() > 1 && info[1].IsBoolean() && info[1].As<Napi::Boolean>().Value();
+    
+    aec_processor_->SetAutoResetAfterIdle(idle_ms, reset_filter);
+    
+    return env.Undefined();
+}
+
+// setErleHistoryLength(entries) - one entry per processCaptureAudio call, at
+// most kMaxErleHistoryLength
+Napi::Value AudioCaptureAddon::SetErleHistoryLength(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber() || info[0].As<Napi::Number>().Int64Value() < 0) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected non-negative history length");
+        return env.Undefined();
+    }
+    
+    double length = info[0].As<Napi::Number>().DoubleValue();
+    if (!(length <= static_cast<double>(kMaxErleHistoryLength))) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "history length must be at most " +
+                                          std::to_string(kMaxErleHistoryLength));
+        return env.Undefined();
+    }
+    
+    aec_processor_->SetErleHistoryLength(static_cast<size_t>(length));
+    
+    return env.Undefined();
+}
+
+// getErleHistory() -> Float32Array of smoothed ERLE in dB, oldest first
+Napi::Value AudioCaptureAddon::GetErleHistory(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    std::vector<float> history = aec_processor_->GetErleHistory();
+    Napi::Float32Array result = Napi::Float32Array::New(env, history.size());
+    std::copy(history.begin(), history.end(), result.Data());
+    
+    return result;
+}
+
+// getLastRawInput() -> Float32Array, the last mic frame before cancellation
+// A debugging aid: listen to it next to processSplit's output to check the
+// reference really is what the speakers play.
+Napi::Value AudioCaptureAddon::GetLastRawInput(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    std::vector<float> raw = aec_processor_->GetLastRawInput();
+    Napi::Float32Array result = Napi::Float32Array::New(env, raw.size());
+    std::copy(raw.begin(), raw.end(), result.Data());
+    
+    return result;
+}
+
+// refBufferAvailable() -> render samples that can be fed before an overrun,
+// for pacing a reference feed that can outrun capture
+Napi::Value AudioCaptureAddon::RefBufferAvailable(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    return Napi::Number::New(env, static_cast<double>(aec_processor_->RefBufferAvailable()));
+}
+
+// getCrossCorrelation() -> { peak, lagMs } over the last 500 ms of mic audio
+Napi::Value AudioCaptureAddon::GetCrossCorrelation(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    AECCrossCorrelation correlation = aec_processor_->GetCrossCorrelation();
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("peak", correlation.peak);
+    result.Set("lagMs", correlation.lag_ms);
+    
+    return result;
+}
+
+// calibrateDelay(maxDelayMs = 500) -> { applied, delaySamples, delayMs, correlation }
+// Estimates the echo delay from the last 500 ms of mic audio and applies it on
+// the nlms engine. applied is false (nothing changed) on webrtc, or unless both
+// mic and reference are active.
+Napi::Value AudioCaptureAddon::CalibrateDelay(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    float max_delay_ms = 500.0f;
+    if (info.Length() > 0 && !info[0].IsUndefined()) {
+        if (!info[0].IsNumber() || !(info[0].As<Napi::Number>().FloatValue() > 0.0f)) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "maxDelayMs must be a positive number");
+            return env.Null();
+        }
+        max_delay_ms = info[0].As<Napi::Number>().FloatValue();
+    }
+    
+    AECDelayEstimate estimate = aec_processor_->CalibrateDelay(max_delay_ms);
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("applied", estimate.applied);
+    result.Set("delaySamples", estimate.delay_samples);
+    result.Set("delayMs", estimate.delay_ms);
+    result.Set("correlation", estimate.correlation);
+    
+    return result;
+}
+
+// startDebugDump(path) -> boolean
+// Records mic / reference / output of every capture call as a 3-channel WAV.
+Napi::Value AudioCaptureAddon::StartDebugDump(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return Napi::Boolean::New(env, false);
+    }
+    
+    if (info.Length() < 1 || !info[0].IsString()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected file path string");
+        return env.Undefined();
+    }
+    
+    std::string path = info[0].As<Napi::String>().Utf8Value();
+    return Napi::Boolean::New(env, aec_processor_->StartDebugDump(path));
+}
+
+// stopDebugDump() -> { framesWritten, droppedChunks } | null when not dumping
+Napi::Value AudioCaptureAddon::StopDebugDump(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_ || !aec_processor_->IsDebugDumping()) {
+        return env.Null();
+    }
+    
+    AECDebugDumpStats stats = aec_processor_->StopDebugDump();
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("framesWritten", static_cast<double>(stats.frames_written));
+    result.Set("droppedChunks", static_cast<double>(stats.dropped_chunks));
+    
+    return result;
+}
+
+// enableDebugCapture(maxSeconds) - keep the last maxSeconds of mic, reference
+// and output in memory; 0 disables and frees it
+Napi::Value AudioCaptureAddon::EnableDebugCapture(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected maxSeconds");
+        return env.Undefined();
+    }
+    
+    float max_seconds = info[0].As<Napi::Number>().FloatValue();
+    if (!std::isfinite(max_seconds) || max_seconds < 0.0f || max_seconds > kMaxDebugCaptureSeconds) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "maxSeconds must be between 0 and " +
+                                          std::to_string(static_cast<int>(kMaxDebugCaptureSeconds)));
+        return env.Undefined();
+    }
+    
+    aec_processor_->EnableDebugCapture(max_seconds);
+    
+    return env.Undefined();
+}
+
+// dumpDebugCapture() -> { mic, reference, output, sampleRate } | null when not capturing
+// Copies the rings; capture keeps running.
+Napi::Value AudioCaptureAddon::DumpDebugCapture(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_ || !aec_processor_->IsDebugCapturing()) {
+        return env.Null();
+    }
+    
+    AECDebugCapture capture = aec_processor_->GetDebugCapture();
+    auto to_array = [&](const std::vector<float>& samples) {
+        Napi::Float32Array array = Napi::Float32Array::New(env, samples.size());
+        std::copy(samples.begin(), samples.end(), array.Data());
+        return array;
+    };
+    
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("mic", to_array(capture.mic));
+    result.Set("reference", to_array(capture.reference));
+    result.Set("output", to_array(capture.output));
+    result.Set("sampleRate", capture.sample_rate);
+    
+    return result;
+}
+
+// setLogger(callback | null, level = "warn")
+// callback(level, message) for native log records at or above level. Records
+// are queued to the JS event loop, never called inline, so logging from inside
+// a processing call cannot re-enter the processor.
+Napi::Value AudioCaptureAddon::SetLogger(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    AECLogLevel level = AECLogLevel::kWarn;
+    if (info.Length() > 1 && info[1].IsString() &&
+        !ParseLogLevel(info[1].As<Napi::String>().Utf8Value(), &level)) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "level must be one of: error, warn, info, debug, trace");
+        return env.Undefined();
+    }
+    
+    aec_processor_->SetLogSink(nullptr, level);
+    ReleaseLogger();
+    if (info.Length() < 1 || !info[0].IsFunction()) {
+        return env.Undefined();
+    }
+    
+    logger_tsfn_ = Napi::ThreadSafeFunction::New(
+        env,
+        info[0].As<Napi::Function>(),
+        "AECLogger",
+        256,
+        1);
+    logger_tsfn_.Unref(env);  // Don't keep the process alive just for logging
+    
+    Napi::ThreadSafeFunction tsfn = logger_tsfn_;
+    aec_processor_->SetLogSink([tsfn](AECLogLevel record_level, const std::string& message) {
+        struct LogRecord {
+            AECLogLevel level;
+            std::string message;
+        };
+        
+        LogRecord* record = new LogRecord{record_level, message};
+        napi_status status = tsfn.NonBlockingCall(record, [](Napi::Env env, Napi::Function jsCallback, LogRecord* record) {
+            try {
+                jsCallback.Call({
+                    Napi::String::New(env, LogLevelName(record->level)),
+                    Napi::String::New(env, record->message)
+                });
+            } catch (...) {
+                // A throwing logger must not take the addon down
+            }
+            delete record;
+        });
+        
+        if (status != napi_ok) {
+            delete record;  // Queue full or closing: drop
+        }
+    }, level);
+    
+    return env.Undefined();
+}
+
+void AudioCaptureAddon::ReleaseLogger() {
+    if (logger_tsfn_) {
+        logger_tsfn_.Release();
+        logger_tsfn_ = Napi::ThreadSafeFunction();
+    }
+}
+
+// setOutputCallback(callback | null)
+// callback(frame, { sequence, droppedFrames }) for every frameSize block of
+// processed capture audio, whatever chunk sizes the process* calls (and
+// processAsync) are given. Frames are queued to the JS event loop; once the
+// consumer is kOutputCallbackQueueFrames behind, new frames are dropped and
+// counted instead of holding up processing. sequence counts frames produced,
+// so gaps show where drops happened.
+Napi::Value AudioCaptureAddon::SetOutputCallback(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    ReleaseOutputCallback();
+    if (info.Length() < 1 || !info[0].IsFunction()) {
+        return env.Undefined();
+    }
+    
+    output_tsfn_ = Napi::ThreadSafeFunction::New(
+        env,
+        info[0].As<Napi::Function>(),
+        "AECOutputCallback",
+        kOutputCallbackQueueFrames,
+        1);
+    output_tsfn_.Unref(env);  // Don't keep the process alive just for the sink
+    
+    return env.Undefined();
+}
+
+void AudioCaptureAddon::EmitOutput(const float* samples, size_t num_samples) {
+    if (!output_tsfn_ || !aec_processor_) {
+        return;
+    }
+    size_t frame_size = aec_processor_->GetFrameSize();
+    if (frame_size == 0) {
+        return;
+    }
+    
+    struct OutputFrame {
+        std::vector<float> samples;
+        uint64_t sequence;
+        uint64_t dropped;
+    };
+    
+    output_pending_.insert(output_pending_.end(), samples, samples + num_samples);
+    size_t offset = 0;
+    for (; output_pending_.size() - offset >= frame_size; offset += frame_size) {
+        auto begin = output_pending_.begin() + offset;
+        OutputFrame* frame = new OutputFrame{std::vector<float>(begin, begin + frame_size),
+                                             output_sequence_++, output_frames_dropped_};
+        napi_status status = output_tsfn_.NonBlockingCall(frame, [](Napi::Env env, Napi::Function jsCallback, OutputFrame* frame) {
+            Napi::Float32Array buffer = Napi::Float32Array::New(env, frame->samples.size());
+            std::copy(frame->samples.begin(), frame->samples.end(), buffer.Data());
+            Napi::Object stats = Napi::Object::New(env);
+            stats.Set("sequence", static_cast<double>(frame->sequence));
+            stats.Set("droppedFrames", static_cast<double>(frame->dropped));
+            try {
+                jsCallback.Call({buffer, stats});
+            } catch (...) {
+                // A throwing sink must not take the addon down
+            }
+            delete frame;
+        });
+        
+        if (status != napi_ok) {
+            delete frame;  // Queue full or closing: drop
+            output_frames_dropped_++;
+        }
+    }
+    output_pending_.erase(output_pending_.begin(), output_pending_.begin() + offset);
+}
+
+void AudioCaptureAddon::ReleaseOutputCallback() {
+    if (output_tsfn_) {
+        output_tsfn_.Release();
+        output_tsfn_ = Napi::ThreadSafeFunction();
+    }
+    output_pending_.clear();
+    output_sequence_ = 0;
+    output_frames_dropped_ = 0;
+}
+
+// setOverloadDetection(rtfThreshold = 0.8, consecutiveChunks = 50, autoBypass = false)
+// consecutiveChunks 0 disables detection.
+Napi::Value AudioCaptureAddon::SetOverloadDetection(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    float threshold = info.Length() > 0 && info[0].IsNumber() ? info[0].As<Napi::Number>().FloatValue() : 0.8f;
+    int chunks = info.Length() > 1 && info[1].IsNumber() ? info[1].As<Napi::Number>().Int32Value() : 50;
+    bool auto_bypass = info.Length() > 2 && info[2].IsBoolean() && info[2].As<Napi::Boolean>().Value();
+    if (!(threshold > 0.0f) || chunks < 0) {
+        ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "rtfThreshold must be positive and consecutiveChunks non-negative");
+        return env.Undefined();
+    }
+    
+    aec_processor_->SetOverloadDetection(threshold, chunks, auto_bypass);
+    return env.Undefined();
+}
+
+Napi::Value AudioCaptureAddon::SetAutoReconvergeOnPathChange(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsBoolean()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected boolean");
+        return env.Undefined();
+    }
+    
+    aec_processor_->SetAutoReconvergeOnPathChange(info[0].As<Napi::Boolean>().Value());
+    return env.Undefined();
+}
+
+// setRealtimeWatchdog(consecutiveChunks, callback | null)
+// callback({ consecutiveChunks, avgProcessingMs, budgetMs }) runs on the JS
+// thread right after the processing call that completed the streak.
+Napi::Value AudioCaptureAddon::SetRealtimeWatchdog(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected consecutive chunk count");
+        return env.Undefined();
+    }
+    
+    int consecutive_chunks = info[0].As<Napi::Number>().Int32Value();
+    if (info.Length() > 1 && info[1].IsFunction() && consecutive_chunks > 0) {
+        watchdog_callback_ = Napi::Persistent(info[1].As<Napi::Function>());
+        aec_processor_->SetRealtimeWatchdog(consecutive_chunks);
+    } else {
+        watchdog_callback_.Reset();
+        aec_processor_->SetRealtimeWatchdog(0);
+    }
+    
+    return env.Undefined();
+}
+
+void AudioCaptureAddon::NotifyWatchdog(Napi::Env env) {
+    if (env.IsExceptionPending()) {
+        return;  // Leave the event queued behind the exception already on its way out
+    }
+    
+    AECWatchdogEvent event;
+    if (!aec_processor_ || !aec_processor_->ConsumeWatchdogEvent(&event) || watchdog_callback_.IsEmpty()) {
+        return;
+    }
+    
+    Napi::Object payload = Napi::Object::New(env);
+    payload.Set("consecutiveChunks", event.consecutive_chunks);
+    payload.Set("avgProcessingMs", event.avg_processing_ms);
+    payload.Set("budgetMs", event.budget_ms);
+    callback_depth_++;
+    watchdog_callback_.Call({payload});
+    callback_depth_--;
+}
+
+// setEventCallback(callback | null)
+// callback({ type, erleDb, correlation?, lagMs? }) runs on the JS thread right
+// after the processing call that produced the state transition.
+Napi::Value AudioCaptureAddon::SetEventCallback(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() > 0 && info[0].IsFunction()) {
+        event_callback_ = Napi::Persistent(info[0].As<Napi::Function>());
+    } else {
+        event_callback_.Reset();
+    }
+    
+    return env.Undefined();
+}
+
+void AudioCaptureAddon::NotifyEvents(Napi::Env env) {
+    if (!aec_processor_ || env.IsExceptionPending()) {
+        return;  // A throwing watchdog callback leaves the events for the next call
+    }
+    
+    std::vector<AECEvent> events = aec_processor_->TakeEvents();
+    if (event_callback_.IsEmpty()) {
+        return;
+    }
+    
+    callback_depth_++;
+    for (const AECEvent& event : events) {
+        Napi::Object payload = Napi::Object::New(env);
+        payload.Set("type", event.type);
+        payload.Set("erleDb", event.erle_db);
+        if (event.type == "echoDetected") {
+            payload.Set("correlation", event.correlation);
+            payload.Set("lagMs", event.lag_ms);
+        }
+        event_callback_.Call({payload});
+        if (env.IsExceptionPending()) {
+            break;  // Let the callback's exception reach the processing call
+        }
+    }
+    callback_depth_--;
+}
+
+Napi::Value AudioCaptureAddon::GetConfig(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    return ConfigToObject(env, *aec_processor_);
+}
+
+Napi::Value AudioCaptureAddon::GetDevices(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    Napi::Array devices = Napi::Array::New(env);
+    
+    AudioObjectPropertyAddress propertyAddress = {
+        kAudioHardwarePropertyDevices,
+        kAudioObjectPropertyScopeGlobal,
         kAudioObjectPropertyElementMain
     };
     
@@ -597,6 +3536,205 @@ Napi::Value AudioCaptureAddon::GetDevices(const Napi::CallbackInfo& info) {
     return devices;
 }
 
+// clone() -> AudioCaptureAddon
+// A new instance continuing from this one's state: config, pending reference
+// and capture audio, calibrated delay and the NLMS filter, so both give the
+// same output for the same input. Metrics start at zero, and callbacks, the
+// logger and debug capture aren't carried over. With the webrtc engine the
+// AEC3 filter can't be copied and the clone re-converges.
+Napi::Value AudioCaptureAddon::Clone(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    std::unique_ptr<AECProcessor> processor;
+    try {
+        processor = aec_processor_->Clone();
+    } catch (const std::exception& e) {
+        std::cerr << "❌ Clone error: " << e.what() << std::endl;
+    }
+    if (!processor) {
+        ThrowCodedError<Napi::Error>(env, kErrConfigFailed, "Failed to clone AEC processor");
+        return env.Null();
+    }
+    
+    // Adopted by the constructor's External path, skipping the default engine build
+    return env.GetInstanceData<Napi::FunctionReference>()->New({
+        Napi::External<std::unique_ptr<AECProcessor>>::New(env, &processor)});
+}
+
+// getState() -> Buffer
+// The state clone() copies, versioned, for setState on a processor created
+// with the same options after a reload. Only for the addon build that wrote it.
+Napi::Value AudioCaptureAddon::GetState(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Null();
+    }
+    
+    if (!aec_processor_) {
+        return env.Null();
+    }
+    
+    std::vector<uint8_t> state;
+    try {
+        state = aec_processor_->SaveState();
+    } catch (const std::exception& e) {
+        std::cerr << "❌ GetState error: " << e.what() << std::endl;
+    }
+    if (state.empty()) {
+        ThrowCodedError<Napi::Error>(env, kErrProcessingFailed, "Failed to save AEC state");
+        return env.Null();
+    }
+    
+    return Napi::Buffer<uint8_t>::Copy(env, state.data(), state.size());
+}
+
+// setState(buffer)
+// Continue from a getState snapshot. A snapshot from another format version
+// or config (including runtime setter changes) throws ERR_AEC_STATE_MISMATCH;
+// a corrupt one throws ERR_AEC_INVALID_VALUE. Metrics are kept.
+Napi::Value AudioCaptureAddon::SetState(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (ThrowIfDestroyed(env)) {
+        return env.Undefined();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Undefined();
+    }
+    
+    if (!aec_processor_) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 1 || !info[0].IsBuffer()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected state Buffer");
+        return env.Undefined();
+    }
+    
+    Napi::Buffer<uint8_t> buffer = info[0].As<Napi::Buffer<uint8_t>>();
+    AECStateResult result = AECStateResult::kInitFailed;
+    try {
+        result = aec_processor_->RestoreState(buffer.Data(), buffer.ElementLength());
+    } catch (const std::exception& e) {
+        std::cerr << "❌ SetState error: " << e.what() << std::endl;
+    }
+    
+    switch (result) {
+        case AECStateResult::kOk:
+            break;
+        case AECStateResult::kMalformed:
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "AEC state buffer is corrupt or truncated");
+            break;
+        case AECStateResult::kVersionMismatch:
+            ThrowCodedError<Napi::Error>(env, kErrStateMismatch,
+                "AEC state was saved by another format version (expected " +
+                std::to_string(AECProcessor::kStateVersion) + ")");
+            break;
+        case AECStateResult::kConfigMismatch:
+            ThrowCodedError<Napi::Error>(env, kErrStateMismatch,
+                "AEC state was saved under a different config or sample rate");
+            break;
+        case AECStateResult::kInitFailed:
+            ThrowCodedError<Napi::Error>(env, kErrConfigFailed, "Failed to rebuild AEC from saved state");
+            break;
+    }
+    
+    return env.Undefined();
+}
+
+// Release the AEC engine and buffers now instead of waiting for GC finalization.
+// The instance is unusable afterwards: processing calls throw.
+Napi::Value AudioCaptureAddon::Destroy(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (is_destroyed_) {
+        return env.Undefined();
+    }
+    
+    if (ThrowIfReentrant(env)) {
+        return env.Undefined();
+    }
+    
+    if (is_capturing_) {
+        StopMicrophoneCapture(info);
+    }
+    
+    UnregisterInstance();
+    if (aec_processor_) {
+        // A running processAsync job keeps its own reference, so the sink
+        // must not outlive the logger TSFN released below. Waits for that job.
+        aec_processor_->SetLogSink(nullptr, AECLogLevel::kWarn);
+    }
+    aec_processor_.reset();
+    for (ProcessAsyncWorker* worker : async_queue_) {
+        worker->Abandon("AudioCaptureAddon has been destroyed");
+        delete worker;
+    }
+    async_queue_.clear();
+    std::vector<float>().swap(render_scratch_);
+    ReleaseLogger();
+    ReleaseOutputCallback();
+    watchdog_callback_.Reset();
+    event_callback_.Reset();
+    is_destroyed_ = true;
+    std::cout << "✅ AudioCaptureAddon destroyed" << std::endl;
+    
+    return env.Undefined();
+}
+
+void AudioCaptureAddon::RegisterInstance() {
+    if (!aec_processor_) {
+        return;  // Failed construction isn't a live processor
+    }
+    std::lock_guard<std::mutex> lock(g_instances_mutex);
+    if (instance_id_ == 0) {
+        instance_id_ = g_next_instance_id++;
+    }
+    g_instances[instance_id_] = InstanceEntry{created_at_ms_, aec_processor_};
+}
+
+void AudioCaptureAddon::UnregisterInstance() {
+    if (instance_id_ == 0) {
+        return;
+    }
+    std::lock_guard<std::mutex> lock(g_instances_mutex);
+    g_instances.erase(instance_id_);
+    instance_id_ = 0;
+}
+
+bool AudioCaptureAddon::ThrowIfDestroyed(Napi::Env env) {
+    if (!is_destroyed_) {
+        return false;
+    }
+    ThrowCodedError<Napi::Error>(env, kErrHandleDestroyed, "AudioCaptureAddon has been destroyed");
+    return true;
+}
+
+// Event and watchdog callbacks run synchronously at the end of a processing
+// call. Processing, rebuilding or reconfiguring the engine (reconfigure,
+// setSampleRate, setFilterLength, applyPreset, setAdaptationRate,
+// setNoiseSuppression, setEchoCancellationEnabled, resetAEC, setState) or
+// destroying from inside one would recurse or free state the dispatch loop is
+// still using, so those calls throw instead.
+bool AudioCaptureAddon::ThrowIfReentrant(Napi::Env env) {
+    if (callback_depth_ == 0) {
+        return false;
+    }
+    ThrowCodedError<Napi::Error>(env, kErrReentrantCall,
+        "Cannot process, reconfigure or destroy from inside an event or watchdog callback");
+    return true;
+}
+
 Napi::Value AudioCaptureAddon::Start(const Napi::CallbackInfo& info) {
     return Napi::Boolean::New(info.Env(), true);
 }
@@ -605,7 +3743,467 @@ Napi::Value AudioCaptureAddon::Stop(const Napi::CallbackInfo& info) {
     return Napi::Boolean::New(info.Env(), true);
 }
 
+// selfTest(sampleRate?, engine?) -> { passed, erleDb }
+// Runs a synthetic echo through a fresh processor; no AudioCaptureAddon needed.
+Napi::Value SelfTest(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    int sample_rate = 48000;
+    if (info.Length() > 0 && info[0].IsNumber()) {
+        sample_rate = info[0].As<Napi::Number>().Int32Value();
+    }
+    std::string engine = "webrtc";
+    if (info.Length() > 1 && info[1].IsString()) {
+        engine = info[1].As<Napi::String>().Utf8Value();
+        if (!IsKnownEngine(engine)) {
+            ThrowCodedError<Napi::RangeError>(env, kErrInvalidValue, "engine must be one of: webrtc, nlms");
+            return env.Undefined();
+        }
+    }
+    
+    Napi::Object result = Napi::Object::New(env);
+    try {
+        AECSelfTestResult test = RunSelfTest(sample_rate, engine);
+        result.Set("passed", test.passed);
+        result.Set("erleDb", test.erle_db);
+        std::cout << (test.passed ? "✅" : "❌") << " AEC self-test (" << engine << "): "
+                  << test.erle_db << " dB ERLE" << std::endl;
+    } catch (const std::exception& e) {
+        std::cerr << "❌ SelfTest error: " << e.what() << std::endl;
+        result.Set("passed", false);
+        result.Set("erleDb", 0.0);
+    }
+    
+    return result;
+}
+
+// downmixStereoToMono(Int16Array interleaved) -> Int16Array
+// Averages L/R in 32-bit, rounding half away from zero; no AudioCaptureAddon needed.
+Napi::Value DownmixStereoToMono(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (info.Length() < 1 || !info[0].IsTypedArray() ||
+        info[0].As<Napi::TypedArray>().TypedArrayType() != napi_int16_array) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected Int16Array");
+        return env.Null();
+    }
+    
+    Napi::Int16Array input = info[0].As<Napi::Int16Array>();
+    if (input.ElementLength() % 2 != 0) {
+        ThrowCodedError<Napi::RangeError>(env, kErrBufferLength, "Interleaved stereo needs an even sample count");
+        return env.Null();
+    }
+    
+    size_t frames = input.ElementLength() / 2;
+    Napi::Int16Array output = Napi::Int16Array::New(env, frames);
+    const int16_t* in = input.Data();
+    int16_t* out = output.Data();
+    for (size_t i = 0; i < frames; i++) {
+        int32_t sum = static_cast<int32_t>(in[2 * i]) + in[2 * i + 1];
+        int32_t mean = (sum + (sum >= 0 ? 1 : -1)) / 2;
+        out[i] = SaturateInt16(mean);
+    }
+    
+    return output;
+}
+
+// mixReferences([Int16Array, ...]) -> Int16Array
+// Sums playback streams into one reference with saturation (MixInt16Saturating).
+// The result is as long as the longest source; shorter ones count as silence.
+Napi::Value MixReferences(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (info.Length() < 1 || !info[0].IsArray()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected an array of Int16Array");
+        return env.Null();
+    }
+    
+    Napi::Array list = info[0].As<Napi::Array>();
+    std::vector<Napi::Int16Array> sources;
+    size_t length = 0;
+    for (uint32_t i = 0; i < list.Length(); i++) {
+        Napi::Value item = list.Get(i);
+        if (!item.IsTypedArray() || item.As<Napi::TypedArray>().TypedArrayType() != napi_int16_array) {
+            ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Every source must be an Int16Array");
+            return env.Null();
+        }
+        sources.push_back(item.As<Napi::Int16Array>());
+        length = std::max(length, sources.back().ElementLength());
+    }
+    
+    // Zero-pad short sources so the mix loop sees equal lengths
+    std::vector<std::vector<int16_t>> padded;
+    std::vector<const int16_t*> pointers;
+    for (Napi::Int16Array& source : sources) {
+        if (source.ElementLength() == length) {
+            pointers.push_back(source.Data());
+        } else {
+            padded.emplace_back(length, 0);
+            std::copy(source.Data(), source.Data() + source.ElementLength(), padded.back().begin());
+        }
+    }
+    for (const std::vector<int16_t>& source : padded) {
+        pointers.push_back(source.data());
+    }
+    
+    Napi::Int16Array output = Napi::Int16Array::New(env, length);
+    MixInt16Saturating(pointers.data(), pointers.size(), length, output.Data());
+    
+    return output;
+}
+
+#ifndef AUDIO_CAPTURE_NATIVE_VERSION
+#define AUDIO_CAPTURE_NATIVE_VERSION "unknown"
+#endif
+#ifndef WEBRTC_BUILD_TAG
+#define WEBRTC_BUILD_TAG "unknown"
+#endif
+
+// getVersion() -> { version, webrtc, engines, features, platform, arch, profile, napiVersion, compiler, buildDate }
+// Everything is fixed at compile time so support can identify the exact build.
+Napi::Value GetVersion(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    Napi::Array engines = Napi::Array::New(env);
+    engines.Set(uint32_t(0), "webrtc");
+    engines.Set(uint32_t(1), "nlms");
+    
+    Napi::Array features = Napi::Array::New(env);
+    uint32_t feature_count = 0;
+#ifdef NAPI_DISABLE_CPP_EXCEPTIONS
+    features.Set(feature_count++, "napi-no-cpp-exceptions");
+#endif
+#ifdef WEBRTC_POSIX
+    features.Set(feature_count++, "webrtc-posix");
+#endif
+#ifdef WEBRTC_MAC
+    features.Set(feature_count++, "webrtc-mac");
+#endif
+    
+#if defined(__APPLE__)
+    const char* platform = "darwin";
+#elif defined(_WIN32)
+    const char* platform = "win32";
+#elif defined(__linux__)
+    const char* platform = "linux";
+#else
+    const char* platform = "unknown";
+#endif
+    
+#if defined(__aarch64__) || defined(_M_ARM64)
+    const char* arch = "arm64";
+#elif defined(__x86_64__) || defined(_M_X64)
+    const char* arch = "x64";
+#else
+    const char* arch = "unknown";
+#endif
+    
+#ifdef NDEBUG
+    const char* profile = "release";
+#else
+    const char* profile = "debug";
+#endif
+    
+#if defined(__clang__)
+    const char* compiler = "clang " __clang_version__;
+#elif defined(__GNUC__)
+    const char* compiler = "gcc " __VERSION__;
+#elif defined(_MSC_VER)
+    const char* compiler = "msvc";
+#else
+    const char* compiler = "unknown";
+#endif
+    
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("version", AUDIO_CAPTURE_NATIVE_VERSION);
+    result.Set("webrtc", WEBRTC_BUILD_TAG);
+    result.Set("engines", engines);
+    result.Set("features", features);
+    result.Set("platform", platform);
+    result.Set("arch", arch);
+    result.Set("profile", profile);
+    result.Set("napiVersion", NAPI_VERSION);
+    result.Set("compiler", compiler);
+    result.Set("buildDate", __DATE__ " " __TIME__);
+    
+    return result;
+}
+
+// getCapabilities() -> { version, webrtc, capabilities: { <name>: boolean } }
+// What this binary can do, for runtime feature detection. A name missing from
+// an older build reads as unsupported.
+Napi::Value GetCapabilities(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+#if defined(__APPLE__)
+    bool native_mic_capture = true;  // AudioUnit input
+#else
+    bool native_mic_capture = false;
+#endif
+    
+    Napi::Object capabilities = Napi::Object::New(env);
+    capabilities.Set("floatPath", true);        // Float32Array capture and render
+    capabilities.Set("int16Render", true);      // Int16Array via processRenderInterleaved
+    capabilities.Set("stereoRender", true);     // Interleaved render, one channel or mixed
+    capabilities.Set("stereoCapture", false);   // Capture is processed mono
+    capabilities.Set("referenceMixing", true);  // mixReferences, saturating
+    capabilities.Set("noiseSuppression", true);
+    capabilities.Set("agc", true);
+    capabilities.Set("limiter", true);
+    capabilities.Set("nlmsEngine", true);
+    capabilities.Set("nativeMicCapture", native_mic_capture);
+    capabilities.Set("processInto", true);
+    capabilities.Set("processSplit", true);
+    capabilities.Set("processAsync", true);
+    capabilities.Set("processMany", true);
+    capabilities.Set("reconfigure", true);
+    capabilities.Set("delayCalibration", true);
+    capabilities.Set("outputCallback", true);
+    capabilities.Set("threadPriority", native_mic_capture);  // QoS classes on macOS only
+    capabilities.Set("deterministic", true);
+    capabilities.Set("testClock", true);  // setTestClock
+    capabilities.Set("sharedProcessors", true);  // createShared and the id-taking functions
+    capabilities.Set("asrTap", true);
+    capabilities.Set("clone", true);  // clone() and cloneShared
+    capabilities.Set("state", true);  // getState / setState
+    capabilities.Set("listInstances", true);
+    capabilities.Set("warmup", true);
+    capabilities.Set("ensureCapacity", true);
+    capabilities.Set("stageGains", true);  // setInputGain / setOutputGain
+    capabilities.Set("lastRawInput", true);
+    capabilities.Set("callOptions", true);  // processCaptureAudio(input, { bypass, outputGainDb, collectStats })
+    capabilities.Set("processAndFeed", true);
+    capabilities.Set("preEmphasis", true);
+    capabilities.Set("debugDump", true);        // startDebugDump WAV
+    capabilities.Set("debugCapture", true);     // enableDebugCapture rings
+    capabilities.Set("eventCallbacks", true);
+    capabilities.Set("logger", true);
+    capabilities.Set("metricsJson", true);
+    capabilities.Set("healthCheck", true);
+    
+    Napi::Object result = Napi::Object::New(env);
+    result.Set("version", AUDIO_CAPTURE_NATIVE_VERSION);
+    result.Set("webrtc", WEBRTC_BUILD_TAG);
+    result.Set("capabilities", capabilities);
+    
+    return result;
+}
+
+// createShared(options?) -> id
+// A processor owned by the module rather than a JS object, so the id can be
+// posted to worker_threads. Takes the constructor's options. Release it with
+// destroyShared; ids are never reused.
+Napi::Value CreateShared(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    AECConfig config;
+    config.frame_duration_ms = 10;
+    int sample_rate = 48000;
+    if (info.Length() > 0 && info[0].IsObject()) {
+        if (!ReadConfigOptions(env, info[0].As<Napi::Object>(), &config, &sample_rate)) {
+            return env.Undefined();
+        }
+    }
+    
+    std::string init_error;
+    std::shared_ptr<AECProcessor> processor = CreateProcessor(config, sample_rate, &init_error);
+    if (!processor) {
+        ThrowCodedError<Napi::Error>(env, kErrConfigFailed,
+            "Failed to initialize shared AEC processor" + (init_error.empty() ? std::string() : ": " + init_error));
+        return env.Undefined();
+    }
+    
+    std::lock_guard<std::mutex> lock(g_shared_mutex);
+    uint32_t id = g_next_shared_id++;
+    g_shared_processors[id] = processor;
+    return Napi::Number::New(env, id);
+}
+
+// processShared(id, input) -> Float32Array, like processCaptureAudio
+Napi::Value ProcessShared(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    std::shared_ptr<AECProcessor> processor = FindSharedProcessor(info);
+    if (!processor) {
+        return env.Null();
+    }
+    
+    if (info.Length() < 2 || !IsFloat32Array(info[1])) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected Float32Array");
+        return env.Null();
+    }
+    
+    Napi::Float32Array input = info[1].As<Napi::Float32Array>();
+    if (ThrowIfInvalidChunk(env, *processor, input.ElementLength(), false)) {
+        return env.Null();
+    }
+    Napi::Float32Array output = Napi::Float32Array::New(env, input.ElementLength());
+    
+    try {
+        processor->ProcessCaptureAudio(input.Data(), output.Data(), input.ElementLength());
+    } catch (const std::exception& e) {
+        ThrowProcessingError(env, "processShared", e);
+        return env.Null();
+    }
+    
+    return output;
+}
+
+// feedReferenceShared(id, input), like processRenderAudio
+Napi::Value FeedReferenceShared(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    std::shared_ptr<AECProcessor> processor = FindSharedProcessor(info);
+    if (!processor) {
+        return env.Undefined();
+    }
+    
+    if (info.Length() < 2 || !IsFloat32Array(info[1])) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected Float32Array");
+        return env.Undefined();
+    }
+    
+    Napi::Float32Array input = info[1].As<Napi::Float32Array>();
+    if (ThrowIfInvalidChunk(env, *processor, input.ElementLength(), true)) {
+        return env.Undefined();
+    }
+    
+    try {
+        processor->ProcessRenderAudio(input.Data(), input.ElementLength());
+    } catch (const std::exception& e) {
+        ThrowProcessingError(env, "feedReferenceShared", e);
+    }
+    
+    return env.Undefined();
+}
+
+// cloneShared(id) -> id
+// A new shared processor copied from an existing one, as clone() does
+Napi::Value CloneShared(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    std::shared_ptr<AECProcessor> source = FindSharedProcessor(info);
+    if (!source) {
+        return env.Undefined();
+    }
+    
+    std::shared_ptr<AECProcessor> processor;
+    try {
+        processor = source->Clone();
+    } catch (const std::exception& e) {
+        std::cerr << "❌ CloneShared error: " << e.what() << std::endl;
+    }
+    if (!processor) {
+        ThrowCodedError<Napi::Error>(env, kErrConfigFailed, "Failed to clone shared AEC processor");
+        return env.Undefined();
+    }
+    
+    std::lock_guard<std::mutex> lock(g_shared_mutex);
+    uint32_t id = g_next_shared_id++;
+    g_shared_processors[id] = processor;
+    return Napi::Number::New(env, id);
+}
+
+// getMetricsShared(id) -> metrics, like getMetrics
+Napi::Value GetMetricsShared(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    std::shared_ptr<AECProcessor> processor = FindSharedProcessor(info);
+    if (!processor) {
+        return env.Null();
+    }
+    
+    try {
+        return MetricsToObject(env, processor->GetMetrics());
+    } catch (const std::exception& e) {
+        std::cerr << "❌ GetMetricsShared error: " << e.what() << std::endl;
+        return env.Null();
+    }
+}
+
+// listInstances() -> [{ label, createdAt, config, metricsSummary }]
+// Every live AudioCaptureAddon in the process, oldest first. createdAt is
+// Unix epoch ms; config is the getConfig() shape.
+Napi::Value ListInstances(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    std::vector<InstanceEntry> entries;
+    {
+        std::lock_guard<std::mutex> lock(g_instances_mutex);
+        for (const auto& item : g_instances) {
+            entries.push_back(item.second);
+        }
+    }
+    
+    Napi::Array result = Napi::Array::New(env, entries.size());
+    try {
+        for (size_t i = 0; i < entries.size(); i++) {
+            const AECProcessor& processor = *entries[i].processor;
+            AECMetrics metrics = processor.GetMetrics();
+            Napi::Object summary = Napi::Object::New(env);
+            summary.Set("engine", metrics.engine);
+            summary.Set("convergenceState", metrics.convergence_state);
+            summary.Set("echoReturnLossEnhancement", metrics.echo_return_loss_enhancement);
+            summary.Set("inputRmsDbfs", metrics.input_rms_dbfs);
+            summary.Set("outputRmsDbfs", metrics.output_rms_dbfs);
+            
+            Napi::Object instance = Napi::Object::New(env);
+            instance.Set("label", processor.GetConfig().label);
+            instance.Set("createdAt", entries[i].created_at_ms);
+            instance.Set("config", ConfigToObject(env, processor));
+            instance.Set("metricsSummary", summary);
+            result.Set(static_cast<uint32_t>(i), instance);
+        }
+    } catch (const std::exception& e) {
+        std::cerr << "❌ ListInstances error: " << e.what() << std::endl;
+        return env.Null();
+    }
+    
+    return result;
+}
+
+// destroyShared(id)
+// Invalidates the id everywhere. Destroying it again throws ERR_AEC_HANDLE_DESTROYED.
+Napi::Value DestroyShared(const Napi::CallbackInfo& info) {
+    Napi::Env env = info.Env();
+    
+    if (info.Length() < 1 || !info[0].IsNumber()) {
+        ThrowCodedError<Napi::TypeError>(env, kErrInvalidArgument, "Expected shared processor id");
+        return env.Undefined();
+    }
+    
+    uint32_t id = info[0].As<Napi::Number>().Uint32Value();
+    std::shared_ptr<AECProcessor> processor;
+    {
+        std::lock_guard<std::mutex> lock(g_shared_mutex);
+        auto it = g_shared_processors.find(id);
+        if (it != g_shared_processors.end()) {
+            processor = std::move(it->second);
+            g_shared_processors.erase(it);
+        }
+    }
+    if (!processor) {
+        ThrowCodedError<Napi::Error>(env, kErrHandleDestroyed,
+            "Shared processor " + std::to_string(id) + " does not exist or has been destroyed");
+        return env.Undefined();
+    }
+    
+    return env.Undefined();
+}
+
 Napi::Object InitAll(Napi::Env env, Napi::Object exports) {
+    exports.Set("getVersion", Napi::Function::New(env, GetVersion));
+    exports.Set("getCapabilities", Napi::Function::New(env, GetCapabilities));
+    exports.Set("selfTest", Napi::Function::New(env, SelfTest));
+    exports.Set("downmixStereoToMono", Napi::Function::New(env, DownmixStereoToMono));
+    exports.Set("mixReferences", Napi::Function::New(env, MixReferences));
+    exports.Set("createShared", Napi::Function::New(env, CreateShared));
+    exports.Set("processShared", Napi::Function::New(env, ProcessShared));
+    exports.Set("feedReferenceShared", Napi::Function::New(env, FeedReferenceShared));
+    exports.Set("cloneShared", Napi::Function::New(env, CloneShared));
+    exports.Set("getMetricsShared", Napi::Function::New(env, GetMetricsShared));
+    exports.Set("destroyShared", Napi::Function::New(env, DestroyShared));
+    exports.Set("listInstances", Napi::Function::New(env, ListInstances));
     return AudioCaptureAddon::Init(env, exports);
 }
 
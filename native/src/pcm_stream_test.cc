@@ -0,0 +1,122 @@
+// Standalone checks for ProcessPcmStreams / ProcessInterleavedPcmStream, built
+// as the pcm_stream_test target (no Node). The processor runs in passthrough,
+// so every sample the streams carry must come back bit-exact.
+#include "aec_processor.h"
+
+#include <cstdint>
+#include <iostream>
+#include <memory>
+#include <sstream>
+#include <string>
+#include <vector>
+
+using namespace kakarot;
+
+namespace {
+
+constexpr int kSampleRate = 48000;
+
+int failures = 0;
+
+void Check(bool condition, const std::string& what) {
+    if (!condition) {
+        failures++;
+        std::cerr << "❌ " << what << std::endl;
+    }
+}
+
+// Little-endian 16-bit PCM
+std::string ToBytes(const std::vector<int16_t>& samples) {
+    std::string bytes;
+    for (int16_t sample : samples) {
+        uint16_t raw = static_cast<uint16_t>(sample);
+        bytes.push_back(static_cast<char>(raw & 0xff));
+        bytes.push_back(static_cast<char>(raw >> 8));
+    }
+    return bytes;
+}
+
+// Full scale at both ends, the smallest steps and a ramp between
+std::vector<int16_t> TestSignal(size_t count, int seed) {
+    std::vector<int16_t> samples(count);
+    const int16_t fixed[] = {-32768, 32767, 0, 1, -1, -32767};
+    for (size_t i = 0; i < count; i++) {
+        samples[i] = i < 6 ? fixed[i] : static_cast<int16_t>((static_cast<int>(i) * 977 + seed) % 65536 - 32768);
+    }
+    return samples;
+}
+
+std::unique_ptr<AECProcessor> CreatePassthrough() {
+    auto processor = std::make_unique<AECProcessor>(AECConfig());
+    processor->InitializePassthrough(kSampleRate, "pcm stream test");
+    return processor;
+}
+
+// Two and a half frames of mic with a stray odd byte, and a reference that
+// ends after one frame: three frames run, the last zero-padded and trimmed
+void TestSeparateStreams() {
+    auto processor = CreatePassthrough();
+    const size_t frame = processor->GetFrameSize();
+    std::vector<int16_t> mic = TestSignal(frame * 5 / 2, 11);
+    std::istringstream mic_in(ToBytes(mic) + '\x7f');
+    std::istringstream reference_in(ToBytes(TestSignal(frame, 23)));
+    std::ostringstream out;
+
+    AECStreamStats stats = ProcessPcmStreams(*processor, mic_in, reference_in, out);
+    Check(stats.ok, "separate: ok");
+    Check(stats.frames == 3, "separate: three frames, the last short");
+    Check(stats.mic_samples == mic.size(), "separate: every whole mic sample counted");
+    Check(stats.reference_samples == frame, "separate: reference counted until it ran out");
+    Check(out.str() == ToBytes(mic), "separate: output is the mic, bit-exact, without the odd byte");
+}
+
+// One and a half frames of mic/reference pairs, then a lone mic sample and a
+// stray byte: the incomplete pair is dropped with the byte
+void TestInterleavedStream() {
+    auto processor = CreatePassthrough();
+    const size_t frame = processor->GetFrameSize();
+    std::vector<int16_t> mic = TestSignal(frame * 3 / 2, 37);
+    std::vector<int16_t> reference = TestSignal(mic.size(), 41);
+    std::vector<int16_t> interleaved;
+    for (size_t i = 0; i < mic.size(); i++) {
+        interleaved.push_back(mic[i]);
+        interleaved.push_back(reference[i]);
+    }
+    interleaved.push_back(1234);
+    std::istringstream in(ToBytes(interleaved) + '\x01');
+    std::ostringstream out;
+
+    AECStreamStats stats = ProcessInterleavedPcmStream(*processor, in, out);
+    Check(stats.ok, "interleaved: ok");
+    Check(stats.frames == 2, "interleaved: two frames, the last short");
+    Check(stats.mic_samples == mic.size() && stats.reference_samples == mic.size(),
+          "interleaved: only whole pairs counted");
+    Check(out.str() == ToBytes(mic), "interleaved: output is channel 0, bit-exact");
+}
+
+// An empty stream writes nothing and still succeeds
+void TestEmptyStream() {
+    auto processor = CreatePassthrough();
+    std::istringstream mic_in("");
+    std::istringstream reference_in("");
+    std::ostringstream out;
+
+    AECStreamStats stats = ProcessPcmStreams(*processor, mic_in, reference_in, out);
+    Check(stats.ok, "empty: ok");
+    Check(stats.frames == 0 && stats.mic_samples == 0, "empty: nothing processed");
+    Check(out.str().empty(), "empty: nothing written");
+}
+
+} // namespace
+
+int main() {
+    TestSeparateStreams();
+    TestInterleavedStream();
+    TestEmptyStream();
+    if (failures > 0) {
+        std::cerr << failures << " PCM stream check(s) failed" << std::endl;
+        return 1;
+    }
+    std::cout << "✅ PCM stream checks passed" << std::endl;
+    return 0;
+}
@@ -113,6 +113,9 @@ fi
 # Cleanup
 rm -f webrtc.tar.gz webrtc.tar.xz
 
+# Record which release we built against; binding.gyp bakes it into getVersion()
+basename "$(dirname "$WEBRTC_URL")" > VERSION
+
 # Verify installation
 echo ""
 echo "🔍 Verifying installation..."
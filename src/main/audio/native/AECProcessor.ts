@@ -19,7 +19,61 @@ import { createLogger } from '@main/core/logger';
 const logger = createLogger('AECProcessor');
 
 /**
- * Configuration options for AEC initialization
+ * Named suppression tuning bundles.
+ * - gentle: shorter filter, light suppression, low noise suppression (most near-end transparency)
+ * - balanced: WebRTC AEC3 defaults
+ * - aggressive: long filter and ~2x stronger suppression (Kakarot default)
+ */
+export type AECPreset = 'gentle' | 'balanced' | 'aggressive';
+
+export type NoiseSuppressionLevel = 'low' | 'moderate' | 'high' | 'veryHigh';
+
+/**
+ * Echo canceller implementation:
+ * - webrtc: WebRTC AEC3 (default)
+ * - nlms: built-in normalized-LMS filter, for targets where AEC3 is unavailable
+ */
+export type AECEngine = 'webrtc' | 'nlms';
+
+/** Frame durations the native module accepts, in milliseconds */
+export type FrameDurationMs = 10 | 20 | 40;
+
+/**
+ * The `code` property on every error the native module throws:
+ * - ERR_AEC_HANDLE_DESTROYED: method called after destroy() (Error)
+ * - ERR_AEC_INVALID_ARGUMENT: missing argument or wrong type (TypeError)
+ * - ERR_AEC_INVALID_VALUE: value out of range or not an accepted name (RangeError)
+ * - ERR_AEC_UNKNOWN_OPTION: options object has a key the module doesn't read (TypeError)
+ * - ERR_AEC_BUFFER_LENGTH: buffer sizes don't fit together (RangeError)
+ * - ERR_AEC_INPUT_TOO_LARGE: capture chunk longer than maxInputSamples (RangeError)
+ * - ERR_AEC_UNSUPPORTED_SAMPLE_RATE: rate outside supportedSampleRates (RangeError)
+ * - ERR_AEC_CONFIG_FAILED: valid configuration that the canceller couldn't apply,
+ *   including an engine that failed to initialize without fallbackToPassthrough (Error)
+ * - ERR_AEC_PROCESSING_FAILED: a processing or reference call failed natively (Error)
+ * - ERR_AEC_DEVICE: CoreAudio microphone setup failed (Error)
+ * - ERR_AEC_REENTRANT_CALL: processing, reconfigure or destroy called from inside
+ *   an event or watchdog callback (Error)
+ * - ERR_AEC_STATE_MISMATCH: setState snapshot from another format version or
+ *   config (Error)
+ */
+export type AECErrorCode =
+  | 'ERR_AEC_HANDLE_DESTROYED'
+  | 'ERR_AEC_INVALID_ARGUMENT'
+  | 'ERR_AEC_INVALID_VALUE'
+  | 'ERR_AEC_UNKNOWN_OPTION'
+  | 'ERR_AEC_BUFFER_LENGTH'
+  | 'ERR_AEC_INPUT_TOO_LARGE'
+  | 'ERR_AEC_UNSUPPORTED_SAMPLE_RATE'
+  | 'ERR_AEC_CONFIG_FAILED'
+  | 'ERR_AEC_PROCESSING_FAILED'
+  | 'ERR_AEC_DEVICE'
+  | 'ERR_AEC_REENTRANT_CALL'
+  | 'ERR_AEC_STATE_MISMATCH';
+
+/**
+ * Configuration options for AEC initialization. The native module validates
+ * every field it reads; a wrong type, an out-of-range value or an unknown key
+ * fails with an error naming the field. Omitted fields take the defaults below.
  */
 export interface AECConfig {
   /** Enable acoustic echo cancellation (default: true) */
@@ -34,11 +88,199 @@ export interface AECConfig {
   /** Disable AEC when headphones are detected (default: true) */
   disableAecOnHeadphones?: boolean;
 
-  /** Frame duration in milliseconds: 10, 20, or 30 (default: 10) */
-  frameDurationMs?: 10 | 20 | 30;
+  /**
+   * Names this processor in native log lines, metrics JSON and listInstances,
+   * e.g. 'call' or 'monitor'. Not a processing setting (default: none)
+   */
+  label?: string;
+
+  /**
+   * If the native engine fails to initialize, keep a handle that passes the
+   * mic through uncancelled instead of losing audio. Metrics report
+   * engineActive: false and initError, and the logger gets one warning on
+   * first use (default: false)
+   */
+  fallbackToPassthrough?: boolean;
+
+  /**
+   * Frame duration in milliseconds (default: 10). AEC3 runs 10 ms blocks, so
+   * 20 and 40 ms frames are split; see CANONICAL_FRAME_SAMPLES for the sizes.
+   */
+  frameDurationMs?: FrameDurationMs;
 
-  /** Sample rate in Hz (default: 48000) */
+  /** Sample rate in Hz (default: 48000); must be one of supportedSampleRates */
   sampleRate?: number;
+
+  /**
+   * Rates the native module accepts at creation and in setSampleRate
+   * (default: DEFAULT_SUPPORTED_SAMPLE_RATES). Others are rejected with an error
+   * listing the allowed values.
+   */
+  supportedSampleRates?: number[];
+
+  /** Time constant for the input/output level meters in ms (default: 300) */
+  levelSmoothingMs?: number;
+
+  /** How long the peak-hold meters hold a peak before decaying, in ms (default: 1500) */
+  peakHoldMs?: number;
+
+  /** Peak-hold decay rate after the hold expires, in dB/s (default: 20) */
+  peakDecayDbPerSec?: number;
+
+  /** Echo canceller implementation (default: 'webrtc') */
+  engine?: AECEngine;
+
+  /** Suppression preset (default: 'aggressive') */
+  preset?: AECPreset;
+
+  /** Override the preset's adaptive filter length, in AEC3 blocks */
+  filterLengthBlocks?: number;
+
+  /** Override the preset's noise suppression level */
+  noiseSuppressionLevel?: NoiseSuppressionLevel;
+
+  /** Flag the mic as dead after this much pure digital zero input, in ms (default: 3000) */
+  deadInputWarningMs?: number;
+
+  /**
+   * Pass the mic through unprocessed while the native processor is overloaded
+   * (see setOverloadDetection), rather than glitching (default: false)
+   */
+  autoBypassOnOverload?: boolean;
+
+  /** ERLE (dB) the canceller must hold to emit 'converged' (default: 10) */
+  convergedErleDb?: number;
+
+  /** How long ERLE must stay on one side of convergedErleDb before an event, in ms (default: 2000) */
+  convergedHoldMs?: number;
+
+  /** Mic/render correlation peak that counts as audible echo for 'echoDetected' (default: 0.5) */
+  echoDetectCorrelation?: number;
+
+  /** Only check for 'echoDetected' while ERLE is below this, in dB (default: 6) */
+  echoDetectMaxErleDb?: number;
+
+  /** Echo-active audio after a reset during which convergenceState reads 'cold', in ms (default: 500) */
+  convergenceColdMs?: number;
+
+  /**
+   * Throw ERR_AEC_BUFFER_LENGTH for capture chunks that aren't a whole
+   * number of frames, and render chunks that aren't a multiple of
+   * renderGranularity, instead of buffering the remainder (default: false)
+   */
+  strictChunkSize?: boolean;
+
+  /** Render chunk multiple enforced by strictChunkSize, in samples; 0 uses the frame size (default: 0) */
+  renderGranularity?: number;
+
+  /**
+   * Longest capture chunk accepted, in samples; longer ones throw
+   * ERR_AEC_INPUT_TOO_LARGE instead of stalling the audio thread. 0 disables
+   * the check (default: 48000)
+   */
+  maxInputSamples?: number;
+
+  /**
+   * NLMS engine: how readily near-end speech during playback slows filter
+   * adaptation, from 0 (off) to 0.99. AEC3 protects its own filter (default: 0.5)
+   */
+  doubleTalkSensitivity?: number;
+
+  /**
+   * How fast the adaptive filter learns, 0.1 to 2 (default: 1). See
+   * setAdaptationRate for the tradeoff
+   */
+  adaptationRate?: number;
+
+  /**
+   * After a capture pause longer than this, the next call drops the stale
+   * queued reference before processing; 0 disables (default: 0)
+   */
+  autoResetAfterIdleMs?: number;
+
+  /** Also reset the canceller on an idle reset, as the echo path has likely changed (default: false) */
+  idleResetFilter?: boolean;
+
+  /**
+   * Smoothed ERLE values kept for getErleHistory, one per capture call, at
+   * most 60000 (default: 1000)
+   */
+  erleHistoryLength?: number;
+
+  /**
+   * TPDF-dither the final Int16 conversion in toInt16 (default: true). Raises
+   * the noise floor by roughly 1 LSB but removes quantization distortion on
+   * quiet passages.
+   */
+  dither?: boolean;
+}
+
+/** Config fields that stay unset unless they override the preset */
+type AECPresetOverride = 'filterLengthBlocks' | 'noiseSuppressionLevel';
+
+type ResolvedAECConfig = Required<Omit<AECConfig, AECPresetOverride>> & Pick<AECConfig, AECPresetOverride>;
+
+/**
+ * Effective configuration reported by the native module: stream format,
+ * runtime settings, and the tuning after resolving the preset and overrides
+ */
+export interface AECNativeConfig {
+  sampleRate: number;
+  channels: number;
+  frameDurationMs: number;
+  supportedSampleRates: number[];
+  frameSize: number;
+  label: string;
+  engine: AECEngine;
+  fallbackToPassthrough: boolean;
+  enableAec: boolean;
+  enableNs: boolean;
+  enableAgc: boolean;
+  referenceGain: number;
+  inputGainDb: number;
+  outputGainDb: number;
+  emptyInputMode: AECEmptyInputMode;
+  underrunStrategy: AECUnderrunStrategy;
+  overrunDrainFraction: number;
+  preEmphasis: number;
+  strictChunkSize: boolean;
+  renderGranularity: number;
+  maxInputSamples: number;
+  doubleTalkSensitivity: number;
+  adaptationRate: number;
+  autoResetAfterIdleMs: number;
+  idleResetFilter: boolean;
+  outputAgcEnabled: boolean;
+  agcTargetDb: number;
+  agcMaxGainDb: number;
+  limiterEnabled: boolean;
+  limiterThresholdDb: number;
+  loudnessNormalizationEnabled: boolean;
+  loudnessTargetDb: number;
+  loudnessMaxGainDb: number;
+  silentChunkSkipEnabled: boolean;
+  silentChunkFloorDb: number;
+  /** null when the silence bypass is off */
+  silenceBypassThresholdDb: number | null;
+  erleHistoryLength: number;
+  overloadRtfThreshold: number;
+  overloadConsecutiveChunks: number;
+  autoBypassOnOverload: boolean;
+  autoReconvergeOnPathChange: boolean;
+  convergedErleDb: number;
+  convergedHoldMs: number;
+  echoDetectCorrelation: number;
+  echoDetectMaxErleDb: number;
+  convergenceColdMs: number;
+  preset: AECPreset;
+  filterLengthBlocks: number;
+  initialFilterLengthBlocks: number;
+  enrTransparentLf: number;
+  enrSuppressLf: number;
+  enrTransparentHf: number;
+  enrSuppressHf: number;
+  maxGainDuringEcho: number;
+  noiseSuppressionLevel: NoiseSuppressionLevel;
 }
 
 /**
@@ -63,478 +305,2923 @@ export interface AECMetrics {
   /** Render delay estimate in milliseconds */
   renderDelayMs?: number;
 
+  /** Reference delay applied by calibrateDelay, in ms (0 until calibrated, and on webrtc) */
+  calibratedDelayMs?: number;
+
+  /**
+   * Wall-clock ms since reference audio last reached the canceller, -1 before
+   * any. Grows steadily once playback stops feeding processRenderAudio while
+   * capture continues; past a few hundred ms the canceller is working from
+   * silence, and callers may prefer to bypass it.
+   */
+  msSinceLastReference?: number;
+
   /** Number of render buffers queued */
   renderQueueSize?: number;
 
   /** Convergence status */
   converged?: boolean;
-}
 
-const DEFAULT_CONFIG: Required<AECConfig> = {
-  enableAec: true,
-  enableNs: true,
-  enableAgc: false,
-  disableAecOnHeadphones: true,
-  frameDurationMs: 10,
-  sampleRate: 48000,
-};
+  /**
+   * cold: barely any echo-active audio since reset; adapting: not yet
+   * converged; converged / diverged: changes together with the events of the
+   * same name (see setEventCallback)
+   */
+  convergenceState?: AECConvergenceState;
 
-/**
- * TypeScript wrapper for the native WebRTC AEC module.
- * Manages render/capture processing and native mic capture.
- */
-export class AECProcessor {
-  private nativeModule: any = null;
-  private nativeInstance: any = null;
-  private config: Required<AECConfig>;
-  private isInitialized = false;
-  private isDestroyed = false;
-  private renderBufferQueue: Float32Array[] = [];
-  private readonly MAX_RENDER_QUEUE = 10;
-  private micCapturing = false;
-  private micAudioCallback?: (samples: Float32Array, timestamp: number) => void;
+  /** Audio time since convergenceState became 'converged', in ms; -1 otherwise */
+  timeSinceConvergedMs?: number;
 
-  constructor(config: AECConfig = {}) {
-    this.config = { ...DEFAULT_CONFIG, ...config };
+  /** Smoothed microphone input RMS in dBFS (-100 = silence) */
+  inputRmsDbfs?: number;
 
-    try {
-      // Load the native module (exports a class AudioCaptureAddon)
-      let nativeModule;
-      
-      try {
-        // First try: use bindings module (works in dev/unbundled)
-        logger.debug('Attempting to load native addon via bindings module...');
-          logger.debug('DEBUG:', { __dirname, cwd: process.cwd() });
-        nativeModule = bindings('audio_capture_native');
-        logger.debug('Loaded native addon via bindings module');
-      } catch (bindingsError) {
-        // Fallback: try to require directly at runtime using multiple possible paths
-        // eslint-disable-next-line @typescript-eslint/no-require-imports
-        const fs = require('fs');
-        // eslint-disable-next-line @typescript-eslint/no-require-imports
-        const path = require('path');
-        
-        const errorMsg = bindingsError instanceof Error ? bindingsError.message : String(bindingsError);
-        logger.debug('bindings() failed, trying direct require paths', { error: errorMsg.substring(0, 100) });
-        
-        const possiblePaths: string[] = [];
-        
-        // Try same directory as the bundled code (after build copy)
-        possiblePaths.push(
-          path.join(__dirname, 'audio_capture_native.node')
-        );
-        
-        // Try absolute dev paths
-        possiblePaths.push(
-          path.resolve('/Users/moxo/Desktop/kakarot-master/native/build/Release/audio_capture_native.node'),
-          path.resolve('/Users/moxo/Desktop/kakarot-master/build/Release/audio_capture_native.node')
-        );
-        
-        // Try cwd-relative paths
-        possiblePaths.push(
-          path.join(process.cwd(), 'native/build/Release/audio_capture_native.node'),
-          path.join(process.cwd(), 'build/Release/audio_capture_native.node'),
-          path.join(process.cwd(), 'audio_capture_native.node')
-        );
-        
-        // Try relative to app directory (for production)
-        if (process.resourcesPath) {
-          possiblePaths.push(
-            path.join(process.resourcesPath, 'app/native/build/Release/audio_capture_native.node'),
-            path.join(process.resourcesPath, 'native/build/Release/audio_capture_native.node'),
-            path.join(process.resourcesPath, 'app/audio_capture_native.node'),
-            path.join(process.resourcesPath, 'audio_capture_native.node')
-          );
-        }
+  /** Smoothed microphone input peak in dBFS */
+  inputPeakDbfs?: number;
 
-        let foundPath: string | null = null;
-        const checkedPaths: string[] = [];
-        
-        for (const testPath of possiblePaths) {
-          checkedPaths.push(testPath);
-          try {
-            if (fs.existsSync(testPath)) {
-              logger.info('Found native addon', { path: testPath });
-              foundPath = testPath;
-              break;
-            }
-          } catch (e) {
-            logger.debug('Path check failed', { path: testPath, error: String(e).substring(0, 50) });
-          }
-        }
+  /** Smoothed echo-cancelled output RMS in dBFS */
+  outputRmsDbfs?: number;
 
-        if (!foundPath) {
-          const checkedStr = checkedPaths.slice(0, 2).join(' OR ');
-          throw new Error(
-            `Could not find native audio_capture_native module. Checked: ${checkedStr}`
-          );
-        }
+  /** Smoothed echo-cancelled output peak in dBFS */
+  outputPeakDbfs?: number;
 
-        logger.debug('Loading native addon using require()', { path: foundPath });
-        // eslint-disable-next-line @typescript-eslint/no-require-imports
-        nativeModule = require(foundPath);
-        logger.debug('Loaded native addon via direct require()');
-      }
+  /** Peak of the most recent input chunk in dBFS */
+  inputPeakInstantDbfs?: number;
 
-      if (!nativeModule || typeof nativeModule.AudioCaptureAddon !== 'function') {
-        logger.error('Invalid native module structure', { hasModule: !!nativeModule, hasAudioCaptureAddon: !!nativeModule?.AudioCaptureAddon });
-        throw new Error('Failed to load native audio_capture_native module');
-      }
+  /** Held input peak in dBFS (peak-hold meter) */
+  inputPeakHoldDbfs?: number;
 
-      this.nativeModule = nativeModule;
+  /** Peak of the most recent output chunk in dBFS */
+  outputPeakInstantDbfs?: number;
 
-      // Create native instance with config (init occurs in constructor)
-      logger.debug('Creating native AudioCaptureAddon instance...');
-      this.nativeInstance = new this.nativeModule.AudioCaptureAddon({
-        enableAec: this.config.enableAec,
-        enableNs: this.config.enableNs,
-        enableAgc: this.config.enableAgc,
-      });
+  /** Held output peak in dBFS (peak-hold meter) */
+  outputPeakHoldDbfs?: number;
 
-      this.isInitialized = true;
-      logger.info('AEC initialized successfully', {
-        enableAec: this.config.enableAec,
-        enableNs: this.config.enableNs,
-        enableAgc: this.config.enableAgc,
-        sampleRate: this.config.sampleRate,
-        frameDurationMs: this.config.frameDurationMs,
-      });
-    } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
-      logger.error('AEC initialization failed', { errorMessage: message, errorType: error instanceof Error ? 'Error' : 'Other' });
-      throw new Error(`AEC initialization failed: ${message}`);
-    }
-  }
+  /** Gain currently applied by loudness normalization, in dB */
+  loudnessGainDb?: number;
+
+  /** Gain currently applied by the output AGC, in dB */
+  agcGainDb?: number;
+
+  /** Largest gain reduction from the output limiter in the last chunk, in dB */
+  limiterGainReductionDb?: number;
+
+  /** Largest limiter gain reduction since the last metrics reset, in dB */
+  limiterMaxGainReductionDb?: number;
+
+  /** Chunks passed through without AEC because mic and render were both silent */
+  skippedSilentChunks?: number;
+
+  /** Chunks passed through uncancelled because the render audio was silent */
+  silenceBypassChunks?: number;
+
+  /** Consecutive ms of exactly-zero mic input (device/permission failure, not quiet audio) */
+  deadInputMs?: number;
+
+  /** deadInputMs has crossed the configured warning threshold */
+  deadInput?: boolean;
+
+  /** Estimated ambient noise floor of the mic input in dBFS */
+  noiseFloorDbfs?: number;
+
+  /** Echo canceller actually running ('naive' when AEC3 failed to build) */
+  engine?: AECEngine | 'naive';
+
+  /** Measured ERLE while the far end is playing, smoothed over ~1s, in dB */
+  erleInstantDb?: number;
+
+  /** Measured ERLE since the last resetMetrics(), in dB */
+  erleAverageDb?: number;
 
   /**
-   * Process render (system/speaker) audio through the AEC reference path.
-   * Must be called BEFORE corresponding processCaptureAudio() call.
+   * processCaptureAudio call counts by duration, buckets
+   * [<0.5ms, <1ms, <2ms, <4ms, <8ms, >=8ms]
    */
-  public processRenderAudio(renderBuffer: Float32Array): boolean {
-    if (this.isDestroyed) {
-      logger.warn('Cannot process render audio: AEC processor is destroyed');
-      return false;
-    }
+  latencyHistogram?: number[];
 
-    if (!this.isInitialized) {
-      logger.warn('Cannot process render audio: AEC not initialized');
-      return false;
+  /** processCaptureAudio duration percentiles in ms (~30% bucket resolution) */
+  latencyP50Ms?: number;
+  latencyP95Ms?: number;
+  latencyP99Ms?: number;
+
+  /** Slowest processCaptureAudio call since the last resetMetrics(), in ms */
+  latencyMaxMs?: number;
+
+  /** Chunk length in samples of that slowest call */
+  latencyMaxChunkSamples?: number;
+
+  /**
+   * Capture calls that arrived with less render audio delivered than they
+   * needed (reference starvation), and the total shortfall in samples
+   */
+  underrunCount?: number;
+  underrunSamples?: number;
+
+  /** underrunCount split by the underrun strategy in effect at the time */
+  underrunZeroFillCount?: number;
+  underrunHoldCount?: number;
+
+  /** Render deliveries more than 1 s ahead of capture, and the samples dropped */
+  overflowCount?: number;
+  overflowSamples?: number;
+
+  /**
+   * Render delivered but not yet consumed, over the 1 s the buffer holds.
+   * Near 1 the reference feed is outrunning capture; see refBufferAvailable.
+   */
+  refBufferFillRatio?: number;
+
+  /**
+   * Render samples fed minus capture samples consumed since reset, and its
+   * rate over the last minute of capture audio in samples/s. A steady non-zero
+   * rate points at mismatched sample rates (e.g. +10 for 48000 vs 47990 Hz).
+   */
+  referenceDriftSamples?: number;
+  referenceDriftPerSecond?: number;
+
+  /** Native processing time / audio time since reset: 0.02 means 2% of real time */
+  realTimeFactor?: number;
+
+  /** Same ratio over roughly the last 5 s of audio, so transient overloads show */
+  realTimeFactorRecent?: number;
+
+  /** Audio duration processed since reset (the realTimeFactor denominator), in ms */
+  audioProcessedMs?: number;
+
+  /**
+   * Lifetime sample counts, not cleared by resetMetrics: every non-empty
+   * capture call counts in full, skipped or bypassed or not; output includes
+   * empty-input filler; reference is what capture consumed
+   */
+  inputSamplesProcessed?: number;
+  outputSamplesProduced?: number;
+  referenceSamplesConsumed?: number;
+
+  /** Lifetime capture audio duration in seconds, correct across sample rate changes */
+  audioSecondsProcessed?: number;
+
+  /** realTimeFactorRecent has stayed above the overload threshold; clears with hysteresis */
+  overloaded?: boolean;
+
+  /** Mic passed through unprocessed because of autoBypassOnOverload */
+  overloadBypass?: boolean;
+
+  /** Times the processor entered overload since reset */
+  overloadEvents?: number;
+
+  /** Sudden sustained ERLE drops after convergence (echo path changes) since reset */
+  pathChangesDetected?: number;
+
+  /** Capture calls in which NLMS adaptation was slowed for double-talk */
+  adaptationThrottledChunks?: number;
+
+  /** Idle resets since reset (see autoResetAfterIdleMs) */
+  idleResets?: number;
+
+  /**
+   * The adaptive filter spans less than one frame, so most real echo paths
+   * can't be modelled. Kept as configured; the native log suggests a longer
+   * filterLengthBlocks.
+   */
+  suboptimalConfig?: boolean;
+
+  /** Zero-length processCaptureAudio calls since reset; a climbing count means the capture device stalled */
+  emptyInputCalls?: number;
+
+  /** notifyDroppedInput() calls since reset */
+  droppedInputEvents?: number;
+
+  /** Capture samples reported skipped via notifyDroppedInput() since reset */
+  droppedInputSamples?: number;
+
+  /**
+   * Capture calls since reset that had to grow a native scratch buffer; stays
+   * flat in steady state once ensureCapacity covers the chunk sizes in use
+   */
+  scratchGrowCount?: number;
+
+  /** False when fallbackToPassthrough kept a handle after the engine failed to initialize */
+  engineActive?: boolean;
+
+  /** Why the native engine last failed to initialize; empty otherwise */
+  initError?: string;
+}
+
+/**
+ * Echo-cancelled output alongside what processing removed from the mic.
+ * Sign convention: removed = input - output, so input = output + removed.
+ */
+export interface AECSplitOutput {
+  /** Echo-cancelled audio */
+  output: Float32Array;
+
+  /** Input minus output, sample-wise; near-end speech here means over-cancellation */
+  removed: Float32Array;
+}
+
+/**
+ * Full-rate echo-cancelled output plus the same audio at 16 kHz for ASR
+ */
+export interface AECAsrTapOutput {
+  /** Echo-cancelled audio at the configured sample rate */
+  output: Float32Array;
+
+  /** output low-passed and decimated to 16 kHz mono; may vary by a sample per chunk */
+  asr: Float32Array;
+}
+
+/**
+ * Delivery info passed with each output callback frame
+ */
+export interface AECOutputFrameStats {
+  /** Frames produced since the callback was set; a gap means frames were dropped */
+  sequence: number;
+
+  /** Frames dropped so far because the callback fell behind */
+  droppedFrames: number;
+}
+
+/**
+ * Outcome of reconfigure(). rebuildFields names the options that made the
+ * canceller rebuild (and re-converge); empty when everything applied live.
+ */
+export interface AECReconfigureResult {
+  rebuilt: boolean;
+  rebuildFields: string[];
+}
+
+/**
+ * One-off overrides for a single processCaptureAudio call. Nothing carries
+ * over to the next call, though an outputGainDb change ramps back over 10 ms.
+ */
+export interface AECCallOptions {
+  /** Skip the canceller, e.g. during a known system sound; the chunk's reference is dropped */
+  bypass?: boolean;
+
+  /** Output gain in dB (-60..40) for this call instead of the setOutputGain value */
+  outputGainDb?: number;
+
+  /** Return the output with this call's measurements, as AECCallStatsOutput */
+  collectStats?: boolean;
+
+  /** Reject unknown keys with ERR_AEC_UNKNOWN_OPTION instead of ignoring them */
+  strict?: boolean;
+}
+
+/**
+ * Echo-cancelled output plus measurements for that specific call
+ */
+export interface AECMeasuredOutput {
+  /** Echo-cancelled audio */
+  output: Float32Array;
+
+  /** Input vs output energy for this call in dB */
+  erleDb: number;
+
+  /** Linear RMS of the microphone input */
+  inputRms: number;
+
+  /** Linear RMS of the echo-cancelled output */
+  outputRms: number;
+
+  /** Render audio active while near-end speech survived cancellation */
+  doubleTalk: boolean;
+
+  /** NLMS adaptation was slowed during this call to protect near-end speech */
+  adaptationThrottled: boolean;
+
+  /** Native processing time for this call, in microseconds */
+  elapsedUs: number;
+
+  /**
+   * Output/input energy ratio per full AEC frame of this call (frameSize
+   * samples each, aligned to output); 1 means nothing removed. Length is
+   * floor(input length / frameSize).
+   */
+  frameGains: Float32Array;
+}
+
+/** processCaptureAudio's result with collectStats: the same measurements, without frameGains */
+export type AECCallStatsOutput = Omit<AECMeasuredOutput, 'frameGains'>;
+
+/**
+ * Result of the native synthetic echo cancellation self-test
+ */
+/**
+ * Mic/render cross-correlation diagnostic. A near-zero peak means the mic
+ * carries no echo of the render stream (inputs swapped, wrong device, or a
+ * delay beyond the 500 ms search); a strong peak at a sensible lag confirms
+ * the expected echo pair.
+ */
+export interface AECCrossCorrelation {
+  /** Peak absolute normalized correlation, 0-1 */
+  peak: number;
+
+  /** Mic lag behind the render audio at the peak, in ms */
+  lagMs: number;
+}
+
+/**
+ * Outcome of calibrateDelay(). applied is false, with the delay unchanged,
+ * when the mic or the reference was too quiet to measure, and always on the
+ * webrtc engine, whose AEC3 tracks the delay itself; the estimate is still
+ * filled in there.
+ */
+export interface AECDelayEstimate {
+  applied: boolean;
+
+  /** Mic lag behind the reference, in samples at the current rate */
+  delaySamples: number;
+
+  delayMs: number;
+
+  /** Normalized correlation at that lag, 0-1; low values mean a weak estimate */
+  correlation: number;
+}
+
+/** What processCaptureAudio returns for a zero-length buffer */
+export type AECEmptyInputMode = 'empty' | 'silence' | 'comfortNoise';
+
+/** What the canceller runs against when the reference runs short */
+export type AECUnderrunStrategy = 'zeros' | 'hold';
+
+/** Scheduling hint for the thread driving AEC processing */
+export type AECThreadPriority = 'normal' | 'high' | 'realtime';
+
+export type AECConvergenceState = 'cold' | 'adapting' | 'converged' | 'diverged';
+
+/**
+ * Canceller state transition. 'converged' / 'diverged' fire once per crossing
+ * of convergedErleDb (held for convergedHoldMs); 'echoDetected' fires once per
+ * episode of strong mic/render correlation while ERLE is poor.
+ */
+export interface AECStateEvent {
+  type: 'converged' | 'diverged' | 'echoDetected';
+  erleDb: number;
+
+  /** echoDetected only: mic/render correlation peak, 0..1 */
+  correlation?: number;
+
+  /** echoDetected only: echo delay at the peak, in ms */
+  lagMs?: number;
+}
+
+export type AECLogLevel = 'error' | 'warn' | 'info' | 'debug' | 'trace';
+
+/**
+ * Raised by the real-time watchdog when processing has been slower than the
+ * audio it processes for the configured number of consecutive chunks
+ */
+export interface AECWatchdogEvent {
+  consecutiveChunks: number;
+
+  /** Mean processing time over the overrunning streak, in ms */
+  avgProcessingMs: number;
+
+  /** Audio duration of the latest chunk, in ms */
+  budgetMs: number;
+}
+
+/**
+ * Result of healthCheck. Each check is true when it passed; the streak-based
+ * ones fail only after the condition has persisted for about 2 s of audio.
+ */
+export interface AECHealthReport {
+  healthy: boolean;
+
+  /** The engine was reset because of this report (autoRecover) */
+  recovered: boolean;
+
+  checks: {
+    /** Output not frozen at a constant value while the mic input moves */
+    outputNotStuck: boolean;
+
+    /** Output energy not far (12 dB) above input beyond the applied output gain */
+    outputNotAboveInput: boolean;
+
+    /** Render not stuck at a non-zero constant or full scale */
+    referenceNotPinned: boolean;
+
+    /** No NaN/Inf in capture input or output since the previous check */
+    finiteSamples: boolean;
+  };
+}
+
+export interface AECDebugDumpStats {
+  /** Per-channel samples written to the WAV */
+  framesWritten: number;
+
+  /** Capture chunks lost because the background writer fell behind */
+  droppedChunks: number;
+}
+
+/** Sample-aligned mono tracks from the in-memory debug capture */
+export interface AECDebugCapture {
+  mic: Float32Array;
+
+  /** Render audio paired with each mic sample; zeros where render underran */
+  reference: Float32Array;
+
+  output: Float32Array;
+  sampleRate: number;
+}
+
+export interface AECSelfTestResult {
+  /** Output energy dropped by at least the native threshold (10 dB) */
+  passed: boolean;
+
+  /** Measured input vs output energy over the converged tail, in dB */
+  erleDb: number;
+}
+
+/** Compile-time build identification from the native module */
+export interface AECBuildInfo {
+  /** native/package.json version the addon was built from */
+  version: string;
+
+  /** Prebuilt libwebrtc release tag, or 'unknown' if not recorded by setup-webrtc.sh */
+  webrtc: string;
+
+  engines: AECEngine[];
+  features: string[];
+  platform: string;
+  arch: string;
+
+  /** 'release' or 'debug' */
+  profile: string;
+
+  napiVersion: number;
+  compiler: string;
+  buildDate: string;
+}
+
+/** Feature flags compiled into the native module */
+export interface AECCapabilities {
+  /** native/package.json version the addon was built from */
+  version: string;
+
+  /** Prebuilt libwebrtc release tag, or 'unknown' */
+  webrtc: string;
+
+  /**
+   * e.g. floatPath, int16Render, stereoRender, stereoCapture, noiseSuppression,
+   * agc, nlmsEngine, nativeMicCapture, debugDump, debugCapture, eventCallbacks.
+   * Names an older build doesn't know are simply absent; treat them as false.
+   */
+  capabilities: Record<string, boolean>;
+}
+
+/** One live native processor, as reported by listInstances */
+export interface AECInstanceInfo {
+  /** AECConfig.label, '' if none was given */
+  label: string;
+
+  /** Creation time, Unix epoch milliseconds */
+  createdAt: number;
+
+  config: AECNativeConfig;
+
+  metricsSummary: {
+    engine: string;
+    convergenceState: AECConvergenceState;
+    echoReturnLossEnhancement: number;
+    inputRmsDbfs: number;
+    outputRmsDbfs: number;
+  };
+}
+
+/**
+ * Module-level functions for processors created with createSharedProcessor.
+ * A worker_threads Worker loads the addon itself and drives the processor by
+ * id; ids stay valid across threads until destroyShared, and calling with a
+ * destroyed id throws ERR_AEC_HANDLE_DESTROYED.
+ */
+export interface AECSharedProcessorModule {
+  createShared(options?: Record<string, unknown>): number;
+  /** Copy of an existing processor's state under a new id, like AECProcessor.clone */
+  cloneShared(id: number): number;
+  processShared(id: number, input: Float32Array): Float32Array | null;
+  feedReferenceShared(id: number, input: Float32Array): void;
+  getMetricsShared(id: number): Record<string, unknown> | null;
+  destroyShared(id: number): void;
+}
+
+/** Rates accepted by default; extend via AECConfig.supportedSampleRates */
+export const DEFAULT_SUPPORTED_SAMPLE_RATES = [8000, 16000, 32000, 44100, 48000];
+
+/**
+ * Samples per frame for the WebRTC / Opus frame sizes, by rate then duration:
+ * 480 at 48 kHz for the usual 10 ms frame.
+ */
+export const CANONICAL_FRAME_SAMPLES: Record<number, Record<FrameDurationMs, number>> = {
+  8000: { 10: 80, 20: 160, 40: 320 },
+  16000: { 10: 160, 20: 320, 40: 640 },
+  32000: { 10: 320, 20: 640, 40: 1280 },
+  48000: { 10: 480, 20: 960, 40: 1920 },
+};
+
+/**
+ * Chunk size in samples for a frame duration, for sizing buffers to match
+ * frameDurationMs. Canonical formats come from CANONICAL_FRAME_SAMPLES; other
+ * rates are derived. Null if the native module would reject the format.
+ */
+export function frameSamplesForMs(sampleRate: number, frameDurationMs: number): number | null {
+  if (frameDurationMs !== 10 && frameDurationMs !== 20 && frameDurationMs !== 40) {
+    return null;
+  }
+  const canonical = CANONICAL_FRAME_SAMPLES[sampleRate];
+  if (canonical) {
+    return canonical[frameDurationMs];
+  }
+  if (!Number.isInteger(sampleRate) || sampleRate < 8000 || sampleRate > 384000 || sampleRate % 100 !== 0) {
+    return null;
+  }
+  return (sampleRate * frameDurationMs) / 1000;
+}
+
+/** Inputs to suggestParameters */
+export interface AECParameterRequest {
+  sampleRate: number;
+  /** Buffering delay the caller can afford, in ms */
+  targetLatencyMs: number;
+  /** How long echo keeps arriving after the reference, in ms (room reverb plus path delay) */
+  expectedEchoTailMs: number;
+  /** Engine the filter length is meant for (default: 'webrtc') */
+  engine?: AECEngine;
+}
+
+/** Recommended frameDurationMs and filterLengthBlocks, from suggestParameters */
+export interface AECParameterSuggestion {
+  frameDurationMs: FrameDurationMs;
+  /** Samples per frame at sampleRate */
+  frameSize: number;
+  filterLengthBlocks: number;
+  /** Echo path filterLengthBlocks covers, in ms */
+  filterLengthMs: number;
+  /** Human-readable tradeoffs behind the numbers */
+  notes: string[];
+}
+
+const MIN_FILTER_LENGTH_BLOCKS = 1;
+const MAX_FILTER_LENGTH_BLOCKS = 32;
+
+/**
+ * Recommend a frame size and filter length for a latency budget and echo tail,
+ * without a processor. Frames are 20 ms when the budget allows two of them and
+ * 10 ms otherwise. The filter covers the tail rounded up to whole frames, then
+ * up to whole 64-sample blocks (4 ms for AEC3, at the stream rate for NLMS),
+ * clamped to 1..32 blocks. Null if the sample rate or times are invalid.
+ */
+export function suggestParameters(request: AECParameterRequest): AECParameterSuggestion | null {
+  const { sampleRate, targetLatencyMs, expectedEchoTailMs } = request;
+  const engine = request.engine ?? 'webrtc';
+  if (!Number.isFinite(targetLatencyMs) || targetLatencyMs < 0 ||
+      !Number.isFinite(expectedEchoTailMs) || expectedEchoTailMs < 0) {
+    return null;
+  }
+
+  const frameDurationMs: FrameDurationMs = targetLatencyMs >= 40 ? 20 : 10;
+  const frameSize = frameSamplesForMs(sampleRate, frameDurationMs);
+  if (frameSize === null) {
+    return null;
+  }
+
+  const notes: string[] = [];
+  if (targetLatencyMs < 10) {
+    notes.push(`10 ms is the shortest frame; latency will be at least that, above the ${targetLatencyMs} ms target.`);
+  } else if (frameDurationMs === 10) {
+    notes.push(`10 ms frames (${frameSize} samples) add the least buffering; a target of 40 ms or more ` +
+      'allows 20 ms frames with half the per-call overhead.');
+  } else {
+    notes.push(`20 ms frames (${frameSize} samples) halve per-call overhead; AEC3 still adapts in 10 ms ` +
+      'blocks, so convergence is unchanged.');
+  }
+
+  // CheckFilterLength flags a filter shorter than one frame, so cover at least that
+  const blockMs = engine === 'nlms' ? 64000 / sampleRate : 4;
+  const coveredMs = Math.max(1, Math.ceil(expectedEchoTailMs / frameDurationMs)) * frameDurationMs;
+  const wantedBlocks = Math.ceil(coveredMs / blockMs - 1e-9);
+  const filterLengthBlocks = Math.min(MAX_FILTER_LENGTH_BLOCKS, Math.max(MIN_FILTER_LENGTH_BLOCKS, wantedBlocks));
+  const filterLengthMs = filterLengthBlocks * blockMs;
+
+  if (wantedBlocks > MAX_FILTER_LENGTH_BLOCKS) {
+    notes.push(`A ${expectedEchoTailMs} ms tail needs ${wantedBlocks} blocks but the filter stops at ` +
+      `${MAX_FILTER_LENGTH_BLOCKS} (${filterLengthMs.toFixed(1)} ms); echo arriving later is left in.`);
+  } else {
+    notes.push(`${filterLengthBlocks} blocks cover ${filterLengthMs.toFixed(1)} ms; longer filters catch ` +
+      'more reverb but converge more slowly and cost more CPU.');
+  }
+  if (engine === 'nlms') {
+    notes.push(`NLMS runs ${filterLengthBlocks * 64} taps per sample at ${sampleRate} Hz, so cost grows ` +
+      'linearly with both filter length and rate.');
+  }
+
+  return { frameDurationMs, frameSize, filterLengthBlocks, filterLengthMs, notes };
+}
+
+const DEFAULT_CONFIG: ResolvedAECConfig = {
+  enableAec: true,
+  enableNs: true,
+  enableAgc: false,
+  disableAecOnHeadphones: true,
+  label: '',
+  fallbackToPassthrough: false,
+  frameDurationMs: 10,
+  sampleRate: 48000,
+  supportedSampleRates: DEFAULT_SUPPORTED_SAMPLE_RATES,
+  levelSmoothingMs: 300,
+  peakHoldMs: 1500,
+  peakDecayDbPerSec: 20,
+  engine: 'webrtc',
+  preset: 'aggressive',
+  deadInputWarningMs: 3000,
+  autoBypassOnOverload: false,
+  convergedErleDb: 10,
+  convergedHoldMs: 2000,
+  echoDetectCorrelation: 0.5,
+  echoDetectMaxErleDb: 6,
+  convergenceColdMs: 500,
+  strictChunkSize: false,
+  renderGranularity: 0,
+  maxInputSamples: 48000,
+  doubleTalkSensitivity: 0.5,
+  adaptationRate: 1,
+  autoResetAfterIdleMs: 0,
+  idleResetFilter: false,
+  erleHistoryLength: 1000,
+  dither: true,
+};
+
+/**
+ * TypeScript wrapper for the native WebRTC AEC module.
+ * Manages render/capture processing and native mic capture.
+ */
+export class AECProcessor {
+  private nativeModule: any = null;
+  private nativeInstance: any = null;
+  private config: ResolvedAECConfig;
+  private isInitialized = false;
+  private isDestroyed = false;
+  private renderBufferQueue: Float32Array[] = [];
+  private readonly MAX_RENDER_QUEUE = 10;
+  private micCapturing = false;
+  private micAudioCallback?: (samples: Float32Array, timestamp: number) => void;
+  private ditherRandom: () => number = Math.random;
+
+  constructor(config: AECConfig = {}) {
+    this.config = { ...DEFAULT_CONFIG, ...config };
+
+    try {
+      // Load the native module (exports a class AudioCaptureAddon)
+      let nativeModule;
+      
+      try {
+        // First try: use bindings module (works in dev/unbundled)
+        logger.debug('Attempting to load native addon via bindings module...');
+          logger.debug('DEBUG:', { __dirname, cwd: process.cwd() });
+        nativeModule = bindings('audio_capture_native');
+        logger.debug('Loaded native addon via bindings module');
+      } catch (bindingsError) {
+        // Fallback: try to require directly at runtime using multiple possible paths
+        // eslint-disable-next-line @typescript-eslint/no-require-imports
+        const fs = require('fs');
+        // eslint-disable-next-line @typescript-eslint/no-require-imports
+        const path = require('path');
+        
+        const errorMsg = bindingsError instanceof Error ? bindingsError.message : String(bindingsError);
+        logger.debug('bindings() failed, trying direct require paths', { error: errorMsg.substring(0, 100) });
+        
+        const possiblePaths: string[] = [];
+        
+        // Try same directory as the bundled code (after build copy)
+        possiblePaths.push(
+          path.join(__dirname, 'audio_capture_native.node')
+        );
+        
+        // Try absolute dev paths
+        possiblePaths.push(
+          path.resolve('/Users/moxo/Desktop/kakarot-master/native/build/Release/audio_capture_native.node'),
+          path.resolve('/Users/moxo/Desktop/kakarot-master/build/Release/audio_capture_native.node')
+        );
+        
+        // Try cwd-relative paths
+        possiblePaths.push(
+          path.join(process.cwd(), 'native/build/Release/audio_capture_native.node'),
+          path.join(process.cwd(), 'build/Release/audio_capture_native.node'),
+          path.join(process.cwd(), 'audio_capture_native.node')
+        );
+        
+        // Try relative to app directory (for production)
+        if (process.resourcesPath) {
+          possiblePaths.push(
+            path.join(process.resourcesPath, 'app/native/build/Release/audio_capture_native.node'),
+            path.join(process.resourcesPath, 'native/build/Release/audio_capture_native.node'),
+            path.join(process.resourcesPath, 'app/audio_capture_native.node'),
+            path.join(process.resourcesPath, 'audio_capture_native.node')
+          );
+        }
+
+        let foundPath: string | null = null;
+        const checkedPaths: string[] = [];
+        
+        for (const testPath of possiblePaths) {
+          checkedPaths.push(testPath);
+          try {
+            if (fs.existsSync(testPath)) {
+              logger.info('Found native addon', { path: testPath });
+              foundPath = testPath;
+              break;
+            }
+          } catch (e) {
+            logger.debug('Path check failed', { path: testPath, error: String(e).substring(0, 50) });
+          }
+        }
+
+        if (!foundPath) {
+          const checkedStr = checkedPaths.slice(0, 2).join(' OR ');
+          throw new Error(
+            `Could not find native audio_capture_native module. Checked: ${checkedStr}`
+          );
+        }
+
+        logger.debug('Loading native addon using require()', { path: foundPath });
+        // eslint-disable-next-line @typescript-eslint/no-require-imports
+        nativeModule = require(foundPath);
+        logger.debug('Loaded native addon via direct require()');
+      }
+
+      if (!nativeModule || typeof nativeModule.AudioCaptureAddon !== 'function') {
+        logger.error('Invalid native module structure', { hasModule: !!nativeModule, hasAudioCaptureAddon: !!nativeModule?.AudioCaptureAddon });
+        throw new Error('Failed to load native audio_capture_native module');
+      }
+
+      this.nativeModule = nativeModule;
+
+      // Create native instance with config (init occurs in constructor)
+      logger.debug('Creating native AudioCaptureAddon instance...');
+      this.nativeInstance = new this.nativeModule.AudioCaptureAddon({
+        label: this.config.label,
+        fallbackToPassthrough: this.config.fallbackToPassthrough,
+        sampleRate: this.config.sampleRate,
+        frameDurationMs: this.config.frameDurationMs,
+        supportedSampleRates: this.config.supportedSampleRates,
+        enableAec: this.config.enableAec,
+        enableNs: this.config.enableNs,
+        enableAgc: this.config.enableAgc,
+        levelSmoothingMs: this.config.levelSmoothingMs,
+        peakHoldMs: this.config.peakHoldMs,
+        peakDecayDbPerSec: this.config.peakDecayDbPerSec,
+        engine: this.config.engine,
+        preset: this.config.preset,
+        filterLengthBlocks: this.config.filterLengthBlocks,
+        noiseSuppressionLevel: this.config.noiseSuppressionLevel,
+        deadInputWarningMs: this.config.deadInputWarningMs,
+        autoBypassOnOverload: this.config.autoBypassOnOverload,
+        convergedErleDb: this.config.convergedErleDb,
+        convergedHoldMs: this.config.convergedHoldMs,
+        echoDetectCorrelation: this.config.echoDetectCorrelation,
+        echoDetectMaxErleDb: this.config.echoDetectMaxErleDb,
+        convergenceColdMs: this.config.convergenceColdMs,
+        strictChunkSize: this.config.strictChunkSize,
+        renderGranularity: this.config.renderGranularity,
+        maxInputSamples: this.config.maxInputSamples,
+        doubleTalkSensitivity: this.config.doubleTalkSensitivity,
+        adaptationRate: this.config.adaptationRate,
+        autoResetAfterIdleMs: this.config.autoResetAfterIdleMs,
+        idleResetFilter: this.config.idleResetFilter,
+        erleHistoryLength: this.config.erleHistoryLength,
+      });
+
+      this.isInitialized = true;
+      logger.info('AEC initialized successfully', {
+        enableAec: this.config.enableAec,
+        enableNs: this.config.enableNs,
+        enableAgc: this.config.enableAgc,
+        sampleRate: this.config.sampleRate,
+        frameDurationMs: this.config.frameDurationMs,
+        preset: this.config.preset,
+      });
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      const code = getAECErrorCode(error);
+      logger.error('AEC initialization failed', { errorMessage: message, code, errorType: error instanceof Error ? 'Error' : 'Other' });
+      if (code) {
+        throw error;  // Keeps the TypeError/RangeError class and code for the caller
+      }
+      throw new Error(`AEC initialization failed: ${message}`);
+    }
+  }
+
+  /**
+   * Process render (system/speaker) audio through the AEC reference path.
+   * Must be called BEFORE corresponding processCaptureAudio() call.
+   */
+  public processRenderAudio(renderBuffer: Float32Array): boolean {
+    if (this.isDestroyed) {
+      logger.warn('Cannot process render audio: AEC processor is destroyed');
+      return false;
+    }
+
+    if (!this.isInitialized) {
+      logger.warn('Cannot process render audio: AEC not initialized');
+      return false;
+    }
+
+    if (!renderBuffer || renderBuffer.length === 0) {
+      logger.warn('Render buffer is empty, skipping');
+      return false;
+    }
+
+    try {
+      // Queue the render buffer for processing
+      // The native module will process it internally and use it as reference
+      // for the next processCaptureAudio call
+      if (this.nativeInstance && typeof this.nativeInstance.processRenderAudio === 'function') {
+        this.nativeInstance.processRenderAudio(renderBuffer);
+      }
+
+      // Keep a reference for metrics if needed
+      if (this.renderBufferQueue.length >= this.MAX_RENDER_QUEUE) {
+        this.renderBufferQueue.shift();
+      }
+      this.renderBufferQueue.push(renderBuffer);
+
+      return true;
+    } catch (error) {
+      this.rethrowNativeError(error);
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error processing render audio', { error: message });
+      return false;
+    }
+  }
+
+  /**
+   * Feed interleaved multichannel render audio, de-interleaving natively
+   * instead of copying per frame in JS. channel selects one channel (must be
+   * less than channels) or 'mix' averages them all. Int16 input is scaled to
+   * the same [-1, 1) range as Float32. The mono processRenderAudio is unchanged.
+   */
+  public processRenderInterleaved(
+    renderBuffer: Int16Array | Float32Array,
+    channels: number,
+    channel: number | 'mix' = 0
+  ): boolean {
+    if (!this.isInitialized || this.isDestroyed) {
+      return false;
+    }
+
+    if (!renderBuffer || renderBuffer.length === 0) {
+      return false;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.processRenderInterleaved === 'function') {
+        this.nativeInstance.processRenderInterleaved(renderBuffer, channels, channel === 'mix' ? -1 : channel);
+        return true;
+      }
+
+      logger.warn('processRenderInterleaved not available in native module');
+      return false;
+    } catch (error) {
+      this.rethrowNativeError(error);
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error processing interleaved render audio', { channels, channel, error: message });
+      return false;
+    }
+  }
+
+  /**
+   * Processing and reference calls let coded native errors (see AECErrorCode)
+   * reach the caller so it can branch on err.code; only uncoded errors are
+   * logged and turned into the method's failure value
+   */
+  private rethrowNativeError(error: unknown): void {
+    if (getAECErrorCode(error)) {
+      throw error;
+    }
+  }
+
+  /**
+   * Process capture (microphone) audio through the AEC to remove echo.
+   * Returns echo-cancelled audio, or null when the processor isn't ready; native
+   * failures throw an error carrying an AECErrorCode. options override handle
+   * settings for this call only; with collectStats the result carries this
+   * call's measurements too.
+   */
+  public processCaptureAudio(
+    captureBuffer: Float32Array,
+    options?: AECCallOptions & { collectStats?: false }
+  ): Float32Array | null;
+  public processCaptureAudio(
+    captureBuffer: Float32Array,
+    options: AECCallOptions & { collectStats: true }
+  ): AECCallStatsOutput | null;
+  public processCaptureAudio(
+    captureBuffer: Float32Array,
+    options?: AECCallOptions
+  ): Float32Array | AECCallStatsOutput | null;
+  public processCaptureAudio(
+    captureBuffer: Float32Array,
+    options?: AECCallOptions
+  ): Float32Array | AECCallStatsOutput | null {
+    if (this.isDestroyed) {
+      logger.warn('Cannot process capture audio: AEC processor is destroyed');
+      return null;
+    }
+
+    if (!this.isInitialized) {
+      logger.warn('Cannot process capture audio: AEC not initialized');
+      return null;
+    }
+
+    if (!captureBuffer) {
+      logger.warn('Capture buffer is missing, returning null');
+      return null;
+    }
+
+    try {
+      // Call the native module to process capture audio and return echo-cancelled result.
+      // Empty buffers still go native so they are counted in emptyInputCalls.
+      if (this.nativeInstance && typeof this.nativeInstance.processCaptureAudio === 'function') {
+        if (options) {
+          const result = this.nativeInstance.processCaptureAudio(captureBuffer, options) as
+            Float32Array | AECCallStatsOutput | null;
+          const output = result instanceof Float32Array ? result : result?.output;
+          return output && output.length > 0 ? result : null;
+        }
+        const result = this.nativeInstance.processCaptureAudio(captureBuffer) as Float32Array | null;
+        return result && result.length > 0 ? result : null;
+      }
+
+      logger.warn('processCaptureAudio not available in native module');
+      return null;
+    } catch (error) {
+      this.rethrowNativeError(error);
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error processing capture audio', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Process a backlog of sequential capture chunks in one native call, e.g.
+   * when catching up after a stall or processing a pre-recorded file with the
+   * reference already fed. Output i is exactly what
+   * processCaptureAudio(chunks[i]) would have returned had the chunks been
+   * passed one by one, including reference consumption.
+   */
+  public processMany(captureBuffers: Float32Array[]): (Float32Array | null)[] | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    if (captureBuffers.length === 0) {
+      return [];
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.processMany === 'function') {
+        const results = this.nativeInstance.processMany(captureBuffers) as Float32Array[] | null;
+        return results ? results.map((result) => (result.length > 0 ? result : null)) : null;
+      }
+
+      logger.warn('processMany not available in native module');
+      return null;
+    } catch (error) {
+      this.rethrowNativeError(error);
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error processing capture audio batch', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Run the native self-test: a synthetic echo is cancelled by a fresh
+   * processor and the energy drop checked. Distinguishes a broken build/ABI
+   * (output silent or unchanged) from genuinely quiet audio. Defaults to the
+   * configured engine; pass another to compare engines on the same scenario.
+   */
+  public selfTest(engine: AECEngine = this.config.engine): AECSelfTestResult | null {
+    if (!this.nativeModule || typeof this.nativeModule.selfTest !== 'function') {
+      return null;
+    }
+
+    try {
+      const result = this.nativeModule.selfTest(this.config.sampleRate, engine) as AECSelfTestResult;
+      logger.info('AEC self-test completed', { engine, passed: result.passed, erleDb: result.erleDb });
+      return result;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('AEC self-test failed to run', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Toggle TPDF dither on toInt16. Dither slightly raises the noise floor but
+   * removes the quantization distortion that is audible in quiet passages of
+   * gain-processed audio. Pass a seed for a reproducible dither sequence.
+   */
+  public setDither(enabled: boolean, seed?: number): void {
+    this.config.dither = enabled;
+    this.ditherRandom = seed === undefined ? Math.random : createSeededRandom(seed);
+    logger.info('AEC output dither updated', { enabled, seeded: seed !== undefined });
+  }
+
+  /**
+   * Convert processed Float32 output to Int16 PCM, dithered per setDither
+   */
+  public toInt16(samples: Float32Array): Int16Array {
+    return float32ToInt16Array(samples, { dither: this.config.dither, random: this.ditherRandom });
+  }
+
+  /**
+   * Identify the exact native build (for support tickets). Only needs the
+   * module loaded, not an initialized processor.
+   */
+  public getVersion(): AECBuildInfo | null {
+    if (!this.nativeModule || typeof this.nativeModule.getVersion !== 'function') {
+      return null;
+    }
+
+    try {
+      return this.nativeModule.getVersion() as AECBuildInfo;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to read native build info', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Copy this processor into a new one that continues from the same state:
+   * config, buffered reference and capture audio, calibrated delay and the
+   * NLMS filter, so both give the same output for the same input. Metrics
+   * start at zero; mic capture, callbacks and the seeded dither aren't
+   * carried over. The webrtc engine's AEC3 filter can't be copied and
+   * re-converges in the clone. Returns null on failure.
+   */
+  public clone(): AECProcessor | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.clone === 'function') {
+        const clone: AECProcessor = Object.create(AECProcessor.prototype);
+        Object.assign(clone, this, {
+          nativeInstance: this.nativeInstance.clone(),
+          config: { ...this.config },
+          renderBufferQueue: [...this.renderBufferQueue],
+          micCapturing: false,
+          micAudioCallback: undefined,
+          ditherRandom: Math.random,
+        });
+        logger.info('AEC processor cloned', { engine: this.config.engine });
+        return clone;
+      }
+      return null;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Failed to clone AEC processor', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Snapshot what clone() copies as a versioned Buffer, e.g. to persist across
+   * a main-process reload mid-call. Restore it with setState on a processor
+   * created with the same options. Returns null on failure.
+   */
+  public getState(): Buffer | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.getState === 'function') {
+        return this.nativeInstance.getState() as Buffer;
+      }
+      return null;
+    } catch (error) {
+      logger.warn('Failed to save AEC state', { code: getAECErrorCode(error) });
+      return null;
+    }
+  }
+
+  /**
+   * Continue from a getState snapshot, keeping metrics. False if it was
+   * rejected: ERR_AEC_STATE_MISMATCH when it came from another format version
+   * or config (runtime setter changes included), ERR_AEC_INVALID_VALUE when
+   * corrupt. The webrtc engine's AEC3 filter re-converges, as with clone().
+   */
+  public setState(state: Buffer): boolean {
+    if (!this.isInitialized || this.isDestroyed) {
+      return false;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setState === 'function') {
+        this.nativeInstance.setState(state);
+        logger.info('AEC state restored', { bytes: state.length });
+        return true;
+      }
+      return false;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to restore AEC state', { code: getAECErrorCode(error), error: message });
+      return false;
+    }
+  }
+
+  /**
+   * Create a processor owned by the native module and return its id, which
+   * (unlike this object) can be posted to a worker_threads Worker and used
+   * there through AECSharedProcessorModule. Takes the same options as the
+   * constructor; only needs the module loaded. Returns null on failure.
+   */
+  public createSharedProcessor(options: Partial<AECConfig> = {}): number | null {
+    if (!this.nativeModule || typeof this.nativeModule.createShared !== 'function') {
+      return null;
+    }
+
+    try {
+      const nativeOptions: Record<string, unknown> = { ...options };
+      delete nativeOptions.dither;
+      delete nativeOptions.disableAecOnHeadphones;
+      const id = this.nativeModule.createShared(nativeOptions) as number;
+      logger.info('Shared AEC processor created', { id });
+      return id;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Failed to create shared AEC processor', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Destroy a processor from createSharedProcessor, invalidating its id on all
+   * threads. False if the id was unknown or already destroyed.
+   */
+  public destroySharedProcessor(id: number): boolean {
+    if (!this.nativeModule || typeof this.nativeModule.destroyShared !== 'function') {
+      return false;
+    }
+
+    try {
+      this.nativeModule.destroyShared(id);
+      logger.info('Shared AEC processor destroyed', { id });
+      return true;
+    } catch (error) {
+      logger.warn('Failed to destroy shared AEC processor', { id, code: getAECErrorCode(error) });
+      return false;
+    }
+  }
+
+  /**
+   * Report what the loaded native module supports, so callers can feature-detect
+   * instead of calling methods a given build lacks. Only needs the module loaded.
+   */
+  public getCapabilities(): AECCapabilities | null {
+    if (!this.nativeModule || typeof this.nativeModule.getCapabilities !== 'function') {
+      return null;
+    }
+
+    try {
+      return this.nativeModule.getCapabilities() as AECCapabilities;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to read native capabilities', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Every live native processor in this process, oldest first, across all
+   * AECProcessor objects and worker threads. Destroyed ones drop out. Only
+   * needs the module loaded; null if unavailable.
+   */
+  public listInstances(): AECInstanceInfo[] | null {
+    if (!this.nativeModule || typeof this.nativeModule.listInstances !== 'function') {
+      return null;
+    }
+
+    try {
+      return this.nativeModule.listInstances() as AECInstanceInfo[];
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to list native AEC instances', { error: message });
+      return null;
+    }
+  }
+
+  /** True if the loaded native module reports the named capability */
+  public hasCapability(name: string): boolean {
+    return this.getCapabilities()?.capabilities[name] === true;
+  }
+
+  /**
+   * Process capture audio into a caller-owned buffer instead of allocating a new
+   * one per call. outputBuffer must be at least as long as captureBuffer and may
+   * be the same array for in-place processing. Both must be Float32Arrays.
+   * @returns Number of bytes written (4 per sample), or -1 on failure
+   */
+  public processInto(captureBuffer: Float32Array, outputBuffer: Float32Array): number {
+    if (!this.isInitialized || this.isDestroyed) {
+      return -1;
+    }
+
+    if (!captureBuffer || captureBuffer.length === 0) {
+      return 0;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.processInto === 'function') {
+        return this.nativeInstance.processInto(captureBuffer, outputBuffer) as number;
+      }
+
+      logger.warn('processInto not available in native module');
+      return -1;
+    } catch (error) {
+      this.rethrowNativeError(error);
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error processing capture audio into buffer', { error: message });
+      return -1;
+    }
+  }
+
+  /**
+   * Downmix interleaved stereo Int16 PCM to mono in native code (L/R average,
+   * rounded and saturated). Independent of AEC state; only needs the module loaded.
+   */
+  public downmixStereoToMono(interleaved: Int16Array): Int16Array | null {
+    if (!this.nativeModule || typeof this.nativeModule.downmixStereoToMono !== 'function') {
+      return null;
+    }
+
+    try {
+      return this.nativeModule.downmixStereoToMono(interleaved) as Int16Array;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to downmix stereo audio', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Sum several Int16 playback streams into one combined reference in native
+   * code with saturation, so near-full-scale streams clip rather than wrap
+   * into loud clicks the canceller would then chase. The result is as long as
+   * the longest source; shorter sources are padded with silence.
+   */
+  public mixReferences(sources: Int16Array[]): Int16Array | null {
+    if (!this.nativeModule || typeof this.nativeModule.mixReferences !== 'function') {
+      return null;
+    }
+
+    try {
+      return this.nativeModule.mixReferences(sources) as Int16Array;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to mix reference streams', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Process capture audio and return per-call cancellation measurements,
+   * avoiding a separate getMetrics() round-trip for live tuning graphs.
+   */
+  public processAndMeasure(captureBuffer: Float32Array): AECMeasuredOutput | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    if (!captureBuffer || captureBuffer.length === 0) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.processAndMeasure === 'function') {
+        return this.nativeInstance.processAndMeasure(captureBuffer) as AECMeasuredOutput;
+      }
+
+      logger.warn('processAndMeasure not available in native module');
+      return null;
+    } catch (error) {
+      this.rethrowNativeError(error);
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error processing capture audio with measurement', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Process capture audio and also return the removed signal (input - output),
+   * computed in the same native pass, for an "echo monitor" view. Listening to
+   * removed reveals whether near-end speech is being cancelled along with echo.
+   */
+  public processSplit(captureBuffer: Float32Array): AECSplitOutput | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    if (!captureBuffer || captureBuffer.length === 0) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.processSplit === 'function') {
+        return this.nativeInstance.processSplit(captureBuffer) as AECSplitOutput;
+      }
+
+      logger.warn('processSplit not available in native module');
+      return null;
+    } catch (error) {
+      this.rethrowNativeError(error);
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error processing capture audio with split output', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Process capture audio on a native worker thread so the main process stays
+   * responsive. Calls are processed strictly in call order and give exactly
+   * the processCaptureAudio output. Use either this or the synchronous
+   * process* calls for a stream, not both: a synchronous call would be
+   * processed ahead of async calls still waiting.
+   */
+  public async processAsync(captureBuffer: Float32Array): Promise<Float32Array | null> {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    if (!captureBuffer || captureBuffer.length === 0) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.processAsync === 'function') {
+        return (await this.nativeInstance.processAsync(captureBuffer)) as Float32Array;
+      }
+
+      logger.warn('processAsync not available in native module');
+      return null;
+    } catch (error) {
+      this.rethrowNativeError(error);
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error processing capture audio asynchronously', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Pre-adapt the canceller on known audio, e.g. a calibration tone played at
+   * session start, so it has converged before real audio flows. micBuffer is
+   * what the microphone picked up while refBuffer played; both must be the
+   * same length. Equivalent to feeding both through the normal path and
+   * discarding the output, so metrics and events see this audio too.
+   * @returns Number of frames processed, or -1 on failure
+   */
+  public warmup(micBuffer: Float32Array, refBuffer: Float32Array): number {
+    if (!this.isInitialized || this.isDestroyed) {
+      return -1;
+    }
+
+    if (!micBuffer || micBuffer.length === 0) {
+      return 0;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.warmup === 'function') {
+        return this.nativeInstance.warmup(micBuffer, refBuffer) as number;
+      }
+
+      logger.warn('warmup not available in native module');
+      return -1;
+    } catch (error) {
+      this.rethrowNativeError(error);
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error warming up AEC', { error: message });
+      return -1;
+    }
+  }
+
+  /**
+   * Pre-allocate native scratch buffers for chunks of up to maxSamples, so the
+   * first real process call doesn't allocate on the audio thread. Call after
+   * create (and after any setSampleRate), before audio starts; calling again
+   * with the same or a smaller size does nothing.
+   */
+  public ensureCapacity(maxSamples: number): boolean {
+    if (!this.isInitialized || this.isDestroyed) {
+      return false;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.ensureCapacity === 'function') {
+        this.nativeInstance.ensureCapacity(maxSamples);
+        return true;
+      }
+      return false;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to reserve AEC buffers', { maxSamples, error: message });
+      return false;
+    }
+  }
+
+  /**
+   * Monitoring ("hear yourself") loopback: cancel echo in captureBuffer, then
+   * feed the output back as reference audio, scaled by gain to match the
+   * monitor volume, in one native call. Same result as processCaptureAudio
+   * followed by processRenderAudio of the scaled output.
+   */
+  public processAndFeed(captureBuffer: Float32Array, gain = 1): Float32Array | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    if (!captureBuffer || captureBuffer.length === 0) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.processAndFeed === 'function') {
+        return this.nativeInstance.processAndFeed(captureBuffer, gain) as Float32Array;
+      }
+
+      logger.warn('processAndFeed not available in native module');
+      return null;
+    } catch (error) {
+      this.rethrowNativeError(error);
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error processing monitoring loopback', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Process capture audio and also get it at 16 kHz for speech recognition,
+   * resampled natively with an anti-aliasing filter so the two streams can
+   * never drift apart. Needs a sample rate that is a multiple of 16 kHz
+   * (16000, 32000, 48000); returns null otherwise.
+   */
+  public processWithAsrTap(captureBuffer: Float32Array): AECAsrTapOutput | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    if (!captureBuffer || captureBuffer.length === 0) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.processWithAsrTap === 'function') {
+        return this.nativeInstance.processWithAsrTap(captureBuffer) as AECAsrTapOutput;
+      }
+
+      logger.warn('processWithAsrTap not available in native module');
+      return null;
+    } catch (error) {
+      this.rethrowNativeError(error);
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error processing capture audio with ASR tap', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Report capture audio that was skipped instead of processed (e.g. chunks
+   * dropped while the renderer was behind). The matching reference audio is
+   * discarded so the next processed chunk stays aligned with its echo.
+   */
+  public notifyDroppedInput(sampleCount: number): void {
+    if (!this.isInitialized || this.isDestroyed || sampleCount <= 0) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.notifyDroppedInput === 'function') {
+        this.nativeInstance.notifyDroppedInput(sampleCount);
+      }
+    } catch (error) {
+      logger.warn('Failed to report dropped input', { error });
+    }
+  }
+
+  /**
+   * Start native microphone capture using AudioUnit.
+   * Timestamps use the same monotonic clock as system audio for AEC sync.
+   */
+  public startMicrophoneCapture(callback: (samples: Float32Array, timestamp: number) => void): boolean {
+    if (this.isDestroyed) {
+      logger.warn('Cannot start mic capture: AEC processor is destroyed');
+      return false;
+    }
+
+    if (!this.isInitialized) {
+      logger.warn('Cannot start mic capture: AEC not initialized');
+      return false;
+    }
+
+    if (this.micCapturing) {
+      logger.warn('Microphone capture already running');
+      return true;
+    }
+
+    if (!callback || typeof callback !== 'function') {
+      logger.error('Invalid callback provided to startMicrophoneCapture');
+      return false;
+    }
+
+    try {
+      this.micAudioCallback = callback;
+
+      if (this.nativeInstance && typeof this.nativeInstance.startMicrophoneCapture === 'function') {
+        const success = this.nativeInstance.startMicrophoneCapture((samples: Float32Array, timestamp: number) => {
+          if (this.micAudioCallback) {
+            this.micAudioCallback(samples, timestamp);
+          }
+        });
+
+        if (success) {
+          this.micCapturing = true;
+          logger.info('Native microphone capture started');
+          return true;
+        } else {
+          logger.error('Native module failed to start microphone capture');
+          this.micAudioCallback = undefined;
+          return false;
+        }
+      } else {
+        logger.error('startMicrophoneCapture not available in native module');
+        this.micAudioCallback = undefined;
+        return false;
+      }
+    } catch (error) {
+      // Better error extraction for debugging
+      let errorMsg = 'Unknown error';
+      let errorStack = '';
+      if (error instanceof Error) {
+        errorMsg = error.message;
+        errorStack = error.stack || '';
+      } else if (typeof error === 'object' && error !== null) {
+        errorMsg = JSON.stringify(error);
+      } else {
+        errorMsg = String(error);
+      }
+      
+      logger.error('Error starting native microphone capture', { 
+        error: errorMsg,
+        stack: errorStack,
+        errorType: typeof error,
+        errorKeys: error && typeof error === 'object' ? Object.keys(error) : []
+      });
+      this.micAudioCallback = undefined;
+      return false;
+    }
+  }
+
+  /**
+   * Stop native microphone capture.
+   */
+  public stopMicrophoneCapture(): boolean {
+    if (!this.micCapturing) {
+      return true;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.stopMicrophoneCapture === 'function') {
+        const success = this.nativeInstance.stopMicrophoneCapture();
+        
+        if (success) {
+          this.micCapturing = false;
+          this.micAudioCallback = undefined;
+          logger.info('Native microphone capture stopped');
+          return true;
+        } else {
+          logger.warn('Native module failed to stop microphone capture');
+          return false;
+        }
+      } else {
+        logger.warn('stopMicrophoneCapture not available in native module');
+        return false;
+      }
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.error('Error stopping native microphone capture', { error: message });
+      return false;
+    }
+  }
+
+  /**
+   * Check if native microphone capture is running.
+   */
+  public isMicrophoneCapturing(): boolean {
+    return this.micCapturing;
+  }
+
+  /**
+   * Get current AEC metrics (ERLE, residual echo level, convergence status).
+   */
+  public getMetrics(): AECMetrics {
+    if (!this.isInitialized || this.isDestroyed) {
+      return {};
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.getMetrics === 'function') {
+        return this.mapNativeMetrics(this.nativeInstance.getMetrics());
+      }
+      return {};
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to get AEC metrics', { error: message });
+      return {};
+    }
+  }
+
+  /**
+   * Run cheap native diagnostics for stuck or pathological processor state
+   * (frozen output, output louder than input, pinned reference, NaN). With
+   * autoRecover the engine is reset when any check fails, instead of having
+   * to recreate the processor.
+   */
+  public healthCheck(autoRecover = false): AECHealthReport | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.healthCheck === 'function') {
+        const report = this.nativeInstance.healthCheck(autoRecover) as AECHealthReport;
+        if (!report.healthy) {
+          logger.warn('AEC health check failed', { ...report.checks, recovered: report.recovered });
+        }
+        return report;
+      }
+      return null;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to run AEC health check', { error: message });
+      return null;
+    }
+  }
+
+  /**
+   * Read all metrics and reset them in one native call, for exact per-interval
+   * telemetry: nothing processed between a getMetrics and a resetMetrics can
+   * be lost or counted twice. Processing and this call share the JS thread, so
+   * no chunk can land between the read and the reset.
+   */
+  public getAndResetMetrics(): AECMetrics {
+    if (!this.isInitialized || this.isDestroyed) {
+      return {};
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.getAndResetMetrics === 'function') {
+        return this.mapNativeMetrics(this.nativeInstance.getAndResetMetrics());
+      }
+      return {};
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to get and reset AEC metrics', { error: message });
+      return {};
+    }
+  }
+
+  /**
+   * All native metrics serialized to one JSON string in a single native call,
+   * for telemetry uploaders. Keys are stable: schemaVersion (1),
+   * configFingerprint (hex hash of the active settings and sample rate),
+   * sampleRate, and otherwise the raw native getMetrics() names, e.g.
+   * echoReturnLossEnhancement, erleInstantDb, latencyHistogram, underrunCount.
+   * Keys are only added within a schema version. Returns null when unavailable.
+   */
+  public getMetricsJson(): string | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.getMetricsJson === 'function') {
+        return this.nativeInstance.getMetricsJson() as string;
+      }
+      return null;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to serialize AEC metrics', { error: message });
+      return null;
+    }
+  }
+
+  /** Map native metric keys to the AECMetrics interface */
+  private mapNativeMetrics(m: any): AECMetrics {
+    return {
+      erle: typeof m.echoReturnLossEnhancement === 'number' ? m.echoReturnLossEnhancement : undefined,
+      rerl: typeof m.echoReturnLoss === 'number' ? m.echoReturnLoss : undefined,
+      renderDelayMs: typeof m.renderDelayMs === 'number' ? m.renderDelayMs : undefined,
+      calibratedDelayMs: typeof m.calibratedDelayMs === 'number' ? m.calibratedDelayMs : undefined,
+      msSinceLastReference: typeof m.msSinceLastReference === 'number' ? m.msSinceLastReference : undefined,
+      converged: typeof m.aecConverged === 'boolean' ? m.aecConverged : undefined,
+      echoPower: typeof m.rmsLevel === 'number' ? m.rmsLevel : undefined,
+      residualEchoLevel: typeof m.peakLevel === 'number' ? m.peakLevel : undefined,
+      inputRmsDbfs: typeof m.inputRmsDbfs === 'number' ? m.inputRmsDbfs : undefined,
+      inputPeakDbfs: typeof m.inputPeakDbfs === 'number' ? m.inputPeakDbfs : undefined,
+      outputRmsDbfs: typeof m.outputRmsDbfs === 'number' ? m.outputRmsDbfs : undefined,
+      outputPeakDbfs: typeof m.outputPeakDbfs === 'number' ? m.outputPeakDbfs : undefined,
+      inputPeakInstantDbfs: typeof m.inputPeakInstantDbfs === 'number' ? m.inputPeakInstantDbfs : undefined,
+      inputPeakHoldDbfs: typeof m.inputPeakHoldDbfs === 'number' ? m.inputPeakHoldDbfs : undefined,
+      outputPeakInstantDbfs: typeof m.outputPeakInstantDbfs === 'number' ? m.outputPeakInstantDbfs : undefined,
+      outputPeakHoldDbfs: typeof m.outputPeakHoldDbfs === 'number' ? m.outputPeakHoldDbfs : undefined,
+      loudnessGainDb: typeof m.loudnessGainDb === 'number' ? m.loudnessGainDb : undefined,
+      agcGainDb: typeof m.agcGainDb === 'number' ? m.agcGainDb : undefined,
+      limiterGainReductionDb:
+        typeof m.limiterGainReductionDb === 'number' ? m.limiterGainReductionDb : undefined,
+      limiterMaxGainReductionDb:
+        typeof m.limiterMaxGainReductionDb === 'number' ? m.limiterMaxGainReductionDb : undefined,
+      skippedSilentChunks: typeof m.skippedSilentChunks === 'number' ? m.skippedSilentChunks : undefined,
+      silenceBypassChunks: typeof m.silenceBypassChunks === 'number' ? m.silenceBypassChunks : undefined,
+      deadInputMs: typeof m.deadInputMs === 'number' ? m.deadInputMs : undefined,
+      deadInput: typeof m.deadInput === 'boolean' ? m.deadInput : undefined,
+      noiseFloorDbfs: typeof m.noiseFloorDbfs === 'number' ? m.noiseFloorDbfs : undefined,
+      engine: typeof m.engine === 'string' ? m.engine : undefined,
+      erleInstantDb: typeof m.erleInstantDb === 'number' ? m.erleInstantDb : undefined,
+      erleAverageDb: typeof m.erleAverageDb === 'number' ? m.erleAverageDb : undefined,
+      latencyHistogram: Array.isArray(m.latencyHistogram) ? m.latencyHistogram : undefined,
+      latencyP50Ms: typeof m.latencyP50Ms === 'number' ? m.latencyP50Ms : undefined,
+      latencyP95Ms: typeof m.latencyP95Ms === 'number' ? m.latencyP95Ms : undefined,
+      latencyP99Ms: typeof m.latencyP99Ms === 'number' ? m.latencyP99Ms : undefined,
+      latencyMaxMs: typeof m.latencyMaxMs === 'number' ? m.latencyMaxMs : undefined,
+      latencyMaxChunkSamples: typeof m.latencyMaxChunkSamples === 'number' ? m.latencyMaxChunkSamples : undefined,
+      underrunCount: typeof m.underrunCount === 'number' ? m.underrunCount : undefined,
+      underrunSamples: typeof m.underrunSamples === 'number' ? m.underrunSamples : undefined,
+      underrunZeroFillCount: typeof m.underrunZeroFillCount === 'number' ? m.underrunZeroFillCount : undefined,
+      underrunHoldCount: typeof m.underrunHoldCount === 'number' ? m.underrunHoldCount : undefined,
+      overflowCount: typeof m.overflowCount === 'number' ? m.overflowCount : undefined,
+      overflowSamples: typeof m.overflowSamples === 'number' ? m.overflowSamples : undefined,
+      refBufferFillRatio: typeof m.refBufferFillRatio === 'number' ? m.refBufferFillRatio : undefined,
+      referenceDriftSamples: typeof m.referenceDriftSamples === 'number' ? m.referenceDriftSamples : undefined,
+      referenceDriftPerSecond: typeof m.referenceDriftPerSecond === 'number' ? m.referenceDriftPerSecond : undefined,
+      realTimeFactor: typeof m.realTimeFactor === 'number' ? m.realTimeFactor : undefined,
+      realTimeFactorRecent: typeof m.realTimeFactorRecent === 'number' ? m.realTimeFactorRecent : undefined,
+      audioProcessedMs: typeof m.audioProcessedMs === 'number' ? m.audioProcessedMs : undefined,
+      inputSamplesProcessed: typeof m.inputSamplesProcessed === 'number' ? m.inputSamplesProcessed : undefined,
+      outputSamplesProduced: typeof m.outputSamplesProduced === 'number' ? m.outputSamplesProduced : undefined,
+      referenceSamplesConsumed:
+        typeof m.referenceSamplesConsumed === 'number' ? m.referenceSamplesConsumed : undefined,
+      audioSecondsProcessed: typeof m.audioSecondsProcessed === 'number' ? m.audioSecondsProcessed : undefined,
+      overloaded: typeof m.overloaded === 'boolean' ? m.overloaded : undefined,
+      overloadBypass: typeof m.overloadBypass === 'boolean' ? m.overloadBypass : undefined,
+      overloadEvents: typeof m.overloadEvents === 'number' ? m.overloadEvents : undefined,
+      pathChangesDetected: typeof m.pathChangesDetected === 'number' ? m.pathChangesDetected : undefined,
+      adaptationThrottledChunks:
+        typeof m.adaptationThrottledChunks === 'number' ? m.adaptationThrottledChunks : undefined,
+      idleResets: typeof m.idleResets === 'number' ? m.idleResets : undefined,
+      suboptimalConfig: typeof m.suboptimalConfig === 'boolean' ? m.suboptimalConfig : undefined,
+      emptyInputCalls: typeof m.emptyInputCalls === 'number' ? m.emptyInputCalls : undefined,
+      convergenceState: typeof m.convergenceState === 'string' ? m.convergenceState : undefined,
+      timeSinceConvergedMs: typeof m.timeSinceConvergedMs === 'number' ? m.timeSinceConvergedMs : undefined,
+      droppedInputEvents: typeof m.droppedInputEvents === 'number' ? m.droppedInputEvents : undefined,
+      droppedInputSamples: typeof m.droppedInputSamples === 'number' ? m.droppedInputSamples : undefined,
+      scratchGrowCount: typeof m.scratchGrowCount === 'number' ? m.scratchGrowCount : undefined,
+      engineActive: typeof m.engineActive === 'boolean' ? m.engineActive : undefined,
+      initError: typeof m.initError === 'string' ? m.initError : undefined,
+    };
+  }
+
+  /**
+   * Clear metric counters and level meters without touching AEC audio state.
+   */
+  public resetMetrics(): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.resetMetrics === 'function') {
+        this.nativeInstance.resetMetrics();
+      }
+    } catch (error) {
+      logger.warn('Failed to reset AEC metrics', { error });
+    }
+  }
+
+  /**
+   * Check if headphones are currently connected.
+   */
+  public isHeadphonesConnected(): boolean {
+    if (!this.isInitialized || this.isDestroyed) {
+      return false;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.isHeadphonesConnected === 'function') {
+        return this.nativeInstance.isHeadphonesConnected() as boolean;
+      }
+      return false;
+    } catch (error) {
+      logger.warn('Failed to check headphone status', { error });
+      return false;
+    }
+  }
+
+  /**
+   * Enable or disable echo cancellation at runtime.
+   */
+  public setEchoCancellationEnabled(enabled: boolean): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setEchoCancellationEnabled === 'function') {
+        this.nativeInstance.setEchoCancellationEnabled(enabled);
+        logger.info('AEC enabled set to:', { enabled });
+      }
+    } catch (error) {
+      logger.warn('Failed to set AEC enabled state', { error });
+    }
+  }
+
+  /**
+   * Enable or disable background noise suppression independently of echo
+   * cancellation. Omitting level keeps the current (preset) level.
+   */
+  public setNoiseSuppression(enabled: boolean, level?: NoiseSuppressionLevel): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setNoiseSuppression === 'function') {
+        this.nativeInstance.setNoiseSuppression(enabled, level);
+        this.config = { ...this.config, enableNs: enabled, noiseSuppressionLevel: level ?? this.config.noiseSuppressionLevel };
+        logger.info('Noise suppression set', { enabled, level });
+      }
+    } catch (error) {
+      logger.warn('Failed to set noise suppression', { error });
+    }
+  }
+
+  /**
+   * Enable slow loudness normalization of the echo-cancelled output so
+   * transcription sees a consistent level. Gain tracks a gated RMS estimate
+   * over a few seconds and is bounded by maxGainDb in either direction.
+   * targetDb must be -60..0 dBFS and maxGainDb 0..40; anything else,
+   * including NaN, is rejected and leaves the current settings in place.
+   */
+  public setLoudnessNormalization(enabled: boolean, targetDb = -20, maxGainDb = 12): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setLoudnessNormalization === 'function') {
+        this.nativeInstance.setLoudnessNormalization(enabled, targetDb, maxGainDb);
+        logger.info('Loudness normalization set', { enabled, targetDb, maxGainDb });
+      }
+    } catch (error) {
+      logger.warn('Failed to set loudness normalization', { error });
+    }
+  }
+
+  /**
+   * Enable a fast AGC on the echo-cancelled output to even out near-end level
+   * swings (e.g. the speaker moving away from the mic). Reacts within tens of
+   * ms, unlike loudness normalization; boosts are capped at maxGainDb.
+   * targetDb must be -60..0 dBFS and maxGainDb 0..40; anything else,
+   * including NaN, is rejected and leaves the current settings in place.
+   */
+  public setAgc(enabled: boolean, targetDb = -18, maxGainDb = 20): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setAgc === 'function') {
+        this.nativeInstance.setAgc(enabled, targetDb, maxGainDb);
+        logger.info('Output AGC set', { enabled, targetDb, maxGainDb });
+      }
+    } catch (error) {
+      logger.warn('Failed to set output AGC', { error });
+    }
+  }
+
+  /**
+   * Enable a soft-knee peak limiter as the final output stage. Peaks nearing
+   * thresholdDb (dBFS, -24..0) are eased down instead of hard-clipped before
+   * the Int16 conversion; the reduction shows up in getMetrics().
+   */
+  public setLimiter(enabled: boolean, thresholdDb = -1): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setLimiter === 'function') {
+        this.nativeInstance.setLimiter(enabled, thresholdDb);
+        logger.info('Output limiter set', { enabled, thresholdDb });
+      }
+    } catch (error) {
+      logger.warn('Failed to set output limiter', { error });
+    }
+  }
+
+  /**
+   * Skip echo cancellation for chunks where both the mic and the render audio
+   * are below floorDb (-120..0), saving CPU during long silences. Keep the
+   * floor conservative so quiet near-end speech still reaches the canceller.
+   * Output stages such as loudness normalization still run on skipped chunks.
+   */
+  public setSilentChunkSkip(enabled: boolean, floorDb = -70): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setSilentChunkSkip === 'function') {
+        this.nativeInstance.setSilentChunkSkip(enabled, floorDb);
+        logger.info('Silent chunk skip set', { enabled, floorDb });
+      }
+    } catch (error) {
+      logger.warn('Failed to set silent chunk skip', { error });
+    }
+  }
+
+  /**
+   * Pass the mic through without echo cancellation or noise suppression while
+   * the render audio paired with the chunk is below thresholdDb (-120..0;
+   * one-way audio has no echo to remove). Applies to every engine. Pass null
+   * to disable.
+   */
+  public setSilenceBypassThreshold(thresholdDb: number | null): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setSilenceBypassThreshold === 'function') {
+        this.nativeInstance.setSilenceBypassThreshold(thresholdDb);
+        logger.info('Silence bypass threshold set', { thresholdDb });
+      }
+    } catch (error) {
+      logger.warn('Failed to set silence bypass threshold', { error });
+    }
+  }
+
+  /**
+   * Scale render audio before cancellation so the reference approximates what
+   * the speakers actually play (e.g. pass the system output volume, 0-1).
+   */
+  public setReferenceGain(gain: number): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setReferenceGain === 'function') {
+        this.nativeInstance.setReferenceGain(gain);
+        logger.info('Reference gain set', { gain });
+      }
+    } catch (error) {
+      logger.warn('Failed to set reference gain', { error });
+    }
+  }
+
+  /**
+   * Share (0-1) of the one-second reference buffer kept when render overruns
+   * it. The default 0.5 drops the oldest half at once; steady feeds that only
+   * occasionally run ahead can keep close to 1.0 so an overrun drops just the
+   * excess and the reference alignment barely moves. Dropped samples are
+   * counted in overflowSamples.
+   */
+  public setOverrunDrainFraction(fraction: number): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setOverrunDrainFraction === 'function') {
+        this.nativeInstance.setOverrunDrainFraction(fraction);
+        logger.info('Overrun drain fraction set', { fraction });
+      }
+    } catch (error) {
+      logger.warn('Failed to set overrun drain fraction', { error });
+    }
+  }
+
+  /**
+   * Fixed mic gain in dB (-60..40) applied before cancellation, e.g. -20 for
+   * a mic that arrives too hot for the canceller. Changes ramp over 10 ms and
+   * the result is clipped to full scale.
+   */
+  public setInputGain(gainDb: number): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setInputGain === 'function') {
+        this.nativeInstance.setInputGain(gainDb);
+        logger.info('Input gain set', { gainDb });
+      }
+    } catch (error) {
+      logger.warn('Failed to set input gain', { error });
+    }
+  }
+
+  /**
+   * Fixed output gain in dB (-60..40) applied after every other output stage,
+   * e.g. to lift a quiet result for a downstream VAD. Ramped and clipped like
+   * setInputGain.
+   */
+  public setOutputGain(gainDb: number): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setOutputGain === 'function') {
+        this.nativeInstance.setOutputGain(gainDb);
+        logger.info('Output gain set', { gainDb });
+      }
+    } catch (error) {
+      logger.warn('Failed to set output gain', { error });
+    }
+  }
+
+  /**
+   * First-order pre-emphasis of the mic before cancellation, with the exact
+   * inverse applied to the output, so levels and output stages are unchanged.
+   * Tilting the mic toward high frequencies focuses adaptation on the speech
+   * band instead of low-frequency rumble and hum. 0 disables; 0.97 is the
+   * usual speech value. Changing it clears the filter state.
+   */
+  public setPreEmphasis(coefficient: number): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setPreEmphasis === 'function') {
+        this.nativeInstance.setPreEmphasis(coefficient);
+        logger.info('Pre-emphasis set', { coefficient });
+      }
+    } catch (error) {
+      logger.warn('Failed to set pre-emphasis', { error });
+    }
+  }
+
+  /**
+   * How readily the NLMS engine slows adaptation during double-talk, 0 (off)
+   * to 0.99. Higher values react to quieter near-end speech but also slow
+   * re-convergence when echo is louder than usual. The current filter keeps
+   * cancelling while adaptation is slowed; see adaptationThrottled.
+   */
+  public setDoubleTalkSensitivity(sensitivity: number): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setDoubleTalkSensitivity === 'function') {
+        this.nativeInstance.setDoubleTalkSensitivity(sensitivity);
+        this.config = { ...this.config, doubleTalkSensitivity: sensitivity };
+        logger.info('Double-talk sensitivity set', { sensitivity });
+      }
+    } catch (error) {
+      logger.warn('Failed to set double-talk sensitivity', { error });
+    }
+  }
+
+  /**
+   * Scale how fast the adaptive filter learns, from 0.1 to 2 (1 is the
+   * default tuning). Higher rates converge sooner at startup and after the
+   * echo path changes, but chase noise and double-talk more, so a converged
+   * filter leaves more residual echo and artifacts; lower rates settle slower
+   * and then hold steadier. Fast for rooms where people move around, slow for
+   * fixed setups. NLMS applies it immediately; AEC3 is rebuilt and re-converges.
+   */
+  public setAdaptationRate(rate: number): boolean {
+    if (!this.isInitialized || this.isDestroyed) {
+      return false;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setAdaptationRate === 'function') {
+        this.nativeInstance.setAdaptationRate(rate);
+        this.config = { ...this.config, adaptationRate: rate };
+        logger.info('Adaptation rate set', { rate });
+        return true;
+      }
+      return false;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to set adaptation rate', { rate, error: message });
+      return false;
+    }
+  }
+
+  /**
+   * Drop the stale queued reference on the first capture call after a pause
+   * longer than idleMs (0 disables), e.g. when the user pauses a call. With
+   * resetFilter the canceller restarts too and re-converges; counted in
+   * idleResets.
+   */
+  public setAutoResetAfterIdle(idleMs: number, resetFilter = false): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setAutoResetAfterIdle === 'function') {
+        this.nativeInstance.setAutoResetAfterIdle(idleMs, resetFilter);
+        this.config = { ...this.config, autoResetAfterIdleMs: idleMs, idleResetFilter: resetFilter };
+        logger.info('AEC idle reset set', { idleMs, resetFilter });
+      }
+    } catch (error) {
+      logger.warn('Failed to set AEC idle reset', { error });
+    }
+  }
+
+  /**
+   * Set how many smoothed ERLE values (one per processCaptureAudio call) the
+   * native history keeps, at most 60000 (ten minutes of 10 ms calls).
+   * Existing values are kept up to the new length.
+   */
+  public setErleHistoryLength(length: number): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setErleHistoryLength === 'function') {
+        this.nativeInstance.setErleHistoryLength(length);
+      }
+    } catch (error) {
+      logger.warn('Failed to set ERLE history length', { error });
+    }
+  }
+
+  /**
+   * Recent smoothed ERLE values in dB, oldest first, for a cancellation quality
+   * chart. Cleared by resetMetrics().
+   */
+  public getErleHistory(): Float32Array | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.getErleHistory === 'function') {
+        return this.nativeInstance.getErleHistory() as Float32Array;
+      }
+      return null;
+    } catch (error) {
+      logger.warn('Failed to get ERLE history', { error });
+      return null;
+    }
+  }
+
+  /**
+   * Debugging facility: the most recent mic frame exactly as it arrived,
+   * echo included, before input gain and cancellation. Play it beside
+   * processSplit's output to confirm the reference matches what the speakers
+   * actually play. Holds at most one frame; empty before the first capture
+   * call. Not meant for the audio path.
+   */
+  public getLastRawInput(): Float32Array | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.getLastRawInput === 'function') {
+        return this.nativeInstance.getLastRawInput() as Float32Array;
+      }
+      return null;
+    } catch (error) {
+      logger.warn('Failed to get last raw input', { error });
+      return null;
+    }
+  }
+
+  /**
+   * Render samples that can be fed before the reference buffer overruns and
+   * drops audio. A feeding thread that can outrun capture should hold back
+   * once this drops below its next chunk. Null when unavailable.
+   */
+  public refBufferAvailable(): number | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.refBufferAvailable === 'function') {
+        return this.nativeInstance.refBufferAvailable() as number;
+      }
+      return null;
+    } catch (error) {
+      logger.warn('Failed to get reference buffer space', { error });
+      return null;
+    }
+  }
+
+  /**
+   * Start recording exactly what the native module sees to a 3-channel 16-bit
+   * WAV at path: mic input, the reference fed for the same span, and the
+   * processed output, sample-aligned for inspection in an audio editor. Writes
+   * happen on a background thread; chunks are dropped (and counted) rather
+   * than blocking processing if it falls behind.
+   */
+  public startDebugDump(path: string): boolean {
+    if (!this.isInitialized || this.isDestroyed) {
+      return false;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.startDebugDump === 'function') {
+        const started = this.nativeInstance.startDebugDump(path) as boolean;
+        if (started) {
+          logger.info('AEC debug dump started', { path });
+        } else {
+          logger.warn('AEC debug dump could not be started', { path });
+        }
+        return started;
+      }
+      return false;
+    } catch (error) {
+      logger.warn('Failed to start AEC debug dump', { error });
+      return false;
+    }
+  }
+
+  /**
+   * Stop the debug dump and finalize the WAV header
+   * @returns Write stats, or null if no dump was running
+   */
+  public stopDebugDump(): AECDebugDumpStats | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.stopDebugDump === 'function') {
+        const stats = this.nativeInstance.stopDebugDump() as AECDebugDumpStats | null;
+        if (stats) {
+          logger.info('AEC debug dump stopped', { ...stats });
+        }
+        return stats;
+      }
+      return null;
+    } catch (error) {
+      logger.warn('Failed to stop AEC debug dump', { error });
+      return null;
+    }
+  }
+
+  /**
+   * Keep the last maxSeconds (up to 60) of mic, reference and output audio in
+   * bounded native memory, for attaching an exact recording to "echo still
+   * audible" reports via dumpDebugCapture(). Off by default; 0 turns it off
+   * and frees the memory. Use startDebugDump() instead to stream to a WAV file.
+   */
+  public enableDebugCapture(maxSeconds: number): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.enableDebugCapture === 'function') {
+        this.nativeInstance.enableDebugCapture(maxSeconds);
+        logger.info('AEC debug capture configured', { maxSeconds });
+      }
+    } catch (error) {
+      logger.warn('Failed to configure AEC debug capture', { error });
+    }
+  }
+
+  /**
+   * Copy out the debug capture rings (oldest sample first). Capture keeps running.
+   * @returns The three tracks, or null if capture is not enabled
+   */
+  public dumpDebugCapture(): AECDebugCapture | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.dumpDebugCapture === 'function') {
+        return this.nativeInstance.dumpDebugCapture() as AECDebugCapture | null;
+      }
+      return null;
+    } catch (error) {
+      logger.warn('Failed to dump AEC debug capture', { error });
+      return null;
+    }
+  }
+
+  /**
+   * Receive converged / diverged / echoDetected transitions, e.g. to show
+   * "echo cancellation active" only once the canceller has actually
+   * converged. Called right after the processing call that produced the
+   * transition, so it must not process, reconfigure or destroy this
+   * processor (those throw ERR_AEC_REENTRANT_CALL). Pass null to stop.
+   */
+  public setEventCallback(callback: ((event: AECStateEvent) => void) | null): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setEventCallback === 'function') {
+        const guarded = callback
+          ? (event: AECStateEvent) => {
+              try {
+                callback(event);
+              } catch (error) {
+                logger.error('AEC event callback threw', { error });
+              }
+            }
+          : null;
+        this.nativeInstance.setEventCallback(guarded);
+      }
+    } catch (error) {
+      logger.warn('Failed to set AEC event callback', { error });
+    }
+  }
+
+  /**
+   * When the echo path changes abruptly (laptop moved, headphones plugged in)
+   * the converged filter leaks echo until it re-adapts. Path changes are always
+   * counted in pathChangesDetected and logged natively; when enabled they also
+   * reset adaptation so the canceller re-converges from scratch.
+   */
+  public setAutoReconvergeOnPathChange(enabled: boolean): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setAutoReconvergeOnPathChange === 'function') {
+        this.nativeInstance.setAutoReconvergeOnPathChange(enabled);
+        logger.info('AEC auto-reconverge on path change set', { enabled });
+      }
+    } catch (error) {
+      logger.warn('Failed to set AEC auto-reconverge on path change', { error });
+    }
+  }
+
+  /**
+   * Configure overload detection: overloaded is flagged once the ~5 s
+   * real-time factor exceeds rtfThreshold for consecutiveChunks calls, and
+   * clears after as many calls below half the threshold. With autoBypass the
+   * mic passes through unprocessed while overloaded. Transitions are logged
+   * natively (see setLogger). consecutiveChunks 0 disables detection.
+   */
+  public setOverloadDetection(rtfThreshold = 0.8, consecutiveChunks = 50, autoBypass = this.config.autoBypassOnOverload): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setOverloadDetection === 'function') {
+        this.nativeInstance.setOverloadDetection(rtfThreshold, consecutiveChunks, autoBypass);
+        this.config = { ...this.config, autoBypassOnOverload: autoBypass };
+        logger.info('AEC overload detection set', { rtfThreshold, consecutiveChunks, autoBypass });
+      }
+    } catch (error) {
+      logger.warn('Failed to set AEC overload detection', { error });
+    }
+  }
+
+  /**
+   * Forward native log records at or above level (default 'warn') to callback,
+   * e.g. render overflow drains and underruns at 'debug'. Records are delivered
+   * asynchronously on the event loop and rate limited natively; excess records
+   * are summarized as a single warning. Pass null to stop forwarding.
+   */
+  public setLogger(callback: ((level: AECLogLevel, message: string) => void) | null, level: AECLogLevel = 'warn'): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setLogger === 'function') {
+        this.nativeInstance.setLogger(callback, level);
+        logger.info('Native AEC logger set', { enabled: !!callback, level });
+      }
+    } catch (error) {
+      logger.warn('Failed to set native AEC logger', { error });
+    }
+  }
+
+  /**
+   * Receive processed capture audio as fixed frameSize frames, whatever chunk
+   * sizes the process* calls and processAsync are fed. Frames arrive
+   * asynchronously on the event loop; a callback that falls about 640 ms
+   * behind loses new frames (counted in droppedFrames) rather than stalling
+   * processing. Pass null to detach.
+   */
+  public setOutputCallback(callback: ((frame: Float32Array, stats: AECOutputFrameStats) => void) | null): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setOutputCallback === 'function') {
+        this.nativeInstance.setOutputCallback(callback);
+        logger.info('AEC output callback set', { enabled: !!callback });
+      }
+    } catch (error) {
+      logger.warn('Failed to set AEC output callback', { error });
+    }
+  }
+
+  /**
+   * Invoke callback once whenever processCaptureAudio overruns its real-time
+   * budget for consecutiveChunks calls in a row, e.g. to fall back to a shorter
+   * filter. The watchdog re-arms once a call is back within budget. Runs
+   * inside the processing call, so defer any reconfiguration (e.g. with
+   * setImmediate); direct calls throw ERR_AEC_REENTRANT_CALL. Pass callback
+   * null (or consecutiveChunks 0) to disable.
+   */
+  public setRealtimeWatchdog(consecutiveChunks: number, callback: ((event: AECWatchdogEvent) => void) | null): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setRealtimeWatchdog === 'function') {
+        const guarded = callback
+          ? (event: AECWatchdogEvent) => {
+              try {
+                callback(event);
+              } catch (error) {
+                logger.error('Realtime watchdog callback threw', { error });
+              }
+            }
+          : null;
+        this.nativeInstance.setRealtimeWatchdog(consecutiveChunks, guarded);
+        logger.info('Realtime watchdog set', { consecutiveChunks, enabled: !!callback });
+      }
+    } catch (error) {
+      logger.warn('Failed to set realtime watchdog', { error });
+    }
+  }
+
+  /**
+   * Correlate recent mic audio against recent render audio to verify the two
+   * streams are the echo pair the canceller expects. Costs up to tens of ms; call it on
+   * demand, not per chunk.
+   */
+  public getCrossCorrelation(): AECCrossCorrelation | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.getCrossCorrelation === 'function') {
+        return this.nativeInstance.getCrossCorrelation() as AECCrossCorrelation;
+      }
+      return null;
+    } catch (error) {
+      logger.warn('Failed to compute mic/render cross-correlation', { error });
+      return null;
+    }
+  }
+
+  /**
+   * Estimate the echo-path delay by correlating recent mic audio against the
+   * reference (lags up to maxDelayMs, at most 500 ms) and, on the nlms engine,
+   * apply it, for when the delay isn't known. Play audio through the speakers
+   * first: nothing changes unless both signals carry sound. Costs about as much
+   * as getCrossCorrelation.
+   */
+  public calibrateDelay(maxDelayMs = 500): AECDelayEstimate | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.calibrateDelay === 'function') {
+        const estimate = this.nativeInstance.calibrateDelay(maxDelayMs) as AECDelayEstimate;
+        logger.info('AEC delay calibration', { ...estimate });
+        return estimate;
+      }
+      return null;
+    } catch (error) {
+      logger.warn('Failed to calibrate AEC reference delay', { error });
+      return null;
+    }
+  }
+
+  /**
+   * Reset AEC audio state (useful between calls or for troubleshooting): buffered
+   * audio is dropped and the canceller re-converges. Metrics are kept unless
+   * resetMetrics is true; use resetMetrics() alone to clear counters without
+   * touching audio state.
+   */
+  public reset(resetMetrics = false): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.resetAEC === 'function') {
+        this.nativeInstance.resetAEC(resetMetrics);
+      }
+      this.renderBufferQueue = [];
+      logger.info('AEC state reset', { resetMetrics });
+    } catch (error) {
+      logger.warn('Failed to reset AEC state', { error });
     }
+  }
 
-    if (!renderBuffer || renderBuffer.length === 0) {
-      logger.warn('Render buffer is empty, skipping');
-      return false;
+  /**
+   * Clean up and destroy the AEC processor. Call when no longer needed.
+   */
+  public destroy(): void {
+    if (this.isDestroyed) {
+      logger.warn('AEC processor already destroyed');
+      return;
     }
 
     try {
-      // Queue the render buffer for processing
-      // The native module will process it internally and use it as reference
-      // for the next processCaptureAudio call
-      if (this.nativeInstance && typeof this.nativeInstance.processRenderAudio === 'function') {
-        this.nativeInstance.processRenderAudio(renderBuffer);
+      // Stop native mic capture if running
+      if (this.micCapturing) {
+        this.stopMicrophoneCapture();
       }
 
-      // Keep a reference for metrics if needed
-      if (this.renderBufferQueue.length >= this.MAX_RENDER_QUEUE) {
-        this.renderBufferQueue.shift();
+      // Free the native AEC engine eagerly rather than waiting for GC
+      if (this.nativeInstance && typeof this.nativeInstance.destroy === 'function') {
+        this.nativeInstance.destroy();
       }
-      this.renderBufferQueue.push(renderBuffer);
 
-      return true;
+      this.renderBufferQueue = [];
+      this.isInitialized = false;
+      this.isDestroyed = true;
+      this.micAudioCallback = undefined;
+      this.nativeInstance = null;
+      this.nativeModule = null;
+
+      logger.info('AEC processor destroyed');
     } catch (error) {
       const message = error instanceof Error ? error.message : String(error);
-      logger.error('Error processing render audio', { error: message });
-      return false;
+      logger.error('Error destroying AEC processor', { error: message });
     }
   }
 
   /**
-   * Process capture (microphone) audio through the AEC to remove echo.
-   * Returns echo-cancelled audio, or null on error.
+   * Check if the processor is initialized and ready to use
    */
-  public processCaptureAudio(captureBuffer: Float32Array): Float32Array | null {
-    if (this.isDestroyed) {
-      logger.warn('Cannot process capture audio: AEC processor is destroyed');
-      return null;
-    }
+  public isReady(): boolean {
+    return this.isInitialized && !this.isDestroyed;
+  }
 
-    if (!this.isInitialized) {
-      logger.warn('Cannot process capture audio: AEC not initialized');
-      return null;
-    }
+  /**
+   * Get the current configuration
+   */
+  public getConfig(): Readonly<ResolvedAECConfig> {
+    return { ...this.config };
+  }
 
-    if (!captureBuffer || captureBuffer.length === 0) {
-      logger.warn('Capture buffer is empty, returning null');
-      return null;
+  /**
+   * Switch suppression preset at runtime. The echo canceller is rebuilt and
+   * re-converges; explicit filter length / noise suppression overrides still win.
+   */
+  public applyPreset(preset: AECPreset): boolean {
+    if (!this.isInitialized || this.isDestroyed) {
+      return false;
     }
 
     try {
-      // Call the native module to process capture audio and return echo-cancelled result
-      if (this.nativeInstance && typeof this.nativeInstance.processCaptureAudio === 'function') {
-        const result = this.nativeInstance.processCaptureAudio(captureBuffer);
-        return result as Float32Array;
+      if (this.nativeInstance && typeof this.nativeInstance.applyPreset === 'function') {
+        this.nativeInstance.applyPreset(preset);
+        this.config = { ...this.config, preset };
+        logger.info('AEC preset applied', { preset });
+        return true;
       }
-
-      logger.warn('processCaptureAudio not available in native module');
-      return null;
+      return false;
     } catch (error) {
       const message = error instanceof Error ? error.message : String(error);
-      logger.error('Error processing capture audio', { error: message });
-      return null;
+      logger.warn('Failed to apply AEC preset', { preset, error: message });
+      return false;
     }
   }
 
   /**
-   * Start native microphone capture using AudioUnit.
-   * Timestamps use the same monotonic clock as system audio for AEC sync.
+   * Choose what processCaptureAudio returns for an empty buffer: 'empty'
+   * (null, the default), or one AEC frame of 'silence' or 'comfortNoise' at
+   * the tracked mic noise floor, so downstream consumers keep receiving audio
+   * through a capture stall. Empty calls are counted in emptyInputCalls either way.
    */
-  public startMicrophoneCapture(callback: (samples: Float32Array, timestamp: number) => void): boolean {
-    if (this.isDestroyed) {
-      logger.warn('Cannot start mic capture: AEC processor is destroyed');
+  public setEmptyInputMode(mode: AECEmptyInputMode): boolean {
+    if (!this.isInitialized || this.isDestroyed) {
       return false;
     }
 
-    if (!this.isInitialized) {
-      logger.warn('Cannot start mic capture: AEC not initialized');
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setEmptyInputMode === 'function') {
+        this.nativeInstance.setEmptyInputMode(mode);
+        logger.info('AEC empty input mode set', { mode });
+        return true;
+      }
+      return false;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to set AEC empty input mode', { mode, error: message });
       return false;
     }
+  }
 
-    if (this.micCapturing) {
-      logger.warn('Microphone capture already running');
-      return true;
-    }
-
-    if (!callback || typeof callback !== 'function') {
-      logger.error('Invalid callback provided to startMicrophoneCapture');
+  /**
+   * Choose what the canceller runs against when a capture chunk needs more
+   * reference than has been delivered: 'zeros' (the default) treats the
+   * missing reference as silence, 'hold' replays the last reference frame it
+   * consumed. Hold keeps cancelling through a brief dropout of steady
+   * playback; zeros suits a jittery feed whose late audio still arrives, where
+   * a replayed frame would be cancelled twice. Underruns are counted per
+   * strategy in underrunZeroFillCount and underrunHoldCount.
+   */
+  public setUnderrunStrategy(strategy: AECUnderrunStrategy): boolean {
+    if (!this.isInitialized || this.isDestroyed) {
       return false;
     }
 
     try {
-      this.micAudioCallback = callback;
-
-      if (this.nativeInstance && typeof this.nativeInstance.startMicrophoneCapture === 'function') {
-        const success = this.nativeInstance.startMicrophoneCapture((samples: Float32Array, timestamp: number) => {
-          if (this.micAudioCallback) {
-            this.micAudioCallback(samples, timestamp);
-          }
-        });
-
-        if (success) {
-          this.micCapturing = true;
-          logger.info('Native microphone capture started');
-          return true;
-        } else {
-          logger.error('Native module failed to start microphone capture');
-          this.micAudioCallback = undefined;
-          return false;
-        }
-      } else {
-        logger.error('startMicrophoneCapture not available in native module');
-        this.micAudioCallback = undefined;
-        return false;
+      if (this.nativeInstance && typeof this.nativeInstance.setUnderrunStrategy === 'function') {
+        this.nativeInstance.setUnderrunStrategy(strategy);
+        logger.info('AEC underrun strategy set', { strategy });
+        return true;
       }
+      return false;
     } catch (error) {
-      // Better error extraction for debugging
-      let errorMsg = 'Unknown error';
-      let errorStack = '';
-      if (error instanceof Error) {
-        errorMsg = error.message;
-        errorStack = error.stack || '';
-      } else if (typeof error === 'object' && error !== null) {
-        errorMsg = JSON.stringify(error);
-      } else {
-        errorMsg = String(error);
-      }
-      
-      logger.error('Error starting native microphone capture', { 
-        error: errorMsg,
-        stack: errorStack,
-        errorType: typeof error,
-        errorKeys: error && typeof error === 'object' ? Object.keys(error) : []
-      });
-      this.micAudioCallback = undefined;
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to set AEC underrun strategy', { strategy, error: message });
       return false;
     }
   }
 
   /**
-   * Stop native microphone capture.
+   * Ask the OS to schedule the calling thread as audio work: 'high' or
+   * 'realtime' raise it, 'normal' restores the default. This affects whichever
+   * thread calls it (the main process thread, or a worker that owns this
+   * instance), so call it from the thread that drives processing. Returns
+   * whether the hint was applied; false where the platform has no equivalent.
    */
-  public stopMicrophoneCapture(): boolean {
-    if (!this.micCapturing) {
-      return true;
+  public setThreadPriority(priority: AECThreadPriority): boolean {
+    if (!this.isInitialized || this.isDestroyed) {
+      return false;
     }
 
     try {
-      if (this.nativeInstance && typeof this.nativeInstance.stopMicrophoneCapture === 'function') {
-        const success = this.nativeInstance.stopMicrophoneCapture();
-        
-        if (success) {
-          this.micCapturing = false;
-          this.micAudioCallback = undefined;
-          logger.info('Native microphone capture stopped');
-          return true;
+      if (this.nativeInstance && typeof this.nativeInstance.setThreadPriority === 'function') {
+        const applied = this.nativeInstance.setThreadPriority(priority) === true;
+        if (applied) {
+          logger.info('AEC thread priority applied', { priority });
         } else {
-          logger.warn('Native module failed to stop microphone capture');
-          return false;
+          logger.warn('AEC thread priority hint not applied', { priority });
         }
-      } else {
-        logger.warn('stopMicrophoneCapture not available in native module');
-        return false;
+        return applied;
       }
+      return false;
     } catch (error) {
       const message = error instanceof Error ? error.message : String(error);
-      logger.error('Error stopping native microphone capture', { error: message });
+      logger.warn('Failed to set AEC thread priority', { priority, error: message });
       return false;
     }
   }
 
   /**
-   * Check if native microphone capture is running.
+   * Testing only: make processing reproducible for golden-file tests. Seeds
+   * the native comfort noise and the toInt16 dither from seed, and has native
+   * timing metrics report a fixed value instead of the measured time.
+   * Disabling restores measured timing and unseeded dither.
    */
-  public isMicrophoneCapturing(): boolean {
-    return this.micCapturing;
+  public setDeterministic(enabled: boolean, seed = 0): void {
+    if (!this.isInitialized || this.isDestroyed) {
+      return;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.setDeterministic === 'function') {
+        this.nativeInstance.setDeterministic(enabled, seed);
+      }
+      this.ditherRandom = enabled ? createSeededRandom(seed) : Math.random;
+      logger.info('AEC deterministic mode set', { enabled, seed });
+    } catch (error) {
+      logger.warn('Failed to set AEC deterministic mode', { error });
+    }
   }
 
   /**
-   * Get current AEC metrics (ERLE, residual echo level, convergence status).
+   * Change the adaptive filter length (in 64-sample blocks, 1..32) at runtime.
+   * Longer filters cover longer echo paths at more CPU cost; the canceller is
+   * rebuilt between chunks and re-converges.
    */
-  public getMetrics(): AECMetrics {
+  public setFilterLength(blocks: number): boolean {
     if (!this.isInitialized || this.isDestroyed) {
-      return {};
+      return false;
     }
 
     try {
-      if (this.nativeInstance && typeof this.nativeInstance.getMetrics === 'function') {
-        const m = this.nativeInstance.getMetrics() as any;
-        // Map native keys to AECMetrics interface
-        const mapped: AECMetrics = {
-          erle: typeof m.echoReturnLossEnhancement === 'number' ? m.echoReturnLossEnhancement : undefined,
-          rerl: typeof m.echoReturnLoss === 'number' ? m.echoReturnLoss : undefined,
-          renderDelayMs: typeof m.renderDelayMs === 'number' ? m.renderDelayMs : undefined,
-          converged: typeof m.aecConverged === 'boolean' ? m.aecConverged : undefined,
-          echoPower: typeof m.rmsLevel === 'number' ? m.rmsLevel : undefined,
-          residualEchoLevel: typeof m.peakLevel === 'number' ? m.peakLevel : undefined,
-        };
-        return mapped;
+      if (this.nativeInstance && typeof this.nativeInstance.setFilterLength === 'function') {
+        this.nativeInstance.setFilterLength(blocks);
+        this.config = { ...this.config, filterLengthBlocks: blocks };
+        logger.info('AEC filter length set', { blocks });
+        return true;
       }
-      return {};
+      return false;
     } catch (error) {
       const message = error instanceof Error ? error.message : String(error);
-      logger.warn('Failed to get AEC metrics', { error: message });
-      return {};
+      logger.warn('Failed to set AEC filter length', { blocks, error: message });
+      return false;
     }
   }
 
   /**
-   * Check if headphones are currently connected.
+   * Change the longest capture chunk accepted (0 removes the limit). Longer
+   * chunks throw ERR_AEC_BUFFER_LENGTH rather than blocking the audio thread
+   * for an unbounded time.
    */
-  public isHeadphonesConnected(): boolean {
+  public setMaxInputSamples(samples: number): boolean {
     if (!this.isInitialized || this.isDestroyed) {
       return false;
     }
 
     try {
-      if (this.nativeInstance && typeof this.nativeInstance.isHeadphonesConnected === 'function') {
-        return this.nativeInstance.isHeadphonesConnected() as boolean;
+      if (this.nativeInstance && typeof this.nativeInstance.setMaxInputSamples === 'function') {
+        this.nativeInstance.setMaxInputSamples(samples);
+        this.config = { ...this.config, maxInputSamples: samples };
+        logger.info('AEC max input samples set', { samples });
+        return true;
       }
       return false;
     } catch (error) {
-      logger.warn('Failed to check headphone status', { error });
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to set AEC max input samples', { samples, error: message });
       return false;
     }
   }
 
   /**
-   * Enable or disable echo cancellation at runtime.
+   * Change any creation options in place, keeping metrics, buffered reference
+   * audio and registered callbacks. Options the canceller can take live apply
+   * from the next chunk; sampleRate, frameDurationMs, engine, preset,
+   * filterLengthBlocks and enableAgc rebuild it, as reported in rebuildFields. Invalid
+   * options leave everything unchanged and return null.
    */
-  public setEchoCancellationEnabled(enabled: boolean): void {
+  public reconfigure(options: Partial<AECConfig>): AECReconfigureResult | null {
     if (!this.isInitialized || this.isDestroyed) {
-      return;
+      return null;
     }
 
     try {
-      if (this.nativeInstance && typeof this.nativeInstance.setEchoCancellationEnabled === 'function') {
-        this.nativeInstance.setEchoCancellationEnabled(enabled);
-        logger.info('AEC enabled set to:', { enabled });
+      if (this.nativeInstance && typeof this.nativeInstance.reconfigure === 'function') {
+        // dither and disableAecOnHeadphones are handled here, not natively
+        const nativeOptions: Record<string, unknown> = { ...options };
+        delete nativeOptions.dither;
+        delete nativeOptions.disableAecOnHeadphones;
+        const result = this.nativeInstance.reconfigure(nativeOptions) as AECReconfigureResult;
+        this.config = { ...this.config, ...options };
+        logger.info('AEC reconfigured', { options, rebuildFields: result.rebuildFields });
+        return result;
       }
+
+      logger.warn('reconfigure not available in native module');
+      return null;
     } catch (error) {
-      logger.warn('Failed to set AEC enabled state', { error });
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to reconfigure AEC', { error: message });
+      return null;
     }
   }
 
   /**
-   * Reset AEC state (useful between calls or for troubleshooting)
+   * Reconfigure for a new input sample rate. The frame size is recomputed from
+   * frameDurationMs, and the canceller re-converges.
    */
-  public reset(): void {
+  public setSampleRate(sampleRate: number): boolean {
     if (!this.isInitialized || this.isDestroyed) {
-      return;
+      return false;
     }
 
     try {
-      if (this.nativeInstance && typeof this.nativeInstance.resetAEC === 'function') {
-        this.nativeInstance.resetAEC();
+      if (this.nativeInstance && typeof this.nativeInstance.setSampleRate === 'function') {
+        this.nativeInstance.setSampleRate(sampleRate);
+        this.config = { ...this.config, sampleRate };
+        logger.info('AEC sample rate set', { sampleRate });
+        return true;
       }
-      this.renderBufferQueue = [];
-      logger.info('AEC state reset');
+      return false;
     } catch (error) {
-      logger.warn('Failed to reset AEC state', { error });
+      const message = error instanceof Error ? error.message : String(error);
+      logger.warn('Failed to set AEC sample rate', { sampleRate, error: message });
+      return false;
     }
   }
 
   /**
-   * Clean up and destroy the AEC processor. Call when no longer needed.
+   * Replace the set of rates setSampleRate accepts (e.g. add 22050 after
+   * validating it on the target hardware). The current rate keeps running.
    */
-  public destroy(): void {
-    if (this.isDestroyed) {
-      logger.warn('AEC processor already destroyed');
-      return;
+  public setSupportedSampleRates(rates: number[]): boolean {
+    if (!this.isInitialized || this.isDestroyed) {
+      return false;
     }
 
     try {
-      // Stop native mic capture if running
-      if (this.micCapturing) {
-        this.stopMicrophoneCapture();
+      if (this.nativeInstance && typeof this.nativeInstance.setSupportedSampleRates === 'function') {
+        this.nativeInstance.setSupportedSampleRates(rates);
+        this.config = { ...this.config, supportedSampleRates: [...rates] };
+        logger.info('AEC supported sample rates set', { rates });
+        return true;
       }
-
-      // Native instance will be GC'd; just drop references
-      this.renderBufferQueue = [];
-      this.isInitialized = false;
-      this.isDestroyed = true;
-      this.micAudioCallback = undefined;
-      this.nativeInstance = null;
-      this.nativeModule = null;
-
-      logger.info('AEC processor destroyed');
+      return false;
     } catch (error) {
       const message = error instanceof Error ? error.message : String(error);
-      logger.error('Error destroying AEC processor', { error: message });
+      logger.warn('Failed to set AEC supported sample rates', { rates, error: message });
+      return false;
     }
   }
 
   /**
-   * Check if the processor is initialized and ready to use
+   * Get the effective configuration the native module is running with,
+   * reflecting any runtime setters called since construction.
    */
-  public isReady(): boolean {
-    return this.isInitialized && !this.isDestroyed;
+  public getNativeConfig(): AECNativeConfig | null {
+    if (!this.isInitialized || this.isDestroyed) {
+      return null;
+    }
+
+    try {
+      if (this.nativeInstance && typeof this.nativeInstance.getConfig === 'function') {
+        return this.nativeInstance.getConfig() as AECNativeConfig;
+      }
+      return null;
+    } catch (error) {
+      logger.warn('Failed to get native AEC config', { error });
+      return null;
+    }
   }
+}
 
+export interface Int16ConversionOptions {
   /**
-   * Get the current configuration
+   * Add triangular-PDF dither (+/-1 LSB) before rounding, which decorrelates
+   * quantization error from quiet signals (default: true)
    */
-  public getConfig(): Readonly<Required<AECConfig>> {
-    return { ...this.config };
-  }
+  dither?: boolean;
+
+  /** Uniform [0, 1) source for the dither; pass a seeded generator for reproducible output */
+  random?: () => number;
+}
+
+/**
+ * The AECErrorCode of an error thrown by the native module, or undefined for
+ * any other error
+ */
+export function getAECErrorCode(error: unknown): AECErrorCode | undefined {
+  const code = (error as { code?: unknown } | null)?.code;
+  return typeof code === 'string' && code.startsWith('ERR_AEC_') ? (code as AECErrorCode) : undefined;
+}
+
+/**
+ * Deterministic uniform [0, 1) generator (mulberry32) for reproducible dither
+ */
+export function createSeededRandom(seed: number): () => number {
+  let state = seed >>> 0;
+  return () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
 }
 
 /**
  * Convert Float32Array audio samples to Int16Array (16-bit PCM).
  */
-export function float32ToInt16Array(float32Samples: Float32Array): Int16Array {
+export function float32ToInt16Array(
+  float32Samples: Float32Array,
+  options: Int16ConversionOptions = {}
+): Int16Array {
+  const { dither = true, random = Math.random } = options;
   const int16Samples = new Int16Array(float32Samples.length);
   for (let i = 0; i < float32Samples.length; i++) {
     const sample = Math.max(-1, Math.min(1, float32Samples[i]));
-    int16Samples[i] = sample < 0 ? sample * 0x8000 : sample * 0x7fff;
+    const scaled = sample < 0 ? sample * 0x8000 : sample * 0x7fff;
+    if (dither) {
+      const noise = random() - random();
+      int16Samples[i] = Math.max(-0x8000, Math.min(0x7fff, Math.round(scaled + noise)));
+    } else {
+      int16Samples[i] = scaled;
+    }
   }
   return int16Samples;
 }
@@ -542,7 +3229,10 @@ export function float32ToInt16Array(float32Samples: Float32Array): Int16Array {
 /**
  * Convert Float32Array audio samples to ArrayBuffer in Int16 format.
  */
-export function float32ToInt16Buffer(float32Samples: Float32Array): ArrayBuffer {
-  const int16Samples = float32ToInt16Array(float32Samples);
+export function float32ToInt16Buffer(
+  float32Samples: Float32Array,
+  options: Int16ConversionOptions = {}
+): ArrayBuffer {
+  const int16Samples = float32ToInt16Array(float32Samples, options);
   return int16Samples.buffer as ArrayBuffer;
 }
\ No newline at end of file
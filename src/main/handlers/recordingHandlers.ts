@@ -5,7 +5,7 @@ import { createLogger } from '../core/logger';
 import { createTranscriptionProvider, ITranscriptionProvider } from '../services/transcription';
 import { SystemAudioService } from '../services/SystemAudioService';
 import { CalloutService } from '../services/CalloutService';
-import { AECProcessor } from '../audio/native/AECProcessor';
+import { AECProcessor, float32ToInt16Array, getAECErrorCode } from '../audio/native/AECProcessor';
 import { AECSync } from '../audio/AECSync';
 import { showCalloutWindow } from '../windows/calloutWindow';
 import { AUDIO_CONFIG, matchesQuestionPattern } from '../config/constants';
@@ -195,30 +195,38 @@ export function registerRecordingHandlers(
                   // Process mic audio through AEC with synchronized timestamps
                   let cleanFloat32: Float32Array | null = null;
 
-                  if (aecSync) {
-                    // Use synchronized AEC processing with native timestamp!
-                    cleanFloat32 = aecSync.processCaptureWithSync(samples, timestamp);
-                    
-                    // Log sync stats occasionally
-                    if (micAudioDataCount % 100 === 0) {
-                      const stats = aecSync.getStats();
-                      logger.debug('AEC sync performance', {
-                        syncRate: `${stats.syncRate.toFixed(1)}%`,
-                        bufferSize: stats.bufferSize,
-                        packet: micAudioDataCount
+                  try {
+                    if (aecSync) {
+                      // Use synchronized AEC processing with native timestamp!
+                      cleanFloat32 = aecSync.processCaptureWithSync(samples, timestamp);
+                      
+                      // Log sync stats occasionally
+                      if (micAudioDataCount % 100 === 0) {
+                        const stats = aecSync.getStats();
+                        logger.debug('AEC sync performance', {
+                          syncRate: `${stats.syncRate.toFixed(1)}%`,
+                          bufferSize: stats.bufferSize,
+                          packet: micAudioDataCount
+                        });
+                      }
+                    } else if (aecProcessor && aecProcessor.isReady()) {
+                      // Fallback: direct AEC without sync
+                      cleanFloat32 = aecProcessor.processCaptureAudio(samples);
+                    }
+                  } catch (error) {
+                    // Coded native failure: log it and fall through to raw mic audio below
+                    if (micAudioDataCount % 100 === 1) {
+                      logger.warn('AEC processing failed', {
+                        code: getAECErrorCode(error),
+                        error: error instanceof Error ? error.message : String(error),
                       });
                     }
-                  } else if (aecProcessor && aecProcessor.isReady()) {
-                    // Fallback: direct AEC without sync
-                    cleanFloat32 = aecProcessor.processCaptureAudio(samples);
+                    cleanFloat32 = null;
                   }
 
                   if (cleanFloat32 && cleanFloat32.length > 0) {
-                    // Convert echo-cancelled audio to Int16
-                    const cleanInt16 = new Int16Array(cleanFloat32.length);
-                    for (let i = 0; i < cleanFloat32.length; i++) {
-                      cleanInt16[i] = Math.max(-32768, Math.min(32767, cleanFloat32[i] * 32768));
-                    }
+                    // Convert echo-cancelled audio to Int16 (dithered, it has been through float gain stages)
+                    const cleanInt16 = aecProcessor ? aecProcessor.toInt16(cleanFloat32) : float32ToInt16Array(cleanFloat32);
                     
                     // Buffer the audio
                     const newBuffer = new Int16Array(micAudioBuffer.length + cleanInt16.length);
@@ -0,0 +1,3049 @@
+#!/usr/bin/env node
+
+// Offline checks for the AEC processor in audio_capture_native.node. Every
+// test runs on synthetic audio, so no devices or permissions are needed.
+//
+//   node test-aec-processor.js [filter]   (filter: run tests whose name contains it)
+
+const assert = require('assert');
+
+const SAMPLE_RATE = 48000;
+const FRAME = 480;  // 10 ms at 48 kHz
+
+let native;
+try {
+    native = require('./build/Release/audio_capture_native.node');
+} catch (err) {
+    console.error('❌ ERROR:', err.message);
+    console.error('');
+    console.error('Build troubleshooting:');
+    console.error('  1. npm run setup:webrtc');
+    console.error('  2. npm run build:native');
+    console.error('  3. ls -lh build/Release/audio_capture_native.node');
+    process.exit(1);
+}
+
+const tests = [];
+
+function test(name, fn) {
+    tests.push({ name, fn });
+}
+
+// ---------------------------------------------------------------------------
+// Signal helpers
+// ---------------------------------------------------------------------------
+
+function sine(frequency, amplitude, numSamples, sampleRate = SAMPLE_RATE) {
+    const out = new Float32Array(numSamples);
+    for (let i = 0; i < numSamples; i++) {
+        out[i] = amplitude * Math.sin(2 * Math.PI * frequency * i / sampleRate);
+    }
+    return out;
+}
+
+function rms(samples) {
+    let sum = 0;
+    for (const s of samples) sum += s * s;
+    return samples.length > 0 ? Math.sqrt(sum / samples.length) : 0;
+}
+
+function dbfs(linear) {
+    return 20 * Math.log10(linear);
+}
+
+function assertNear(actual, expected, tolerance, what) {
+    assert.ok(Math.abs(actual - expected) <= tolerance,
+        `${what}: expected ${expected} ± ${tolerance}, got ${actual}`);
+}
+
+// Split a signal into consecutive chunks of chunkSize samples
+function chunks(samples, chunkSize = FRAME) {
+    const out = [];
+    for (let i = 0; i < samples.length; i += chunkSize) {
+        out.push(samples.subarray(i, Math.min(i + chunkSize, samples.length)));
+    }
+    return out;
+}
+
+// Pass-through processor: no cancellation or suppression, so the meters see
+// exactly what is fed
+function createMeteringProcessor(options = {}) {
+    return new native.AudioCaptureAddon({ enableAec: false, enableNs: false, levelSmoothingMs: 0, ...options });
+}
+
+// Seeded PRNG (mulberry32) so noise-driven tests are reproducible
+function makeRng(seed = 1) {
+    let state = seed >>> 0;
+    return () => {
+        state = (state + 0x6d2b79f5) >>> 0;
+        let t = state;
+        t = Math.imul(t ^ (t >>> 15), t | 1);
+        t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+        return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+    };
+}
+
+// Low-passed white noise, a rough stand-in for speech energy, at rmsLevel
+function speechNoise(rmsLevel, numSamples, rng = makeRng()) {
+    const out = new Float32Array(numSamples);
+    let state = 0;
+    for (let i = 0; i < numSamples; i++) {
+        state += 0.3 * ((rng() * 2 - 1) - state);
+        out[i] = state;
+    }
+    const scale = rmsLevel / rms(out);
+    for (let i = 0; i < numSamples; i++) out[i] *= scale;
+    return out;
+}
+
+// Mic signal for a pure echo path: the reference delayed by `delay` samples
+// and scaled by `gain`, with no near-end talker
+function echo(ref, delay, gain) {
+    const mic = new Float32Array(ref.length);
+    for (let i = delay; i < ref.length; i++) {
+        mic[i] = gain * ref[i - delay];
+    }
+    return mic;
+}
+
+// Feed each reference chunk, then the matching mic chunk, the way the app
+// interleaves the two streams. Returns the concatenated output.
+function processPair(aec, ref, mic, chunkSize = FRAME) {
+    const out = new Float32Array(mic.length);
+    for (let i = 0; i < mic.length; i += chunkSize) {
+        const end = Math.min(i + chunkSize, mic.length);
+        aec.processRenderAudio(ref.subarray(i, end));
+        out.set(aec.processCaptureAudio(mic.subarray(i, end)), i);
+    }
+    return out;
+}
+
+// Echo attenuation in dB between a mic span and its output
+function attenuationDb(input, output) {
+    return 20 * Math.log10((rms(input) + 1e-12) / (rms(output) + 1e-12));
+}
+
+// Zero every sample in [start, end) of each signal
+function silence(signals, start, end) {
+    for (const signal of signals) signal.fill(0, start, end);
+}
+
+function assertFinite(samples, what) {
+    for (let i = 0; i < samples.length; i++) {
+        assert.ok(Number.isFinite(samples[i]), `${what}: sample ${i} is ${samples[i]}`);
+    }
+}
+
+// Power spectrum |X[k]|^2 for k in [0, N/2] of a power-of-two length signal
+function powerSpectrum(samples) {
+    const n = samples.length;
+    const re = Float64Array.from(samples);
+    const im = new Float64Array(n);
+    for (let i = 1, j = 0; i < n; i++) {
+        let bit = n >> 1;
+        for (; j & bit; bit >>= 1) j ^= bit;
+        j ^= bit;
+        if (i < j) {
+            [re[i], re[j]] = [re[j], re[i]];
+        }
+    }
+    for (let size = 2; size <= n; size <<= 1) {
+        const angle = -2 * Math.PI / size;
+        for (let start = 0; start < n; start += size) {
+            for (let k = 0; k < size / 2; k++) {
+                const wr = Math.cos(angle * k);
+                const wi = Math.sin(angle * k);
+                const a = start + k;
+                const b = a + size / 2;
+                const tr = re[b] * wr - im[b] * wi;
+                const ti = re[b] * wi + im[b] * wr;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+    }
+    const power = new Float64Array(n / 2 + 1);
+    for (let k = 0; k <= n / 2; k++) power[k] = re[k] * re[k] + im[k] * im[k];
+    return power;
+}
+
+function median(values) {
+    const sorted = Array.from(values).sort((a, b) => a - b);
+    return sorted[Math.floor(sorted.length / 2)];
+}
+
+// The TypeScript wrapper, transpiled in memory (typescript is a devDependency)
+// with its Electron-side imports stubbed, for the helpers that need no app
+let wrapper;
+function loadWrapper() {
+    if (wrapper) return wrapper;
+    const fs = require('fs');
+    const path = require('path');
+    const ts = require('typescript');
+    const file = path.join(__dirname, 'src/main/audio/native/AECProcessor.ts');
+    const { outputText } = ts.transpileModule(fs.readFileSync(file, 'utf8'), {
+        compilerOptions: { module: ts.ModuleKind.CommonJS, target: ts.ScriptTarget.ES2020, esModuleInterop: true },
+    });
+    const quiet = () => {};
+    const stubs = {
+        bindings: () => native,
+        '@main/core/logger': { createLogger: () => ({ debug: quiet, info: quiet, warn: quiet, error: quiet }) },
+    };
+    const module = { exports: {} };
+    new Function('require', 'module', 'exports', outputText)(
+        (id) => stubs[id] || require(id), module, module.exports);
+    wrapper = module.exports;
+    return wrapper;
+}
+
+// Let queued native callbacks (logger, events, output frames) reach JS
+function settle(ms = 50) {
+    return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+test('level metering: sine RMS and peak dBFS', () => {
+    const aec = createMeteringProcessor();
+    for (const chunk of chunks(sine(1000, 0.5, SAMPLE_RATE / 10))) {
+        aec.processCaptureAudio(chunk);
+    }
+    const metrics = aec.getMetrics();
+    assertNear(metrics.inputRmsDbfs, dbfs(0.5 / Math.SQRT2), 0.1, 'inputRmsDbfs');
+    assertNear(metrics.inputPeakDbfs, dbfs(0.5), 0.1, 'inputPeakDbfs');
+    aec.destroy();
+});
+
+test('level metering: -20 dBFS sine', () => {
+    const aec = createMeteringProcessor();
+    const amplitude = Math.pow(10, -20 / 20) * Math.SQRT2;
+    for (const chunk of chunks(sine(440, amplitude, SAMPLE_RATE / 10))) {
+        aec.processCaptureAudio(chunk);
+    }
+    assertNear(aec.getMetrics().inputRmsDbfs, -20, 0.1, 'inputRmsDbfs');
+    aec.destroy();
+});
+
+test('level metering: levelSmoothingMs lags a step', () => {
+    const aec = createMeteringProcessor({ levelSmoothingMs: 300 });
+    aec.processCaptureAudio(sine(1000, 0.5, FRAME));
+    const afterOneChunk = aec.getMetrics().inputRmsDbfs;
+    assert.ok(afterOneChunk < dbfs(0.5 / Math.SQRT2) - 6,
+        `one 10 ms chunk should not reach the level with 300 ms smoothing (got ${afterOneChunk})`);
+    for (const chunk of chunks(sine(1000, 0.5, SAMPLE_RATE * 3))) {
+        aec.processCaptureAudio(chunk);
+    }
+    assertNear(aec.getMetrics().inputRmsDbfs, dbfs(0.5 / Math.SQRT2), 0.2, 'settled inputRmsDbfs');
+    aec.destroy();
+});
+
+test('peak hold: impulse holds for peakHoldMs then decays at peakDecayDbPerSec', () => {
+    const aec = createMeteringProcessor({ peakHoldMs: 100, peakDecayDbPerSec: 20 });
+    const impulse = new Float32Array(FRAME);
+    impulse[0] = 0.5;
+    const silence = new Float32Array(FRAME);
+
+    aec.processCaptureAudio(impulse);
+    assertNear(aec.getMetrics().inputPeakHoldDbfs, dbfs(0.5), 0.01, 'held peak after impulse');
+
+    // 100 ms of silence: still inside the hold
+    for (let i = 0; i < 10; i++) aec.processCaptureAudio(silence);
+    const metrics = aec.getMetrics();
+    assertNear(metrics.inputPeakHoldDbfs, dbfs(0.5), 0.01, 'held peak at the end of the hold');
+    assert.ok(metrics.inputPeakInstantDbfs < -90, 'instantaneous peak follows the silence');
+
+    // One more chunk: 10 ms of decay at 20 dB/s
+    aec.processCaptureAudio(silence);
+    assertNear(aec.getMetrics().inputPeakHoldDbfs, dbfs(0.5) - 0.2, 0.01, 'held peak 10 ms into the decay');
+
+    // Another 990 ms: 1 s of decay in total
+    for (let i = 0; i < 99; i++) aec.processCaptureAudio(silence);
+    assertNear(aec.getMetrics().inputPeakHoldDbfs, dbfs(0.5) - 20, 0.05, 'held peak 1 s into the decay');
+    aec.destroy();
+});
+
+test('peak hold: a louder peak restarts the hold', () => {
+    const aec = createMeteringProcessor({ peakHoldMs: 100, peakDecayDbPerSec: 20 });
+    const silence = new Float32Array(FRAME);
+    const quiet = new Float32Array(FRAME);
+    quiet[0] = 0.1;
+    const loud = new Float32Array(FRAME);
+    loud[0] = 0.8;
+
+    aec.processCaptureAudio(quiet);
+    for (let i = 0; i < 20; i++) aec.processCaptureAudio(silence);
+    assert.ok(aec.getMetrics().inputPeakHoldDbfs < dbfs(0.1), 'quiet peak has started decaying');
+    aec.processCaptureAudio(loud);
+    for (let i = 0; i < 10; i++) aec.processCaptureAudio(silence);
+    assertNear(aec.getMetrics().inputPeakHoldDbfs, dbfs(0.8), 0.01, 'loud peak held');
+    aec.destroy();
+});
+
+// Documented preset bundles (see ResolveTuning in aec_processor.cc)
+const PRESETS = {
+    gentle: {
+        filterLengthBlocks: 13, initialFilterLengthBlocks: 12, enrTransparentLf: 0.4, enrSuppressLf: 0.6,
+        enrTransparentHf: 0.1, enrSuppressHf: 0.15, maxGainDuringEcho: 1.0, noiseSuppressionLevel: 'low',
+    },
+    balanced: {
+        filterLengthBlocks: 13, initialFilterLengthBlocks: 12, enrTransparentLf: 0.3, enrSuppressLf: 0.4,
+        enrTransparentHf: 0.07, enrSuppressHf: 0.1, maxGainDuringEcho: 1.0, noiseSuppressionLevel: 'moderate',
+    },
+    aggressive: {
+        filterLengthBlocks: 24, initialFilterLengthBlocks: 18, enrTransparentLf: 0.15, enrSuppressLf: 0.2,
+        enrTransparentHf: 0.04, enrSuppressHf: 0.05, maxGainDuringEcho: 0.01, noiseSuppressionLevel: 'moderate',
+    },
+};
+
+function assertTuning(config, expected) {
+    for (const [key, value] of Object.entries(expected)) {
+        if (typeof value === 'number') {
+            assertNear(config[key], value, 1e-6, key);
+        } else {
+            assert.strictEqual(config[key], value, key);
+        }
+    }
+}
+
+test('presets: each preset resolves to its documented bundle', () => {
+    for (const [preset, expected] of Object.entries(PRESETS)) {
+        const aec = new native.AudioCaptureAddon({ preset });
+        const config = aec.getConfig();
+        assert.strictEqual(config.preset, preset);
+        assertTuning(config, expected);
+        aec.destroy();
+    }
+});
+
+test('presets: aggressive is the default', () => {
+    const aec = new native.AudioCaptureAddon();
+    assert.strictEqual(aec.getConfig().preset, 'aggressive');
+    assertTuning(aec.getConfig(), PRESETS.aggressive);
+    aec.destroy();
+});
+
+test('presets: explicit overrides win over the preset', () => {
+    const aec = new native.AudioCaptureAddon({ preset: 'gentle', filterLengthBlocks: 8, noiseSuppressionLevel: 'high' });
+    const config = aec.getConfig();
+    assert.strictEqual(config.preset, 'gentle');
+    assertTuning(config, {
+        ...PRESETS.gentle, filterLengthBlocks: 8, initialFilterLengthBlocks: 8, noiseSuppressionLevel: 'high',
+    });
+    aec.destroy();
+});
+
+test('presets: applyPreset switches the bundle at runtime', () => {
+    const aec = new native.AudioCaptureAddon({ preset: 'aggressive' });
+    aec.applyPreset('balanced');
+    assert.strictEqual(aec.getConfig().preset, 'balanced');
+    assertTuning(aec.getConfig(), PRESETS.balanced);
+    assert.throws(() => aec.applyPreset('loud'), (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE');
+    assert.strictEqual(aec.getConfig().preset, 'balanced');
+    aec.destroy();
+});
+
+test('loudness normalization: converges on alternating loud/quiet noise without pumping', () => {
+    const aec = createMeteringProcessor();
+    aec.setLoudnessNormalization(true, -20, 12);
+    const rng = makeRng(7);
+    const gains = [];
+    const tail = [];
+    // 30 s of 500 ms segments alternating -26 and -38 dBFS RMS (average ≈ -28.7 dBFS)
+    for (let segment = 0; segment < 60; segment++) {
+        const level = Math.pow(10, (segment % 2 === 0 ? -26 : -38) / 20);
+        for (const chunk of chunks(speechNoise(level, SAMPLE_RATE / 2, rng))) {
+            const output = aec.processCaptureAudio(chunk);
+            if (segment >= 40) {
+                gains.push(aec.getMetrics().loudnessGainDb);
+                tail.push(...output);
+            }
+        }
+    }
+    const meanGain = gains.reduce((a, b) => a + b, 0) / gains.length;
+    assertNear(meanGain, 8.7, 1.5, 'steady-state loudnessGainDb');
+    const swing = Math.max(...gains) - Math.min(...gains);
+    assert.ok(swing < 2, `gain should not follow the 500 ms segments (swing ${swing.toFixed(2)} dB)`);
+    assertNear(dbfs(rms(tail)), -20, 1.5, 'normalized output level');
+    aec.destroy();
+});
+
+test('loudness normalization: gain is clamped to maxGainDb', () => {
+    const aec = createMeteringProcessor();
+    aec.setLoudnessNormalization(true, -20, 6);
+    for (const chunk of chunks(speechNoise(Math.pow(10, -40 / 20), SAMPLE_RATE * 10, makeRng(3)))) {
+        aec.processCaptureAudio(chunk);
+    }
+    assertNear(aec.getMetrics().loudnessGainDb, 6, 1e-3, 'loudnessGainDb at the ceiling');
+    aec.destroy();
+});
+
+test('loudness normalization: rejects non-numeric and out-of-range levels', () => {
+    const aec = createMeteringProcessor();
+    aec.setLoudnessNormalization(true, -23, 9);
+    for (const args of [[NaN, 12], [-20, NaN], [5, 12], [-80, 12], [-20, -1], [-20, 50], [-20, Infinity]]) {
+        assert.throws(() => aec.setLoudnessNormalization(true, ...args),
+            (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE', String(args));
+    }
+    for (const args of [['-20', 12], [-20, null]]) {
+        assert.throws(() => aec.setLoudnessNormalization(true, ...args),
+            (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT', String(args));
+    }
+    const config = aec.getConfig();
+    assert.strictEqual(config.loudnessTargetDb, -23);
+    assert.strictEqual(config.loudnessMaxGainDb, 9);
+
+    // The gain stays finite and bounded after the rejected calls
+    for (const chunk of chunks(speechNoise(0.01, SAMPLE_RATE, makeRng(54)))) aec.processCaptureAudio(chunk);
+    const { loudnessGainDb } = aec.getMetrics();
+    assert.ok(Number.isFinite(loudnessGainDb) && Math.abs(loudnessGainDb) <= 9, `gain ${loudnessGainDb}`);
+    aec.destroy();
+});
+
+test('output AGC: rejects non-numeric and out-of-range levels', () => {
+    const aec = createMeteringProcessor();
+    aec.setAgc(true, -16, 15);
+    for (const args of [[NaN, 20], [-18, NaN], [3, 20], [-90, 20], [-18, -6], [-18, 60], [-Infinity, 20]]) {
+        assert.throws(() => aec.setAgc(true, ...args),
+            (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE', String(args));
+    }
+    for (const args of [['-18', 20], [-18, {}]]) {
+        assert.throws(() => aec.setAgc(true, ...args),
+            (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT', String(args));
+    }
+    const config = aec.getConfig();
+    assert.strictEqual(config.agcTargetDb, -16);
+    assert.strictEqual(config.agcMaxGainDb, 15);
+
+    // The gain stays finite and bounded after the rejected calls
+    for (const chunk of chunks(speechNoise(0.01, SAMPLE_RATE, makeRng(67)))) aec.processCaptureAudio(chunk);
+    const { agcGainDb } = aec.getMetrics();
+    assert.ok(Number.isFinite(agcGainDb) && agcGainDb <= 15, `gain ${agcGainDb}`);
+    aec.destroy();
+});
+
+test('silent chunk skip: echo stays aligned across skipped gaps', () => {
+    // 3 s of echo to converge, then four 200 ms gaps where mic and reference
+    // are both digital silence, each followed by 1 s of echo. A skip that
+    // dropped capture without its reference would push the echo 200 ms
+    // further out per gap, past the range AEC3 searches.
+    const ms = (t) => t * SAMPLE_RATE / 1000;
+    const ref = speechNoise(0.1, ms(7800), makeRng(11));
+    const gap = ms(200);
+    const gaps = [3000, 4200, 5400, 6600].map(ms);
+    for (const start of gaps) silence([ref], start - 96, start + gap);
+    const mic = echo(ref, 96, 0.5);
+
+    const skipping = new native.AudioCaptureAddon();
+    skipping.setSilentChunkSkip(true);
+    const reference = new native.AudioCaptureAddon();
+    const skippedOut = processPair(skipping, ref, mic);
+    const referenceOut = processPair(reference, ref, mic);
+
+    assert.strictEqual(skipping.getMetrics().skippedSilentChunks, 4 * gap / FRAME, 'every gap chunk was skipped');
+    assert.strictEqual(reference.getMetrics().skippedSilentChunks, 0);
+
+    const lastSecond = [ms(6800), ms(7800)];
+    const skipped = attenuationDb(mic.subarray(...lastSecond), skippedOut.subarray(...lastSecond));
+    const unskipped = attenuationDb(mic.subarray(...lastSecond), referenceOut.subarray(...lastSecond));
+    assert.ok(skipped >= 10, `echo after the last gap should still be cancelled (${skipped.toFixed(1)} dB)`);
+    assert.ok(skipped >= unskipped - 6,
+        `skipping should not cost cancellation (${skipped.toFixed(1)} vs ${unskipped.toFixed(1)} dB)`);
+    skipping.destroy();
+    reference.destroy();
+});
+
+test('silent chunk skip: only skips on frame boundaries, output length is unchanged', () => {
+    const aec = new native.AudioCaptureAddon();
+    aec.setSilentChunkSkip(true);
+    // 320-sample chunks leave part of a 480-sample frame pending on two of
+    // every three calls. Silence starts at chunk 76, with 320 samples pending,
+    // so chunks 76 and 77 still go through AEC3 and skipping starts at 78.
+    const silentFrom = 76 * 320;
+    const ref = speechNoise(0.1, SAMPLE_RATE, makeRng(5));
+    silence([ref], silentFrom - 96, SAMPLE_RATE);
+    const mic = echo(ref, 96, 0.5);
+    const out = processPair(aec, ref, mic, 320);
+    assert.strictEqual(out.length, mic.length);
+    const silentChunks = (SAMPLE_RATE - silentFrom) / 320;
+    assert.strictEqual(aec.getMetrics().skippedSilentChunks, silentChunks - 2,
+        'skipping waits for the pending partial frame to drain');
+    aec.destroy();
+});
+
+test('silent chunk skip: output stages still run and the floor is validated', () => {
+    // A -40 dBFS mic with no reference is "silent" under a -30 dB floor, so
+    // every chunk skips AEC3 while loudness normalization raises the gain
+    const aec = new native.AudioCaptureAddon();
+    aec.setSilentChunkSkip(true, -30);
+    aec.setLoudnessNormalization(true, -20, 20);
+    const mic = speechNoise(Math.pow(10, -40 / 20), SAMPLE_RATE * 4, makeRng(9));
+    let out;
+    for (const chunk of chunks(mic)) out = aec.processCaptureAudio(chunk);
+    const metrics = aec.getMetrics();
+    assert.strictEqual(metrics.skippedSilentChunks, mic.length / FRAME);
+    assert.ok(metrics.loudnessGainDb > 3, `loudness gain rose (${metrics.loudnessGainDb.toFixed(1)} dB)`);
+    assertNear(dbfs(rms(out)) - dbfs(rms(mic.subarray(-FRAME))), metrics.loudnessGainDb, 0.5,
+        'skipped chunk carries the loudness gain');
+
+    for (const floorDb of [NaN, Infinity, -Infinity, 1, -121]) {
+        assert.throws(() => aec.setSilentChunkSkip(true, floorDb),
+            (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE', `floorDb ${floorDb}`);
+    }
+    assert.throws(() => aec.setSilentChunkSkip(true, '-70'),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+    assert.strictEqual(aec.getConfig().silentChunkFloorDb, -30, 'a rejected floor leaves the setting alone');
+    aec.destroy();
+});
+
+test('AEC3 render: reference fed faster than capture still cancels', () => {
+    // For the first 200 ms every capture chunk gets two reference chunks, so
+    // the reference then runs 200 ms ahead. Render beyond the few frames held
+    // for pairing reaches AEC3 as it arrives, which leaves it a 200 ms echo
+    // delay to find rather than a backlog of stale reference.
+    const ref = speechNoise(0.1, SAMPLE_RATE * 6, makeRng(13));
+    const mic = echo(ref, 96, 0.5);
+    const aec = new native.AudioCaptureAddon();
+    const lead = SAMPLE_RATE / 5;
+    const out = new Float32Array(SAMPLE_RATE * 5);
+    let fed = 0;
+    for (let i = 0; i < out.length; i += FRAME) {
+        const target = Math.min(i + FRAME + Math.min(i + FRAME, lead), ref.length);
+        aec.processRenderAudio(ref.subarray(fed, target));
+        fed = target;
+        out.set(aec.processCaptureAudio(mic.subarray(i, i + FRAME)), i);
+    }
+    const lastSecond = [SAMPLE_RATE * 4, SAMPLE_RATE * 5];
+    const erle = attenuationDb(mic.subarray(...lastSecond), out.subarray(...lastSecond));
+    assert.ok(erle >= 10, `echo is cancelled with the reference ahead (${erle.toFixed(1)} dB)`);
+    assert.strictEqual(aec.getMetrics().overflowCount, 0);
+    aec.destroy();
+});
+
+test('dead input: flags digital zeros at deadInputWarningMs and recovers on audio', () => {
+    const aec = createMeteringProcessor({ deadInputWarningMs: 3000 });
+    const zeros = new Float32Array(FRAME);
+    for (let i = 0; i < 299; i++) aec.processCaptureAudio(zeros);
+    let metrics = aec.getMetrics();
+    assert.strictEqual(metrics.deadInput, false, 'not flagged at 2990 ms');
+    assertNear(metrics.deadInputMs, 2990, 1e-3, 'deadInputMs');
+
+    aec.processCaptureAudio(zeros);
+    metrics = aec.getMetrics();
+    assert.strictEqual(metrics.deadInput, true, 'flagged at 3000 ms');
+
+    aec.processCaptureAudio(sine(1000, 0.01, FRAME));
+    metrics = aec.getMetrics();
+    assert.strictEqual(metrics.deadInput, false, 'cleared when audio resumes');
+    assert.strictEqual(metrics.deadInputMs, 0);
+    aec.destroy();
+});
+
+test('dead input: quiet but non-zero input is not dead', () => {
+    const aec = createMeteringProcessor({ deadInputWarningMs: 3000 });
+    const quiet = new Float32Array(FRAME);
+    quiet[FRAME - 1] = 1e-7;  // Far below any audible level, but not digital zero
+    for (let i = 0; i < 500; i++) aec.processCaptureAudio(quiet);
+    const metrics = aec.getMetrics();
+    assert.strictEqual(metrics.deadInput, false);
+    assert.strictEqual(metrics.deadInputMs, 0);
+    aec.destroy();
+});
+
+test('filter length: changing it mid-stream re-converges', () => {
+    for (const engine of ['webrtc', 'nlms']) {
+        const aec = new native.AudioCaptureAddon({ engine, filterLengthBlocks: 24 });
+        const ref = speechNoise(0.1, SAMPLE_RATE * 6, makeRng(13));
+        const mic = echo(ref, 96, 0.5);
+        const half = SAMPLE_RATE * 3;
+
+        const before = processPair(aec, ref.subarray(0, half), mic.subarray(0, half));
+        aec.setFilterLength(12);
+        assert.strictEqual(aec.getConfig().filterLengthBlocks, 12, `${engine}: new length applied`);
+        const after = processPair(aec, ref.subarray(half), mic.subarray(half));
+
+        assertFinite(before, `${engine} output before the change`);
+        assertFinite(after, `${engine} output after the change`);
+        const lastSecond = [SAMPLE_RATE * 2, SAMPLE_RATE * 3];
+        const erle = attenuationDb(mic.subarray(half).subarray(...lastSecond), after.subarray(...lastSecond));
+        assert.ok(erle >= 10, `${engine}: cancellation resumes after the change (${erle.toFixed(1)} dB)`);
+        aec.destroy();
+    }
+});
+
+test('filter length: out-of-range lengths are rejected and keep the old length', () => {
+    const aec = new native.AudioCaptureAddon({ filterLengthBlocks: 16 });
+    for (const blocks of [0, 33]) {
+        assert.throws(() => aec.setFilterLength(blocks),
+            (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE');
+    }
+    assert.strictEqual(aec.getConfig().filterLengthBlocks, 16);
+    aec.destroy();
+});
+
+test('warm start: restored NLMS filter cancels from the first chunk', () => {
+    // AEC3 keeps its filter private, so saved taps only exist for the NLMS engine
+    const options = { engine: 'nlms', enableNs: false };
+    const ref = speechNoise(0.1, SAMPLE_RATE * 4, makeRng(17));
+    const mic = echo(ref, 96, 0.5);
+    const split = SAMPLE_RATE * 3;
+
+    const trained = new native.AudioCaptureAddon(options);
+    processPair(trained, ref.subarray(0, split), mic.subarray(0, split));
+    const state = trained.getState();
+
+    const warm = new native.AudioCaptureAddon(options);
+    warm.setState(state);
+    const cold = new native.AudioCaptureAddon(options);
+    const warmOut = processPair(warm, ref.subarray(split), mic.subarray(split));
+    const coldOut = processPair(cold, ref.subarray(split), mic.subarray(split));
+
+    const first = [0, SAMPLE_RATE / 4];
+    const micStart = mic.subarray(split).subarray(...first);
+    const warmErle = attenuationDb(micStart, warmOut.subarray(...first));
+    const coldErle = attenuationDb(micStart, coldOut.subarray(...first));
+    assert.ok(warmErle >= 15, `warm start cancels at once (${warmErle.toFixed(1)} dB)`);
+    assert.ok(warmErle >= coldErle + 6,
+        `warm start beats cold start (${warmErle.toFixed(1)} vs ${coldErle.toFixed(1)} dB)`);
+    for (const aec of [trained, warm, cold]) aec.destroy();
+});
+
+test('nlms engine: at least 15 dB ERLE on a stationary echo path', () => {
+    const ref = speechNoise(0.1, SAMPLE_RATE * 5, makeRng(19));
+    const mic = echo(ref, 240, 0.4);
+    const lastSecond = [SAMPLE_RATE * 4, SAMPLE_RATE * 5];
+    const results = {};
+    for (const engine of ['nlms', 'webrtc']) {
+        const aec = new native.AudioCaptureAddon({ engine });
+        assert.strictEqual(aec.getMetrics().engine, engine, 'metrics report the active engine');
+        const out = processPair(aec, ref, mic);
+        results[engine] = attenuationDb(mic.subarray(...lastSecond), out.subarray(...lastSecond));
+        aec.destroy();
+    }
+    assert.ok(results.nlms >= 15, `NLMS ERLE ${results.nlms.toFixed(1)} dB (AEC3: ${results.webrtc.toFixed(1)} dB)`);
+});
+
+test('dither: -60 dBFS sine quantized to Int16 has no harmonic spurs above the noise floor', () => {
+    const { float32ToInt16Array } = loadWrapper();
+    // 8192 samples holding exactly 171 cycles, so every harmonic lands on a bin
+    const n = 8192;
+    const bin = 171;
+    const input = sine(SAMPLE_RATE * bin / n, 0.001, n);
+    const harmonics = [2, 3, 4, 5].map((h) => h * bin);
+
+    const spurRatio = (int16) => {
+        const power = powerSpectrum(Float64Array.from(int16));
+        const floor = Math.max(median(power), 1e-9);
+        return Math.max(...harmonics.map((k) => power[k])) / floor;
+    };
+
+    const dithered = spurRatio(float32ToInt16Array(input, { random: makeRng(23) }));
+    assert.ok(dithered < 20,
+        `dithered: strongest harmonic ${(10 * Math.log10(dithered)).toFixed(1)} dB above the median bin`);
+
+    // Without dither the quantization error is periodic and sits on the harmonics,
+    // which shows the check would catch it
+    const plain = spurRatio(float32ToInt16Array(input, { dither: false }));
+    assert.ok(plain > 20, `undithered: harmonic spurs expected (${(10 * Math.log10(plain)).toFixed(1)} dB)`);
+});
+
+test('dither: seeded random gives reproducible output', () => {
+    const { float32ToInt16Array } = loadWrapper();
+    const input = sine(1000, 0.001, FRAME);
+    assert.deepStrictEqual(float32ToInt16Array(input, { random: makeRng(29) }),
+        float32ToInt16Array(input, { random: makeRng(29) }));
+});
+
+test('ERLE: grows as the filter converges on echo-only input', () => {
+    for (const engine of ['webrtc', 'nlms']) {
+        const aec = new native.AudioCaptureAddon({ engine });
+        const ref = speechNoise(0.1, SAMPLE_RATE * 5, makeRng(31));
+        const mic = echo(ref, 96, 0.5);
+        const readings = [];
+        for (const [start, end] of [[0, 0.5], [0.5, 2], [2, 5]]) {
+            const span = [start * SAMPLE_RATE, end * SAMPLE_RATE];
+            processPair(aec, ref.subarray(...span), mic.subarray(...span));
+            readings.push(aec.getMetrics());
+        }
+        const [early, , late] = readings;
+        assert.ok(late.erleInstantDb > early.erleInstantDb + 3,
+            `${engine}: instant ERLE grows (${early.erleInstantDb.toFixed(1)} -> ${late.erleInstantDb.toFixed(1)} dB)`);
+        assert.ok(readings[1].erleAverageDb > early.erleAverageDb && late.erleAverageDb > readings[1].erleAverageDb,
+            `${engine}: average ERLE grows (${readings.map((m) => m.erleAverageDb.toFixed(1)).join(' -> ')} dB)`);
+        assert.ok(late.erleInstantDb >= 10, `${engine}: converged ERLE ${late.erleInstantDb.toFixed(1)} dB`);
+        aec.destroy();
+    }
+});
+
+test('ERLE: silence gives finite readings', () => {
+    const aec = new native.AudioCaptureAddon();
+    const zeros = new Float32Array(FRAME);
+    for (let i = 0; i < 100; i++) {
+        aec.processRenderAudio(zeros);
+        aec.processCaptureAudio(zeros);
+    }
+    const metrics = aec.getMetrics();
+    assert.ok(Number.isFinite(metrics.erleInstantDb), `erleInstantDb ${metrics.erleInstantDb}`);
+    assert.ok(Number.isFinite(metrics.erleAverageDb), `erleAverageDb ${metrics.erleAverageDb}`);
+    assert.ok(Number.isFinite(metrics.echoReturnLossEnhancement));
+    aec.destroy();
+});
+
+// Process one chunk of silence reported as having taken elapsedMs
+function processWithElapsed(aec, elapsedMs, numSamples = FRAME) {
+    aec.setDeterministic(true, 0, elapsedMs);
+    aec.processCaptureAudio(new Float32Array(numSamples));
+}
+
+test('silence bypass: passes the mic through on every engine path', () => {
+    // No reference at all, so every chunk's reference is silent
+    const mic = speechNoise(0.1, FRAME * 20, makeRng(53));
+    for (const options of [{ engine: 'nlms' }, { engine: 'webrtc' }, { enableAec: false }]) {
+        const aec = new native.AudioCaptureAddon(options);
+        aec.setSilenceBypassThreshold(-60);
+        const out = new Float32Array(mic.length);
+        chunks(mic).forEach((chunk, i) => out.set(aec.processCaptureAudio(chunk), i * FRAME));
+        const label = JSON.stringify(options);
+        assert.strictEqual(aec.getMetrics().silenceBypassChunks, 20, `${label}: every chunk bypassed`);
+        assert.deepStrictEqual(out, mic, `${label}: mic passed through unmodified`);
+        aec.destroy();
+    }
+});
+
+test('silence bypass: gates on the reference paired with the chunk, not the newest', () => {
+    const far = speechNoise(0.1, FRAME, makeRng(59));
+    const quiet = new Float32Array(FRAME);
+    const mic = speechNoise(0.1, FRAME, makeRng(61));
+
+    // Far-end audio queued ahead of a silent chunk pairs with this capture chunk
+    const talking = new native.AudioCaptureAddon({ engine: 'nlms' });
+    talking.setSilenceBypassThreshold(-60);
+    talking.processRenderAudio(far);
+    talking.processRenderAudio(quiet);
+    talking.processCaptureAudio(mic);
+    assert.strictEqual(talking.getMetrics().silenceBypassChunks, 0, 'paired reference is active');
+    talking.processCaptureAudio(mic);
+    assert.strictEqual(talking.getMetrics().silenceBypassChunks, 1, 'the queued silence is bypassed next');
+    talking.destroy();
+
+    // The mirror case: silence pairs with this chunk although speech arrived last
+    const silent = new native.AudioCaptureAddon({ engine: 'nlms' });
+    silent.setSilenceBypassThreshold(-60);
+    silent.processRenderAudio(quiet);
+    silent.processRenderAudio(far);
+    silent.processCaptureAudio(mic);
+    assert.strictEqual(silent.getMetrics().silenceBypassChunks, 1, 'paired reference is silent');
+    silent.destroy();
+});
+
+test('silence bypass: the threshold must be a finite dBFS value or null', () => {
+    const aec = new native.AudioCaptureAddon();
+    for (const db of [NaN, Infinity, -Infinity, 1, -121]) {
+        assert.throws(() => aec.setSilenceBypassThreshold(db),
+            (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE', `threshold ${db}`);
+    }
+    assert.strictEqual(aec.getConfig().silenceBypassThresholdDb, null, 'rejected thresholds leave it disabled');
+    aec.setSilenceBypassThreshold(-120);
+    assert.strictEqual(aec.getConfig().silenceBypassThresholdDb, -120);
+    aec.destroy();
+});
+
+test('latency histogram: synthetic delays land in the right buckets and percentiles', () => {
+    const aec = createMeteringProcessor();
+    for (let i = 0; i < 90; i++) processWithElapsed(aec, 0.2);
+    for (let i = 0; i < 9; i++) processWithElapsed(aec, 3);
+    processWithElapsed(aec, 20);
+
+    const metrics = aec.getMetrics();
+    // Buckets: <0.5, <1, <2, <4, <8 and >=8 ms
+    assert.deepStrictEqual(Array.from(metrics.latencyHistogram), [90, 0, 0, 9, 0, 1]);
+    // Percentiles report the upper bound of a log-spaced bucket, at most ~32% above the value
+    assert.ok(metrics.latencyP50Ms >= 0.2 && metrics.latencyP50Ms <= 0.27, `p50 ${metrics.latencyP50Ms}`);
+    assert.ok(metrics.latencyP95Ms >= 3 && metrics.latencyP95Ms <= 3.95, `p95 ${metrics.latencyP95Ms}`);
+    assert.ok(metrics.latencyP99Ms >= 3 && metrics.latencyP99Ms <= 3.95, `p99 ${metrics.latencyP99Ms}`);
+    assert.strictEqual(metrics.latencyMaxMs, 20);
+
+    aec.resetMetrics();
+    const cleared = aec.getMetrics();
+    assert.deepStrictEqual(Array.from(cleared.latencyHistogram), [0, 0, 0, 0, 0, 0]);
+    assert.strictEqual(cleared.latencyP99Ms, 0);
+    aec.destroy();
+});
+
+test('latency histogram: delays beyond 50 ms fall in the top bucket, max keeps the value', () => {
+    const aec = createMeteringProcessor();
+    for (let i = 0; i < 10; i++) processWithElapsed(aec, 80);
+    const metrics = aec.getMetrics();
+    assert.strictEqual(metrics.latencyHistogram[5], 10);
+    assertNear(metrics.latencyP50Ms, 50, 1e-6, 'p50 at the top of the 10 µs..50 ms range');
+    assert.strictEqual(metrics.latencyMaxMs, 80);
+    aec.destroy();
+});
+
+test('peak processing time: records the chunk size of the slowest call', () => {
+    const aec = createMeteringProcessor();
+    processWithElapsed(aec, 1, 480);
+    processWithElapsed(aec, 9, 12288);
+    processWithElapsed(aec, 2, 960);
+    let metrics = aec.getMetrics();
+    assert.strictEqual(metrics.latencyMaxMs, 9);
+    assert.strictEqual(metrics.latencyMaxChunkSamples, 12288);
+
+    aec.resetMetrics();
+    metrics = aec.getMetrics();
+    assert.strictEqual(metrics.latencyMaxMs, 0);
+    assert.strictEqual(metrics.latencyMaxChunkSamples, 0);
+    aec.destroy();
+});
+
+test('peak processing time: with measured timing the largest chunk is the slowest', () => {
+    const aec = new native.AudioCaptureAddon();
+    const ref = speechNoise(0.1, SAMPLE_RATE * 4, makeRng(37));
+    const mic = echo(ref, 96, 0.5);
+    // Mostly 10 ms chunks with a few 256 ms ones, which cost ~25x as much
+    let offset = 0;
+    for (let i = 0; offset < ref.length; i++) {
+        const size = Math.min(i % 20 === 10 ? 12288 : FRAME, ref.length - offset);
+        processPair(aec, ref.subarray(offset, offset + size), mic.subarray(offset, offset + size), size);
+        offset += size;
+    }
+    assert.strictEqual(aec.getMetrics().latencyMaxChunkSamples, 12288);
+    aec.destroy();
+});
+
+test('resetMetrics: clears counters without touching the audio path', () => {
+    const ref = speechNoise(0.1, SAMPLE_RATE * 3, makeRng(41));
+    const mic = echo(ref, 96, 0.5);
+    const half = SAMPLE_RATE * 3 / 2;
+    const resetting = new native.AudioCaptureAddon();
+    const untouched = new native.AudioCaptureAddon();
+    for (const aec of [resetting, untouched]) aec.setDeterministic(true, 1);
+
+    const first = processPair(resetting, ref.subarray(0, half), mic.subarray(0, half));
+    assert.deepStrictEqual(first, processPair(untouched, ref.subarray(0, half), mic.subarray(0, half)));
+    const histogramTotal = (aec) => aec.getMetrics().latencyHistogram.reduce((a, b) => a + b, 0);
+    assert.strictEqual(histogramTotal(resetting), half / FRAME);
+
+    resetting.resetMetrics();
+    assert.strictEqual(histogramTotal(resetting), 0);
+    assert.strictEqual(resetting.getMetrics().erleAverageDb, 0);
+
+    // The filter, buffered audio and levels carry on: output matches an uninterrupted processor
+    const second = processPair(resetting, ref.subarray(half), mic.subarray(half));
+    assert.deepStrictEqual(second, processPair(untouched, ref.subarray(half), mic.subarray(half)));
+    resetting.destroy();
+    untouched.destroy();
+});
+
+test('underrun and overflow: starving and flooding the reference are counted', () => {
+    const aec = new native.AudioCaptureAddon();
+    const chunk = speechNoise(0.1, FRAME, makeRng(43));
+
+    // Capture with no reference at all, then with too little
+    for (let i = 0; i < 5; i++) aec.processCaptureAudio(chunk);
+    aec.processRenderAudio(chunk.subarray(0, 200));
+    aec.processCaptureAudio(chunk);
+    let metrics = aec.getMetrics();
+    assert.strictEqual(metrics.underrunCount, 6);
+    assert.strictEqual(metrics.underrunSamples, 5 * FRAME + (FRAME - 200));
+    assert.strictEqual(metrics.overflowCount, 0);
+
+    // 1.5 s of reference with no capture: passing 1 s drains the oldest half
+    for (let i = 0; i < 150; i++) aec.processRenderAudio(chunk);
+    metrics = aec.getMetrics();
+    assert.strictEqual(metrics.overflowCount, 1);
+    assert.strictEqual(metrics.overflowSamples, 101 * FRAME - SAMPLE_RATE / 2);
+
+    // resetAEC clears the buffers but keeps the counters; resetMetrics clears them
+    aec.resetAEC();
+    metrics = aec.getMetrics();
+    assert.strictEqual(metrics.underrunCount, 6);
+    assert.strictEqual(metrics.overflowCount, 1);
+    aec.resetMetrics();
+    metrics = aec.getMetrics();
+    assert.strictEqual(metrics.underrunCount, 0);
+    assert.strictEqual(metrics.underrunSamples, 0);
+    assert.strictEqual(metrics.overflowCount, 0);
+    assert.strictEqual(metrics.overflowSamples, 0);
+    aec.destroy();
+});
+
+test('overrun drain fraction: sets how much of the buffer an overrun keeps', () => {
+    const chunk = speechNoise(0.1, FRAME, makeRng(49));
+    const overflowAfter = (fraction, chunks) => {
+        const aec = new native.AudioCaptureAddon({ engine: 'nlms' });
+        aec.setOverrunDrainFraction(fraction);
+        assert.strictEqual(aec.getConfig().overrunDrainFraction, fraction);
+        for (let i = 0; i < chunks; i++) aec.processRenderAudio(chunk);
+
+        // Capture consumes exactly what was kept, then starves
+        const { overflowSamples } = aec.getMetrics();
+        const kept = chunks * FRAME - overflowSamples;
+        for (let i = 0; i < Math.ceil(kept / FRAME) + 1; i++) aec.processCaptureAudio(chunk);
+        const metrics = aec.getMetrics();
+        assert.strictEqual(metrics.referenceSamplesConsumed, kept);
+        aec.destroy();
+        return { count: metrics.overflowCount, samples: overflowSamples };
+    };
+
+    // Keeping everything drops only the excess, one chunk per overrun
+    assert.deepStrictEqual(overflowAfter(1, 150), { count: 50, samples: 50 * FRAME });
+    // Keeping a quarter drains to 12000 samples, refilling past 1 s once more
+    assert.deepStrictEqual(overflowAfter(0.25, 180), { count: 2, samples: 2 * (101 * FRAME - SAMPLE_RATE / 4) });
+    assert.deepStrictEqual(overflowAfter(0, 101), { count: 1, samples: 101 * FRAME });
+});
+
+test('overrun drain fraction: rejects values outside 0..1', () => {
+    const aec = new native.AudioCaptureAddon();
+    assert.strictEqual(aec.getConfig().overrunDrainFraction, 0.5);
+    for (const bad of [-0.1, 1.5, NaN, Infinity]) {
+        assert.throws(() => aec.setOverrunDrainFraction(bad),
+            (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE', String(bad));
+    }
+    assert.throws(() => aec.setOverrunDrainFraction('0.5'),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+    assert.strictEqual(aec.getConfig().overrunDrainFraction, 0.5);
+    aec.destroy();
+});
+
+test('refBufferAvailable: tracks the fill level and paces a feed without overruns', () => {
+    const aec = new native.AudioCaptureAddon();
+    const chunk = speechNoise(0.1, FRAME, makeRng(57));
+    assert.strictEqual(aec.refBufferAvailable(), SAMPLE_RATE);
+    assert.strictEqual(aec.getMetrics().refBufferFillRatio, 0);
+
+    for (let i = 0; i < 25; i++) aec.processRenderAudio(chunk);
+    assert.strictEqual(aec.refBufferAvailable(), SAMPLE_RATE - 25 * FRAME);
+    assertNear(aec.getMetrics().refBufferFillRatio, 0.25, 1e-6, 'fill ratio');
+    aec.processCaptureAudio(chunk);
+    assert.strictEqual(aec.refBufferAvailable(), SAMPLE_RATE - 24 * FRAME);
+
+    // A feed three times faster than capture that waits for space never overruns
+    let held = 0;
+    for (let i = 0; i < 300; i++) {
+        for (let k = 0; k < 3; k++) {
+            if (aec.refBufferAvailable() >= FRAME) {
+                aec.processRenderAudio(chunk);
+            } else {
+                held++;
+            }
+        }
+        aec.processCaptureAudio(chunk);
+    }
+    const metrics = aec.getMetrics();
+    assert.ok(held > 0, 'the feed had to wait');
+    assert.strictEqual(metrics.overflowCount, 0);
+    assert.ok(metrics.refBufferFillRatio > 0.9, `fill ${metrics.refBufferFillRatio}`);
+    aec.destroy();
+});
+
+test('underrun and overflow: balanced feeding counts neither', () => {
+    const aec = new native.AudioCaptureAddon();
+    const ref = speechNoise(0.1, SAMPLE_RATE, makeRng(47));
+    processPair(aec, ref, echo(ref, 96, 0.5));
+    const metrics = aec.getMetrics();
+    assert.strictEqual(metrics.underrunCount, 0);
+    assert.strictEqual(metrics.overflowCount, 0);
+    aec.destroy();
+});
+
+test('underrun strategy: hold replays the last reference frame through a dropout', () => {
+    // Steady playback that repeats every frame, so the held frame is exactly what was missed
+    const period = speechNoise(0.1, FRAME, makeRng(53));
+    const ref = new Float32Array(SAMPLE_RATE * 2);
+    for (let i = 0; i < ref.length; i += FRAME) ref.set(period, i);
+    const mic = echo(ref, 96, 0.5);
+    const warm = SAMPLE_RATE;
+
+    const dropoutAttenuation = (strategy) => {
+        const aec = new native.AudioCaptureAddon({ engine: 'nlms' });
+        aec.setUnderrunStrategy(strategy);
+        assert.strictEqual(aec.getConfig().underrunStrategy, strategy);
+        processPair(aec, ref.subarray(0, warm), mic.subarray(0, warm));
+
+        // Every fifth render chunk never arrives
+        const dropped = [];
+        const out = [];
+        for (let i = warm, n = 0; i < ref.length; i += FRAME, n++) {
+            if (n % 5 !== 4) {
+                aec.processRenderAudio(ref.subarray(i, i + FRAME));
+            } else {
+                dropped.push(i);
+            }
+            out.push(aec.processCaptureAudio(mic.subarray(i, i + FRAME)));
+        }
+        const metrics = aec.getMetrics();
+        assert.strictEqual(metrics.underrunCount, dropped.length);
+        assert.strictEqual(metrics.underrunHoldCount, strategy === 'hold' ? dropped.length : 0);
+        assert.strictEqual(metrics.underrunZeroFillCount, strategy === 'zeros' ? dropped.length : 0);
+        aec.destroy();
+
+        const droppedIn = new Float32Array(dropped.length * FRAME);
+        const droppedOut = new Float32Array(dropped.length * FRAME);
+        dropped.forEach((start, k) => {
+            droppedIn.set(mic.subarray(start, start + FRAME), k * FRAME);
+            droppedOut.set(out[(start - warm) / FRAME], k * FRAME);
+        });
+        return attenuationDb(droppedIn, droppedOut);
+    };
+    const hold = dropoutAttenuation('hold');
+    const zeros = dropoutAttenuation('zeros');
+    assert.ok(hold >= 15, `hold: ${hold.toFixed(1)} dB through the dropouts`);
+    assert.ok(zeros < 6, `zeros: ${zeros.toFixed(1)} dB through the dropouts`);
+});
+
+test('underrun strategy: rejects unknown strategies', () => {
+    const aec = new native.AudioCaptureAddon();
+    assert.strictEqual(aec.getConfig().underrunStrategy, 'zeros');
+    assert.throws(() => aec.setUnderrunStrategy('repeat'),
+        (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE');
+    assert.throws(() => aec.setUnderrunStrategy(1),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+    assert.strictEqual(aec.getConfig().underrunStrategy, 'zeros');
+    aec.destroy();
+});
+
+test('downmixStereoToMono: averages without overflow at full scale', () => {
+    // [left, right, expected]: half-way means round away from zero
+    const cases = [
+        [32767, 32767, 32767],
+        [-32768, -32768, -32768],
+        [32767, 32766, 32767],
+        [-32768, -32767, -32768],
+        [32767, -32768, -1],
+        [1, 0, 1],
+        [-1, 0, -1],
+        [100, -100, 0],
+        [0, 0, 0],
+    ];
+    const input = Int16Array.from(cases.flatMap(([left, right]) => [left, right]));
+    const output = native.downmixStereoToMono(input);
+    assert.ok(output instanceof Int16Array);
+    assert.deepStrictEqual(Array.from(output), cases.map(([, , expected]) => expected));
+});
+
+test('downmixStereoToMono: rejects odd lengths and non-Int16 input', () => {
+    assert.throws(() => native.downmixStereoToMono(new Int16Array(3)),
+        (err) => err instanceof RangeError && err.code === 'ERR_AEC_BUFFER_LENGTH');
+    assert.throws(() => native.downmixStereoToMono(new Float32Array(4)),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+});
+
+test('call stats: collectStats returns the output with per-call measurements', () => {
+    const withStats = new native.AudioCaptureAddon();
+    const plain = new native.AudioCaptureAddon();
+    const ref = speechNoise(0.1, SAMPLE_RATE, makeRng(53));
+    const mic = echo(ref, 96, 0.5);
+    for (let i = 0; i < mic.length; i += FRAME) {
+        const refChunk = ref.subarray(i, i + FRAME);
+        const micChunk = mic.subarray(i, i + FRAME);
+        withStats.processRenderAudio(refChunk);
+        plain.processRenderAudio(refChunk);
+        const result = withStats.processCaptureAudio(micChunk, { collectStats: true });
+        const expected = plain.processCaptureAudio(micChunk);
+        assert.ok(expected instanceof Float32Array, 'plain call still returns the array');
+        assert.deepStrictEqual(result.output, expected, 'stats do not change the output');
+
+        for (const key of ['erleDb', 'inputRms', 'outputRms', 'elapsedUs']) {
+            assert.strictEqual(typeof result[key], 'number', key);
+        }
+        assert.strictEqual(typeof result.doubleTalk, 'boolean');
+        assert.strictEqual(typeof result.adaptationThrottled, 'boolean');
+        assertNear(result.inputRms, rms(micChunk), 1e-4 * rms(micChunk) + 1e-9, 'inputRms');
+        assertNear(result.outputRms, rms(expected), 1e-4 * rms(expected) + 1e-9, 'outputRms');
+        if (rms(expected) > 1e-3) {  // Well above the ratio's log-of-zero guard
+            assertNear(result.erleDb, dbfs(result.inputRms / result.outputRms), 0.01, 'erleDb');
+        }
+        assert.ok(result.elapsedUs > 0);
+    }
+    withStats.destroy();
+    plain.destroy();
+});
+
+test('call stats: elapsedUs reports the call time', () => {
+    const aec = createMeteringProcessor();
+    aec.setDeterministic(true, 0, 5);
+    assert.strictEqual(aec.processCaptureAudio(new Float32Array(FRAME), { collectStats: true }).elapsedUs, 5000);
+    const measured = aec.processAndMeasure(new Float32Array(FRAME * 2 + 100));
+    assert.strictEqual(measured.elapsedUs, 5000);
+    assert.strictEqual(measured.output.length, FRAME * 2 + 100);
+    assert.strictEqual(measured.frameGains.length, 2, 'one gain per full frame');
+    aec.destroy();
+});
+
+test('getConfig: reflects create options and later runtime changes', () => {
+    const aec = new native.AudioCaptureAddon({
+        sampleRate: 16000, frameDurationMs: 20, label: 'config-test', engine: 'nlms',
+        maxInputSamples: 32000, doubleTalkSensitivity: 0.5, preset: 'balanced',
+    });
+    let config = aec.getConfig();
+    assert.strictEqual(config.sampleRate, 16000);
+    assert.strictEqual(config.frameDurationMs, 20);
+    assert.strictEqual(config.frameSize, 320);
+    assert.strictEqual(config.label, 'config-test');
+    assert.strictEqual(config.engine, 'nlms');
+    assert.strictEqual(config.maxInputSamples, 32000);
+    assertNear(config.doubleTalkSensitivity, 0.5, 1e-6, 'doubleTalkSensitivity');
+    assert.strictEqual(config.preset, 'balanced');
+    assert.strictEqual(config.limiterEnabled, false);
+    assert.strictEqual(config.silenceBypassThresholdDb, null);
+
+    aec.setReferenceGain(0.5);
+    aec.setOutputGain(-3);
+    aec.setLimiter(true, -2);
+    aec.setSilenceBypassThreshold(-60);
+    aec.setSampleRate(48000);
+    config = aec.getConfig();
+    assertNear(config.referenceGain, 0.5, 1e-6, 'referenceGain');
+    assertNear(config.outputGainDb, -3, 1e-6, 'outputGainDb');
+    assert.strictEqual(config.limiterEnabled, true);
+    assertNear(config.limiterThresholdDb, -2, 1e-6, 'limiterThresholdDb');
+    assertNear(config.silenceBypassThresholdDb, -60, 1e-6, 'silenceBypassThresholdDb');
+    assert.strictEqual(config.sampleRate, 48000);
+    assert.strictEqual(config.frameSize, 960);
+    aec.destroy();
+});
+
+test('getConfig: returns a fresh object each call', () => {
+    const aec = new native.AudioCaptureAddon();
+    const first = aec.getConfig();
+    first.sampleRate = 1;
+    first.supportedSampleRates.push(1);
+    const second = aec.getConfig();
+    assert.notStrictEqual(first, second);
+    assert.strictEqual(second.sampleRate, 48000);
+    assert.ok(!second.supportedSampleRates.includes(1));
+    aec.destroy();
+});
+
+test('getVersion: every build field is a non-empty string', () => {
+    const info = native.getVersion();
+    for (const key of ['version', 'webrtc', 'platform', 'arch', 'profile', 'compiler', 'buildDate']) {
+        assert.strictEqual(typeof info[key], 'string', key);
+        assert.ok(info[key].length > 0, `${key} is empty`);
+    }
+    assert.strictEqual(info.platform, process.platform);
+    assert.ok(['release', 'debug'].includes(info.profile));
+    assert.ok(Number.isInteger(info.napiVersion) && info.napiVersion > 0);
+    assert.deepStrictEqual(Array.from(info.engines), ['webrtc', 'nlms']);
+    assert.ok(Array.isArray(info.features) && info.features.every((f) => typeof f === 'string' && f.length > 0));
+});
+
+test('getCapabilities: reports the same version and boolean flags', () => {
+    const caps = native.getCapabilities();
+    assert.strictEqual(caps.version, native.getVersion().version);
+    assert.ok(Object.keys(caps.capabilities).length > 0);
+    for (const [name, supported] of Object.entries(caps.capabilities)) {
+        assert.strictEqual(typeof supported, 'boolean', name);
+    }
+});
+
+test('debug dump: writes a valid 3-channel WAV with aligned channels', () => {
+    const fs = require('fs');
+    const os = require('os');
+    const path = require('path');
+    const file = path.join(os.tmpdir(), `aec-dump-${process.pid}.wav`);
+    const aec = createMeteringProcessor();
+    assert.strictEqual(aec.startDebugDump(file), true);
+
+    // Clicks at the same instants on mic and reference; with cancellation off
+    // the output is the mic, so all three channels should click together
+    const length = FRAME * 20;
+    const clicks = [1234, 5000, 8000];
+    const ref = new Float32Array(length);
+    const mic = new Float32Array(length);
+    for (const at of clicks) {
+        ref[at] = 0.5;
+        mic[at] = 0.25;
+    }
+    processPair(aec, ref, mic);
+    const stats = aec.stopDebugDump();
+    assert.strictEqual(stats.framesWritten, length);
+    assert.strictEqual(stats.droppedChunks, 0);
+
+    const wav = fs.readFileSync(file);
+    fs.unlinkSync(file);
+    const dataBytes = length * 3 * 2;
+    assert.strictEqual(wav.length, 44 + dataBytes);
+    assert.strictEqual(wav.toString('ascii', 0, 4), 'RIFF');
+    assert.strictEqual(wav.readUInt32LE(4), 36 + dataBytes);
+    assert.strictEqual(wav.toString('ascii', 8, 16), 'WAVEfmt ');
+    assert.strictEqual(wav.readUInt32LE(16), 16, 'fmt chunk size');
+    assert.strictEqual(wav.readUInt16LE(20), 1, 'PCM');
+    assert.strictEqual(wav.readUInt16LE(22), 3, 'channels');
+    assert.strictEqual(wav.readUInt32LE(24), SAMPLE_RATE, 'sample rate');
+    assert.strictEqual(wav.readUInt32LE(28), SAMPLE_RATE * 6, 'byte rate');
+    assert.strictEqual(wav.readUInt16LE(32), 6, 'block align');
+    assert.strictEqual(wav.readUInt16LE(34), 16, 'bits per sample');
+    assert.strictEqual(wav.toString('ascii', 36, 40), 'data');
+    assert.strictEqual(wav.readUInt32LE(40), dataBytes);
+
+    const channelClicks = [0, 1, 2].map((channel) => {
+        const found = [];
+        for (let frame = 0; frame < length; frame++) {
+            if (wav.readInt16LE(44 + frame * 6 + channel * 2) !== 0) found.push(frame);
+        }
+        return found;
+    });
+    assert.deepStrictEqual(channelClicks, [clicks, clicks, clicks], 'mic, reference and output line up');
+    assert.strictEqual(wav.readInt16LE(44 + clicks[0] * 6 + 2), Math.round(0.5 * 32767), 'reference sample value');
+    aec.destroy();
+});
+
+test('debug dump: stopDebugDump without a dump returns null', () => {
+    const aec = createMeteringProcessor();
+    assert.strictEqual(aec.stopDebugDump(), null);
+    aec.destroy();
+});
+
+test('logger: a warning reaches the callback with its level and message', async () => {
+    const aec = createMeteringProcessor({ deadInputWarningMs: 100, label: 'logger-test' });
+    const records = [];
+    aec.setLogger((level, message) => records.push({ level, message }), 'warn');
+    for (let i = 0; i < 10; i++) aec.processCaptureAudio(new Float32Array(FRAME));
+    await settle();
+    const warnings = records.filter((r) => r.message.includes('pure digital zeros'));
+    assert.strictEqual(warnings.length, 1, `one dead-input warning (got ${JSON.stringify(records)})`);
+    assert.strictEqual(warnings[0].level, 'warn');
+    assert.ok(warnings[0].message.startsWith('[logger-test] '), 'label prefixes the message');
+    aec.destroy();
+});
+
+test('logger: records below the level are filtered, null detaches', async () => {
+    const aec = createMeteringProcessor({ deadInputWarningMs: 100 });
+    const records = [];
+    aec.setLogger((level, message) => records.push({ level, message }), 'error');
+    for (let i = 0; i < 10; i++) aec.processCaptureAudio(new Float32Array(FRAME));
+    await settle();
+    assert.deepStrictEqual(records, [], 'warnings are below error');
+
+    aec.setLogger((level, message) => records.push({ level, message }), 'debug');
+    aec.processCaptureAudio(new Float32Array(0));
+    await settle();
+    assert.ok(records.some((r) => r.level === 'debug' && r.message.includes('Empty capture buffer')),
+        `debug record for an empty buffer (got ${JSON.stringify(records)})`);
+
+    records.length = 0;
+    aec.setLogger(null);
+    aec.processCaptureAudio(new Float32Array(0));
+    await settle();
+    assert.deepStrictEqual(records, []);
+    assert.throws(() => aec.setLogger(() => {}, 'loud'),
+        (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE');
+    aec.destroy();
+});
+
+test('real-time factor: processing time over audio time, in ms of audio', () => {
+    const aec = createMeteringProcessor();
+    for (let i = 0; i < 100; i++) processWithElapsed(aec, 2);
+    let metrics = aec.getMetrics();
+    assertNear(metrics.realTimeFactor, 0.2, 1e-5, 'realTimeFactor');
+    assertNear(metrics.realTimeFactorRecent, 0.2, 1e-5, 'realTimeFactorRecent');
+    assertNear(metrics.audioProcessedMs, 1000, 1e-3, 'audioProcessedMs');
+
+    // Mixed chunk sizes weigh by audio length, not by call
+    aec.resetMetrics();
+    for (let i = 0; i < 10; i++) {
+        processWithElapsed(aec, 1, FRAME);
+        processWithElapsed(aec, 1, FRAME * 2);
+    }
+    metrics = aec.getMetrics();
+    assertNear(metrics.realTimeFactor, 20 / 300, 1e-5, 'mixed-size realTimeFactor');
+    assertNear(metrics.audioProcessedMs, 300, 1e-3, 'mixed-size audioProcessedMs');
+
+    aec.resetMetrics();
+    metrics = aec.getMetrics();
+    assert.strictEqual(metrics.realTimeFactor, 0);
+    assert.strictEqual(metrics.realTimeFactorRecent, 0);
+    assert.strictEqual(metrics.audioProcessedMs, 0);
+    aec.destroy();
+});
+
+test('real-time factor: audio time follows the configured sample rate', () => {
+    const aec = createMeteringProcessor({ sampleRate: 16000 });
+    for (let i = 0; i < 50; i++) {
+        processWithElapsed(aec, 3, 160);
+        processWithElapsed(aec, 3, 320);
+    }
+    const metrics = aec.getMetrics();
+    assertNear(metrics.audioProcessedMs, 1500, 1e-3, 'audioProcessedMs at 16 kHz');
+    assertNear(metrics.realTimeFactor, 0.2, 1e-5, 'realTimeFactor at 16 kHz');
+    aec.destroy();
+});
+
+test('real-time factor: the recent value covers only the last 5 s of audio', () => {
+    const aec = createMeteringProcessor();
+    for (let i = 0; i < 1000; i++) processWithElapsed(aec, 1);  // 10 s at 0.1
+    for (let i = 0; i < 600; i++) processWithElapsed(aec, 5);   // 6 s at 0.5
+    const metrics = aec.getMetrics();
+    assertNear(metrics.realTimeFactor, 4000 / 16000, 1e-5, 'lifetime realTimeFactor');
+    assertNear(metrics.realTimeFactorRecent, 0.5, 1e-5, 'recent realTimeFactor');
+    aec.destroy();
+});
+
+test('overload: a sustained high RTF sets overloaded, recovery needs half the threshold', () => {
+    const aec = createMeteringProcessor();
+    aec.setOverloadDetection(0.8, 10);
+    for (let i = 0; i < 9; i++) processWithElapsed(aec, 9);
+    assert.strictEqual(aec.getMetrics().overloaded, false, 'one chunk short of the streak');
+    processWithElapsed(aec, 9);
+    let metrics = aec.getMetrics();
+    assert.strictEqual(metrics.overloaded, true);
+    assert.strictEqual(metrics.overloadBypass, false, 'no bypass unless asked for');
+    assert.strictEqual(metrics.overloadEvents, 1);
+
+    // Between half the threshold and the threshold the flag holds
+    for (let i = 0; i < 600; i++) processWithElapsed(aec, 6);
+    assert.strictEqual(aec.getMetrics().overloaded, true, 'RTF 0.6 is above the 0.4 recovery level');
+
+    for (let i = 0; i < 600; i++) processWithElapsed(aec, 1);
+    metrics = aec.getMetrics();
+    assert.strictEqual(metrics.overloaded, false);
+    assert.strictEqual(metrics.overloadEvents, 1);
+    aec.destroy();
+});
+
+test('overload: autoBypass passes audio through until load recovers', () => {
+    const aec = new native.AudioCaptureAddon();
+    aec.setOverloadDetection(0.8, 10, true);
+    const chunk = speechNoise(0.1, FRAME, makeRng(71));
+    aec.setDeterministic(true, 0, 9);
+    for (let i = 0; i < 10; i++) {
+        aec.processRenderAudio(chunk);
+        aec.processCaptureAudio(chunk);
+    }
+    assert.strictEqual(aec.getMetrics().overloadBypass, true);
+    aec.processRenderAudio(chunk);
+    assert.deepStrictEqual(aec.processCaptureAudio(chunk), chunk, 'bypassed chunk is the input');
+
+    aec.setDeterministic(true, 0, 0.1);
+    for (let i = 0; i < 40; i++) {
+        aec.processRenderAudio(chunk);
+        aec.processCaptureAudio(chunk);
+    }
+    const metrics = aec.getMetrics();
+    assert.strictEqual(metrics.overloaded, false);
+    assert.strictEqual(metrics.overloadBypass, false);
+    assert.strictEqual(metrics.overloadEvents, 1);
+    aec.destroy();
+});
+
+test('overload: consecutiveChunks 0 disables detection, bad values throw', () => {
+    const aec = createMeteringProcessor();
+    aec.setOverloadDetection(0.8, 0);
+    for (let i = 0; i < 100; i++) processWithElapsed(aec, 20);
+    assert.strictEqual(aec.getMetrics().overloaded, false);
+    assert.strictEqual(aec.getMetrics().overloadEvents, 0);
+
+    for (const args of [[0, 10], [-1, 10], [0.8, -1]]) {
+        assert.throws(() => aec.setOverloadDetection(...args),
+            (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE');
+    }
+    aec.destroy();
+});
+
+function whiteNoise(rmsLevel, numSamples, rng) {
+    const out = new Float32Array(numSamples);
+    for (let i = 0; i < numSamples; i++) out[i] = rmsLevel * Math.sqrt(3) * (2 * rng() - 1);
+    return out;
+}
+
+function assertHealth(report, failing) {
+    const expected = { outputNotStuck: true, outputNotAboveInput: true, referenceNotPinned: true, finiteSamples: true };
+    for (const name of failing) expected[name] = false;
+    assert.deepStrictEqual(report.checks, expected);
+    assert.strictEqual(report.healthy, failing.length === 0);
+}
+
+test('healthCheck: normal echo cancellation is healthy', () => {
+    const aec = new native.AudioCaptureAddon();
+    const ref = speechNoise(0.1, SAMPLE_RATE * 3, makeRng(73));
+    processPair(aec, ref, echo(ref, 96, 0.5));
+    const report = aec.healthCheck();
+    assertHealth(report, []);
+    assert.strictEqual(report.recovered, false);
+    aec.destroy();
+});
+
+test('healthCheck: a reference pinned at a constant fails after ~2 s', () => {
+    const aec = createMeteringProcessor();
+    const mic = speechNoise(0.1, FRAME, makeRng(74));
+    const feed = (render, chunks) => {
+        for (let i = 0; i < chunks; i++) {
+            aec.processRenderAudio(render);
+            aec.processCaptureAudio(mic);
+        }
+    };
+    feed(new Float32Array(FRAME).fill(0.5), 150);
+    assertHealth(aec.healthCheck(), []);
+    feed(new Float32Array(FRAME).fill(0.5), 60);
+    assertHealth(aec.healthCheck(), ['referenceNotPinned']);
+
+    // Moving reference clears the streak; full scale counts as pinned too
+    feed(speechNoise(0.1, FRAME, makeRng(75)), 1);
+    assertHealth(aec.healthCheck(), []);
+    feed(new Float32Array(FRAME).fill(1), 210);
+    assertHealth(aec.healthCheck(), ['referenceNotPinned']);
+    aec.destroy();
+});
+
+test('healthCheck: NaN in the capture path fails once, then the flag clears', () => {
+    const aec = createMeteringProcessor();
+    const chunk = speechNoise(0.1, FRAME, makeRng(76));
+    const poisoned = chunk.slice();
+    poisoned[100] = NaN;
+    aec.processCaptureAudio(chunk);
+    aec.processCaptureAudio(poisoned);
+    aec.processCaptureAudio(chunk);
+    assertHealth(aec.healthCheck(), ['finiteSamples']);
+    aec.processCaptureAudio(chunk);
+    assertHealth(aec.healthCheck(), []);
+    aec.destroy();
+});
+
+test('healthCheck: output frozen while the input moves fails after ~2 s', () => {
+    const aec = createMeteringProcessor();
+    // A -60 dB output stage underflows a near-denormal input to exact zeros
+    aec.setOutputGain(-60);
+    const tiny = new Float32Array(FRAME).map((_, i) => (i % 2 ? 1e-44 : -1e-44));
+    for (let i = 0; i < 150; i++) aec.processCaptureAudio(tiny);
+    assertHealth(aec.healthCheck(), []);
+    for (let i = 0; i < 60; i++) aec.processCaptureAudio(tiny);
+    assertHealth(aec.healthCheck(), ['outputNotStuck']);
+    aec.destroy();
+});
+
+test('healthCheck: output above input fails, autoRecover resets the canceller', () => {
+    const aec = new native.AudioCaptureAddon({
+        engine: 'nlms', enableNs: false, filterLengthBlocks: 32, adaptationRate: 2, doubleTalkSensitivity: 0,
+    });
+    const rng = makeRng(77);
+    const train = whiteNoise(0.4, SAMPLE_RATE * 3, rng);
+    processPair(aec, train, echo(train, 96, 0.8));
+
+    // The echo path vanishes and the slowed filter keeps subtracting an echo
+    // ~39 dB above the quiet mic, decaying ~10 dB/s
+    aec.setAdaptationRate(0.1);
+    const ref = whiteNoise(0.4, SAMPLE_RATE * 21 / 10, rng);
+    processPair(aec, ref, whiteNoise(0.0035, ref.length, rng));
+    const failed = aec.healthCheck(true);
+    assertHealth(failed, ['outputNotAboveInput']);
+    assert.strictEqual(failed.recovered, true);
+
+    // The reset filter starts from zero, so the quiet mic passes through
+    processPair(aec, ref, whiteNoise(0.0035, ref.length, rng));
+    const report = aec.healthCheck(true);
+    assertHealth(report, []);
+    assert.strictEqual(report.recovered, false);
+    aec.destroy();
+});
+
+test('events: converged, diverged and echoDetected fire once per transition', () => {
+    const aec = new native.AudioCaptureAddon({ engine: 'nlms', filterLengthBlocks: 4, convergedHoldMs: 500 });
+    const events = [];
+    aec.setEventCallback((event) => events.push(event));
+
+    // The echo moves out of the 256-tap filter's reach for 4 s, then back
+    const ref = speechNoise(0.1, SAMPLE_RATE * 12, makeRng(78));
+    const near = echo(ref, 96, 0.4);
+    const far = echo(ref, 3000, 0.4);
+    const mic = near.slice();
+    mic.set(far.subarray(SAMPLE_RATE * 4, SAMPLE_RATE * 8), SAMPLE_RATE * 4);
+    processPair(aec, ref, mic);
+
+    assert.deepStrictEqual(events.map((event) => event.type), ['converged', 'diverged', 'echoDetected', 'converged']);
+    assert.ok(events[0].erleDb >= 10, `converged at ${events[0].erleDb} dB`);
+    assert.ok(events[1].erleDb < 10, `diverged at ${events[1].erleDb} dB`);
+    const detected = events[2];
+    assert.ok(detected.erleDb < 6, `echoDetected at ${detected.erleDb} dB`);
+    assert.ok(detected.correlation >= 0.5, `correlation ${detected.correlation}`);
+    assertNear(detected.lagMs, 3000 / 48, 1, 'echoDetected lag');
+    assert.ok(!('correlation' in events[0]), 'correlation only on echoDetected');
+    aec.destroy();
+});
+
+test('events: thresholds come from create options, null detaches the callback', () => {
+    const aec = new native.AudioCaptureAddon({ engine: 'nlms', filterLengthBlocks: 4, convergedErleDb: 100 });
+    const events = [];
+    aec.setEventCallback((event) => events.push(event));
+    const ref = speechNoise(0.1, SAMPLE_RATE * 4, makeRng(79));
+    processPair(aec, ref, echo(ref, 96, 0.4));
+    assert.deepStrictEqual(events, [], 'ERLE never reaches 100 dB');
+    aec.destroy();
+
+    const detached = new native.AudioCaptureAddon({ engine: 'nlms', filterLengthBlocks: 4, convergedHoldMs: 500 });
+    detached.setEventCallback((event) => events.push(event));
+    detached.setEventCallback(null);
+    processPair(detached, ref, echo(ref, 96, 0.4));
+    assert.deepStrictEqual(events, []);
+    detached.destroy();
+});
+
+// Feed render for every chunk but skip capture for chunks [dropFrom, dropTo),
+// as a renderer that fell behind would; notify reports the skips
+function processWithDrop(aec, ref, mic, dropFrom, dropTo, notify) {
+    const out = [];
+    const refChunks = chunks(ref);
+    const micChunks = chunks(mic);
+    for (let i = 0; i < refChunks.length; i++) {
+        aec.processRenderAudio(refChunks[i]);
+        if (i < dropFrom || i >= dropTo) {
+            out.push(aec.processCaptureAudio(micChunks[i]));
+        } else if (notify) {
+            aec.notifyDroppedInput(micChunks[i].length);
+        }
+    }
+    return out;
+}
+
+function firstAbove(samples, threshold) {
+    return samples.findIndex((value) => Math.abs(value) > threshold);
+}
+
+test('notifyDroppedInput: a marker tone stays aligned across a dropped span', () => {
+    for (const notify of [true, false]) {
+        const aec = createMeteringProcessor();
+        aec.enableDebugCapture(10);
+        const ref = new Float32Array(SAMPLE_RATE * 6);
+        ref.set(sine(1000, 0.5, SAMPLE_RATE / 20), SAMPLE_RATE * 4);
+        processWithDrop(aec, ref, ref, 200, 220, notify);
+
+        const capture = aec.dumpDebugCapture();
+        const micMarker = firstAbove(capture.mic, 0.05);
+        assert.strictEqual(micMarker, SAMPLE_RATE * 4 - 20 * FRAME + 1, 'sine starts at 0');
+        const refMarker = firstAbove(capture.reference, 0.05);
+        assert.strictEqual(refMarker - micMarker, notify ? 0 : 20 * FRAME, `notify=${notify}`);
+
+        const metrics = aec.getMetrics();
+        assert.strictEqual(metrics.droppedInputEvents, notify ? 20 : 0);
+        assert.strictEqual(metrics.droppedInputSamples, notify ? 20 * FRAME : 0);
+        aec.destroy();
+    }
+});
+
+test('notifyDroppedInput: NLMS keeps cancelling after the drop only when told', () => {
+    const ref = speechNoise(0.1, SAMPLE_RATE * 6, makeRng(80));
+    const mic = echo(ref, 96, 0.5);
+    const attenuation = {};
+    for (const notify of [true, false]) {
+        const aec = new native.AudioCaptureAddon({ engine: 'nlms', filterLengthBlocks: 4 });
+        const out = processWithDrop(aec, ref, mic, 300, 320, notify);
+        // Last second of output against the mic it came from
+        const tail = Float32Array.from(out.slice(-100).flatMap((chunk) => Array.from(chunk)));
+        attenuation[notify] = attenuationDb(mic.subarray(mic.length - tail.length), tail);
+        aec.destroy();
+    }
+    assert.ok(attenuation[true] >= 10, `with notify ${attenuation[true].toFixed(1)} dB`);
+    assert.ok(attenuation[false] < 3, `without notify ${attenuation[false].toFixed(1)} dB`);
+});
+
+test('notifyDroppedInput: rejects negative and non-numeric counts', () => {
+    const aec = createMeteringProcessor();
+    assert.throws(() => aec.notifyDroppedInput(-1),
+        (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE');
+    assert.throws(() => aec.notifyDroppedInput('480'),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+    aec.destroy();
+});
+
+const METRICS_JSON_SCHEMA = {
+    integer: [
+        'schemaVersion', 'sampleRate', 'skippedSilentChunks', 'silenceBypassChunks', 'latencyMaxChunkSamples',
+        'underrunCount', 'underrunSamples', 'underrunZeroFillCount', 'underrunHoldCount', 'overflowCount',
+        'overflowSamples', 'inputSamplesProcessed', 'outputSamplesProduced', 'referenceSamplesConsumed',
+        'overloadEvents', 'pathChangesDetected', 'adaptationThrottledChunks', 'idleResets', 'emptyInputCalls',
+        'droppedInputEvents', 'droppedInputSamples', 'scratchGrowCount',
+    ],
+    // Non-finite values are written as null
+    number: [
+        'echoReturnLoss', 'echoReturnLossEnhancement', 'renderDelayMs', 'calibratedDelayMs', 'msSinceLastReference',
+        'rmsLevel', 'peakLevel', 'inputRmsDbfs', 'inputPeakDbfs', 'outputRmsDbfs', 'outputPeakDbfs',
+        'inputPeakInstantDbfs', 'inputPeakHoldDbfs', 'outputPeakInstantDbfs', 'outputPeakHoldDbfs',
+        'loudnessGainDb', 'agcGainDb', 'limiterGainReductionDb', 'limiterMaxGainReductionDb', 'deadInputMs',
+        'noiseFloorDbfs', 'erleInstantDb', 'erleAverageDb', 'latencyP50Ms', 'latencyP95Ms', 'latencyP99Ms',
+        'latencyMaxMs', 'refBufferFillRatio', 'referenceDriftSamples', 'referenceDriftPerSecond', 'realTimeFactor',
+        'realTimeFactorRecent', 'audioProcessedMs', 'audioSecondsProcessed', 'timeSinceConvergedMs',
+    ],
+    boolean: ['aecConverged', 'deadInput', 'overloaded', 'overloadBypass', 'suboptimalConfig', 'engineActive'],
+    string: ['configFingerprint', 'label', 'engine', 'convergenceState', 'initError'],
+};
+
+test('getMetricsJson: parses to the documented schema', () => {
+    const aec = new native.AudioCaptureAddon({ label: 'json "test"\n' });
+    const ref = speechNoise(0.1, SAMPLE_RATE, makeRng(81));
+    processPair(aec, ref, echo(ref, 96, 0.5));
+    const json = JSON.parse(aec.getMetricsJson());
+
+    const expectedKeys = [...Object.values(METRICS_JSON_SCHEMA).flat(), 'latencyHistogram'].sort();
+    assert.deepStrictEqual(Object.keys(json).sort(), expectedKeys);
+    for (const key of METRICS_JSON_SCHEMA.integer) {
+        assert.ok(Number.isInteger(json[key]) && json[key] >= 0, `${key}: ${json[key]}`);
+    }
+    for (const key of METRICS_JSON_SCHEMA.number) {
+        assert.ok(json[key] === null || typeof json[key] === 'number', `${key}: ${json[key]}`);
+    }
+    for (const key of METRICS_JSON_SCHEMA.boolean) assert.strictEqual(typeof json[key], 'boolean', key);
+    for (const key of METRICS_JSON_SCHEMA.string) assert.strictEqual(typeof json[key], 'string', key);
+    assert.strictEqual(json.latencyHistogram.length, 6);
+    assert.ok(json.latencyHistogram.every(Number.isInteger));
+
+    assert.strictEqual(json.schemaVersion, 1);
+    assert.strictEqual(json.sampleRate, SAMPLE_RATE);
+    assert.strictEqual(json.label, 'json "test"\n', 'label is escaped and round-trips');
+    assert.match(json.configFingerprint, /^[0-9a-f]{16}$/);
+
+    // Same names and values as getMetrics
+    const metrics = aec.getMetrics();
+    for (const key of [...METRICS_JSON_SCHEMA.integer, ...METRICS_JSON_SCHEMA.number]) {
+        if (!(key in metrics) || json[key] === null) continue;
+        assertNear(json[key], metrics[key], 1e-6 * Math.max(1, Math.abs(metrics[key])), key);
+    }
+    assert.deepStrictEqual(json.latencyHistogram, Array.from(metrics.latencyHistogram));
+    aec.destroy();
+});
+
+test('getMetricsJson: the config fingerprint tracks the active settings', () => {
+    const fingerprint = (aec) => JSON.parse(aec.getMetricsJson()).configFingerprint;
+    const a = new native.AudioCaptureAddon();
+    const b = new native.AudioCaptureAddon();
+    assert.strictEqual(fingerprint(a), fingerprint(b));
+    b.setFilterLength(12);
+    assert.notStrictEqual(fingerprint(a), fingerprint(b));
+    const rate = new native.AudioCaptureAddon({ sampleRate: 16000 });
+    assert.notStrictEqual(fingerprint(a), fingerprint(rate));
+    for (const aec of [a, b, rate]) aec.destroy();
+});
+
+test('scratch reuse: output is identical whatever the scratch was sized for', () => {
+    const ref = speechNoise(0.1, SAMPLE_RATE * 2, makeRng(82));
+    const mic = echo(ref, 96, 0.5);
+    const sizes = [FRAME, 12288, 160, 1, FRAME * 3, 479];
+    const run = (aec) => {
+        const out = [];
+        for (let offset = 0, i = 0; offset < mic.length; i++) {
+            const end = Math.min(offset + sizes[i % sizes.length], mic.length);
+            aec.processRenderAudio(ref.subarray(offset, end));
+            out.push(...aec.processCaptureAudio(mic.subarray(offset, end)));
+            offset = end;
+        }
+        return out;
+    };
+    const growing = new native.AudioCaptureAddon();
+    const reserved = new native.AudioCaptureAddon();
+    reserved.ensureCapacity(12288);
+    for (const aec of [growing, reserved]) aec.setDeterministic(true, 1);
+    assert.deepStrictEqual(run(growing), run(reserved));
+    growing.destroy();
+    reserved.destroy();
+});
+
+test('scratch reuse: steady-state processInto allocates no buffers', () => {
+    const aec = new native.AudioCaptureAddon();
+    const ref = speechNoise(0.1, FRAME, makeRng(83));
+    const mic = echo(ref, 96, 0.5);
+    const output = new Float32Array(FRAME);
+    aec.ensureCapacity(FRAME);
+    for (let i = 0; i < 100; i++) {
+        aec.processRenderAudio(ref);
+        aec.processInto(mic, output);
+    }
+
+    const calls = 5000;
+    const grows = aec.getMetrics().scratchGrowCount;
+    const before = process.memoryUsage().arrayBuffers;
+    for (let i = 0; i < calls; i++) {
+        aec.processRenderAudio(ref);
+        aec.processInto(mic, output);
+    }
+    const growth = process.memoryUsage().arrayBuffers - before;
+    // The native buffers never grew, and no per-call result was created:
+    // processCaptureAudio would make calls * FRAME * 4 bytes of them
+    assert.strictEqual(aec.getMetrics().scratchGrowCount, grows);
+    assert.ok(growth < 64 * 1024, `${growth} bytes of ArrayBuffers over ${calls} calls`);
+
+    // A chunk larger than ensureCapacity covered does grow them, once
+    const large = new Float32Array(FRAME * 8);
+    aec.processRenderAudio(large);
+    aec.processInto(large, large);
+    assert.strictEqual(aec.getMetrics().scratchGrowCount, grows + 1);
+    aec.processRenderAudio(large);
+    aec.processInto(large, large);
+    assert.strictEqual(aec.getMetrics().scratchGrowCount, grows + 1);
+    aec.destroy();
+});
+
+test('convergenceState: walks cold, adapting, converged, diverged in step with the events', () => {
+    const aec = new native.AudioCaptureAddon({
+        engine: 'nlms', filterLengthBlocks: 4, convergedHoldMs: 500, convergenceColdMs: 1000,
+    });
+    const events = [];
+    aec.setEventCallback((event) => {
+        if (event.type !== 'echoDetected') events.push(event.type);
+    });
+
+    // 2 s of unrelated mic (nothing to cancel), 3 s of echo, 3 s of echo out of
+    // the filter's reach, 3 s of echo again
+    const second = SAMPLE_RATE;
+    const ref = speechNoise(0.1, second * 11, makeRng(84));
+    const mic = echo(ref, 96, 0.4);
+    mic.set(speechNoise(0.1, second * 2, makeRng(85)), 0);
+    mic.set(echo(ref, 3000, 0.4).subarray(second * 5, second * 8), second * 5);
+
+    const states = [];
+    const refChunks = chunks(ref);
+    const micChunks = chunks(mic);
+    for (let i = 0; i < micChunks.length; i++) {
+        aec.processRenderAudio(refChunks[i]);
+        aec.processCaptureAudio(micChunks[i]);
+        const metrics = aec.getMetrics();
+        states.push({ state: metrics.convergenceState, since: metrics.timeSinceConvergedMs, events: events.length });
+    }
+
+    const changes = states.filter((entry, i) => i === 0 || entry.state !== states[i - 1].state);
+    assert.deepStrictEqual(changes.map((entry) => entry.state), ['cold', 'adapting', 'converged', 'diverged', 'converged']);
+    assert.strictEqual(states.findIndex((entry) => entry.state === 'adapting'), 99, 'cold for the first 1000 ms');
+    assert.deepStrictEqual(events, ['converged', 'diverged', 'converged']);
+
+    // Each converged/diverged state starts on the chunk that fired its event
+    let convergedAt = -1;
+    for (let i = 1; i < states.length; i++) {
+        const { state, since } = states[i];
+        if (state !== states[i - 1].state && state !== 'adapting') {
+            assert.strictEqual(states[i].events, states[i - 1].events + 1, `event with the ${state} transition at chunk ${i}`);
+        }
+        if (state !== 'converged') {
+            assert.strictEqual(since, -1, `chunk ${i}`);
+            convergedAt = -1;
+            continue;
+        }
+        if (convergedAt < 0) convergedAt = i;
+        assert.strictEqual(since, (i - convergedAt + 1) * 10, `timeSinceConvergedMs at chunk ${i}`);
+    }
+
+    aec.resetAEC();
+    const reset = aec.getMetrics();
+    assert.strictEqual(reset.convergenceState, 'cold');
+    assert.strictEqual(reset.timeSinceConvergedMs, -1);
+    aec.destroy();
+});
+
+// One second of 10 ms render chunks against capture totalling captureSamples
+function feedSecond(aec, captureSamples) {
+    const render = new Float32Array(FRAME);
+    let remaining = captureSamples;
+    for (let i = 0; i < 100; i++) {
+        aec.processRenderAudio(render);
+        const size = i === 99 ? remaining : Math.min(FRAME, remaining);
+        if (size > 0) aec.processCaptureAudio(new Float32Array(size));
+        remaining -= size;
+    }
+}
+
+test('reference drift: feeding 48 000/s against 47 990/s reports about +10/s', () => {
+    const aec = createMeteringProcessor();
+    for (let s = 0; s < 20; s++) feedSecond(aec, 47990);
+    let metrics = aec.getMetrics();
+    assert.strictEqual(metrics.referenceDriftSamples, 200);
+    assertNear(metrics.referenceDriftPerSecond, 10, 0.5, 'referenceDriftPerSecond');
+
+    aec.resetMetrics();
+    metrics = aec.getMetrics();
+    assert.strictEqual(metrics.referenceDriftSamples, 0);
+    assert.strictEqual(metrics.referenceDriftPerSecond, 0);
+
+    // Consuming faster than the reference arrives drifts the other way
+    for (let s = 0; s < 10; s++) feedSecond(aec, 48010);
+    assertNear(aec.getMetrics().referenceDriftPerSecond, -10, 0.5, 'negative drift');
+    aec.destroy();
+});
+
+test('reference drift: the rate covers only the last minute', () => {
+    const aec = createMeteringProcessor();
+    for (let s = 0; s < 70; s++) feedSecond(aec, 47990);
+    for (let s = 0; s < 30; s++) feedSecond(aec, 48000);
+    const metrics = aec.getMetrics();
+    assert.strictEqual(metrics.referenceDriftSamples, 700, 'the absolute count is cumulative');
+    // Half of the last 60 s drifted at +10/s
+    assertNear(metrics.referenceDriftPerSecond, 5, 0.5, 'referenceDriftPerSecond');
+    aec.destroy();
+});
+
+test('lifetime counters: cross-check after a scripted sequence of calls', () => {
+    const aec = new native.AudioCaptureAddon();
+    aec.setSilentChunkSkip(true);
+    aec.setEmptyInputMode('silence');
+    const noise = speechNoise(0.1, FRAME, makeRng(86));
+    const quiet = new Float32Array(FRAME);
+
+    for (let i = 0; i < 10; i++) {
+        aec.processRenderAudio(noise);
+        aec.processCaptureAudio(noise);
+    }
+    for (let i = 0; i < 2; i++) {                       // Silent, skipped
+        aec.processRenderAudio(quiet);
+        aec.processCaptureAudio(quiet);
+    }
+    for (let i = 0; i < 2; i++) aec.processCaptureAudio(noise);  // No reference to consume
+    aec.processRenderAudio(noise);
+    aec.processCaptureAudio(noise, { bypass: true });
+    aec.processRenderAudio(noise.subarray(0, 160));
+    aec.processCaptureAudio(noise.subarray(0, 160));
+    assert.strictEqual(aec.processCaptureAudio(new Float32Array(0)).length, FRAME, 'silence filler');
+    aec.processRenderAudio(noise);
+    aec.notifyDroppedInput(FRAME);                       // Its reference is discarded, not consumed
+
+    let metrics = aec.getMetrics();
+    assert.ok(metrics.skippedSilentChunks >= 1, 'the silent chunks took the skip path');
+    const input = 10 * FRAME + 2 * FRAME + 2 * FRAME + FRAME + 160;
+    assert.strictEqual(metrics.inputSamplesProcessed, input);
+    assert.strictEqual(metrics.outputSamplesProduced, input + FRAME);
+    assert.strictEqual(metrics.referenceSamplesConsumed, 10 * FRAME + 2 * FRAME + FRAME + 160);
+    assertNear(metrics.audioSecondsProcessed, input / SAMPLE_RATE, 1e-9, 'audioSecondsProcessed');
+
+    // Lifetime: resetMetrics leaves them, a rate change keeps the seconds right
+    aec.resetMetrics();
+    aec.setSampleRate(16000);
+    aec.processRenderAudio(noise.subarray(0, 160));
+    aec.processCaptureAudio(noise.subarray(0, 160));
+    metrics = aec.getMetrics();
+    assert.strictEqual(metrics.inputSamplesProcessed, input + 160);
+    assertNear(metrics.audioSecondsProcessed, input / SAMPLE_RATE + 0.01, 1e-9, 'seconds across rates');
+    aec.destroy();
+});
+
+test('destroy: every handle method rejects a destroyed handle with a coded error', () => {
+    const aec = new native.AudioCaptureAddon();
+    aec.destroy();
+    aec.destroy();  // Idempotent
+
+    // Device queries and the no-op start/stop don't touch the processor
+    const exempt = new Set(['constructor', 'destroy', 'getDevices', 'stopMicrophoneCapture', 'start', 'stop']);
+    const methods = Object.getOwnPropertyNames(native.AudioCaptureAddon.prototype)
+        .filter((name) => !exempt.has(name) && typeof aec[name] === 'function');
+    assert.ok(methods.length >= 60, `${methods.length} methods`);
+    const input = new Float32Array(FRAME);
+    for (const name of methods) {
+        assert.throws(() => aec[name](input, input),
+            (err) => err instanceof Error && err.code === 'ERR_AEC_HANDLE_DESTROYED', name);
+    }
+});
+
+test('mixReferences: sums saturate at the int16 bounds', () => {
+    // [sources..., expected] per output sample
+    const cases = [
+        [[32767, 32767, 32767, 32767], 32767],
+        [[-32768, -32768, -32768], -32768],
+        [[32000, 767], 32767],
+        [[32000, 768], 32767],
+        [[-32000, -768], -32768],
+        [[-32000, -769], -32768],
+        [[32767, -32768], -1],
+        [[30000, 30000, -30000], 30000],   // Clamped only after the whole sum
+        [[1, 2, 3], 6],
+    ];
+    const sourceCount = 4;
+    const sources = Array.from({ length: sourceCount }, (_, s) => Int16Array.from(cases, ([values]) => values[s] || 0));
+    const output = native.mixReferences(sources);
+    assert.ok(output instanceof Int16Array);
+    assert.deepStrictEqual(Array.from(output), cases.map(([, expected]) => expected));
+});
+
+test('mixReferences: shorter sources count as silence, bad input throws', () => {
+    const output = native.mixReferences([Int16Array.of(1000, 1000, 1000), Int16Array.of(32767)]);
+    assert.deepStrictEqual(Array.from(output), [32767, 1000, 1000]);
+    assert.deepStrictEqual(Array.from(native.mixReferences([])), []);
+    assert.deepStrictEqual(Array.from(native.mixReferences([Int16Array.of(-5, 7)])), [-5, 7]);
+
+    assert.throws(() => native.mixReferences(Int16Array.of(1)),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+    assert.throws(() => native.mixReferences([Int16Array.of(1), new Float32Array(1)]),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+});
+
+test('processAsync: overlapping calls resolve in order, bit-exact with the sync path', async () => {
+    const ref = speechNoise(0.1, SAMPLE_RATE * 2, makeRng(87));
+    const mic = echo(ref, 96, 0.5);
+    const refChunks = chunks(ref);
+    const micChunks = chunks(mic);
+    const asyncAec = new native.AudioCaptureAddon();
+    const syncAec = new native.AudioCaptureAddon();
+    for (const aec of [asyncAec, syncAec]) aec.setDeterministic(true, 1);
+
+    const asyncOut = [];
+    const resolved = [];
+    const syncOut = [];
+    // Rounds of 10 render chunks, then 10 capture chunks in flight at once
+    for (let start = 0; start < micChunks.length; start += 10) {
+        const end = Math.min(start + 10, micChunks.length);
+        const pending = [];
+        for (let i = start; i < end; i++) {
+            asyncAec.processRenderAudio(refChunks[i]);
+            syncAec.processRenderAudio(refChunks[i]);
+        }
+        for (let i = start; i < end; i++) {
+            pending.push(asyncAec.processAsync(micChunks[i]).then((output) => {
+                resolved.push(i);
+                asyncOut[i] = output;
+            }));
+            syncOut[i] = syncAec.processCaptureAudio(micChunks[i]);
+        }
+        await Promise.all(pending);
+    }
+
+    assert.deepStrictEqual(resolved, micChunks.map((_, i) => i), 'resolution order');
+    for (let i = 0; i < micChunks.length; i++) {
+        assert.deepStrictEqual(asyncOut[i], syncOut[i], `chunk ${i}`);
+    }
+    asyncAec.destroy();
+    syncAec.destroy();
+});
+
+test('processAsync: jobs still queued at destroy reject with a coded error', async () => {
+    const aec = new native.AudioCaptureAddon();
+    const input = speechNoise(0.1, FRAME, makeRng(88));
+    const results = await Promise.allSettled(Array.from({ length: 8 }, () => aec.processAsync(input)));
+    assert.ok(results.every((result) => result.status === 'fulfilled'));
+
+    const pending = Array.from({ length: 8 }, () => aec.processAsync(input));
+    aec.destroy();
+    const settled = await Promise.allSettled(pending);
+    const rejected = settled.filter((result) => result.status === 'rejected');
+    assert.ok(rejected.length >= 7, `${rejected.length} of 8 rejected; only the running job may finish`);
+    for (const { reason } of rejected) assert.strictEqual(reason.code, 'ERR_AEC_HANDLE_DESTROYED');
+    assert.throws(() => aec.processAsync(input), (err) => err.code === 'ERR_AEC_HANDLE_DESTROYED');
+});
+
+test('processMany: batch output equals sequential calls on the same input', () => {
+    const ref = speechNoise(0.1, SAMPLE_RATE * 2, makeRng(89));
+    const mic = echo(ref, 96, 0.5);
+    const micChunks = chunks(mic, 320);  // Not frame-sized, so frames straddle chunks
+    const refChunks = chunks(ref, 320);
+    const batch = new native.AudioCaptureAddon();
+    const sequential = new native.AudioCaptureAddon();
+    for (const aec of [batch, sequential]) aec.setDeterministic(true, 1);
+
+    for (let start = 0; start < micChunks.length; start += 15) {
+        const round = micChunks.slice(start, start + 15);
+        for (const chunk of refChunks.slice(start, start + 15)) {
+            batch.processRenderAudio(chunk);
+            sequential.processRenderAudio(chunk);
+        }
+        round.splice(5, 0, new Float32Array(0));  // Empty input mid-batch
+        const outputs = batch.processMany(round);
+        assert.strictEqual(outputs.length, round.length);
+        round.forEach((chunk, i) => {
+            assert.deepStrictEqual(outputs[i], sequential.processCaptureAudio(chunk), `chunk ${start}+${i}`);
+        });
+    }
+    assert.strictEqual(batch.getMetrics().inputSamplesProcessed, sequential.getMetrics().inputSamplesProcessed);
+    batch.destroy();
+    sequential.destroy();
+});
+
+test('processMany: validates every element before processing any', () => {
+    const aec = new native.AudioCaptureAddon();
+    assert.deepStrictEqual(aec.processMany([]), []);
+    const good = new Float32Array(FRAME);
+    assert.throws(() => aec.processMany([good, good, new Int16Array(FRAME)]),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT' && /element 2/.test(err.message));
+    assert.strictEqual(aec.getMetrics().inputSamplesProcessed, 0, 'nothing processed');
+    aec.destroy();
+});
+
+test('reconfigure: a live-only change applies without a rebuild', () => {
+    const aec = new native.AudioCaptureAddon();
+    aec.setDeterministic(true, 1);
+    const ref = speechNoise(0.1, SAMPLE_RATE * 4, makeRng(90));
+    const mic = echo(ref, 96, 0.5);
+    const half = SAMPLE_RATE * 2;
+    processPair(aec, ref.subarray(0, half), mic.subarray(0, half));
+    const before = aec.getMetrics();
+
+    const result = aec.reconfigure({ doubleTalkSensitivity: 0.3, convergedErleDb: 15 });
+    assert.deepStrictEqual(result, { rebuilt: false, rebuildFields: [] });
+    const config = aec.getConfig();
+    assertNear(config.doubleTalkSensitivity, 0.3, 1e-6, 'doubleTalkSensitivity');
+    assert.strictEqual(config.convergedErleDb, 15);
+
+    // Still converged: the very next 200 ms is attenuated like the last
+    const span = SAMPLE_RATE / 5;
+    const out = processPair(aec, ref.subarray(half, half + span), mic.subarray(half, half + span));
+    assert.ok(attenuationDb(mic.subarray(half, half + span), out) >= 10, 'filter kept');
+    assert.strictEqual(aec.getMetrics().inputSamplesProcessed, before.inputSamplesProcessed + span);
+    aec.destroy();
+});
+
+// processPair in half-second slices, yielding between them so the output
+// callback queue (64 frames) drains instead of dropping
+async function processPairDraining(aec, ref, mic, chunkSize = FRAME) {
+    const slice = SAMPLE_RATE / 2;
+    for (let start = 0; start < mic.length; start += slice) {
+        const end = Math.min(start + slice, mic.length);
+        processPair(aec, ref.subarray(start, end), mic.subarray(start, end), chunkSize);
+        await settle(20);
+    }
+}
+
+test('reconfigure: rebuild-forcing fields are reported, metrics and callbacks survive', async () => {
+    const aec = new native.AudioCaptureAddon();
+    const ref = speechNoise(0.1, SAMPLE_RATE * 2, makeRng(91));
+    const mic = echo(ref, 96, 0.5);
+    const frames = [];
+    aec.setOutputCallback((frame) => frames.push(frame.length));
+    await processPairDraining(aec, ref.subarray(0, SAMPLE_RATE), mic.subarray(0, SAMPLE_RATE));
+
+    const result = aec.reconfigure({ engine: 'nlms', filterLengthBlocks: 12, convergedHoldMs: 1000 });
+    assert.strictEqual(result.rebuilt, true);
+    assert.deepStrictEqual(result.rebuildFields, ['engine', 'filterLengthBlocks']);
+    const config = aec.getConfig();
+    assert.strictEqual(config.engine, 'nlms');
+    assert.strictEqual(config.filterLengthBlocks, 12);
+    assert.strictEqual(config.convergedHoldMs, 1000);
+    assert.strictEqual(aec.getMetrics().engine, 'nlms');
+
+    await processPairDraining(aec, ref.subarray(SAMPLE_RATE), mic.subarray(SAMPLE_RATE));
+    assert.strictEqual(aec.getMetrics().inputSamplesProcessed, SAMPLE_RATE * 2, 'metrics kept');
+    assert.strictEqual(frames.length, SAMPLE_RATE * 2 / FRAME, 'output callback kept');
+    aec.destroy();
+});
+
+test('reconfigure: invalid settings change nothing', () => {
+    const aec = new native.AudioCaptureAddon();
+    const before = aec.getConfig();
+    assert.throws(() => aec.reconfigure({ engine: 'speex' }),
+        (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE');
+    assert.throws(() => aec.reconfigure({ sampleRate: 44100 }),
+        (err) => err instanceof RangeError && err.code === 'ERR_AEC_UNSUPPORTED_SAMPLE_RATE');
+    assert.throws(() => aec.reconfigure({ filterLength: 12 }),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_UNKNOWN_OPTION');
+    assert.throws(() => aec.reconfigure('nlms'),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+    assert.deepStrictEqual(aec.getConfig(), before);
+    aec.destroy();
+});
+
+test('output callback: constant frames whatever the chunk sizes, samples match', async () => {
+    const aec = new native.AudioCaptureAddon();
+    const frames = [];
+    aec.setOutputCallback((frame, info) => frames.push({ frame, ...info }));
+
+    const ref = speechNoise(0.1, SAMPLE_RATE * 2, makeRng(92));
+    const mic = echo(ref, 96, 0.5);
+    const outputs = [];
+    const sizes = [320, 1000, 7, FRAME, 2049];
+    for (let offset = 0, i = 0, sinceYield = 0; offset < mic.length; i++) {
+        const end = Math.min(offset + sizes[i % sizes.length], mic.length);
+        aec.processRenderAudio(ref.subarray(offset, end));
+        const input = mic.subarray(offset, end);
+        if (i % 3 === 0) {
+            const output = new Float32Array(input.length);
+            aec.processInto(input, output);
+            outputs.push(output);
+        } else {
+            outputs.push(aec.processCaptureAudio(input));
+        }
+        sinceYield += end - offset;
+        offset = end;
+        if (sinceYield >= SAMPLE_RATE / 4) {
+            await settle(20);
+            sinceYield = 0;
+        }
+    }
+    outputs.push(await aec.processAsync(new Float32Array(FRAME - (mic.length % FRAME))));
+    await settle();
+
+    assert.ok(frames.every(({ frame }) => frame.length === FRAME), 'every frame is frameSize');
+    assert.deepStrictEqual(frames.map(({ sequence }) => sequence), frames.map((_, i) => i));
+    assert.ok(frames.every(({ droppedFrames }) => droppedFrames === 0));
+    const produced = Float32Array.from(outputs.flatMap((output) => Array.from(output)));
+    assert.strictEqual(frames.length * FRAME, produced.length, 'total samples');
+    const delivered = Float32Array.from(frames.flatMap(({ frame }) => Array.from(frame)));
+    assert.deepStrictEqual(delivered, produced);
+    aec.destroy();
+});
+
+test('output callback: a stalled consumer drops and counts frames', async () => {
+    const aec = createMeteringProcessor();
+    const frames = [];
+    aec.setOutputCallback((frame, info) => frames.push(info));
+    for (let i = 0; i < 100; i++) aec.processCaptureAudio(new Float32Array(FRAME));  // No yield
+    await settle();
+    assert.ok(frames.length >= 1 && frames.length < 100, `${frames.length} delivered`);
+
+    aec.processCaptureAudio(new Float32Array(FRAME));
+    await settle();
+    const last = frames[frames.length - 1];
+    assert.strictEqual(last.sequence, 100);
+    assert.strictEqual(last.droppedFrames, 100 - (frames.length - 1), 'dropped frames are counted');
+
+    aec.setOutputCallback(null);
+    aec.processCaptureAudio(new Float32Array(FRAME));
+    await settle();
+    assert.strictEqual(frames[frames.length - 1], last, 'null detaches');
+    aec.destroy();
+});
+
+test('processAndFeed: matches processCaptureAudio then processRenderAudio by hand', () => {
+    for (const gain of [undefined, 1, 0.35]) {
+        const fed = new native.AudioCaptureAddon();
+        const manual = new native.AudioCaptureAddon();
+        for (const aec of [fed, manual]) aec.setDeterministic(true, 1);
+        // The native gain is a float; the product of two floats is exact in a double
+        const scale = gain === undefined ? 1 : Math.fround(gain);
+        const mic = speechNoise(0.1, SAMPLE_RATE * 2, makeRng(93));
+        chunks(mic).forEach((chunk, i) => {
+            const output = fed.processAndFeed(chunk, gain);
+            const expected = manual.processCaptureAudio(chunk);
+            manual.processRenderAudio(expected.map((sample) => sample * scale));
+            assert.deepStrictEqual(output, expected, `gain ${gain}, chunk ${i}`);
+        });
+        const fedMetrics = fed.getMetrics();
+        const manualMetrics = manual.getMetrics();
+        assert.strictEqual(fedMetrics.referenceSamplesConsumed, manualMetrics.referenceSamplesConsumed);
+        assert.strictEqual(fedMetrics.referenceDriftSamples, manualMetrics.referenceDriftSamples);
+        fed.destroy();
+        manual.destroy();
+    }
+});
+
+test('processAndFeed: rejects a negative or non-numeric gain', () => {
+    const aec = new native.AudioCaptureAddon();
+    for (const gain of [-1, NaN, '1']) {
+        assert.throws(() => aec.processAndFeed(new Float32Array(FRAME), gain),
+            (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE', String(gain));
+    }
+    aec.destroy();
+});
+
+test('erle history: the length is capped for the setter and the option', () => {
+    const aec = new native.AudioCaptureAddon({ erleHistoryLength: 4 });
+    assert.strictEqual(aec.getConfig().erleHistoryLength, 4);
+    const ref = speechNoise(0.1, FRAME * 10, makeRng(64));
+    processPair(aec, ref, echo(ref, 96, 0.5));
+    assert.strictEqual(aec.getErleHistory().length, 4);
+
+    aec.setErleHistoryLength(60000);
+    assert.strictEqual(aec.getConfig().erleHistoryLength, 60000);
+    for (const length of [60001, 1e12, Infinity]) {
+        assert.throws(() => aec.setErleHistoryLength(length),
+            (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE', String(length));
+    }
+    assert.throws(() => aec.setErleHistoryLength(-1),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+    assert.strictEqual(aec.getConfig().erleHistoryLength, 60000);
+    aec.destroy();
+
+    assert.throws(() => new native.AudioCaptureAddon({ erleHistoryLength: 1e12 }),
+        (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE');
+});
+
+test('options: unknown keys are rejected by name', () => {
+    for (const key of ['sampleRat', 'enableAEC', 'outputGainDb']) {
+        assert.throws(() => new native.AudioCaptureAddon({ [key]: 1 }),
+            (err) => err instanceof TypeError && err.code === 'ERR_AEC_UNKNOWN_OPTION' && err.message.includes(key), key);
+    }
+});
+
+test('options: wrong types and out-of-range values throw coded errors', () => {
+    const typeErrors = [
+        { sampleRate: '48000' }, { enableAec: 1 }, { engine: 5 }, { label: false }, { levelSmoothingMs: [] },
+    ];
+    for (const options of typeErrors) {
+        assert.throws(() => new native.AudioCaptureAddon(options),
+            (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT', JSON.stringify(options));
+    }
+    const rangeErrors = [
+        { filterLengthBlocks: 2.5 }, { filterLengthBlocks: 0 }, { levelSmoothingMs: -1 }, { peakHoldMs: NaN },
+        { frameDurationMs: 15 }, { engine: 'speex' }, { preset: 'loud' }, { adaptationRate: 5 },
+    ];
+    for (const options of rangeErrors) {
+        assert.throws(() => new native.AudioCaptureAddon(options),
+            (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE', JSON.stringify(options));
+    }
+});
+
+test('options: absent, undefined and null fields resolve to the defaults', () => {
+    const defaults = new native.AudioCaptureAddon().getConfig();
+    for (const options of [{}, { sampleRate: undefined, engine: null, enableNs: undefined }]) {
+        const aec = new native.AudioCaptureAddon(options);
+        assert.deepStrictEqual(aec.getConfig(), defaults, JSON.stringify(options));
+        aec.destroy();
+    }
+    assert.strictEqual(defaults.sampleRate, 48000);
+    assert.strictEqual(defaults.frameDurationMs, 10);
+    assert.strictEqual(defaults.frameSize, 480);
+    assert.strictEqual(defaults.engine, 'webrtc');
+    assert.strictEqual(defaults.preset, 'aggressive');
+    assert.strictEqual(defaults.enableAec, true);
+    assert.strictEqual(defaults.enableNs, true);
+    assert.strictEqual(defaults.enableAgc, false);
+});
+
+// Every failure path of the constructor, the processing calls and the shared
+// API, with the error class and code each must throw
+test('calibrateDelay: applies the estimate on nlms and only reports it on webrtc', () => {
+    const ref = speechNoise(0.1, SAMPLE_RATE, makeRng(67));
+    const mic = echo(ref, FRAME, 0.5);  // 10 ms echo delay
+    for (const engine of ['nlms', 'webrtc']) {
+        const aec = new native.AudioCaptureAddon({ engine });
+        processPair(aec, ref, mic);
+        const estimate = aec.calibrateDelay();
+        assertNear(estimate.delayMs, 10, 0.5, `${engine}: estimated delay`);
+        assert.ok(estimate.correlation > 0.5, `${engine}: correlation ${estimate.correlation}`);
+        assert.strictEqual(estimate.applied, engine === 'nlms', `${engine}: applied`);
+        assertNear(aec.getMetrics().calibratedDelayMs, engine === 'nlms' ? estimate.delayMs : 0, 1e-6,
+            `${engine}: calibratedDelayMs`);
+        aec.destroy();
+    }
+});
+
+test('errors: each failure path throws its documented class and code', () => {
+    const isCoded = (Class, code) => (err) => err instanceof Class && err.code === code;
+    const frame = new Float32Array(FRAME);
+
+    const createCases = [
+        [{ bogus: 1 }, TypeError, 'ERR_AEC_UNKNOWN_OPTION'],
+        [{ sampleRate: '48000' }, TypeError, 'ERR_AEC_INVALID_ARGUMENT'],
+        [{ filterLengthBlocks: 0 }, RangeError, 'ERR_AEC_INVALID_VALUE'],
+        [{ sampleRate: 44100 }, RangeError, 'ERR_AEC_UNSUPPORTED_SAMPLE_RATE'],
+    ];
+    for (const [options, Class, code] of createCases) {
+        assert.throws(() => new native.AudioCaptureAddon(options), isCoded(Class, code), JSON.stringify(options));
+        assert.throws(() => native.createShared(options), isCoded(Class, code), `shared ${JSON.stringify(options)}`);
+    }
+
+    const aec = new native.AudioCaptureAddon({ maxInputSamples: FRAME, strictChunkSize: true, renderGranularity: FRAME });
+    assert.throws(() => aec.processCaptureAudio('x'), isCoded(TypeError, 'ERR_AEC_INVALID_ARGUMENT'));
+    assert.throws(() => aec.processRenderAudio([0]), isCoded(TypeError, 'ERR_AEC_INVALID_ARGUMENT'));
+    assert.throws(() => aec.processCaptureAudio(frame, { strict: true, bogus: 1 }), isCoded(TypeError, 'ERR_AEC_UNKNOWN_OPTION'));
+    assert.throws(() => aec.processInto(frame, new Float32Array(FRAME - 1)), isCoded(RangeError, 'ERR_AEC_BUFFER_LENGTH'));
+    assert.throws(() => aec.processCaptureAudio(new Float32Array(FRAME / 2)), isCoded(RangeError, 'ERR_AEC_BUFFER_LENGTH'));
+    assert.throws(() => aec.processRenderAudio(new Float32Array(FRAME / 2)), isCoded(RangeError, 'ERR_AEC_BUFFER_LENGTH'));
+    assert.throws(() => aec.processCaptureAudio(new Float32Array(FRAME * 2)), isCoded(RangeError, 'ERR_AEC_INPUT_TOO_LARGE'));
+    assert.throws(() => aec.setFilterLength(0), isCoded(RangeError, 'ERR_AEC_INVALID_VALUE'));
+
+    // A state saved under another rate, a bumped format version and garbage
+    const other = new native.AudioCaptureAddon({ sampleRate: 16000 });
+    const versioned = Buffer.from(aec.getState());
+    versioned[4] ^= 0xff;
+    assert.throws(() => aec.setState(other.getState()), isCoded(Error, 'ERR_AEC_STATE_MISMATCH'));
+    assert.throws(() => aec.setState(versioned), isCoded(Error, 'ERR_AEC_STATE_MISMATCH'));
+    assert.throws(() => aec.setState(Buffer.from('garbage')), isCoded(RangeError, 'ERR_AEC_INVALID_VALUE'));
+    other.destroy();
+
+    // Processing from inside a watchdog callback: every chunk reports 20 ms
+    let reentrant = null;
+    aec.setRealtimeWatchdog(1, () => {
+        try {
+            aec.processCaptureAudio(frame);
+        } catch (err) {
+            reentrant = err;
+        }
+    });
+    aec.setDeterministic(true, 0, 20);
+    aec.processCaptureAudio(frame);
+    assert.ok(isCoded(Error, 'ERR_AEC_REENTRANT_CALL')(reentrant), String(reentrant));
+
+    aec.destroy();
+    assert.throws(() => aec.processCaptureAudio(frame), isCoded(Error, 'ERR_AEC_HANDLE_DESTROYED'));
+    assert.throws(() => aec.processRenderAudio(frame), isCoded(Error, 'ERR_AEC_HANDLE_DESTROYED'));
+
+    const id = native.createShared();
+    assert.throws(() => native.processShared(id, 'x'), isCoded(TypeError, 'ERR_AEC_INVALID_ARGUMENT'));
+    native.destroyShared(id);
+    for (const call of [() => native.processShared(id, frame), () => native.feedReferenceShared(id, frame),
+        () => native.getMetricsShared(id), () => native.destroyShared(id)]) {
+        assert.throws(call, isCoded(Error, 'ERR_AEC_HANDLE_DESTROYED'));
+    }
+});
+
+test('strictChunkSize: off by default, so any chunk size is accepted', () => {
+    const aec = new native.AudioCaptureAddon();
+    assert.strictEqual(aec.getConfig().strictChunkSize, false);
+    for (const size of [FRAME, 240, 720, 1]) {
+        aec.processRenderAudio(new Float32Array(size));
+        assert.strictEqual(aec.processCaptureAudio(new Float32Array(size)).length, size, `size ${size}`);
+    }
+    assert.strictEqual(aec.getMetrics().inputSamplesProcessed, FRAME + 240 + 720 + 1);
+    aec.destroy();
+});
+
+test('strictChunkSize: misaligned capture and render chunks throw naming both sizes', () => {
+    const aec = new native.AudioCaptureAddon({ strictChunkSize: true });
+    const isMisaligned = (size, granularity) => (err) => err instanceof RangeError && err.code === 'ERR_AEC_BUFFER_LENGTH' &&
+        err.message.includes(String(size)) && err.message.includes(String(granularity));
+
+    for (const size of [FRAME, 2 * FRAME, 4 * FRAME]) {
+        aec.processRenderAudio(new Float32Array(size));
+        assert.strictEqual(aec.processCaptureAudio(new Float32Array(size)).length, size, `aligned ${size}`);
+    }
+    const accepted = aec.getMetrics();
+    for (const size of [240, 720, FRAME + 1]) {
+        assert.throws(() => aec.processCaptureAudio(new Float32Array(size)), isMisaligned(size, FRAME), `capture ${size}`);
+        assert.throws(() => aec.processInto(new Float32Array(size), new Float32Array(size)), isMisaligned(size, FRAME));
+        assert.throws(() => aec.processRenderAudio(new Float32Array(size)), isMisaligned(size, FRAME), `render ${size}`);
+    }
+    // Rejected chunks are never processed or queued
+    const metrics = aec.getMetrics();
+    assert.strictEqual(metrics.inputSamplesProcessed, accepted.inputSamplesProcessed);
+    aec.processCaptureAudio(new Float32Array(FRAME));
+    assert.strictEqual(aec.getMetrics().referenceSamplesConsumed, accepted.referenceSamplesConsumed);
+    aec.destroy();
+});
+
+test('strictChunkSize: renderGranularity sets the render multiple independently of the frame', () => {
+    const aec = new native.AudioCaptureAddon({ strictChunkSize: true, renderGranularity: 160 });
+    for (const size of [160, 320, FRAME]) aec.processRenderAudio(new Float32Array(size));
+    assert.throws(() => aec.processRenderAudio(new Float32Array(240)),
+        (err) => err instanceof RangeError && err.code === 'ERR_AEC_BUFFER_LENGTH' &&
+            err.message.includes('240') && err.message.includes('160'));
+    // Capture still follows the frame size
+    assert.throws(() => aec.processCaptureAudio(new Float32Array(320)),
+        (err) => err instanceof RangeError && err.code === 'ERR_AEC_BUFFER_LENGTH');
+    aec.destroy();
+
+    // Without strictChunkSize the granularity is not enforced
+    const permissive = new native.AudioCaptureAddon({ renderGranularity: 160 });
+    permissive.processRenderAudio(new Float32Array(240));
+    permissive.destroy();
+});
+
+// Runs source in a worker_threads Worker with the addon loaded as `native`
+// and resolves with the first message it posts
+function runInWorker(source, workerData) {
+    const { Worker } = require('worker_threads');
+    const addonPath = require.resolve('./build/Release/audio_capture_native.node');
+    const worker = new Worker(
+        `const { parentPort, workerData } = require('worker_threads');
+         const native = require(${JSON.stringify(addonPath)});
+         ${source}`,
+        { eval: true, workerData });
+    return new Promise((resolve, reject) => {
+        worker.once('message', (message) => {
+            resolve(message);
+            worker.terminate();
+        });
+        worker.once('error', reject);
+    });
+}
+
+test('shared processors: an id created on the main thread works from a Worker', async () => {
+    const near = speechNoise(0.1, SAMPLE_RATE, makeRng(90));
+    const ref = speechNoise(0.1, SAMPLE_RATE, makeRng(91));
+    const mic = echo(ref, 96, 0.5).map((sample, i) => sample + near[i]);
+    const id = native.createShared({ sampleRate: SAMPLE_RATE });
+    const twin = new native.AudioCaptureAddon({ sampleRate: SAMPLE_RATE });
+    const expected = processPair(twin, ref, mic);
+    twin.destroy();
+
+    const result = await runInWorker(`
+        const { id, ref, mic, frame } = workerData;
+        const output = new Float32Array(mic.length);
+        for (let i = 0; i < mic.length; i += frame) {
+            native.feedReferenceShared(id, ref.subarray(i, i + frame));
+            output.set(native.processShared(id, mic.subarray(i, i + frame)), i);
+        }
+        parentPort.postMessage({ output, metrics: native.getMetricsShared(id) });
+    `, { id, ref, mic, frame: FRAME });
+
+    assert.deepStrictEqual(result.output, expected, 'worker output matches an in-thread processor');
+    assert.strictEqual(result.metrics.inputSamplesProcessed, SAMPLE_RATE);
+    assert.strictEqual(native.getMetricsShared(id).inputSamplesProcessed, SAMPLE_RATE, 'main thread sees the same processor');
+    native.destroyShared(id);
+});
+
+test('shared processors: destroying an id in a Worker invalidates it everywhere', async () => {
+    const isDestroyed = (err) => err instanceof Error && err.code === 'ERR_AEC_HANDLE_DESTROYED';
+    const id = native.createShared();
+    const result = await runInWorker(`
+        native.destroyShared(workerData.id);
+        let code = null;
+        try {
+            native.destroyShared(workerData.id);
+        } catch (err) {
+            code = err.code;
+        }
+        parentPort.postMessage({ code });
+    `, { id });
+
+    assert.strictEqual(result.code, 'ERR_AEC_HANDLE_DESTROYED', 'double destroy throws in the worker');
+    assert.throws(() => native.processShared(id, new Float32Array(FRAME)), isDestroyed);
+    assert.throws(() => native.destroyShared(id), isDestroyed);
+
+    // Ids are never reused
+    const next = native.createShared();
+    assert.ok(next > id);
+    native.destroyShared(next);
+});
+
+test('shared processors: feeding reference from a Worker while capturing loses no samples', async () => {
+    const STRESS_MS = 3000;
+    const id = native.createShared();
+    const flags = new Int32Array(new SharedArrayBuffer(8));  // [started, finished]
+    const fedPromise = runInWorker(`
+        const flags = new Int32Array(workerData.flags);
+        const { id, chunk, durationMs } = workerData;
+        let fed = 0;
+        Atomics.store(flags, 0, 1);
+        Atomics.notify(flags, 0);
+        for (const end = Date.now() + durationMs; Date.now() < end;) {
+            native.feedReferenceShared(id, chunk);
+            fed += chunk.length;
+        }
+        Atomics.store(flags, 1, 1);
+        parentPort.postMessage({ fed });
+    `, { id, chunk: speechNoise(0.1, FRAME, makeRng(95)), durationMs: STRESS_MS, flags: flags.buffer });
+
+    assert.notStrictEqual(Atomics.wait(flags, 0, 0, 10000), 'timed-out', 'worker started');
+    const mic = speechNoise(0.1, FRAME, makeRng(96));
+    let captured = 0;
+    while (Atomics.load(flags, 1) === 0) {
+        assertFinite(native.processShared(id, mic), `chunk ${captured}`);
+        captured++;
+    }
+    const { fed } = await fedPromise;
+
+    // Drain what is still queued; every fed sample is consumed or counted as overflow
+    let metrics = native.getMetricsShared(id);
+    for (let consumed = -1; consumed !== metrics.referenceSamplesConsumed;) {
+        consumed = metrics.referenceSamplesConsumed;
+        native.processShared(id, mic);
+        metrics = native.getMetricsShared(id);
+    }
+    assert.ok(captured > 100 && fed > 100 * FRAME, `captured ${captured} chunks, fed ${fed} samples`);
+    assert.strictEqual(metrics.referenceSamplesConsumed + metrics.overflowSamples, fed);
+    native.destroyShared(id);
+});
+
+// Every engine-touching call, as attempted from inside a callback
+function reentrantCalls(aec, state) {
+    const frame = new Float32Array(FRAME);
+    return {
+        processCaptureAudio: () => aec.processCaptureAudio(frame),
+        processRenderAudio: () => aec.processRenderAudio(frame),
+        processInto: () => aec.processInto(frame, new Float32Array(FRAME)),
+        processMany: () => aec.processMany([frame]),
+        processAsync: () => aec.processAsync(frame),
+        processAndFeed: () => aec.processAndFeed(frame),
+        reconfigure: () => aec.reconfigure({}),
+        setSampleRate: () => aec.setSampleRate(SAMPLE_RATE),
+        setFilterLength: () => aec.setFilterLength(13),
+        applyPreset: () => aec.applyPreset('balanced'),
+        setAdaptationRate: () => aec.setAdaptationRate(1),
+        setNoiseSuppression: () => aec.setNoiseSuppression(true),
+        setEchoCancellationEnabled: () => aec.setEchoCancellationEnabled(true),
+        resetAEC: () => aec.resetAEC(),
+        setState: () => aec.setState(state),
+        destroy: () => aec.destroy(),
+    };
+}
+
+const isReentrant = (err) => err instanceof Error && err.code === 'ERR_AEC_REENTRANT_CALL';
+
+test('re-entrancy: engine calls from a watchdog callback throw instead of recursing', () => {
+    const aec = new native.AudioCaptureAddon();
+    const state = aec.getState();
+    const outcomes = {};
+    aec.setRealtimeWatchdog(1, () => {
+        for (const [name, call] of Object.entries(reentrantCalls(aec, state))) {
+            try {
+                call();
+                outcomes[name] = 'returned';
+            } catch (err) {
+                outcomes[name] = isReentrant(err) ? 'reentrant' : String(err);
+            }
+        }
+        aec.getMetrics();  // Read-only calls stay allowed
+    });
+    aec.setDeterministic(true, 0, 20);  // Every 10 ms chunk overruns its budget
+    aec.processCaptureAudio(new Float32Array(FRAME));
+    for (const name of Object.keys(reentrantCalls(aec, state))) {
+        assert.strictEqual(outcomes[name], 'reentrant', name);
+    }
+
+    // The processor survives: with the watchdog gone, calls work again
+    aec.setRealtimeWatchdog(0, null);
+    aec.setDeterministic(false);
+    assert.strictEqual(aec.processCaptureAudio(new Float32Array(FRAME)).length, FRAME);
+    aec.destroy();
+});
+
+test('re-entrancy: an uncaught error in an event callback reaches the processing call', () => {
+    const aec = new native.AudioCaptureAddon({ engine: 'nlms', filterLengthBlocks: 4, convergedHoldMs: 500 });
+    let attempts = 0;
+    aec.setEventCallback(() => {
+        attempts++;
+        aec.processCaptureAudio(new Float32Array(FRAME));
+    });
+    const ref = speechNoise(0.1, SAMPLE_RATE * 4, makeRng(92));
+    assert.throws(() => processPair(aec, ref, echo(ref, 96, 0.4)), isReentrant);
+    assert.strictEqual(attempts, 1, 'the first event threw');
+
+    aec.setEventCallback(null);
+    assert.strictEqual(aec.processCaptureAudio(new Float32Array(FRAME)).length, FRAME);
+    aec.destroy();
+});
+
+test('clone: continues with the same filter, delay and buffered audio as the original', () => {
+    // NLMS so the adaptive filter is part of what gets copied
+    const options = { engine: 'nlms', filterLengthBlocks: 4, enableNs: false };
+    const ref = speechNoise(0.1, SAMPLE_RATE * 3, makeRng(97));
+    const near = speechNoise(0.01, SAMPLE_RATE * 3, makeRng(98));
+    const mic = echo(ref, 1500, 0.5).map((sample, i) => sample + near[i]);
+    const split = SAMPLE_RATE * 2;
+
+    // Train, calibrate the delay the 256 taps can't cover, then leave a
+    // reference chunk queued and a partial frame carried over
+    const original = new native.AudioCaptureAddon(options);
+    processPair(original, ref.subarray(0, split), mic.subarray(0, split));
+    assert.ok(original.calibrateDelay().applied, 'delay calibrated');
+    original.processRenderAudio(ref.subarray(split, split + FRAME));
+    original.processCaptureAudio(mic.subarray(split, split + 200));
+
+    const copy = original.clone();
+    assert.deepStrictEqual(copy.getConfig(), original.getConfig());
+    assert.strictEqual(copy.getMetrics().inputSamplesProcessed, 0, 'metrics start fresh');
+
+    const rest = [split + FRAME, ref.length];
+    const tail = mic.subarray(split + 200, split + FRAME);
+    const originalFirst = original.processCaptureAudio(tail);
+    const copyFirst = copy.processCaptureAudio(tail);
+    assert.deepStrictEqual(copyFirst, originalFirst, 'first chunk');
+    const originalOut = processPair(original, ref.subarray(...rest), mic.subarray(...rest), 320);
+    const copyOut = processPair(copy, ref.subarray(...rest), mic.subarray(...rest), 320);
+    assert.deepStrictEqual(copyOut, originalOut, 'every later chunk');
+    assert.ok(attenuationDb(mic.subarray(...rest), copyOut) >= 10, 'the clone is still cancelling');
+
+    // From here the two are independent: resetting the clone leaves the original converged
+    copy.resetAEC();
+    const again = [0, SAMPLE_RATE / 2];
+    const originalAgain = attenuationDb(mic.subarray(...again), processPair(original, ref.subarray(...again), mic.subarray(...again)));
+    const copyAgain = attenuationDb(mic.subarray(...again), processPair(copy, ref.subarray(...again), mic.subarray(...again)));
+    assert.ok(originalAgain >= copyAgain + 3, `original ${originalAgain.toFixed(1)} dB, reset clone ${copyAgain.toFixed(1)} dB`);
+    original.destroy();
+    copy.destroy();
+});
+
+test('clone: cloneShared gives an independent id with identical output', () => {
+    const options = { engine: 'nlms', enableNs: false };
+    const ref = speechNoise(0.1, SAMPLE_RATE * 2, makeRng(99));
+    const mic = echo(ref, 96, 0.5);
+    const refChunks = chunks(ref);
+    const micChunks = chunks(mic);
+    const half = refChunks.length / 2;
+    const id = native.createShared(options);
+    for (let i = 0; i < half; i++) {
+        native.feedReferenceShared(id, refChunks[i]);
+        native.processShared(id, micChunks[i]);
+    }
+    const cloneId = native.cloneShared(id);
+    assert.notStrictEqual(cloneId, id);
+    assert.strictEqual(native.getMetricsShared(cloneId).inputSamplesProcessed, 0);
+
+    for (let i = half; i < refChunks.length; i++) {
+        native.feedReferenceShared(id, refChunks[i]);
+        native.feedReferenceShared(cloneId, refChunks[i]);
+        assert.deepStrictEqual(native.processShared(cloneId, micChunks[i]), native.processShared(id, micChunks[i]), `chunk ${i}`);
+    }
+    native.destroyShared(id);
+    assert.strictEqual(native.processShared(cloneId, micChunks[0]).length, FRAME, 'the clone outlives its source');
+    native.destroyShared(cloneId);
+});
+
+test('state: a setState round trip continues exactly like the uninterrupted processor', () => {
+    const options = { engine: 'nlms', filterLengthBlocks: 4, enableNs: false };
+    const ref = speechNoise(0.1, SAMPLE_RATE * 3, makeRng(101));
+    const near = speechNoise(0.01, SAMPLE_RATE * 3, makeRng(102));
+    const mic = echo(ref, 1500, 0.5).map((sample, i) => sample + near[i]);
+    const split = SAMPLE_RATE * 2;
+
+    // Save mid-stream: delay calibrated, reference queued, a partial frame carried over
+    const uninterrupted = new native.AudioCaptureAddon(options);
+    processPair(uninterrupted, ref.subarray(0, split), mic.subarray(0, split));
+    uninterrupted.calibrateDelay();
+    uninterrupted.processRenderAudio(ref.subarray(split, split + FRAME));
+    uninterrupted.processCaptureAudio(mic.subarray(split, split + 200));
+    const state = uninterrupted.getState();
+    assert.ok(Buffer.isBuffer(state) && state.length > 0);
+
+    const restored = new native.AudioCaptureAddon(options);
+    restored.setState(state);
+    const tail = mic.subarray(split + 200, split + FRAME);
+    assert.deepStrictEqual(restored.processCaptureAudio(tail), uninterrupted.processCaptureAudio(tail), 'first chunk');
+    const rest = [split + FRAME, ref.length];
+    const expected = processPair(uninterrupted, ref.subarray(...rest), mic.subarray(...rest));
+    assert.deepStrictEqual(processPair(restored, ref.subarray(...rest), mic.subarray(...rest)), expected);
+
+    // Saving is repeatable: the same processor gives the same bytes twice
+    assert.deepStrictEqual(uninterrupted.getState(), uninterrupted.getState());
+    uninterrupted.destroy();
+    restored.destroy();
+});
+
+test('state: a mismatched or corrupt buffer is rejected and changes nothing', () => {
+    const options = { engine: 'nlms', filterLengthBlocks: 4, enableNs: false };
+    const ref = speechNoise(0.1, SAMPLE_RATE, makeRng(103));
+    const mic = echo(ref, 96, 0.5);
+    const source = new native.AudioCaptureAddon(options);
+    processPair(source, ref, mic);
+    const state = source.getState();
+    source.destroy();
+
+    const isMismatch = (err) => err instanceof Error && err.code === 'ERR_AEC_STATE_MISMATCH';
+    const isCorrupt = (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE';
+    const versioned = Buffer.from(state);
+    versioned.writeUInt32LE(versioned.readUInt32LE(4) + 1, 4);
+    const rejected = [
+        [{ ...options, filterLengthBlocks: 8 }, state, isMismatch],
+        [{ ...options, sampleRate: 16000 }, state, isMismatch],
+        [{ ...options, engine: 'webrtc' }, state, isMismatch],
+        [options, versioned, isMismatch],
+        [options, state.subarray(0, state.length - 1), isCorrupt],
+        [options, Buffer.concat([state, Buffer.alloc(1)]), isCorrupt],
+    ];
+    for (const [targetOptions, buffer, check] of rejected) {
+        const target = new native.AudioCaptureAddon(targetOptions);
+        const fresh = new native.AudioCaptureAddon(targetOptions);
+        assert.throws(() => target.setState(buffer), check, JSON.stringify(targetOptions));
+        const size = targetOptions.sampleRate ? targetOptions.sampleRate / 100 : FRAME;
+        const probe = speechNoise(0.1, size * 20, makeRng(104));
+        assert.deepStrictEqual(processPair(target, probe, probe, size), processPair(fresh, probe, probe, size),
+            `unchanged after ${JSON.stringify(targetOptions)}`);
+        target.destroy();
+        fresh.destroy();
+    }
+    assert.throws(() => new native.AudioCaptureAddon(options).setState('state'),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+});
+
+test('fast path: output matches the general path when no optional stage changes the audio', () => {
+    const ref = speechNoise(0.1, SAMPLE_RATE * 2, makeRng(105));
+    const mic = echo(ref, 96, 0.5);
+    // A limiter with its knee far above the signal runs the output stages
+    // without touching a sample
+    for (const engine of ['webrtc', 'nlms']) {
+        const fast = new native.AudioCaptureAddon({ engine });
+        const general = new native.AudioCaptureAddon({ engine });
+        general.setLimiter(true, 0);
+        const fastOut = processPair(fast, ref, mic);
+        assert.deepStrictEqual(processPair(general, ref, mic), fastOut, engine);
+        assert.strictEqual(general.getMetrics().limiterMaxGainReductionDb, 0, `${engine}: limiter idle`);
+        fast.destroy();
+        general.destroy();
+    }
+});
+
+test('fast path: the detector-free NLMS kernel matches the detector that never fires', () => {
+    // A pure echo at half the reference never crosses a 0.99 Geigel threshold
+    const ref = speechNoise(0.1, SAMPLE_RATE * 2, makeRng(106));
+    const mic = echo(ref, 96, 0.5);
+    const plain = new native.AudioCaptureAddon({ engine: 'nlms', doubleTalkSensitivity: 0 });
+    const detecting = new native.AudioCaptureAddon({ engine: 'nlms', doubleTalkSensitivity: 0.01 });
+    const plainOut = processPair(plain, ref, mic);
+    assert.deepStrictEqual(processPair(detecting, ref, mic), plainOut);
+    assert.strictEqual(detecting.getMetrics().adaptationThrottledChunks, 0, 'detector never fired');
+    assert.ok(attenuationDb(mic.subarray(SAMPLE_RATE), plainOut.subarray(SAMPLE_RATE)) >= 10, 'both cancel');
+
+    // Switching stages on and off at run time lands back on the fast path
+    plain.setLimiter(true, 0);
+    plain.setLimiter(false);
+    detecting.reconfigure({ doubleTalkSensitivity: 0 });
+    assert.deepStrictEqual(processPair(detecting, ref, mic), processPair(plain, ref, mic), 'after toggling');
+    plain.destroy();
+    detecting.destroy();
+});
+
+test('idle reset: one simulated gap over autoResetAfterIdleMs resets once', () => {
+    const aec = new native.AudioCaptureAddon({ autoResetAfterIdleMs: 10000 });
+    const chunk = speechNoise(0.1, FRAME, makeRng(107));
+    let now = 0;
+    const step = (ms) => {
+        now += ms;
+        aec.setTestClock(now);
+        aec.processRenderAudio(chunk);
+        aec.processCaptureAudio(chunk);
+    };
+
+    for (let i = 0; i < 100; i++) step(10);
+    step(5000);        // Short pauses
+    step(10000);       // Exactly the limit is not over it
+    assert.strictEqual(aec.getMetrics().idleResets, 0, 'no reset for gaps up to the limit');
+    step(10001);
+    for (let i = 0; i < 100; i++) step(10);
+    assert.strictEqual(aec.getMetrics().idleResets, 1, 'exactly one reset for one long gap');
+    assert.strictEqual(aec.getMetrics().msSinceLastReference, 0, 'reference age follows the test clock');
+
+    // 0 disables it
+    aec.setAutoResetAfterIdle(0);
+    step(60000);
+    assert.strictEqual(aec.getMetrics().idleResets, 1);
+    aec.destroy();
+});
+
+test('idle reset: drops stale reference, and the filter only with idleResetFilter', () => {
+    const options = { engine: 'nlms', filterLengthBlocks: 4, enableNs: false, autoResetAfterIdleMs: 1000 };
+    const ref = speechNoise(0.1, SAMPLE_RATE * 3, makeRng(108));
+    const mic = echo(ref, 96, 0.5);
+    const train = [0, SAMPLE_RATE * 2];
+    const after = [SAMPLE_RATE * 2, SAMPLE_RATE * 2 + SAMPLE_RATE / 10];
+    const extra = new Float32Array(FRAME * 5);
+
+    const attenuationAfterGap = (idleResetFilter) => {
+        const aec = new native.AudioCaptureAddon({ ...options, idleResetFilter });
+        aec.setTestClock(0);
+        processPair(aec, ref.subarray(...train), mic.subarray(...train));
+        aec.processRenderAudio(extra);   // Reference the pause leaves behind
+        aec.processCaptureAudio(new Float32Array(FRAME));
+        aec.setTestClock(5000);
+        const underruns = aec.getMetrics().underrunCount;
+        aec.processCaptureAudio(new Float32Array(FRAME));
+        assert.strictEqual(aec.getMetrics().underrunCount, underruns + 1, 'the leftover reference was dropped');
+        assert.strictEqual(aec.getMetrics().idleResets, 1);
+        const db = attenuationDb(mic.subarray(...after), processPair(aec, ref.subarray(...after), mic.subarray(...after)));
+        aec.destroy();
+        return db;
+    };
+    const kept = attenuationAfterGap(false);
+    const reset = attenuationAfterGap(true);
+    assert.ok(kept >= 15, `filter kept: ${kept.toFixed(1)} dB at once`);
+    assert.ok(kept >= reset + 6, `kept ${kept.toFixed(1)} dB vs reset ${reset.toFixed(1)} dB`);
+});
+
+test('idle reset: setTestClock validates and null restores the system clock', () => {
+    const aec = new native.AudioCaptureAddon({ autoResetAfterIdleMs: 1000 });
+    assert.throws(() => aec.setTestClock('now'), (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+    assert.throws(() => aec.setTestClock(-1), (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE');
+    aec.setTestClock(0);
+    aec.processCaptureAudio(new Float32Array(FRAME));
+    aec.setTestClock(null);  // The next capture starts a fresh gap measurement
+    aec.processCaptureAudio(new Float32Array(FRAME));
+    aec.processCaptureAudio(new Float32Array(FRAME));
+    assert.strictEqual(aec.getMetrics().idleResets, 0);
+    aec.destroy();
+});
+
+// listInstances entries created by this test, by label; other tests' processors may still be alive
+function instancesLabelled(prefix) {
+    return native.listInstances().filter((instance) => instance.label.startsWith(prefix));
+}
+
+test('listInstances: lists live processors with their labels, oldest first', () => {
+    const prefix = `list-${process.pid}-`;
+    const before = Date.now();
+    const call = new native.AudioCaptureAddon({ label: `${prefix}call` });
+    const monitor = new native.AudioCaptureAddon({ label: `${prefix}monitor`, sampleRate: 16000, engine: 'nlms' });
+    const after = Date.now();
+
+    const listed = instancesLabelled(prefix);
+    assert.deepStrictEqual(listed.map((instance) => instance.label), [`${prefix}call`, `${prefix}monitor`]);
+    for (const instance of listed) {
+        assert.ok(instance.createdAt >= before - 1 && instance.createdAt <= after + 1, `createdAt ${instance.createdAt}`);
+    }
+    assert.deepStrictEqual(listed[0].config, call.getConfig());
+    assert.deepStrictEqual(listed[1].config, monitor.getConfig());
+    assert.strictEqual(listed[1].config.sampleRate, 16000);
+
+    // The summary is live, not a snapshot from creation
+    const chunk = speechNoise(0.1, 160, makeRng(109));
+    for (let i = 0; i < 10; i++) monitor.processCaptureAudio(chunk);
+    const summary = instancesLabelled(`${prefix}monitor`)[0].metricsSummary;
+    assert.deepStrictEqual(Object.keys(summary).sort(),
+        ['convergenceState', 'echoReturnLossEnhancement', 'engine', 'inputRmsDbfs', 'outputRmsDbfs']);
+    assert.strictEqual(summary.engine, 'nlms');
+    assertNear(summary.inputRmsDbfs, monitor.getMetrics().inputRmsDbfs, 1e-3, 'inputRmsDbfs');
+
+    call.destroy();
+    assert.deepStrictEqual(instancesLabelled(prefix).map((instance) => instance.label), [`${prefix}monitor`]);
+    monitor.destroy();
+    assert.deepStrictEqual(instancesLabelled(prefix), []);
+});
+
+test('listInstances: unlabelled processors and clones are listed, shared ones are not', () => {
+    const prefix = `clone-${process.pid}-`;
+    const unlabelled = native.listInstances().filter((instance) => instance.label === '').length;
+    const plain = new native.AudioCaptureAddon();
+    assert.strictEqual(native.listInstances().filter((instance) => instance.label === '').length, unlabelled + 1);
+    plain.destroy();
+
+    const source = new native.AudioCaptureAddon({ label: `${prefix}source` });
+    const copy = source.clone();
+    assert.deepStrictEqual(instancesLabelled(prefix).map((instance) => instance.label), [`${prefix}source`, `${prefix}source`]);
+    copy.reconfigure({ label: `${prefix}copy` });
+    assert.deepStrictEqual(instancesLabelled(prefix).map((instance) => instance.label), [`${prefix}source`, `${prefix}copy`]);
+
+    const count = native.listInstances().length;
+    const id = native.createShared({ label: `${prefix}shared` });
+    assert.strictEqual(native.listInstances().length, count);
+    native.destroyShared(id);
+    source.destroy();
+    copy.destroy();
+    assert.deepStrictEqual(instancesLabelled(prefix), []);
+});
+
+test('suggestParameters: frame size and filter length for 16 kHz and 48 kHz', () => {
+    const { suggestParameters } = loadWrapper();
+    // [request, frameDurationMs, frameSize, filterLengthBlocks, filterLengthMs]
+    const cases = [
+        // 50 ms tail in 10 ms frames: 50 ms over 4 ms AEC3 blocks
+        [{ sampleRate: 16000, targetLatencyMs: 20, expectedEchoTailMs: 50 }, 10, 160, 13, 52],
+        // 20 ms frames round the tail up to 60 ms; NLMS blocks are 64 samples = 4 ms at 16 kHz
+        [{ sampleRate: 16000, targetLatencyMs: 60, expectedEchoTailMs: 50, engine: 'nlms' }, 20, 320, 15, 60],
+        [{ sampleRate: 48000, targetLatencyMs: 20, expectedEchoTailMs: 30 }, 10, 480, 8, 32],
+        // 64 samples at 48 kHz are 4/3 ms, so 40 ms is exactly 30 blocks
+        [{ sampleRate: 48000, targetLatencyMs: 40, expectedEchoTailMs: 25, engine: 'nlms' }, 20, 960, 30, 40],
+        // No tail still covers one frame
+        [{ sampleRate: 48000, targetLatencyMs: 10, expectedEchoTailMs: 0 }, 10, 480, 3, 12],
+        // A 500 ms tail needs 125 blocks and is clamped to 32
+        [{ sampleRate: 48000, targetLatencyMs: 10, expectedEchoTailMs: 500 }, 10, 480, 32, 128],
+    ];
+    for (const [request, frameDurationMs, frameSize, filterLengthBlocks, filterLengthMs] of cases) {
+        const what = JSON.stringify(request);
+        const suggestion = suggestParameters(request);
+        assert.strictEqual(suggestion.frameDurationMs, frameDurationMs, what);
+        assert.strictEqual(suggestion.frameSize, frameSize, what);
+        assert.strictEqual(suggestion.filterLengthBlocks, filterLengthBlocks, what);
+        assertNear(suggestion.filterLengthMs, filterLengthMs, 1e-9, what);
+        assert.ok(suggestion.notes.length > 0 && suggestion.notes.every((note) => typeof note === 'string'), what);
+
+        // The suggestion is a config the addon accepts as-is
+        const aec = new native.AudioCaptureAddon({
+            sampleRate: request.sampleRate, engine: request.engine, frameDurationMs, filterLengthBlocks,
+        });
+        assert.strictEqual(aec.getConfig().frameSize, frameSize, what);
+        aec.destroy();
+    }
+});
+
+test('suggestParameters: notes explain clamping and latency; invalid input gives null', () => {
+    const { suggestParameters } = loadWrapper();
+    const clamped = suggestParameters({ sampleRate: 48000, targetLatencyMs: 10, expectedEchoTailMs: 500 });
+    assert.ok(clamped.notes.some((note) => note.includes('125 blocks')), clamped.notes.join(' | '));
+    const tight = suggestParameters({ sampleRate: 16000, targetLatencyMs: 5, expectedEchoTailMs: 50 });
+    assert.strictEqual(tight.frameDurationMs, 10);
+    assert.ok(tight.notes.some((note) => note.includes('shortest frame')), tight.notes.join(' | '));
+    const nlms = suggestParameters({ sampleRate: 16000, targetLatencyMs: 20, expectedEchoTailMs: 50, engine: 'nlms' });
+    assert.ok(nlms.notes.some((note) => note.includes(`${nlms.filterLengthBlocks * 64} taps`)), nlms.notes.join(' | '));
+
+    for (const request of [
+        { sampleRate: 11025, targetLatencyMs: 20, expectedEchoTailMs: 50 },
+        { sampleRate: 48000, targetLatencyMs: -1, expectedEchoTailMs: 50 },
+        { sampleRate: 48000, targetLatencyMs: 20, expectedEchoTailMs: NaN },
+    ]) {
+        assert.strictEqual(suggestParameters(request), null, JSON.stringify(request));
+    }
+});
+
+test('stage gains: +6 dB output gain doubles the amplitude once the ramp ends', () => {
+    const aec = createMeteringProcessor();
+    aec.setOutputGain(6);
+    const input = speechNoise(0.1, SAMPLE_RATE, makeRng(110));
+    const output = processPair(aec, new Float32Array(input.length), input);
+    const settled = [FRAME, input.length];  // The ramp takes the first 10 ms
+    assertNear(rms(output.subarray(...settled)) / rms(input.subarray(...settled)), 2, 0.01, '+6 dB ratio');
+    for (let i = FRAME; i < input.length; i++) {
+        assertNear(output[i], input[i] * 10 ** (6 / 20), 1e-6, `sample ${i}`);
+    }
+    assert.strictEqual(aec.getConfig().outputGainDb, 6);
+    aec.destroy();
+
+    // The input gain scales the same way, ahead of the canceller
+    const pre = createMeteringProcessor();
+    pre.setInputGain(-6);
+    const halved = processPair(pre, new Float32Array(input.length), input);
+    assertNear(rms(halved.subarray(...settled)) / rms(input.subarray(...settled)), 0.5, 0.01, '-6 dB ratio');
+    assert.strictEqual(pre.getConfig().inputGainDb, -6);
+    pre.destroy();
+});
+
+test('stage gains: changes ramp linearly over 10 ms with no discontinuity', () => {
+    const level = 0.25;
+    for (const [sampleRate, chunkSize] of [[48000, 160], [16000, 64]]) {
+        const rampSamples = sampleRate / 100;
+        const aec = createMeteringProcessor({ sampleRate });
+        const input = new Float32Array(sampleRate / 10).fill(level);
+        const run = () => processPair(aec, new Float32Array(input.length), input, chunkSize);
+        const settledAt = (samples, value, what) => {
+            for (let i = rampSamples; i < samples.length; i++) assertNear(samples[i], value, 1e-6, `${what} ${i}`);
+        };
+
+        for (const [from, to] of [[0, 6], [6, -20], [-20, 0]]) {
+            run();  // Settle at `from`
+            aec.setOutputGain(to);
+            const output = run();
+            const start = level * 10 ** (from / 20);
+            const end = level * 10 ** (to / 20);
+            const step = (end - start) / rampSamples;
+            const what = `${sampleRate} Hz, ${from} -> ${to} dB`;
+            // Each sample moves one step on, across chunk boundaries too
+            assertNear(output[0], start + step, 1e-6, `${what}: first sample`);
+            for (let i = 1; i < rampSamples; i++) {
+                assertNear(output[i] - output[i - 1], step, 1e-6, `${what}: step ${i}`);
+            }
+            settledAt(output, end, what);
+        }
+        aec.destroy();
+    }
+});
+
+test('stage gains: clamp at full scale and reject out-of-range settings', () => {
+    const aec = createMeteringProcessor();
+    aec.setInputGain(40);
+    const output = processPair(aec, new Float32Array(SAMPLE_RATE / 10), sine(1000, 0.5, SAMPLE_RATE / 10));
+    assertFinite(output, '+40 dB');
+    assert.ok(output.every((sample) => Math.abs(sample) <= 1), 'never past full scale');
+    for (const gain of [-61, 41, NaN]) {
+        for (const setter of ['setInputGain', 'setOutputGain']) {
+            assert.throws(() => aec[setter](gain),
+                (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE', `${setter}(${gain})`);
+        }
+    }
+    assert.strictEqual(aec.getConfig().inputGainDb, 40);
+    assert.strictEqual(aec.getConfig().outputGainDb, 0);
+    aec.destroy();
+});
+
+test('call options: a per-call outputGainDb applies to that call only', () => {
+    const aec = createMeteringProcessor();
+    const level = 0.25;
+    const chunk = new Float32Array(FRAME * 2).fill(level);
+    const settled = (output) => output.subarray(FRAME);  // Past the 10 ms ramp
+
+    const boosted = aec.processCaptureAudio(chunk, { outputGainDb: 6 });
+    settled(boosted).forEach((sample, i) => assertNear(sample, level * 10 ** (6 / 20), 1e-6, `boosted ${i}`));
+    assert.strictEqual(aec.getConfig().outputGainDb, 0, 'the handle setting is untouched');
+
+    // The next plain call ramps back to the handle's 0 dB
+    const plain = aec.processCaptureAudio(chunk);
+    assert.ok(plain[0] > level, 'ramping down from the override');
+    settled(plain).forEach((sample, i) => assertNear(sample, level, 1e-6, `plain ${i}`));
+
+    // An override on top of a handle gain replaces it, then the handle gain returns
+    aec.setOutputGain(-6);
+    aec.processCaptureAudio(chunk);
+    settled(aec.processCaptureAudio(chunk, { outputGainDb: 0 })).forEach((sample, i) => assertNear(sample, level, 1e-6, `${i}`));
+    settled(aec.processCaptureAudio(chunk)).forEach((sample, i) => assertNear(sample, level * 10 ** (-6 / 20), 1e-6, `${i}`));
+    aec.destroy();
+});
+
+test('call options: bypass and collectStats do not carry over to the next call', () => {
+    const aec = new native.AudioCaptureAddon({ engine: 'nlms', enableNs: false });
+    const ref = speechNoise(0.1, SAMPLE_RATE * 3, makeRng(111));
+    const mic = echo(ref, 96, 0.5);
+    const train = SAMPLE_RATE * 2;
+    processPair(aec, ref.subarray(0, train), mic.subarray(0, train));
+
+    const micChunk = mic.subarray(train, train + FRAME);
+    aec.processRenderAudio(ref.subarray(train, train + FRAME));
+    const bypassed = aec.processCaptureAudio(micChunk, { bypass: true, collectStats: true });
+    assert.deepStrictEqual(bypassed.output, micChunk, 'bypassed chunk is the input');
+
+    const next = [train + FRAME, train + SAMPLE_RATE / 2];
+    aec.processRenderAudio(ref.subarray(next[0], next[0] + FRAME));
+    const plain = aec.processCaptureAudio(mic.subarray(next[0], next[0] + FRAME));
+    assert.ok(plain instanceof Float32Array, 'no stats object without collectStats');
+    const rest = processPair(aec, ref.subarray(next[0] + FRAME, next[1]), mic.subarray(next[0] + FRAME, next[1]));
+    assert.ok(attenuationDb(mic.subarray(next[0] + FRAME, next[1]), rest) >= 10, 'cancelling again');
+    assert.strictEqual(aec.getMetrics().underrunCount, 0, 'the bypassed chunk took its reference with it');
+    aec.destroy();
+});
+
+test('call options: unknown keys are ignored unless strict is set for that call', () => {
+    const aec = createMeteringProcessor();
+    const chunk = speechNoise(0.1, FRAME, makeRng(112));
+    assert.deepStrictEqual(aec.processCaptureAudio(chunk, { denoise: true }), chunk, 'ignored without strict');
+    assert.throws(() => aec.processCaptureAudio(chunk, { strict: true, denoise: true }),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_UNKNOWN_OPTION' && err.message.includes('denoise'));
+    assert.deepStrictEqual(aec.processCaptureAudio(chunk, { strict: true, bypass: false, outputGainDb: 0, collectStats: false }), chunk);
+    assert.deepStrictEqual(aec.processCaptureAudio(chunk, { denoise: true }), chunk, 'strict did not persist');
+    for (const options of [{ bypass: 1 }, { collectStats: 'yes' }, { strict: 'true' }]) {
+        assert.throws(() => aec.processCaptureAudio(chunk, options),
+            (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT', JSON.stringify(options));
+    }
+    assert.throws(() => aec.processCaptureAudio(chunk, { outputGainDb: 41 }),
+        (err) => err instanceof RangeError && err.code === 'ERR_AEC_INVALID_VALUE');
+    aec.destroy();
+});
+
+test('passthrough fallback: without it a failed engine init throws ERR_AEC_CONFIG_FAILED', () => {
+    const isConfigFailed = (err) => err instanceof Error && err.code === 'ERR_AEC_CONFIG_FAILED' &&
+        err.message.includes('Unsupported stream format');
+    assert.throws(() => new native.AudioCaptureAddon({ testInitFailure: true }), isConfigFailed);
+    assert.throws(() => native.createShared({ testInitFailure: true }), isConfigFailed);
+    // Only the instance that asked for it fails
+    const aec = new native.AudioCaptureAddon({ fallbackToPassthrough: true });
+    assert.strictEqual(aec.getMetrics().engineActive, true);
+    assert.strictEqual(aec.getMetrics().initError, '');
+    aec.destroy();
+    assert.throws(() => new native.AudioCaptureAddon({ testInitFailure: 'yes' }),
+        (err) => err instanceof TypeError && err.code === 'ERR_AEC_INVALID_ARGUMENT');
+});
+
+test('passthrough fallback: copies input to output, keeps metering and warns once', async () => {
+    const aec = new native.AudioCaptureAddon({ fallbackToPassthrough: true, testInitFailure: true, levelSmoothingMs: 0 });
+    let metrics = aec.getMetrics();
+    assert.strictEqual(metrics.engineActive, false);
+    assert.ok(metrics.initError.length > 0, 'initError names the reason');
+    assert.strictEqual(aec.getConfig().sampleRate, SAMPLE_RATE, 'the stream format is the requested one');
+
+    const records = [];
+    aec.setLogger((level, message) => records.push({ level, message }), 'warn');
+    const ref = speechNoise(0.1, SAMPLE_RATE, makeRng(113));
+    const mic = echo(ref, 96, 0.5);
+    const output = processPair(aec, ref, mic);
+    assert.deepStrictEqual(output, mic, 'no cancellation, no suppression');
+
+    metrics = aec.getMetrics();
+    const lastChunk = mic.subarray(mic.length - FRAME);
+    assertNear(metrics.inputRmsDbfs, dbfs(rms(lastChunk)), 0.01, 'input meter');
+    assertNear(metrics.outputRmsDbfs, dbfs(rms(lastChunk)), 0.01, 'output meter');
+    assert.strictEqual(metrics.inputSamplesProcessed, mic.length);
+    assert.strictEqual(metrics.engineActive, false);
+
+    await settle();
+    const warnings = records.filter((r) => r.level === 'warn' && r.message.includes('failed to initialize'));
+    assert.strictEqual(warnings.length, 1, `one warning on first use (got ${JSON.stringify(records)})`);
+    assert.ok(warnings[0].message.includes(metrics.initError), 'the warning carries the reason');
+    aec.destroy();
+});
+
+test('passthrough fallback: createShared falls back the same way', () => {
+    const id = native.createShared({ fallbackToPassthrough: true, testInitFailure: true });
+    const chunk = speechNoise(0.1, FRAME, makeRng(114));
+    native.feedReferenceShared(id, chunk);
+    assert.deepStrictEqual(native.processShared(id, chunk), chunk);
+    const metrics = native.getMetricsShared(id);
+    assert.strictEqual(metrics.engineActive, false);
+    assert.ok(metrics.initError.length > 0);
+    native.destroyShared(id);
+});
+
+// ---------------------------------------------------------------------------
+// Runner
+// ---------------------------------------------------------------------------
+
+(async () => {
+    const filter = process.argv[2];
+    let passed = 0;
+    let failed = 0;
+
+    console.log('🧪 Testing AEC processor\n');
+    for (const { name, fn } of tests) {
+        if (filter && !name.includes(filter)) continue;
+        try {
+            await fn();
+            passed++;
+            console.log(`✅ ${name}`);
+        } catch (err) {
+            failed++;
+            console.log(`❌ ${name}`);
+            console.log(`   ${err.stack}`);
+        }
+    }
+
+    console.log('\n' + '='.repeat(60));
+    console.log(`${passed} passed, ${failed} failed`);
+    process.exit(failed > 0 ? 1 : 0);
+})();